@@ -1,9 +1,11 @@
-// Quick hack to get a static random graph at compile time. Make sure any changes to constants or
-// types in the library are copied here. Lib types and constants are at the bottom of this module.
+// Quick hack to get a static random graph at compile time. The types and constants we share with
+// the library are included straight from its source below. The wide metadata types own their names
+// here so they're still copies at the bottom of this module, along with how everything is written
+// out as Rust.
 
 use std::{
     cmp::min,
-    collections::{HashSet, VecDeque},
+    collections::{BTreeSet, HashSet, VecDeque},
     num::NonZeroU16,
 };
 
@@ -11,10 +13,23 @@ use rand::distributions::WeightedIndex;
 use rand::prelude::*;
 use rand_chacha::ChaCha20Rng;
 
+#[allow(dead_code)]
+#[path = "src/constants.rs"]
+mod constants;
+#[allow(dead_code)]
+#[path = "src/types.rs"]
+mod types;
+
+use constants::{MAX_OUT_DEGREE, NUM_EDGES, NUM_EDGES_PADDED, NUM_VERTICES, NUM_VERTICES_PADDED};
+use types::{
+    DoorFlags, EdgeType, Item, NodeData, NodeType, ReqBuilder, Requirement, Tier,
+    REQ_CONTAINER_LEN, REQ_TREES,
+};
+
 const AUTOGEN_WARNING: &str =
     "// THIS IS AN AUTOMATICALLY GENERATED MODULE. ANY CHANGES WILL BE OVERWRITTEN.";
 const IMPORTS: &str = r#"use core::num::NonZeroU16;
-use crate::{graph::{NodeData, NodeType}, constants::{NUM_VERTICES_PADDED, NUM_EDGES_PADDED}};"#;
+use crate::{graph::{DoorFlags, DoorNodeData, EdgeType, ItemNodeData, NodeData, NodeType, PlaceNodeData}, logic::Item, constants::{NUM_VERTICES_PADDED, NUM_EDGES_PADDED}, raw::nonzero};"#;
 
 // The distribution should produce ~38k edges. The rest will be used to randomly connect any
 // remaining unconnected nodes and then randomly placed to fill out NUM_EDGES. We use a seeded
@@ -23,45 +38,434 @@ use crate::{graph::{NodeData, NodeType}, constants::{NUM_VERTICES_PADDED, NUM_ED
 const EDGES_PER: [u8; 5] = [0, 1, 2, 3, 4];
 const WEIGHTS: [u8; 5] = [3, 10, 50, 31, 6];
 
+// Rough percentage of nodes that will be item locations and doors. Everything else is a place.
+const ITEM_NODE_PERCENT: u32 = 5;
+const DOOR_NODE_PERCENT: u32 = 3;
+const REGION_SIZE: usize = 2500;
+
+// Rough percentage of edges not touching a door that will be warps and logical shortcuts.
+const WARP_EDGE_PERCENT: u32 = 2;
+const SHORTCUT_EDGE_PERCENT: u32 = 3;
+
+// The tricks players can opt into and the rough percentage of edges with a requirement that one
+// of them opens.
+const TRICK_NAMES: [&str; 4] = ["fake_flipper", "hover", "bomb_jump", "water_walk"];
+const TRICK_EDGE_PERCENT: u32 = 5;
+
 fn main() {
     println!("cargo:rerun-if-changed=build.rs");
     let path = "src/gen.rs";
-    let (node_ptrs, node_data, edge_ptrs, edge_data) = new_random();
+    let (node_ptrs, edge_ptrs, edge_data) = new_random();
+    let (node_data, place_data, item_data, door_data) = new_node_metadata();
+    let region_of = new_region_of();
+    let (edge_kinds, edge_weights) = new_edge_metadata(&node_ptrs, &edge_ptrs, &node_data);
+    let edge_tricks = new_edge_tricks(&edge_data);
     let np_string = format!(
         "pub(crate) const NODE_POINTERS: [NonZeroU16; NUM_VERTICES_PADDED] = {};",
-        ArrayFormatter(node_ptrs)
+        ArrayFormatter(&node_ptrs)
     );
     let nd_string = format!(
         "pub(crate) const NODE_DATA: [NodeData; NUM_VERTICES_PADDED] = {};",
-        ArrayFormatter(node_data)
+        ArrayFormatter(&node_data)
     );
     let ep_string = format!(
         "pub(crate) const EDGE_POINTERS: [NonZeroU16; NUM_EDGES_PADDED] = {};",
-        ArrayFormatter(edge_ptrs)
+        ArrayFormatter(&edge_ptrs)
     );
     let ed_string = format!(
         "pub(crate) const EDGE_DATA: [u16; NUM_EDGES_PADDED] = {};",
-        ArrayFormatter(edge_data)
+        ArrayFormatter(&edge_data)
+    );
+    let pd_string = format!(
+        "pub(crate) static PLACE_DATA: [PlaceNodeData; {}] = {};",
+        place_data.len(),
+        ArrayFormatter(&place_data)
+    );
+    let id_string = format!(
+        "pub(crate) static ITEM_DATA: [ItemNodeData; {}] = {};",
+        item_data.len(),
+        ArrayFormatter(&item_data)
+    );
+    let dd_string = format!(
+        "pub(crate) static DOOR_DATA: [DoorNodeData; {}] = {};",
+        door_data.len(),
+        ArrayFormatter(&door_data)
+    );
+    let ro_string = format!(
+        "pub(crate) const REGION_OF: [u16; NUM_VERTICES_PADDED] = {};",
+        ArrayFormatter(&region_of)
+    );
+    let ek_string = format!(
+        "pub(crate) const EDGE_KINDS: [EdgeType; NUM_EDGES_PADDED] = {};",
+        ArrayFormatter(&edge_kinds)
+    );
+    let ew_string = format!(
+        "pub(crate) const EDGE_WEIGHTS: [u8; NUM_EDGES_PADDED] = {};",
+        ArrayFormatter(&edge_weights)
+    );
+    let tn_string = format!(
+        "pub(crate) static TRICKS: [&str; {}] = {:?};",
+        TRICK_NAMES.len(),
+        TRICK_NAMES
     );
+    let et_string = format!(
+        "pub(crate) const EDGE_TRICKS: [u8; NUM_EDGES_PADDED] = {};",
+        ArrayFormatter(&edge_tricks)
+    );
+    let model = WorldModel {
+        node_ptrs: &node_ptrs,
+        edge_ptrs: &edge_ptrs,
+        edge_data: &edge_data,
+        node_data: &node_data,
+        place_data: &place_data,
+        item_data: &item_data,
+        door_data: &door_data,
+    };
+    // Problems with the model become compile errors in the generated module, so they show up
+    // right where the library fails to build instead of at runtime.
+    let (errors, warnings) = model.lint();
+    warnings
+        .iter()
+        .for_each(|w| println!("cargo:warning={}", w));
+    let lint_string: String = errors
+        .iter()
+        .map(|e| format!("compile_error!({:?});\n", e))
+        .collect();
+    let (stack_size, queue_size) = model.search_sizes();
+    let ss_string = format!("pub const SEARCH_STACK_SIZE: usize = {};", stack_size);
+    let sq_string = format!("pub const SEARCH_QUEUE_SIZE: usize = {};", queue_size);
     let module_string = format!(
-        "{}\n{}\n\n{}\n{}\n{}\n{}\n",
-        AUTOGEN_WARNING, IMPORTS, np_string, nd_string, ep_string, ed_string
+        "{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n",
+        AUTOGEN_WARNING,
+        IMPORTS,
+        lint_string,
+        np_string,
+        nd_string,
+        ep_string,
+        ed_string,
+        pd_string,
+        id_string,
+        dd_string,
+        ro_string,
+        ek_string,
+        ew_string,
+        tn_string,
+        et_string,
+        ss_string,
+        sq_string
     );
     std::fs::write(path, module_string).unwrap();
 }
 
+/// The tables we've generated, borrowed together so we can lint the world model before writing
+/// it out.
+struct WorldModel<'a> {
+    node_ptrs: &'a [OptionNonZeroWrapper],
+    edge_ptrs: &'a [OptionNonZeroWrapper],
+    edge_data: &'a [u16],
+    node_data: &'a [NodeData],
+    place_data: &'a [PlaceNodeData],
+    item_data: &'a [ItemNodeData],
+    door_data: &'a [DoorNodeData],
+}
+
+impl WorldModel<'_> {
+    /// Run every validation pass and return one message per problem as `(errors, warnings)`. Each
+    /// message names the node it's about so model authors can find it without digging through
+    /// the generated tables.
+    fn lint(&self) -> (Vec<String>, Vec<String>) {
+        let mut errors = Vec::new();
+        errors.extend(self.lint_requirements());
+        errors.extend(self.lint_duplicate_edges());
+        errors.extend(self.lint_out_degree());
+        errors.extend(self.lint_unreachable());
+        errors.extend(self.lint_orphaned_items());
+        let (door_errors, warnings) = self.lint_dangling_doors();
+        errors.extend(door_errors);
+
+        (errors, warnings)
+    }
+
+    fn name(&self, node: usize) -> String {
+        let data = &self.node_data[node];
+        let name = match data.node_type {
+            NodeType::Place => &self.place_data[data.data_index as usize].name,
+            NodeType::Item => &self.item_data[data.data_index as usize].name,
+            NodeType::Door => &self.door_data[data.data_index as usize].name,
+        };
+        format!("node {} ({})", node, name)
+    }
+
+    /// `(edge index, destination)` for every edge leaving `node`.
+    fn edges(&self, node: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let start = self.node_ptrs[node].0.map_or(1, u16::from) as usize;
+        let end = self.node_ptrs[node + 1].0.map_or(1, u16::from) as usize;
+        (start..end).map(|e| (e, self.edge_ptrs[e].0.map_or(1, u16::from) as usize))
+    }
+
+    fn incoming(&self) -> Vec<u16> {
+        let mut incoming = vec![0u16; NUM_VERTICES + 1];
+        for src in 1..=NUM_VERTICES {
+            self.edges(src).for_each(|(_, dest)| incoming[dest] += 1);
+        }
+
+        incoming
+    }
+
+    /// Every edge has to point at a requirement tree we actually have, and every tree has to
+    /// stay inside the container and only require items some location in the model provides.
+    fn lint_requirements(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for (i, tree) in REQ_TREES.0.iter().enumerate().skip(2) {
+            let mut links = [tree.and, tree.or].into_iter().flatten().map(u16::from);
+            if let Some(link) = links.find(|&l| l as usize >= REQ_CONTAINER_LEN) {
+                errors.push(format!(
+                    "requirement tree {} links to undefined tree {}",
+                    i, link
+                ));
+            }
+            let provided = match tree.req.item() {
+                Some(item) => self.item_data.iter().any(|d| d.vanilla_item == item),
+                // Tiers and settings are chosen by the player rather than found, and events
+                // happen wherever the world model puts them.
+                None => true,
+            };
+            if !provided {
+                errors.push(format!(
+                    "requirement tree {} requires {} which no item location provides",
+                    i, tree.req
+                ));
+            }
+        }
+        // Every tree should be stored once, which is only worth checking once they're all in range.
+        if errors.is_empty() {
+            let mut builder = ReqBuilder::<REQ_CONTAINER_LEN>::new();
+            let mut roots = [0; REQ_CONTAINER_LEN];
+            for i in 2..REQ_CONTAINER_LEN {
+                roots[i] = builder.insert_tree(&REQ_TREES, i as u16).unwrap();
+                if let Some(j) = (2..i).find(|&j| roots[j] == roots[i]) {
+                    errors.push(format!("requirement tree {} duplicates tree {}", i, j));
+                }
+            }
+        }
+        for src in 1..=NUM_VERTICES {
+            for (edge, dest) in self.edges(src) {
+                if self.edge_data[edge] as usize >= REQ_CONTAINER_LEN {
+                    errors.push(format!(
+                        "edge {} from {} to {} uses undefined requirement tree {}",
+                        edge,
+                        self.name(src),
+                        self.name(dest),
+                        self.edge_data[edge]
+                    ));
+                }
+            }
+        }
+
+        errors
+    }
+
+    fn lint_duplicate_edges(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for src in 1..=NUM_VERTICES {
+            let mut seen = HashSet::new();
+            for (_, dest) in self.edges(src).filter(|&(_, dest)| !seen.insert(dest)) {
+                errors.push(format!(
+                    "{} has more than one edge to {}",
+                    self.name(src),
+                    self.name(dest)
+                ));
+            }
+        }
+
+        errors
+    }
+
+    fn lint_out_degree(&self) -> Vec<String> {
+        (1..=NUM_VERTICES)
+            .map(|n| (n, self.edges(n).count()))
+            .filter(|&(_, degree)| degree > MAX_OUT_DEGREE)
+            .map(|(n, degree)| {
+                format!(
+                    "{} has {} outgoing edges but at most {} are allowed",
+                    self.name(n),
+                    degree,
+                    MAX_OUT_DEGREE
+                )
+            })
+            .collect()
+    }
+
+    /// Walk the model from the root with every item. Anything we don't reach is dead weight at
+    /// best and a location that can never be checked at worst.
+    fn lint_unreachable(&self) -> Vec<String> {
+        let mut visited = vec![false; NUM_VERTICES + 1];
+        let mut stack = vec![1usize];
+        visited[1] = true;
+        while let Some(node) = stack.pop() {
+            for (edge, dest) in self.edges(node) {
+                if !visited[dest] && eval_all_items(self.edge_data[edge]) {
+                    visited[dest] = true;
+                    stack.push(dest);
+                }
+            }
+        }
+
+        (1..=NUM_VERTICES)
+            .filter(|&n| !visited[n])
+            .map(|n| format!("{} is unreachable with every item", self.name(n)))
+            .collect()
+    }
+
+    fn lint_orphaned_items(&self) -> Vec<String> {
+        let incoming = self.incoming();
+        (1..=NUM_VERTICES)
+            .filter(|&n| matches!(self.node_data[n].node_type, NodeType::Item) && incoming[n] == 0)
+            .map(|n| format!("item location {} has no incoming edges", self.name(n)))
+            .collect()
+    }
+
+    /// A door has to be entered from somewhere and lead somewhere, otherwise it's one half of a
+    /// transition whose other side is missing. Doors that don't lead anywhere are only warnings
+    /// since our random generator happily turns terminal nodes into doors.
+    fn lint_dangling_doors(&self) -> (Vec<String>, Vec<String>) {
+        let incoming = self.incoming();
+        let doors =
+            (1..=NUM_VERTICES).filter(|&n| matches!(self.node_data[n].node_type, NodeType::Door));
+        let (mut errors, mut warnings) = (Vec::new(), Vec::new());
+        for door in doors {
+            if incoming[door] == 0 {
+                errors.push(format!("door {} can't be entered", self.name(door)));
+            }
+            if self.edges(door).next().is_none() {
+                warnings.push(format!("door {} doesn't lead anywhere", self.name(door)));
+            }
+        }
+
+        (errors, warnings)
+    }
+
+    /// Run the library's depth- and breadth-first searches from the root under every combination
+    /// of the requirements our edges check, including locked edges, and size `DfsStack` and
+    /// `BfsQueue` to fit the deepest stack and widest queue we see, plus the slot both keep free.
+    /// Both sizes have to be powers of two. `codegen::GenTables` in the library works them out
+    /// the same way from a built graph, and its tests check the two agree.
+    fn search_sizes(&self) -> (usize, usize) {
+        // Requirements no edge checks can't change where a search goes, so leaving them out keeps
+        // the number of combinations down as requirements are added.
+        let mut checked = BTreeSet::new();
+        for root in self.edge_data.iter().copied().collect::<BTreeSet<u16>>() {
+            checked_requirements(root, &mut checked);
+        }
+        let always = [Requirement::Open, Requirement::Tier(Tier::Normal)].map(|r| r.id());
+        let checked: Vec<u16> = checked
+            .into_iter()
+            .filter(|id| !always.contains(id))
+            .collect();
+        let (mut stack_max, mut queue_max) = (0, 0);
+        for combo in 0..1u32 << checked.len() {
+            let state = checked
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| combo & 1 << i != 0)
+                .fold(0, |state, (_, &id)| state | 1 << id);
+            let mut visited = vec![false; NUM_VERTICES + 1];
+            let mut stack = vec![1];
+            visited[1] = true;
+            while let Some(node) = stack.pop() {
+                for (edge, dest) in self.edges(node) {
+                    if eval_state(self.edge_data[edge], state) && !visited[dest] {
+                        visited[dest] = true;
+                        stack.push(dest);
+                    }
+                }
+                stack_max = stack_max.max(stack.len());
+            }
+
+            let mut visited = vec![false; NUM_VERTICES + 1];
+            let mut queue = VecDeque::from([1]);
+            visited[1] = true;
+            while let Some(node) = queue.pop_front() {
+                for (edge, dest) in self.edges(node) {
+                    if eval_state(self.edge_data[edge], state) && !visited[dest] {
+                        visited[dest] = true;
+                        queue.push_back(dest);
+                    }
+                }
+                queue_max = queue_max.max(queue.len());
+            }
+        }
+
+        (
+            (stack_max + 1).next_power_of_two(),
+            (queue_max + 1).next_power_of_two(),
+        )
+    }
+}
+
+/// Add the id of every requirement tree `idx` checks to `ids`.
+fn checked_requirements(idx: u16, ids: &mut BTreeSet<u16>) {
+    let tree = &REQ_TREES.0[idx as usize];
+    // A negation checks the same bit as the requirement it negates.
+    ids.insert(match tree.req {
+        Requirement::Not(negated) => negated.requirement().id(),
+        req => req.id(),
+    });
+    for link in [tree.and, tree.or].into_iter().flatten() {
+        checked_requirements(link.get(), ids);
+    }
+}
+
+/// Evaluate a requirement tree the way the library does, where bit `n` of `state` says whether
+/// the requirement with id `n` is satisfied. Locked requirements get a bit too since
+/// `new_static_graph_open` opens them, and so does every optional tier, setting and event.
+fn eval_state(mut idx: u16, state: u32) -> bool {
+    loop {
+        let tree = &REQ_TREES.0[idx as usize];
+        let satisfied = match tree.req {
+            Requirement::Open | Requirement::Tier(Tier::Normal) => true,
+            Requirement::Not(negated) => state & (1 << negated.requirement().id()) == 0,
+            req => state & (1 << req.id()) != 0,
+        };
+        let next = match satisfied {
+            true => tree.and,
+            false => tree.or,
+        };
+        match next {
+            Some(n) => idx = n.get(),
+            None => break satisfied,
+        }
+    }
+}
+
+/// Evaluate a requirement tree the way the library does with every item collected, which only
+/// leaves locked and negated requirements unsatisfied.
+fn eval_all_items(mut idx: u16) -> bool {
+    loop {
+        let Some(tree) = REQ_TREES.0.get(idx as usize) else {
+            break false;
+        };
+        let next = match tree.req {
+            Requirement::Locked | Requirement::Not(_) => tree.or,
+            _ => tree.and,
+        };
+        match next {
+            Some(n) => idx = n.get(),
+            None => break !matches!(tree.req, Requirement::Locked | Requirement::Not(_)),
+        }
+    }
+}
+
 /// Generate a new random graph that looks vaguely like our randomizer world model will. In
 /// a library we'd deserialize and process a plaintext model at compile time instead.
 fn new_random() -> (
     [OptionNonZeroWrapper; NUM_VERTICES_PADDED],
-    [NodeData; NUM_VERTICES_PADDED],
     [OptionNonZeroWrapper; NUM_EDGES_PADDED],
     [u16; NUM_EDGES_PADDED],
 ) {
     let dist = WeightedIndex::new(WEIGHTS).unwrap();
     let mut fill_done = false;
     let mut node_pointers = [OptionNonZeroWrapper::DEFAULT; NUM_VERTICES_PADDED];
-    let node_data = [NodeData::DEFAULT; NUM_VERTICES_PADDED];
     let mut edge_pointers = [OptionNonZeroWrapper::DEFAULT; NUM_EDGES_PADDED];
     let mut edge_data = [0; NUM_EDGES_PADDED];
 
@@ -225,7 +629,7 @@ fn new_random() -> (
             .filter(|x| x.0 as usize == i)
             .copied()
             .collect();
-        assert!(these_edges.len() <= 13);
+        assert!(these_edges.len() <= MAX_OUT_DEGREE);
         *node_pointer = match these_edges.is_empty() {
             false => OptionNonZeroWrapper(Some(NonZeroU16::new(edge_cursor).unwrap())),
             true => OptionNonZeroWrapper(None),
@@ -309,7 +713,152 @@ fn new_random() -> (
         edge_data[idx] = 6u16;
     }
 
-    (node_pointers, node_data, edge_pointers, edge_data)
+    (node_pointers, edge_pointers, edge_data)
+}
+
+/// Give every node a type and generate the "wide" metadata tables each node's `data_index` points
+/// into. This uses its own seeded RNG so changes here don't perturb the structure of the graph.
+fn new_node_metadata() -> (
+    [NodeData; NUM_VERTICES_PADDED],
+    Vec<PlaceNodeData>,
+    Vec<ItemNodeData>,
+    Vec<DoorNodeData>,
+) {
+    let mut rng = ChaCha20Rng::seed_from_u64(0x2B7E151628AED2A6);
+    let mut node_data = [NodeData::DEFAULT; NUM_VERTICES_PADDED];
+    // The 0th place is shared by the zero index and padding nodes, which aren't part of the
+    // graph and have no region.
+    let mut place_data = vec![PlaceNodeData {
+        name: "terminal".to_string(),
+        region: 0,
+    }];
+    let mut item_data: Vec<ItemNodeData> = Vec::new();
+    let mut door_data: Vec<DoorNodeData> = Vec::new();
+
+    for (i, data) in node_data
+        .iter_mut()
+        .enumerate()
+        .take(NUM_VERTICES + 1)
+        .skip(1)
+    {
+        let region = ((i - 1) / REGION_SIZE) as u16 + 1;
+        let roll = rng.gen_range(0..100);
+        // The root is always a place.
+        if i != 1 && roll < ITEM_NODE_PERCENT {
+            *data = NodeData {
+                node_type: NodeType::Item,
+                data_index: item_data.len() as u16,
+            };
+            item_data.push(ItemNodeData {
+                name: format!("item_{}", i),
+                region,
+                vanilla_item: Item::Rupees,
+            });
+        } else if i != 1 && roll < ITEM_NODE_PERCENT + DOOR_NODE_PERCENT {
+            let flags = match rng.gen_range(0..100) {
+                0..=69 => DoorFlags::NONE,
+                70..=84 => DoorFlags::SMALL_KEY,
+                85..=89 => DoorFlags::BIG_KEY,
+                _ => DoorFlags::BOMBABLE,
+            };
+            *data = NodeData {
+                node_type: NodeType::Door,
+                data_index: door_data.len() as u16,
+            };
+            door_data.push(DoorNodeData {
+                name: format!("door_{}", i),
+                region,
+                flags,
+            });
+        } else {
+            *data = NodeData {
+                node_type: NodeType::Place,
+                data_index: place_data.len() as u16,
+            };
+            place_data.push(PlaceNodeData {
+                name: format!("place_{}", i),
+                region,
+            });
+        }
+    }
+
+    // Every progression item gets a single, distinct vanilla location. Everything else is junk.
+    for item in [Item::Boots, Item::Gloves, Item::Flute, Item::Hammer] {
+        loop {
+            let idx = rng.gen_range(0..item_data.len());
+            if let Item::Rupees = item_data[idx].vanilla_item {
+                item_data[idx].vanilla_item = item;
+                break;
+            }
+        }
+    }
+
+    (node_data, place_data, item_data, door_data)
+}
+
+/// Map every node to the id of the region it belongs to. This mirrors the region in each node's
+/// wide metadata so a traversal can group nodes without looking at the wide tables at all. The
+/// zero index and padding nodes get region 0.
+fn new_region_of() -> [u16; NUM_VERTICES_PADDED] {
+    let mut region_of = [0u16; NUM_VERTICES_PADDED];
+    for (i, region) in region_of
+        .iter_mut()
+        .enumerate()
+        .take(NUM_VERTICES + 1)
+        .skip(1)
+    {
+        *region = ((i - 1) / REGION_SIZE) as u16 + 1;
+    }
+
+    region_of
+}
+
+/// Give every edge a kind and a traversal weight. Edges into or out of a door are door
+/// transitions, and a few of the rest become warps or logical shortcuts. Like the node metadata
+/// this has its own seeded RNG.
+fn new_edge_metadata(
+    node_ptrs: &[OptionNonZeroWrapper],
+    edge_ptrs: &[OptionNonZeroWrapper],
+    node_data: &[NodeData],
+) -> ([EdgeType; NUM_EDGES_PADDED], [u8; NUM_EDGES_PADDED]) {
+    let mut rng = ChaCha20Rng::seed_from_u64(0x9E3779B97F4A7C15);
+    let mut edge_kinds = [EdgeType::Normal; NUM_EDGES_PADDED];
+    let mut edge_weights = [1u8; NUM_EDGES_PADDED];
+    let is_door = |n: usize| matches!(node_data[n].node_type, NodeType::Door);
+
+    for src in 1..=NUM_VERTICES {
+        let start = node_ptrs[src].0.map_or(1, u16::from) as usize;
+        let end = node_ptrs[src + 1].0.map_or(1, u16::from) as usize;
+        for edge in start..end {
+            let dest = edge_ptrs[edge].0.map_or(1, u16::from) as usize;
+            let (kind, weight) = match is_door(src) || is_door(dest) {
+                true => (EdgeType::Door, 2),
+                false => match rng.gen_range(0..100) {
+                    r if r < WARP_EDGE_PERCENT => (EdgeType::Warp, 1),
+                    r if r < WARP_EDGE_PERCENT + SHORTCUT_EDGE_PERCENT => (EdgeType::Shortcut, 1),
+                    _ => (EdgeType::Normal, rng.gen_range(1..=3)),
+                },
+            };
+            edge_kinds[edge] = kind;
+            edge_weights[edge] = weight;
+        }
+    }
+
+    (edge_kinds, edge_weights)
+}
+
+/// Give a few of the edges that have a requirement a trick that opens them anyway, as a bitfield
+/// indexed like `TRICK_NAMES`. Edges that are already open never need one.
+fn new_edge_tricks(edge_data: &[u16]) -> [u8; NUM_EDGES_PADDED] {
+    let mut rng = ChaCha20Rng::seed_from_u64(0x2545F4914F6CDD1D);
+    let mut edge_tricks = [0u8; NUM_EDGES_PADDED];
+    for edge in 1..=NUM_EDGES {
+        if edge_data[edge] != 0 && rng.gen_range(0..100) < TRICK_EDGE_PERCENT {
+            edge_tricks[edge] = 1 << rng.gen_range(0..TRICK_NAMES.len());
+        }
+    }
+
+    edge_tricks
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -320,45 +869,95 @@ impl OptionNonZeroWrapper {
 }
 
 impl std::fmt::Display for OptionNonZeroWrapper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "nonzero({})", self.0.map_or(1u16, u16::from))
+    }
+}
+
+impl std::fmt::Display for NodeData {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "unsafe {{ NonZeroU16::new_unchecked({}) }}",
-            self.0.map_or(1u16, u16::from)
+            "NodeData {{ node_type: {}, data_index: {} }}",
+            self.node_type, self.data_index
         )
     }
 }
 
-pub struct NodeData {
-    pub node_type: NodeType,
-    pub data_index: u16,
+pub struct PlaceNodeData {
+    pub name: String,
+    pub region: u16,
 }
 
-impl NodeData {
-    pub const DEFAULT: NodeData = NodeData::default();
+impl std::fmt::Display for PlaceNodeData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "PlaceNodeData {{ name: \"{}\", region: {} }}",
+            self.name, self.region
+        )
+    }
+}
 
-    pub const fn default() -> Self {
-        NodeData {
-            node_type: NodeType::Place,
-            data_index: 0u16,
-        }
+pub struct ItemNodeData {
+    pub name: String,
+    pub region: u16,
+    pub vanilla_item: Item,
+}
+
+impl std::fmt::Display for ItemNodeData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ItemNodeData {{ name: \"{}\", region: {}, vanilla_item: {} }}",
+            self.name, self.region, self.vanilla_item
+        )
     }
 }
 
-impl std::fmt::Display for NodeData {
+pub struct DoorNodeData {
+    pub name: String,
+    pub region: u16,
+    pub flags: DoorFlags,
+}
+
+impl std::fmt::Display for DoorNodeData {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "NodeData {{ node_type: {}, data_index: {} }}",
-            self.node_type, self.data_index
+            "DoorNodeData {{ name: \"{}\", region: {}, flags: {} }}",
+            self.name, self.region, self.flags
         )
     }
 }
 
-pub enum NodeType {
-    Place,
-    Item,
-    Door,
+impl std::fmt::Display for DoorFlags {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "DoorFlags({})", self.0)
+    }
+}
+
+impl std::fmt::Display for Item {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Item::Boots => write!(f, "Item::Boots"),
+            Item::Gloves => write!(f, "Item::Gloves"),
+            Item::Flute => write!(f, "Item::Flute"),
+            Item::Hammer => write!(f, "Item::Hammer"),
+            Item::Rupees => write!(f, "Item::Rupees"),
+        }
+    }
+}
+
+impl std::fmt::Display for EdgeType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EdgeType::Normal => write!(f, "EdgeType::Normal"),
+            EdgeType::Door => write!(f, "EdgeType::Door"),
+            EdgeType::Warp => write!(f, "EdgeType::Warp"),
+            EdgeType::Shortcut => write!(f, "EdgeType::Shortcut"),
+        }
+    }
 }
 
 impl std::fmt::Display for NodeType {
@@ -371,9 +970,26 @@ impl std::fmt::Display for NodeType {
     }
 }
 
-struct ArrayFormatter<T, const N: usize>([T; N]);
+impl std::fmt::Display for Requirement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Requirement::Open => write!(f, "Open"),
+            Requirement::Boots => write!(f, "Boots"),
+            Requirement::Gloves => write!(f, "Gloves"),
+            Requirement::Flute => write!(f, "Flute"),
+            Requirement::Hammer => write!(f, "Hammer"),
+            Requirement::Locked => write!(f, "Locked"),
+            Requirement::Tier(tier) => write!(f, "Tier(Tier::{:?})", tier),
+            Requirement::Setting(flag) => write!(f, "Setting(SettingFlag::{:?})", flag),
+            Requirement::Event(event) => write!(f, "Event(EventId::{:?})", event),
+            Requirement::Not(negated) => write!(f, "Not(Negatable::{})", negated.requirement()),
+        }
+    }
+}
 
-impl<T, const N: usize> std::fmt::Display for ArrayFormatter<T, N>
+struct ArrayFormatter<'a, T>(&'a [T]);
+
+impl<T> std::fmt::Display for ArrayFormatter<'_, T>
 where
     T: std::fmt::Display,
 {
@@ -381,7 +997,7 @@ where
         let sep = ", ";
         let mut s = "[".to_string();
 
-        for n in &self.0 {
+        for n in self.0 {
             s.push_str(&n.to_string());
             if !std::ptr::eq(n, self.0.last().unwrap()) {
                 s.push_str(sep);
@@ -391,8 +1007,3 @@ where
         write!(f, "{}", s)
     }
 }
-
-const NUM_VERTICES: usize = 20_000;
-const NUM_EDGES: usize = (NUM_VERTICES * 2) + (NUM_VERTICES >> 2) + 500;
-const NUM_VERTICES_PADDED: usize = u16::MAX as usize + 1;
-const NUM_EDGES_PADDED: usize = u16::MAX as usize + 1;
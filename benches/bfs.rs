@@ -2,7 +2,8 @@ use std::num::NonZeroU16;
 
 use sparsegraph::{
     bfs_iter::BfsQueue,
-    graph::{new_static_graph, new_static_graph_open},
+    graph::{new_static_graph, new_static_graph_open, StampedVisited},
+    logic::CollectionState,
 };
 
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
@@ -33,12 +34,31 @@ fn bfs_bench(c: &mut Criterion) {
 
     let bfs_iter_check_visited = graph.bfs_iter();
     c.bench_function("BFS Check Node Visited", |b| {
-        b.iter(|| bfs_iter_check_visited.visited.check_visited(1))
+        b.iter(|| bfs_iter_check_visited.core.visited.check_visited(1))
+    });
+
+    // Small searches from a walker we keep around, where clearing the visited nodes between
+    // searches is a big part of the work.
+    let mut bfs_iter_cleared = graph.bfs_iter();
+    c.bench_function("BFS Clear And Search 16 Nodes (VisitedCache)", |b| {
+        b.iter(|| {
+            bfs_iter_cleared.clear();
+            bfs_iter_cleared.by_ref().take(black_box(16)).count()
+        })
+    });
+
+    let mut bfs_iter_stamped =
+        graph.bfs_iter_with_visited(CollectionState::default(), <StampedVisited>::new());
+    c.bench_function("BFS Clear And Search 16 Nodes (StampedVisited)", |b| {
+        b.iter(|| {
+            bfs_iter_stamped.clear();
+            bfs_iter_stamped.by_ref().take(black_box(16)).count()
+        })
     });
 
     c.bench_function("BFS Queue Push and Pop", |b| {
         b.iter_batched_ref(
-            || BfsQueue::new(),
+            BfsQueue::new,
             |bfs_queue| {
                 push_and_pop(bfs_queue);
             },
@@ -3,6 +3,7 @@ use std::num::NonZeroU16;
 use sparsegraph::{
     dfs_iter::DfsStack,
     graph::{new_static_graph, new_static_graph_open},
+    logic::{EvaluateLogic, ReqIndex},
 };
 
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
@@ -33,12 +34,12 @@ fn dfs_bench(c: &mut Criterion) {
 
     let dfs_iter_check_visited = graph.dfs_iter();
     c.bench_function("DFS Check Node Visited", |b| {
-        b.iter(|| dfs_iter_check_visited.visited.check_visited(1))
+        b.iter(|| dfs_iter_check_visited.core.visited.check_visited(1))
     });
 
     c.bench_function("DFS Stack Push and Pop", |b| {
         b.iter_batched_ref(
-            || DfsStack::new(),
+            DfsStack::new,
             |dfs_stack| {
                 push_and_pop(dfs_stack);
             },
@@ -48,11 +49,11 @@ fn dfs_bench(c: &mut Criterion) {
 
     let dfs_iter_logic = graph.dfs_iter();
     c.bench_function("Eval Logic AND Requirement", |b| {
-        b.iter(|| dfs_iter_logic.eval_logic_tree(black_box(5)))
+        b.iter(|| dfs_iter_logic.eval_logic_tree(black_box(ReqIndex::new(5).unwrap())))
     });
 
     c.bench_function("Eval Logic OR Requirement", |b| {
-        b.iter(|| dfs_iter_logic.eval_logic_tree(black_box(2)))
+        b.iter(|| dfs_iter_logic.eval_logic_tree(black_box(ReqIndex::new(2).unwrap())))
     });
 }
 
@@ -0,0 +1,32 @@
+use sparsegraph::{graph::new_static_graph, logic::CollectionState};
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+// Search for targets that are visited progressively later in a BFS from the root to find where
+// lazily evaluating edges stops paying for itself compared to evaluating all of them up front.
+fn logic_mode_bench(c: &mut Criterion) {
+    let graph = new_static_graph();
+    let bfs_order: Vec<u16> = graph.bfs_iter().map(u16::from).collect();
+    let mut group = c.benchmark_group("Logic Mode Search");
+    for visited in [10, 100, 1_000, 5_000, 10_000, bfs_order.len()] {
+        let target = bfs_order[visited - 1];
+        group.bench_with_input(BenchmarkId::new("Eager", visited), &target, |b, &t| {
+            b.iter(|| {
+                graph
+                    .bfs_iter_with_state(CollectionState::default())
+                    .search(black_box(t))
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("Lazy", visited), &target, |b, &t| {
+            b.iter(|| {
+                graph
+                    .bfs_iter_lazy_with_state(CollectionState::default())
+                    .search(black_box(t))
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, logic_mode_bench);
+criterion_main!(benches);
@@ -0,0 +1,30 @@
+use sparsegraph::{graph::new_static_graph, logic::CollectionState, reachability::Reachability};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+fn reachability_bench(c: &mut Criterion) {
+    let graph = new_static_graph();
+    // Roughly the number of locations a fill loop checks after every placement.
+    let locations: Vec<u16> = (1..=20_000u16)
+        .filter(|&n| graph.item_data(n).is_some())
+        .take(200)
+        .collect();
+
+    c.bench_function("Batch Reachability 200 Locations", |b| {
+        b.iter(|| Reachability::batch(&graph, &CollectionState::default(), black_box(&locations)))
+    });
+
+    c.bench_function("Repeated Search 200 Locations", |b| {
+        b.iter(|| {
+            let mut dfs_iter = graph.dfs_iter();
+            locations
+                .iter()
+                .map(|&l| dfs_iter.search(black_box(l)))
+                .filter(|&r| r)
+                .count()
+        })
+    });
+}
+
+criterion_group!(benches, reachability_bench);
+criterion_main!(benches);
@@ -0,0 +1,32 @@
+use sparsegraph::{
+    bench_support::{assumed_fill, item_pool, reachability_sweep, state_changes, tracker_workload},
+    graph::new_static_graph,
+    scratch::TraversalScratch,
+};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+// End-to-end workloads rather than single searches. The scenarios themselves live in
+// sparsegraph::bench_support so other world models can run the same ones.
+fn scenarios_bench(c: &mut Criterion) {
+    let graph = new_static_graph();
+    let states = state_changes();
+    let pool = item_pool(200);
+    let mut group = c.benchmark_group("Scenarios");
+    group.sample_size(20);
+
+    let mut scratch = TraversalScratch::new();
+    group.bench_function("Full Reachability Sweep Per State Change", |b| {
+        b.iter(|| reachability_sweep(&graph, black_box(&states), &mut scratch))
+    });
+    group.bench_function("Tracker Session 1000 Iterations", |b| {
+        b.iter(|| tracker_workload(&graph, 1000, black_box(7)))
+    });
+    group.bench_function("Assumed Fill 200 Items", |b| {
+        b.iter(|| assumed_fill(&graph, black_box(&pool), 7))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, scenarios_bench);
+criterion_main!(benches);
@@ -1,6 +1,10 @@
 use std::num::NonZeroU16;
 
-use sparsegraph::graph::new_static_graph_open;
+use sparsegraph::{
+    constants::ACCESS_BITFIELD_LEN,
+    graph::{new_static_graph, new_static_graph_open, static_graph_req_masks, AccessCache},
+    logic::CollectionState,
+};
 
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 
@@ -15,5 +19,20 @@ fn get_neighbors_out(c: &mut Criterion) {
     });
 }
 
-criterion_group!(static_graph, get_neighbors_out);
+fn logical_access(c: &mut Criterion) {
+    let graph = new_static_graph();
+    let mut dfs_iter = graph.dfs_iter();
+    c.bench_function("Evaluate Logical Access", |b| {
+        b.iter(|| dfs_iter.core.evaluate_logical_access())
+    });
+
+    let state = CollectionState::default();
+    let mut access = AccessCache::<ACCESS_BITFIELD_LEN>::new();
+    let masks = static_graph_req_masks();
+    c.bench_function("Rebuild Access From Masks", |b| {
+        b.iter(|| access.rebuild_from_masks(masks, &graph.req_memo(black_box(&state))))
+    });
+}
+
+criterion_group!(static_graph, get_neighbors_out, logical_access);
 criterion_main!(static_graph);
@@ -0,0 +1,316 @@
+//! A proc-macro front-end to sparsegraph's codegen so downstream crates can compile their own
+//! world model without copying our build script and its duplicated type definitions.
+//!
+//! ```ignore
+//! use sparsegraph::{constants::*, graph::{ModelTables, StaticGraph}, static_graph};
+//!
+//! const MODEL: ModelTables = static_graph! { include = "model/example.model" };
+//! let graph = StaticGraph::<NUM_VERTICES_PADDED, NUM_EDGES_PADDED>::from_tables(&MODEL);
+//! ```
+//!
+//! Include paths are relative to the calling crate's manifest directory. A model can also be
+//! written inline with `static_graph! { model = "..." }`.
+//!
+//! # Model format
+//!
+//! One entry per line, with `#` starting a comment. Nodes are numbered in the order they're
+//! declared, starting at 1, so the first node is the root.
+//!
+//! ```text
+//! place <name> <region>
+//! item <name> <region> <boots|gloves|flute|hammer|rupees>
+//! door <name> <region> [small_key|big_key|bombable]
+//! edge <from> <to> [req=<requirement>] [kind=<normal|door|warp|shortcut>] [weight=<weight>]
+//!      [trick=<name>]...
+//! ```
+//!
+//! Requirements are either an index into the library's `REQ_CONTAINER` or its name: `open`,
+//! `locked`, `boots_or_hammer`, `hammer`, `gloves`, `gloves_and_hammer`, `flute`, the logic
+//! tiers `hard` and `glitched`, `gloves_or_hard_hammer`, `hammer_and_hard`, the settings
+//! `swordless` and `keysanity` or the events `agahnim` and `crystal_switch`. Edges are open by
+//! default, and like the generated model, edges touching a door are door transitions with a
+//! weight of 2 unless told otherwise. Each `trick=` option names a trick that opens the edge
+//! whatever its requirement, up to 8 distinct tricks per model.
+//!
+//! The model goes through the same validation passes as our build script and every problem is
+//! reported as a compile error naming the node it's about.
+
+extern crate proc_macro;
+
+#[allow(dead_code)]
+#[path = "../../src/constants.rs"]
+mod constants;
+mod lint;
+mod model;
+#[allow(dead_code)]
+#[path = "../../src/types.rs"]
+mod types;
+
+use proc_macro2::{Literal, Span, TokenStream};
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    Ident, LitStr, Token,
+};
+
+use model::{Model, NodeKind};
+
+enum Source {
+    Include(LitStr),
+    Inline(LitStr),
+}
+
+impl Parse for Source {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key: Ident = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let value: LitStr = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+        match key.to_string().as_str() {
+            "include" => Ok(Source::Include(value)),
+            "model" => Ok(Source::Inline(value)),
+            _ => Err(syn::Error::new(
+                key.span(),
+                "expected `include = \"path\"` or `model = \"...\"`",
+            )),
+        }
+    }
+}
+
+/// Compile a plain text world model into a `sparsegraph::graph::ModelTables` constant. See the
+/// crate docs for the model format.
+#[proc_macro]
+pub fn static_graph(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let source = syn::parse_macro_input!(input as Source);
+    expand(source)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+fn expand(source: Source) -> syn::Result<TokenStream> {
+    let (text, span, tracked) = match &source {
+        Source::Include(path) => {
+            let root = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+            let full = std::path::Path::new(&root).join(path.value());
+            let text = std::fs::read_to_string(&full).map_err(|e| {
+                syn::Error::new(
+                    path.span(),
+                    format!("couldn't read {}: {}", full.display(), e),
+                )
+            })?;
+            // Rebuild whenever the model changes.
+            let full = full.to_string_lossy().into_owned();
+            let tracked = quote! { const _: &str = include_str!(#full); };
+            (text, path.span(), tracked)
+        }
+        Source::Inline(model) => (model.value(), model.span(), TokenStream::new()),
+    };
+    let model = Model::parse(&text).map_err(|e| syn::Error::new(span, e))?;
+    let errors = lint::lint(&model);
+    if !errors.is_empty() {
+        return Ok(quote! {
+            { #(::core::compile_error!(#errors));* }
+        });
+    }
+
+    Ok(codegen(&model, tracked))
+}
+
+// Lay the model out the same way build.rs lays out gen.rs, only without the padding, which
+// `StaticGraph::from_tables` fills in.
+fn codegen(model: &Model, tracked: TokenStream) -> TokenStream {
+    let node_count = model.nodes.len() as u16;
+    let edge_count = model.edges.len() as u16;
+
+    // Index 0 is the terminal node, which shares its (empty) edge slice with the root.
+    let mut node_pointers = vec![1u16];
+    for node in 1..=node_count {
+        node_pointers.push(model.edges_out_of(node).next().map_or(0, |(e, _)| e) as u16);
+    }
+    node_pointers.push(edge_count + 1);
+    // Nodes without edges point at the next node's edges so their slice is empty.
+    for i in (1..=node_count as usize).rev() {
+        if node_pointers[i] == 0 {
+            node_pointers[i] = node_pointers[i + 1];
+        }
+    }
+    let edge_pointers = std::iter::once(1).chain(model.edges.iter().map(|e| e.to));
+    let edge_data = std::iter::once(0).chain(model.edges.iter().map(|e| e.req));
+    let edge_kinds = std::iter::once("Normal".to_string())
+        .chain(model.edges.iter().map(|e| format!("{:?}", e.kind)))
+        .map(|k| Ident::new(&k, Span::call_site()));
+    let edge_weights = std::iter::once(1).chain(model.edges.iter().map(|e| e.weight));
+    let edge_tricks = std::iter::once(0u8).chain(model.edges.iter().map(|e| e.tricks));
+    let tricks = &model.tricks;
+    let region_of = std::iter::once(0).chain(model.nodes.iter().map(|n| n.region));
+
+    // The 0th place is the terminal node's, like in gen.rs.
+    let mut places = vec![quote! {
+        ::sparsegraph::graph::PlaceNodeData { name: "terminal", region: 0 }
+    }];
+    let mut items = Vec::new();
+    let mut doors = Vec::new();
+    let mut node_data = vec![node_data_tokens("Place", 0)];
+    for node in &model.nodes {
+        let (name, region) = (&node.name, node.region);
+        let data = match node.kind {
+            NodeKind::Place => {
+                places.push(quote! {
+                    ::sparsegraph::graph::PlaceNodeData { name: #name, region: #region }
+                });
+                node_data_tokens("Place", places.len() - 1)
+            }
+            NodeKind::Item(item) => {
+                let item = Ident::new(&format!("{:?}", item), Span::call_site());
+                items.push(quote! {
+                    ::sparsegraph::graph::ItemNodeData {
+                        name: #name,
+                        region: #region,
+                        vanilla_item: ::sparsegraph::logic::Item::#item,
+                    }
+                });
+                node_data_tokens("Item", items.len() - 1)
+            }
+            NodeKind::Door(flags) => {
+                let (_, flag, _) = model::DOOR_FLAGS
+                    .iter()
+                    .find(|(.., f)| *f == flags)
+                    .unwrap();
+                let flag = Ident::new(flag, Span::call_site());
+                doors.push(quote! {
+                    ::sparsegraph::graph::DoorNodeData {
+                        name: #name,
+                        region: #region,
+                        flags: ::sparsegraph::graph::DoorFlags::#flag,
+                    }
+                });
+                node_data_tokens("Door", doors.len() - 1)
+            }
+        };
+        node_data.push(data);
+    }
+
+    quote! {
+        {
+            #tracked
+            ::sparsegraph::graph::ModelTables {
+                node_pointers: &[#(#node_pointers),*],
+                node_data: &[#(#node_data),*],
+                edge_pointers: &[#(#edge_pointers),*],
+                edge_data: &::sparsegraph::logic::ReqIndex::checked_array(&[#(#edge_data),*]),
+                edge_kinds: &[#(::sparsegraph::graph::EdgeType::#edge_kinds),*],
+                edge_weights: &[#(#edge_weights),*],
+                edge_tricks: &[#(#edge_tricks),*],
+                tricks: &[#(#tricks),*],
+                place_data: &[#(#places),*],
+                item_data: &[#(#items),*],
+                door_data: &[#(#doors),*],
+                region_of: &[#(#region_of),*],
+                node_count: #node_count,
+                edge_count: #edge_count,
+            }
+        }
+    }
+}
+
+fn node_data_tokens(node_type: &str, data_index: usize) -> TokenStream {
+    let node_type = Ident::new(node_type, Span::call_site());
+    let data_index = Literal::u16_unsuffixed(data_index as u16);
+    quote! {
+        ::sparsegraph::graph::NodeData {
+            node_type: ::sparsegraph::graph::NodeType::#node_type,
+            data_index: #data_index,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODEL: &str = "
+        # A tiny dungeon.
+        place entrance 1
+        door front_door 1 small_key
+        place hall 2
+        item chest 2 hammer
+        item boss 2 rupees
+
+        edge entrance front_door
+        edge front_door hall
+        edge hall front_door kind=door weight=3
+        edge hall chest trick=hover trick=bomb_jump
+        edge hall boss req=hammer
+        edge entrance hall req=locked trick=bomb_jump
+    ";
+
+    #[test]
+    fn parse_and_lint() {
+        let model = Model::parse(MODEL).unwrap();
+        assert_eq!(model.nodes.len(), 5);
+        assert_eq!(
+            model.nodes[1].kind,
+            NodeKind::Door(types::DoorFlags::SMALL_KEY)
+        );
+        // Grouped by source in the order they were written.
+        let from: Vec<u16> = model.edges.iter().map(|e| e.from).collect();
+        assert_eq!(from, [1, 1, 2, 3, 3, 3]);
+        assert_eq!(model.edges[1].req, 1);
+        assert_eq!(model.edges[0].weight, 2);
+        assert_eq!(model.edges[3].weight, 3);
+        assert_eq!(model.edges[5].req, 3);
+        assert_eq!(model.tricks, ["hover", "bomb_jump"]);
+        assert_eq!(model.edges[1].tricks, 0b10);
+        assert_eq!(model.edges[4].tricks, 0b11);
+        assert!(lint::lint(&model).is_empty());
+
+        assert!(Model::parse("place a 1\nedge a b").is_err());
+        assert!(Model::parse("item a 1 sword").is_err());
+        assert!(Model::parse("place a 1\nplace a 2").is_err());
+        assert!(Model::parse("place a 1\nedge a a req=everything").is_err());
+        let too_many: Vec<String> = (0..9).map(|i| format!("trick=t{}", i)).collect();
+        assert!(Model::parse(&format!("place a 1\nedge a a {}", too_many.join(" "))).is_err());
+
+        let broken = Model::parse(
+            "place root 1
+             place island 1
+             item chest 1 rupees
+             door door 1
+             edge root door
+             edge root root req=flute
+             edge root island req=locked
+             edge root root
+             edge root door req=16",
+        )
+        .unwrap();
+        let errors = lint::lint(&broken);
+        let expected = [
+            "edge 5 from node 1 (root) to node 4 (door) uses undefined requirement tree 16",
+            "requirement tree 6 requires Flute which no item location provides",
+            "node 1 (root) has more than one edge to node 1 (root)",
+            "node 1 (root) has more than one edge to node 4 (door)",
+            "node 2 (island) is unreachable with every item",
+            "node 3 (chest) is unreachable with every item",
+            "item location node 3 (chest) has no incoming edges",
+            "door node 4 (door) doesn't lead anywhere",
+        ];
+        assert_eq!(errors, expected);
+    }
+
+    #[test]
+    fn codegen_layout() {
+        let model = Model::parse(MODEL).unwrap();
+        let tokens = codegen(&model, TokenStream::new()).to_string();
+        // The terminal node and the root share the root's edges, a node without edges gets an
+        // empty slice and the pointer past the last node ends the edge array.
+        assert!(
+            tokens.contains("node_pointers : & [1u16 , 1u16 , 3u16 , 4u16 , 7u16 , 7u16 , 7u16]")
+        );
+        assert!(
+            tokens.contains("edge_pointers : & [1u16 , 2u16 , 3u16 , 3u16 , 2u16 , 4u16 , 5u16]")
+        );
+        assert!(tokens.contains("name : \"terminal\""));
+        assert!(tokens.contains("DoorFlags :: SMALL_KEY"));
+        assert!(tokens.contains("tricks : & [\"hover\" , \"bomb_jump\"]"));
+    }
+}
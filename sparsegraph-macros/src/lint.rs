@@ -0,0 +1,181 @@
+// The same validation passes build.rs runs on the generated model, over a parsed model instead.
+// Proc macros can't emit warnings on stable so everything here is an error.
+
+use std::collections::HashSet;
+
+use crate::{
+    constants::MAX_OUT_DEGREE,
+    model::{Model, NodeKind},
+    types::{Requirement, REQ_CONTAINER_LEN, REQ_TREES},
+};
+
+/// Run every validation pass and return one message per problem.
+pub(crate) fn lint(model: &Model) -> Vec<String> {
+    let mut errors = Vec::new();
+    errors.extend(lint_requirements(model));
+    errors.extend(lint_duplicate_edges(model));
+    errors.extend(lint_out_degree(model));
+    errors.extend(lint_unreachable(model));
+    errors.extend(lint_orphaned_items(model));
+    errors.extend(lint_dangling_doors(model));
+
+    errors
+}
+
+fn node_count(model: &Model) -> u16 {
+    model.nodes.len() as u16
+}
+
+fn incoming(model: &Model) -> Vec<u16> {
+    let mut incoming = vec![0u16; model.nodes.len() + 1];
+    model
+        .edges
+        .iter()
+        .for_each(|e| incoming[e.to as usize] += 1);
+
+    incoming
+}
+
+// Every edge has to use a requirement tree the library actually has, and every tree an edge uses
+// can only require items some location in the model provides.
+fn lint_requirements(model: &Model) -> Vec<String> {
+    let mut errors = Vec::new();
+    let mut used = HashSet::new();
+    for (i, edge) in model.edges.iter().enumerate() {
+        match (edge.req as usize) < REQ_CONTAINER_LEN {
+            true => {
+                used.insert(edge.req);
+            }
+            false => errors.push(format!(
+                "edge {} from {} to {} uses undefined requirement tree {}",
+                i + 1,
+                model.describe(edge.from),
+                model.describe(edge.to),
+                edge.req
+            )),
+        }
+    }
+    let mut used: Vec<u16> = used.into_iter().collect();
+    used.sort_unstable();
+    for tree in used {
+        let mut idx = Some(tree);
+        while let Some(node) = idx.map(|i| &REQ_TREES[i]) {
+            let missing = node
+                .req
+                .item()
+                .filter(|&item| !model.nodes.iter().any(|n| n.kind == NodeKind::Item(item)));
+            if let Some(item) = missing {
+                errors.push(format!(
+                    "requirement tree {} requires {:?} which no item location provides",
+                    tree, item
+                ));
+            }
+            idx = node.and.or(node.or).map(u16::from);
+        }
+    }
+
+    errors
+}
+
+fn lint_duplicate_edges(model: &Model) -> Vec<String> {
+    let mut errors = Vec::new();
+    for src in 1..=node_count(model) {
+        let mut seen = HashSet::new();
+        for (_, edge) in model.edges_out_of(src).filter(|(_, e)| !seen.insert(e.to)) {
+            errors.push(format!(
+                "{} has more than one edge to {}",
+                model.describe(src),
+                model.describe(edge.to)
+            ));
+        }
+    }
+
+    errors
+}
+
+fn lint_out_degree(model: &Model) -> Vec<String> {
+    (1..=node_count(model))
+        .map(|n| (n, model.edges_out_of(n).count()))
+        .filter(|&(_, degree)| degree > MAX_OUT_DEGREE)
+        .map(|(n, degree)| {
+            format!(
+                "{} has {} outgoing edges but at most {} are allowed",
+                model.describe(n),
+                degree,
+                MAX_OUT_DEGREE
+            )
+        })
+        .collect()
+}
+
+// Walk the model from the root with every item, which only leaves locked requirements unsatisfied.
+fn lint_unreachable(model: &Model) -> Vec<String> {
+    if model.nodes.is_empty() {
+        return Vec::new();
+    }
+    let mut visited = vec![false; model.nodes.len() + 1];
+    let mut stack = vec![1u16];
+    visited[1] = true;
+    while let Some(node) = stack.pop() {
+        for (_, edge) in model.edges_out_of(node) {
+            if !visited[edge.to as usize] && eval_all_items(edge.req) {
+                visited[edge.to as usize] = true;
+                stack.push(edge.to);
+            }
+        }
+    }
+
+    (1..=node_count(model))
+        .filter(|&n| !visited[n as usize])
+        .map(|n| format!("{} is unreachable with every item", model.describe(n)))
+        .collect()
+}
+
+fn lint_orphaned_items(model: &Model) -> Vec<String> {
+    let incoming = incoming(model);
+    (1..=node_count(model))
+        .filter(|&n| {
+            matches!(model.nodes[n as usize - 1].kind, NodeKind::Item(_))
+                && incoming[n as usize] == 0
+        })
+        .map(|n| format!("item location {} has no incoming edges", model.describe(n)))
+        .collect()
+}
+
+// Unlike build.rs we don't have to put up with randomly generated terminal doors, so a door that
+// doesn't lead anywhere is an error here too.
+fn lint_dangling_doors(model: &Model) -> Vec<String> {
+    let incoming = incoming(model);
+    let mut errors = Vec::new();
+    let doors = (1..=node_count(model))
+        .filter(|&n| matches!(model.nodes[n as usize - 1].kind, NodeKind::Door(_)));
+    for door in doors {
+        if incoming[door as usize] == 0 {
+            errors.push(format!("door {} can't be entered", model.describe(door)));
+        }
+        if model.edges_out_of(door).next().is_none() {
+            errors.push(format!(
+                "door {} doesn't lead anywhere",
+                model.describe(door)
+            ));
+        }
+    }
+
+    errors
+}
+
+fn eval_all_items(mut idx: u16) -> bool {
+    loop {
+        let Some(tree) = REQ_TREES.0.get(idx as usize) else {
+            break false;
+        };
+        let next = match tree.req {
+            Requirement::Locked | Requirement::Not(_) => tree.or,
+            _ => tree.and,
+        };
+        match next {
+            Some(n) => idx = n.get(),
+            None => break !matches!(tree.req, Requirement::Locked | Requirement::Not(_)),
+        }
+    }
+}
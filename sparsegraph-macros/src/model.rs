@@ -0,0 +1,263 @@
+// A parsed plain text world model. See the crate docs for the format.
+
+use crate::types::{DoorFlags, EdgeType, Item};
+
+fn parse_item(s: &str) -> Option<Item> {
+    match s {
+        "boots" => Some(Item::Boots),
+        "gloves" => Some(Item::Gloves),
+        "flute" => Some(Item::Flute),
+        "hammer" => Some(Item::Hammer),
+        "rupees" => Some(Item::Rupees),
+        _ => None,
+    }
+}
+
+// `(model keyword, DoorFlags constant, flags)` for every door flag.
+pub(crate) const DOOR_FLAGS: [(&str, &str, DoorFlags); 4] = [
+    ("none", "NONE", DoorFlags::NONE),
+    ("small_key", "SMALL_KEY", DoorFlags::SMALL_KEY),
+    ("big_key", "BIG_KEY", DoorFlags::BIG_KEY),
+    ("bombable", "BOMBABLE", DoorFlags::BOMBABLE),
+];
+
+fn parse_edge_type(s: &str) -> Option<EdgeType> {
+    match s {
+        "normal" => Some(EdgeType::Normal),
+        "door" => Some(EdgeType::Door),
+        "warp" => Some(EdgeType::Warp),
+        "shortcut" => Some(EdgeType::Shortcut),
+        _ => None,
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum NodeKind {
+    Place,
+    Item(Item),
+    Door(DoorFlags),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Node {
+    pub(crate) name: String,
+    pub(crate) region: u16,
+    pub(crate) kind: NodeKind,
+}
+
+/// An edge between two nodes, both given as graph indexes (i.e. starting at 1).
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Edge {
+    pub(crate) from: u16,
+    pub(crate) to: u16,
+    pub(crate) req: u16,
+    pub(crate) kind: EdgeType,
+    pub(crate) weight: u8,
+    /// Bit `i` set when `Model::tricks[i]` opens the edge.
+    pub(crate) tricks: u8,
+}
+
+// The names of the requirement trees in the library's `REQ_CONTAINER`, by index.
+pub(crate) const REQ_NAMES: [&str; 15] = [
+    "open",
+    "locked",
+    "boots_or_hammer",
+    "hammer",
+    "gloves",
+    "gloves_and_hammer",
+    "flute",
+    "hard",
+    "glitched",
+    "gloves_or_hard_hammer",
+    "hammer_and_hard",
+    "swordless",
+    "keysanity",
+    "agahnim",
+    "crystal_switch",
+];
+
+// The number of bits in the library's `TrickMask`.
+const MAX_TRICKS: usize = 8;
+
+// Leave room for the terminal node and edge at index 0 and the pointer past the last node.
+const MAX_NODES: usize = u16::MAX as usize - 1;
+const MAX_EDGES: usize = u16::MAX as usize - 1;
+
+/// A world model with its edges grouped by the node they leave from, which is the order they
+/// end up in the generated edge arrays.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Model {
+    /// Node `i` of the graph is `nodes[i - 1]`. The first node is the root.
+    pub(crate) nodes: Vec<Node>,
+    pub(crate) edges: Vec<Edge>,
+    /// Trick names in the order edges first mention them.
+    pub(crate) tricks: Vec<String>,
+}
+
+impl Model {
+    pub(crate) fn parse(text: &str) -> Result<Self, String> {
+        let mut model = Model::default();
+        let mut edge_lines: Vec<(usize, Vec<&str>)> = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line_no = i + 1;
+            let words: Vec<&str> = line
+                .split('#')
+                .next()
+                .unwrap_or_default()
+                .split_whitespace()
+                .collect();
+            let kind = match words.first() {
+                None => continue,
+                Some(&"place") => NodeKind::Place,
+                Some(&"item") => {
+                    let item = words.get(3).ok_or_else(|| {
+                        format!("line {}: item locations need a vanilla item", line_no)
+                    })?;
+                    NodeKind::Item(
+                        parse_item(item)
+                            .ok_or_else(|| format!("line {}: unknown item `{}`", line_no, item))?,
+                    )
+                }
+                Some(&"door") => {
+                    let flag = words.get(3).unwrap_or(&"none");
+                    let flags = DOOR_FLAGS.iter().find(|(keyword, ..)| keyword == flag);
+                    match flags {
+                        Some(&(_, _, flags)) => NodeKind::Door(flags),
+                        None => {
+                            return Err(format!("line {}: unknown door flag `{}`", line_no, flag))
+                        }
+                    }
+                }
+                // Edges can name nodes declared after them, so we resolve them once we've seen
+                // every node.
+                Some(&"edge") => {
+                    edge_lines.push((line_no, words));
+                    continue;
+                }
+                Some(other) => return Err(format!("line {}: unknown entry `{}`", line_no, other)),
+            };
+            let (name, region) = match (words.get(1), words.get(2)) {
+                (Some(name), Some(region)) => (name, region),
+                _ => return Err(format!("line {}: nodes need a name and a region", line_no)),
+            };
+            if model.nodes.iter().any(|n| n.name == *name) {
+                return Err(format!(
+                    "line {}: node `{}` is declared twice",
+                    line_no, name
+                ));
+            }
+            model.nodes.push(Node {
+                name: name.to_string(),
+                region: region
+                    .parse()
+                    .map_err(|_| format!("line {}: bad region `{}`", line_no, region))?,
+                kind,
+            });
+        }
+        if model.nodes.len() > MAX_NODES {
+            return Err(format!("models can have at most {} nodes", MAX_NODES));
+        }
+        if edge_lines.len() > MAX_EDGES {
+            return Err(format!("models can have at most {} edges", MAX_EDGES));
+        }
+
+        for (line_no, words) in edge_lines {
+            let edge = model
+                .parse_edge(&words[1..])
+                .map_err(|e| format!("line {}: {}", line_no, e))?;
+            model.edges.push(edge);
+        }
+        // Stable, so edges leaving the same node keep the order they were written in.
+        model.edges.sort_by_key(|e| e.from);
+
+        Ok(model)
+    }
+
+    // `<from> <to> [req=<requirement>] [kind=<kind>] [weight=<weight>] [trick=<name>]...`
+    fn parse_edge(&mut self, words: &[&str]) -> Result<Edge, String> {
+        let (from, to) = match words {
+            [from, to, ..] => (self.node_index(from)?, self.node_index(to)?),
+            _ => return Err("edges need a source and a destination".to_string()),
+        };
+        let is_door = |n: u16| matches!(self.nodes[n as usize - 1].kind, NodeKind::Door(_));
+        let mut edge = Edge {
+            from,
+            to,
+            req: 0,
+            kind: match is_door(from) || is_door(to) {
+                true => EdgeType::Door,
+                false => EdgeType::Normal,
+            },
+            weight: 0,
+            tricks: 0,
+        };
+        let mut weight = None;
+        for option in &words[2..] {
+            let (key, value) = option
+                .split_once('=')
+                .ok_or_else(|| format!("expected `key=value`, found `{}`", option))?;
+            match key {
+                "req" => {
+                    edge.req = match REQ_NAMES.iter().position(|&r| r == value) {
+                        Some(i) => i as u16,
+                        None => value
+                            .parse()
+                            .map_err(|_| format!("unknown requirement `{}`", value))?,
+                    }
+                }
+                "kind" => {
+                    edge.kind = parse_edge_type(value)
+                        .ok_or_else(|| format!("unknown edge kind `{}`", value))?
+                }
+                "weight" => {
+                    weight = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("bad weight `{}`", value))?,
+                    )
+                }
+                "trick" => {
+                    let i = match self.tricks.iter().position(|t| t == value) {
+                        Some(i) => i,
+                        None if self.tricks.len() < MAX_TRICKS => {
+                            self.tricks.push(value.to_string());
+                            self.tricks.len() - 1
+                        }
+                        None => {
+                            return Err(format!("models can have at most {} tricks", MAX_TRICKS))
+                        }
+                    };
+                    edge.tricks |= 1 << i;
+                }
+                _ => return Err(format!("unknown edge option `{}`", key)),
+            }
+        }
+        // Same defaults as the random generator: going through a door costs a little more.
+        edge.weight = weight.unwrap_or(match edge.kind {
+            EdgeType::Door => 2,
+            _ => 1,
+        });
+
+        Ok(edge)
+    }
+
+    fn node_index(&self, name: &str) -> Result<u16, String> {
+        match self.nodes.iter().position(|n| n.name == name) {
+            Some(i) => Ok(i as u16 + 1),
+            None => Err(format!("unknown node `{}`", name)),
+        }
+    }
+
+    /// A node's name along with its index, for error messages.
+    pub(crate) fn describe(&self, node: u16) -> String {
+        format!("node {} ({})", node, self.nodes[node as usize - 1].name)
+    }
+
+    /// `(edge index, edge)` for every edge leaving `node`. Edge indexes start at 1 like in the
+    /// generated arrays.
+    pub(crate) fn edges_out_of(&self, node: u16) -> impl Iterator<Item = (usize, &Edge)> + '_ {
+        let start = self.edges.partition_point(|e| e.from < node);
+        let end = self.edges.partition_point(|e| e.from <= node);
+        (start..end).map(|i| (i + 1, &self.edges[i]))
+    }
+}
@@ -0,0 +1,769 @@
+use alloc::{collections::VecDeque, vec, vec::Vec};
+use core::{fmt, num::NonZeroU16};
+
+use crate::{
+    constants::*,
+    graph::{StaticGraph, VisitedCache},
+    logic::{
+        non_monotone_nodes, CollectionState, EvaluateLogic, Item, ItemSet, ReqIndex, ReqMemo,
+        ReqTreeView, Requirement,
+    },
+    rng::SplitMix64,
+    tracker::TrackerSession,
+};
+
+/// An edge from a node the player can get back to safety from into one they can't.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct OneWayEdge {
+    pub src: u16,
+    pub edge: u16,
+    pub dest: u16,
+}
+
+/// Regions the player can enter under some collection state but never leave. See
+/// `find_soft_locks`.
+#[derive(Clone, Debug, Default)]
+pub struct SoftLockReport<'graph> {
+    /// Nodes reachable from the root from which no safe node is reachable.
+    pub trap_nodes: Vec<u16>,
+    /// The accessible edges leading into those nodes from nodes that can still get back.
+    pub one_way_edges: Vec<OneWayEdge>,
+    /// The closed edges out of those nodes that would get the player back if they were open,
+    /// along with what each of them needs.
+    pub blocked_exits: Vec<BlockedEdge<'graph>>,
+}
+
+impl SoftLockReport<'_> {
+    pub fn is_empty(&self) -> bool {
+        self.trap_nodes.is_empty()
+    }
+}
+
+/// Finds every node the player can reach from the root under `state` but can't get back to any of
+/// `safe_nodes` from (or the root if `safe_nodes` is empty), along with the one-way edges that
+/// lead into them and the closed edges that would lead back out. This is a forward sweep from the
+/// root and a backward sweep from the safe nodes over the same accessible edges.
+pub fn find_soft_locks<'graph, const M: usize, const N: usize, T>(
+    graph: &'graph StaticGraph<M, N, T>,
+    state: &CollectionState,
+    safe_nodes: &[u16],
+) -> SoftLockReport<'graph> {
+    let mut forward = graph.bfs_iter_with_state(state.clone());
+    forward.by_ref().for_each(drop);
+    let core = &forward.core;
+    let safe_nodes = match safe_nodes.is_empty() {
+        true => &[core.root][..],
+        false => safe_nodes,
+    };
+
+    let reverse = graph.reverse_adjacency();
+    let mut can_return =
+        VisitedCache::<VISITED_BITFIELD_LEN>::with_len(StaticGraph::<M, N, T>::VISITED_LEN);
+    let mut queue: VecDeque<u16> = safe_nodes.iter().copied().collect();
+    safe_nodes.iter().for_each(|&n| can_return.mark_visited(n));
+    while let Some(node) = queue.pop_front() {
+        reverse
+            .neighbors_in(node)
+            .filter(|&(_, edge)| core.edge_access.check_access(edge))
+            .for_each(|(src, _)| {
+                if !can_return.test_set_visited(src) {
+                    queue.push_back(src);
+                }
+            });
+    }
+
+    let mut report = SoftLockReport::default();
+    for node in 1..=graph.node_count {
+        if !core.visited.check_visited(node) || can_return.check_visited(node) {
+            continue;
+        }
+        report.trap_nodes.push(node);
+        reverse
+            .neighbors_in(node)
+            .filter(|&(src, edge)| {
+                core.edge_access.check_access(edge)
+                    && core.visited.check_visited(src)
+                    && can_return.check_visited(src)
+            })
+            .for_each(|(src, edge)| {
+                report.one_way_edges.push(OneWayEdge {
+                    src,
+                    edge,
+                    dest: node,
+                })
+            });
+        let (edge_pointers, edge_offset) = graph.get_neighbors_out(NonZeroU16::new(node));
+        for (i, &dest) in edge_pointers.iter().enumerate() {
+            let (edge, dest) = (edge_offset + i as u16, u16::from(dest));
+            if !core.edge_access.check_access(edge) && can_return.check_visited(dest) {
+                report.blocked_exits.push(BlockedEdge {
+                    src: node,
+                    edge,
+                    dest,
+                    req: graph.requirement_tree(graph.edge_data(edge).req),
+                });
+            }
+        }
+    }
+
+    report
+}
+
+/// An elementary cycle of accessible edges. See `find_cycles`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cycle {
+    /// The nodes around the cycle starting from the lowest index, each with an edge to the next
+    /// and the last with one back to the first.
+    pub nodes: Vec<u16>,
+    /// The edges between them, where `edges[i]` leaves `nodes[i]`.
+    pub edges: Vec<u16>,
+}
+
+/// Every elementary cycle of at most `max_len` edges that `state` opens all of, so model authors
+/// can check their loops and shortcuts exist and a one-way edge can be told apart from part of a
+/// loop. Cycles are found from their lowest node only, so each one is listed once, ordered by
+/// that node. Cycles through parallel edges are listed once per edge.
+///
+/// This is a depth-first search from every node bounded by `max_len`, so it's meant for short
+/// loops. The number of longer cycles in a well-connected graph grows very quickly.
+pub fn find_cycles<const M: usize, const N: usize, T>(
+    graph: &StaticGraph<M, N, T>,
+    state: &CollectionState,
+    max_len: usize,
+) -> Vec<Cycle> {
+    let memo = graph.req_memo(state);
+    let mut cycles = Vec::new();
+    for start in graph.nodes() {
+        let mut path = Cycle {
+            nodes: vec![start],
+            edges: Vec::new(),
+        };
+        extend_cycles(graph, &memo, max_len, &mut path, &mut cycles);
+    }
+
+    cycles
+}
+
+// Try every accessible edge out of the end of `path` that goes back to its start or on to a node
+// above the start that isn't on it yet.
+fn extend_cycles<const M: usize, const N: usize, T>(
+    graph: &StaticGraph<M, N, T>,
+    memo: &ReqMemo,
+    max_len: usize,
+    path: &mut Cycle,
+    cycles: &mut Vec<Cycle>,
+) {
+    if path.edges.len() == max_len {
+        return;
+    }
+    let (start, end) = (path.nodes[0], *path.nodes.last().unwrap());
+    let (edge_pointers, edge_offset) = graph.get_neighbors_out(NonZeroU16::new(end));
+    for (i, &n) in edge_pointers.iter().enumerate() {
+        let (edge, next) = (edge_offset + i as u16, u16::from(n));
+        if !memo.get(graph.edge_data(edge).req) {
+            continue;
+        }
+        path.edges.push(edge);
+        if next == start {
+            cycles.push(path.clone());
+        } else if next > start && !path.nodes.contains(&next) {
+            path.nodes.push(next);
+            extend_cycles(graph, memo, max_len, path, cycles);
+            path.nodes.pop();
+        }
+        path.edges.pop();
+    }
+}
+
+/// A closed edge out of the reachable part of the graph. See `Unreachability`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BlockedEdge<'graph> {
+    pub src: u16,
+    pub edge: u16,
+    pub dest: u16,
+    pub req: ReqTreeView<'graph>,
+}
+
+impl fmt::Display for BlockedEdge<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} -> {} via {} needs {}",
+            self.src, self.dest, self.edge, self.req
+        )
+    }
+}
+
+/// Why a node can't be reached under some collection state. See `explain_unreachable`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Unreachability<'graph> {
+    /// Every closed edge from a reachable node into one that could lead on to the target if
+    /// logic allowed it. Opening any of them gets us closer. If there are none the target isn't
+    /// connected to the root at all.
+    pub blocked_edges: Vec<BlockedEdge<'graph>>,
+    /// The items that would each make the target reachable if we collected just that one.
+    pub unlocking_items: Vec<Item>,
+}
+
+/// Explain why `target` isn't reachable from the root under `state`, or `None` if it is.
+///
+/// We search forward from the root under `state` and backward from the target ignoring logic.
+/// The edges between the two searches that `state` doesn't open are what's in the way.
+pub fn explain_unreachable<'graph, const M: usize, const N: usize, T>(
+    graph: &'graph StaticGraph<M, N, T>,
+    state: &CollectionState,
+    target: u16,
+) -> Option<Unreachability<'graph>> {
+    let mut forward = graph.bfs_iter_with_state(state.clone());
+    forward.by_ref().for_each(drop);
+    let reached = &forward.core.visited;
+    if reached.check_visited(target) {
+        return None;
+    }
+
+    let reverse = graph.reverse_adjacency();
+    let mut leads_to =
+        VisitedCache::<VISITED_BITFIELD_LEN>::with_len(StaticGraph::<M, N, T>::VISITED_LEN);
+    let mut queue = VecDeque::from([target]);
+    leads_to.mark_visited(target);
+    let mut blocked_edges = Vec::new();
+    while let Some(node) = queue.pop_front() {
+        for (src, edge) in reverse.neighbors_in(node) {
+            match reached.check_visited(src) {
+                // Reachable nodes only have closed edges into unreached ones.
+                true => blocked_edges.push(BlockedEdge {
+                    src,
+                    edge,
+                    dest: node,
+                    req: graph.requirement_tree(graph.edge_data(edge).req),
+                }),
+                false if !leads_to.test_set_visited(src) => queue.push_back(src),
+                false => (),
+            }
+        }
+    }
+    blocked_edges.sort_unstable_by_key(|e| e.edge);
+
+    let unlocking_items = [Item::Boots, Item::Gloves, Item::Flute, Item::Hammer]
+        .into_iter()
+        .filter(|&item| !state.has(item))
+        .filter(|&item| {
+            let mut with_item = state.clone();
+            with_item.set(item, true);
+            // Only worth a search if the item opens one of the blocked edges.
+            blocked_edges.iter().any(|e| e.req.eval(&with_item))
+                && graph.bfs_iter_with_state(with_item).search(target)
+        })
+        .collect();
+
+    Some(Unreachability {
+        blocked_edges,
+        unlocking_items,
+    })
+}
+
+/// How much more of the graph one missing item opens up. See `rank_key_items`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct KeyItem {
+    pub item: Item,
+    /// How many nodes collecting the item makes reachable.
+    pub new_nodes: u16,
+    /// How many of those are item locations.
+    pub new_checks: u16,
+}
+
+/// For every progression item missing from `state`, how many more nodes and checks we'd reach
+/// if we collected it, ranked by new checks and then new nodes, most first. Items that open
+/// nothing are still listed, at the end. Hints and fill heuristics both want to know which items
+/// matter most right now.
+///
+/// One tracker session does all the searching: we collect each item, count what it opened and
+/// undo it again, so each item only costs the search past the edges it opens. On a graph that
+/// isn't monotone an item can close off more than it opens, and then it counts as opening nothing.
+pub fn rank_key_items<const M: usize, const N: usize, T>(
+    graph: &StaticGraph<M, N, T>,
+    state: &CollectionState,
+) -> Vec<KeyItem> {
+    let mut session = TrackerSession::new(graph, state.clone());
+    let checks_before = session.reachable_checks().len();
+    let mut ranked: Vec<KeyItem> = [Item::Boots, Item::Gloves, Item::Flute, Item::Hammer]
+        .into_iter()
+        .filter(|&item| !state.has(item))
+        .map(|item| {
+            let new_nodes = session.collect(item);
+            let new_checks = session
+                .reachable_checks()
+                .len()
+                .saturating_sub(checks_before) as u16;
+            session.undo();
+            KeyItem {
+                item,
+                new_nodes,
+                new_checks,
+            }
+        })
+        .collect();
+    // Stable, so ties keep item order.
+    ranked.sort_by_key(|k| core::cmp::Reverse((k.new_checks, k.new_nodes)));
+
+    ranked
+}
+
+/// How spread out a graph is in hops, ignoring logic. See `metrics`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Metrics {
+    /// The most hops from any node to another it can reach.
+    pub diameter: u16,
+    /// The mean hops from a node to each other node it can reach, over every such pair.
+    pub average_path_length: f64,
+    /// Every node's eccentricity, the most hops to any node it can reach, indexed by node. Nodes
+    /// that reach nothing else have zero, and so does the terminal node.
+    pub eccentricity: Vec<u16>,
+    /// How many nodes we searched from. Every node unless the metrics were sampled.
+    pub sources: usize,
+}
+
+/// The diameter, average shortest path length and every node's eccentricity of `graph` with every
+/// edge open, so model authors can compare a world model's shape with the real game's pacing,
+/// e.g. that the furthest point isn't only a handful of rooms from the start.
+///
+/// This searches from every node, which is quadratic in the size of the graph. See
+/// `sampled_metrics` for an estimate on big graphs.
+pub fn metrics<const M: usize, const N: usize, T>(graph: &StaticGraph<M, N, T>) -> Metrics {
+    sampled_metrics(graph, graph.node_count as usize, 0)
+}
+
+/// Estimate `metrics` from searches out of `samples` nodes picked by `seed`. The average is over
+/// pairs starting at those nodes, and for every other node we search back from the samples to
+/// find how far it is from each of them, so eccentricities and the diameter are lower bounds that
+/// only the samples are sure to meet. Asking for as many samples as there are nodes gives the
+/// exact metrics.
+pub fn sampled_metrics<const M: usize, const N: usize, T>(
+    graph: &StaticGraph<M, N, T>,
+    samples: usize,
+    seed: u64,
+) -> Metrics {
+    let len = graph.node_count as usize + 1;
+    let mut sources: Vec<u16> = graph.nodes().collect();
+    let exact = samples >= sources.len();
+    if !exact {
+        SplitMix64::new(seed).shuffle(&mut sources);
+        sources.truncate(samples);
+    }
+    let reverse = graph.reverse_adjacency();
+    let mut eccentricity = vec![0u16; len];
+    let mut distances = vec![u16::MAX; len];
+    let mut queue = VecDeque::new();
+    let (mut total, mut pairs) = (0u64, 0u64);
+    for &source in sources.iter() {
+        hop_distances(source, &mut distances, &mut queue, |node| {
+            let (edge_pointers, _) = graph.get_neighbors_out(NonZeroU16::new(node));
+            edge_pointers.iter().map(|&n| u16::from(n))
+        });
+        for &d in distances.iter().filter(|&&d| d != u16::MAX && d != 0) {
+            eccentricity[source as usize] = eccentricity[source as usize].max(d);
+            total += d as u64;
+            pairs += 1;
+        }
+        // Searching from every node already gives every eccentricity exactly.
+        if exact {
+            continue;
+        }
+        hop_distances(source, &mut distances, &mut queue, |node| {
+            reverse.neighbors_in(node).map(|(src, _)| src)
+        });
+        for (node, &d) in distances.iter().enumerate().filter(|(_, &d)| d != u16::MAX) {
+            eccentricity[node] = eccentricity[node].max(d);
+        }
+    }
+
+    Metrics {
+        diameter: eccentricity.iter().copied().max().unwrap_or(0),
+        average_path_length: match pairs {
+            0 => 0.0,
+            _ => total as f64 / pairs as f64,
+        },
+        eccentricity,
+        sources: sources.len(),
+    }
+}
+
+// Fill `distances` with the hops from `from` to every node, or `u16::MAX` where there's no path,
+// with a breadth-first search along `neighbors`.
+fn hop_distances<I: Iterator<Item = u16>>(
+    from: u16,
+    distances: &mut [u16],
+    queue: &mut VecDeque<u16>,
+    mut neighbors: impl FnMut(u16) -> I,
+) {
+    distances.fill(u16::MAX);
+    distances[from as usize] = 0;
+    queue.push_back(from);
+    while let Some(node) = queue.pop_front() {
+        let next = distances[node as usize] + 1;
+        for n in neighbors(node) {
+            if distances[n as usize] == u16::MAX {
+                distances[n as usize] = next;
+                queue.push_back(n);
+            }
+        }
+    }
+}
+
+/// The checks that can't be reached without one item. See `item_gates`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ItemGate {
+    pub item: Item,
+    /// Every item location that's reachable under the state but not with the item withheld, in
+    /// node index order.
+    pub gated_checks: Vec<u16>,
+}
+
+/// For every progression item `state` has, the checks we'd lose if we withheld just that item,
+/// listed in item order. This is the "what does each item gate" table, worked out from the logic
+/// rather than written by hand, and an item gating nothing on its own is listed with no checks.
+///
+/// Like `rank_key_items` one tracker session does the searching, taking each item away and
+/// putting it back so each item only costs revalidating past the edges it closes.
+pub fn item_gates<const M: usize, const N: usize, T>(
+    graph: &StaticGraph<M, N, T>,
+    state: &CollectionState,
+) -> Vec<ItemGate> {
+    let mut session = TrackerSession::new(graph, state.clone());
+    let checks = session.reachable_checks();
+    [Item::Boots, Item::Gloves, Item::Flute, Item::Hammer]
+        .into_iter()
+        .filter(|&item| state.has(item))
+        .map(|item| {
+            session.uncollect(item);
+            let gated_checks = checks
+                .iter()
+                .copied()
+                .filter(|&n| !session.is_reachable(n))
+                .collect();
+            session.undo();
+            ItemGate { item, gated_checks }
+        })
+        .collect()
+}
+
+/// An edge that collecting something can close. See `find_non_monotone_edges`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct NonMonotoneEdge<'graph> {
+    pub src: u16,
+    pub edge: u16,
+    pub dest: u16,
+    /// The endpoints' names, empty for nodes without one.
+    pub src_name: &'static str,
+    pub dest_name: &'static str,
+    pub req: ReqTreeView<'graph>,
+    /// Requirements the edge is open under...
+    pub open_with: ItemSet,
+    /// ...that close it again once this is added to them.
+    pub closed_by: Requirement,
+}
+
+impl fmt::Display for NonMonotoneEdge<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "edge {} from {} ({}) to {} ({}) is open with {} but closes with {} too: {}",
+            self.edge,
+            self.src,
+            self.src_name,
+            self.dest,
+            self.dest_name,
+            self.open_with,
+            self.closed_by,
+            self.req
+        )
+    }
+}
+
+/// Every edge whose requirement collecting something can close. Everything else we do assumes
+/// more items only ever open more of the graph, e.g. `StateLattice` and `lock_dead_edges`, so
+/// this is worth running over any model that wasn't built with our parser. Each distinct tree is
+/// only checked once however many edges use it.
+pub fn find_non_monotone_edges<const M: usize, const N: usize, T>(
+    graph: &StaticGraph<M, N, T>,
+) -> Vec<NonMonotoneEdge<'_>> {
+    let mut checked: Vec<(ReqIndex, Option<(ItemSet, Requirement)>)> = Vec::new();
+    let mut found = Vec::new();
+    for (src, edge, dest) in graph.edges() {
+        let req = graph.requirement_root(edge);
+        let witness = match checked.iter().find(|&&(r, _)| r == req) {
+            Some(&(_, witness)) => witness,
+            None => {
+                let witness = non_monotone_nodes(graph.req_trees(), req.get());
+                checked.push((req, witness));
+                witness
+            }
+        };
+        if let Some((open_with, closed_by)) = witness {
+            found.push(NonMonotoneEdge {
+                src,
+                edge,
+                dest,
+                src_name: graph.node_name(src).unwrap_or(""),
+                dest_name: graph.node_name(dest).unwrap_or(""),
+                req: graph.requirement_tree(req),
+                open_with,
+                closed_by,
+            });
+        }
+    }
+
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+
+    use super::*;
+    use crate::{
+        graph::new_static_graph,
+        logic::{
+            non_monotone, non_monotone_in, EventId, ReqBuilder, RequirementNode, REQ_CONTAINER_LEN,
+        },
+    };
+
+    #[test]
+    fn cycles() {
+        let graph = new_static_graph();
+        let state = CollectionState::ALL_ITEMS;
+        let cycles = find_cycles(&graph, &state, 3);
+        assert!(!cycles.is_empty());
+        for cycle in cycles.iter() {
+            assert!(cycle.edges.len() <= 3);
+            assert_eq!(cycle.nodes.len(), cycle.edges.len());
+            assert!(cycle.nodes[1..].iter().all(|&n| n > cycle.nodes[0]));
+            for (i, &edge) in cycle.edges.iter().enumerate() {
+                let next = cycle.nodes[(i + 1) % cycle.nodes.len()];
+                assert!(graph.edges().any(|e| e == (cycle.nodes[i], edge, next)));
+                assert!(state.eval_logic_tree(graph.edge_data(edge).req));
+            }
+        }
+
+        // Every pair of edges back and forth is a cycle of two.
+        let open: Vec<(u16, u16)> = graph
+            .edges()
+            .filter(|&(_, e, _)| state.eval_logic_tree(graph.edge_data(e).req))
+            .map(|(src, _, dst)| (src, dst))
+            .collect();
+        let pairs = open
+            .iter()
+            .filter(|&&(a, b)| a < b)
+            .map(|&(a, b)| open.iter().filter(|&&e| e == (b, a)).count())
+            .sum::<usize>();
+        let loops = open.iter().filter(|&&(a, b)| a == b).count();
+        assert_eq!(find_cycles(&graph, &state, 2).len(), pairs + loops);
+        // Fewer edges open, fewer cycles.
+        assert!(find_cycles(&graph, &CollectionState::default(), 3).len() <= cycles.len());
+    }
+
+    #[test]
+    fn soft_locks() {
+        let graph = new_static_graph();
+        let state = CollectionState::default();
+        let report = find_soft_locks(&graph, &state, &[]);
+        let mut bfs_iter = graph.bfs_iter();
+        bfs_iter.by_ref().for_each(drop);
+        assert!(report
+            .trap_nodes
+            .iter()
+            .all(|&n| bfs_iter.core.visited.check_visited(n)));
+        assert!(report
+            .one_way_edges
+            .iter()
+            .all(|e| report.trap_nodes.contains(&e.dest) && !report.trap_nodes.contains(&e.src)));
+        assert_eq!(
+            report.trap_nodes.is_empty(),
+            report.one_way_edges.is_empty()
+        );
+
+        // If every node is safe there's nowhere to get stuck.
+        let everywhere: Vec<u16> = (1..=NUM_VERTICES as u16).collect();
+        assert!(find_soft_locks(&graph, &state, &everywhere).is_empty());
+
+        // A one-way drop into a room whose only way back needs gloves, which we don't have.
+        let graph =
+            StaticGraph::<8, 64>::from_edge_list(["1 2", "2 1", "2 3", "3 4", "4 2 gloves"])
+                .unwrap();
+        let report = find_soft_locks(&graph, &state, &[]);
+        assert_eq!(report.trap_nodes, [3, 4]);
+        assert_eq!(
+            report.one_way_edges,
+            [OneWayEdge {
+                src: 2,
+                edge: 3,
+                dest: 3
+            }]
+        );
+        assert_eq!(report.blocked_exits.len(), 1);
+        let exit = report.blocked_exits[0];
+        assert_eq!((exit.src, exit.dest), (4, 2));
+        assert!(exit.to_string().contains("Gloves"), "{}", exit);
+        let mut with_gloves = state.clone();
+        with_gloves.set(Item::Gloves, true);
+        assert!(find_soft_locks(&graph, &with_gloves, &[]).is_empty());
+    }
+
+    #[test]
+    fn explain_unreachable_nodes() {
+        let graph = new_static_graph();
+        let state = CollectionState::default();
+        assert_eq!(explain_unreachable(&graph, &state, 1), None);
+        let reached: Vec<u16> = graph
+            .bfs_iter_with_state(state.clone())
+            .map(u16::from)
+            .collect();
+        let all: Vec<u16> = graph
+            .bfs_iter_with_state(CollectionState::ALL_ITEMS)
+            .map(u16::from)
+            .collect();
+        // Default state is only missing gloves, so gloves unlock everything else there is.
+        let target = *all.iter().find(|n| !reached.contains(n)).unwrap();
+        let why = explain_unreachable(&graph, &state, target).unwrap();
+        assert_eq!(why.unlocking_items, [Item::Gloves]);
+        assert!(!why.blocked_edges.is_empty());
+        for e in &why.blocked_edges {
+            assert!(reached.contains(&e.src) && !reached.contains(&e.dest));
+            assert!(!e.req.eval(&state));
+            assert!(e.to_string().contains("Gloves"), "{}", e);
+        }
+    }
+
+    #[test]
+    fn key_items() {
+        let graph = new_static_graph();
+        let nothing = CollectionState::NOTHING;
+        let reached = |state: &CollectionState| graph.dfs_iter_with_state(state.clone()).count();
+        let ranked = rank_key_items(&graph, &nothing);
+        assert_eq!(ranked.len(), 4);
+        assert!(ranked[0].new_checks > 0);
+        assert!(ranked
+            .windows(2)
+            .all(|w| (w[0].new_checks, w[0].new_nodes) >= (w[1].new_checks, w[1].new_nodes)));
+        for key in &ranked {
+            let mut with_item = nothing.clone();
+            with_item.set(key.item, true);
+            assert_eq!(
+                reached(&with_item) - reached(&nothing),
+                key.new_nodes as usize
+            );
+        }
+        assert!(rank_key_items(&graph, &CollectionState::ALL_ITEMS).is_empty());
+    }
+
+    #[test]
+    fn shape_metrics() {
+        let graph = StaticGraph::<8, 64>::from_edge_list(["1 2", "2 3", "3 1", "3 4"]).unwrap();
+        let exact = metrics(&graph);
+        assert_eq!(exact.eccentricity, [0, 3, 2, 2, 0]);
+        assert_eq!(exact.diameter, 3);
+        assert_eq!(exact.average_path_length, 15.0 / 9.0);
+        assert_eq!(exact.sources, 4);
+        // Sampling never overestimates.
+        for seed in 0..4 {
+            let sampled = sampled_metrics(&graph, 1, seed);
+            assert_eq!(sampled.sources, 1);
+            assert!(sampled
+                .eccentricity
+                .iter()
+                .zip(exact.eccentricity.iter())
+                .all(|(s, e)| s <= e));
+        }
+
+        let graph = new_static_graph();
+        let sampled = sampled_metrics(&graph, 8, 7);
+        assert!(sampled.diameter > 0 && sampled.average_path_length > 1.0);
+        assert_eq!(
+            sampled.eccentricity.iter().copied().max(),
+            Some(sampled.diameter)
+        );
+        assert_eq!(sampled, sampled_metrics(&graph, 8, 7));
+    }
+
+    #[test]
+    fn single_item_gates() {
+        let graph = new_static_graph();
+        let checks = |state: &CollectionState| -> Vec<u16> {
+            let mut checks: Vec<u16> = graph
+                .bfs_iter_with_state(state.clone())
+                .map(u16::from)
+                .filter(|&n| graph.item_data(n).is_some())
+                .collect();
+            checks.sort_unstable();
+            checks
+        };
+        let state = CollectionState::ALL_ITEMS;
+        let all_checks = checks(&state);
+        let gates = item_gates(&graph, &state);
+        assert_eq!(gates.len(), 4);
+        for gate in &gates {
+            let mut without = state.clone();
+            without.set(gate.item, false);
+            let left = checks(&without);
+            let lost: Vec<u16> = all_checks
+                .iter()
+                .copied()
+                .filter(|n| !left.contains(n))
+                .collect();
+            assert_eq!(gate.gated_checks, lost, "{:?}", gate.item);
+        }
+        // The default state is missing gloves, so there are no gloves to withhold.
+        assert!(item_gates(&graph, &CollectionState::default())
+            .iter()
+            .all(|gate| gate.item != Item::Gloves));
+        assert!(gates.iter().any(|gate| !gate.gated_checks.is_empty()));
+    }
+
+    #[test]
+    fn monotonicity() {
+        // Everything the parser builds without a negation only ever opens with more items.
+        let mut graph = new_static_graph();
+        assert!(find_non_monotone_edges(&graph).is_empty());
+        for i in 0..REQ_CONTAINER_LEN as u16 {
+            let req = ReqIndex::new(i).unwrap();
+            assert_eq!(
+                non_monotone(req).is_some(),
+                ReqTreeView::new(req).has_negation()
+            );
+        }
+        let not_agahnim = ReqIndex::new(15).unwrap();
+        assert_eq!(
+            non_monotone(not_agahnim),
+            Some((ItemSet::EMPTY, Requirement::Event(EventId::Agahnim)))
+        );
+        Arc::make_mut(&mut graph.edge_data)[1] = not_agahnim;
+        let found = find_non_monotone_edges(&graph);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].edge, 1);
+
+        // A tree that carries on to locked when boots hold and falls back to gloves when they
+        // don't is `!boots & gloves`, so collecting boots closes it.
+        let mut builder = ReqBuilder::<8>::new();
+        let leaf = |req| RequirementNode {
+            req,
+            and: None,
+            or: None,
+        };
+        let gloves = builder.insert(leaf(Requirement::Gloves)).unwrap();
+        let not_boots = builder
+            .insert(RequirementNode {
+                and: NonZeroU16::new(ReqIndex::LOCKED.get()),
+                or: NonZeroU16::new(gloves),
+                ..leaf(Requirement::Boots)
+            })
+            .unwrap();
+        assert_eq!(non_monotone_in(&builder, gloves), None);
+        assert_eq!(
+            non_monotone_in(&builder, not_boots),
+            Some((
+                ItemSet::from_iter([Requirement::Gloves]),
+                Requirement::Boots
+            ))
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use alloc::sync::Arc;
+
+use crate::graph::{EdgeIndexArray, NodeIndexArray, StaticGraph};
+
+/// A base graph we stamp out variants of, e.g. a population of candidate door shuffles.
+///
+/// Shuffling only moves edge destinations, so the node data, requirements, edge kinds, weights
+/// and regions of every variant are the same as the base graph's. Variants share those with the
+/// base and get their own copy of the node and edge pointer arrays, which cuts a variant of the
+/// generated graph down to a fraction of the memory of building it again. A variant is still a
+/// plain `StaticGraph` and writing to any shared table through it (e.g. `node_data_mut`) gives
+/// that variant its own copy first, so variants can't step on each other or the base.
+pub struct GraphArena<const M: usize, const N: usize, T = ()> {
+    base: StaticGraph<M, N, T>,
+}
+
+impl<const M: usize, const N: usize, T: Clone> GraphArena<M, N, T> {
+    pub fn new(base: StaticGraph<M, N, T>) -> Self {
+        GraphArena { base }
+    }
+
+    /// The graph every variant starts out as.
+    pub fn base(&self) -> &StaticGraph<M, N, T> {
+        &self.base
+    }
+
+    pub fn into_base(self) -> StaticGraph<M, N, T> {
+        self.base
+    }
+
+    /// A new copy of the base graph that shares everything but its pointer arrays and payload.
+    pub fn variant(&self) -> StaticGraph<M, N, T> {
+        let base = &self.base;
+        StaticGraph {
+            node_pointers: NodeIndexArray(base.node_pointers.0.clone()),
+            node_data: Arc::clone(&base.node_data),
+            edge_pointers: EdgeIndexArray(base.edge_pointers.0.clone()),
+            edge_data: Arc::clone(&base.edge_data),
+            req_trees: base.req_trees.clone(),
+            edge_kinds: Arc::clone(&base.edge_kinds),
+            edge_weights: Arc::clone(&base.edge_weights),
+            edge_tricks: Arc::clone(&base.edge_tricks),
+            place_data: base.place_data,
+            item_data: base.item_data,
+            door_data: base.door_data,
+            tricks: base.tricks,
+            region_of: Arc::clone(&base.region_of),
+            payload: base.payload.clone(),
+            node_count: base.node_count,
+            edge_count: base.edge_count,
+            validated: base.validated,
+        }
+    }
+
+    /// Whether `graph` still shares every table with the base, i.e. it's a variant that has only
+    /// had its edges moved around.
+    pub fn shares_storage(&self, graph: &StaticGraph<M, N, T>) -> bool {
+        let base = &self.base;
+        Arc::ptr_eq(&base.node_data, &graph.node_data)
+            && Arc::ptr_eq(&base.edge_data, &graph.edge_data)
+            && Arc::ptr_eq(&base.edge_kinds, &graph.edge_kinds)
+            && Arc::ptr_eq(&base.edge_weights, &graph.edge_weights)
+            && Arc::ptr_eq(&base.edge_tricks, &graph.edge_tricks)
+            && Arc::ptr_eq(&base.region_of, &graph.region_of)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        constants::*,
+        door::DoorShuffle,
+        graph::{new_static_graph, NodeType},
+        logic::CollectionState,
+    };
+
+    #[test]
+    fn variants_share_tables() {
+        let arena = GraphArena::new(new_static_graph());
+        let transitions = arena.base().door_transitions();
+        let state = CollectionState::default();
+        let reachable = arena.base().dfs_iter_with_state(state.clone()).count();
+
+        let mut variants: alloc::vec::Vec<_> = (0..4).map(|_| arena.variant()).collect();
+        for (seed, variant) in variants.iter_mut().enumerate() {
+            let shuffle = DoorShuffle::new(1, state.clone()).with_seed(seed as u64);
+            assert!(variant.shuffle_doors(&shuffle).is_ok());
+            assert!(arena.shares_storage(variant));
+        }
+        assert!(variants.iter().any(|v| v.door_transitions() != transitions));
+        // Shuffling a variant leaves the base alone.
+        assert_eq!(arena.base().door_transitions(), transitions);
+        assert_eq!(arena.base().dfs_iter_with_state(state).count(), reachable);
+
+        // Retagging a node copies the node data rather than changing it for everyone.
+        let is_door = |graph: &StaticGraph<NUM_VERTICES_PADDED, NUM_EDGES_PADDED>| {
+            matches!(graph.node_data(1).node_type, NodeType::Door)
+        };
+        let was_door = is_door(arena.base());
+        variants[0].node_data_mut(1).node_type = match was_door {
+            true => NodeType::Item,
+            false => NodeType::Door,
+        };
+        assert!(!arena.shares_storage(&variants[0]));
+        assert!(arena.shares_storage(&variants[1]));
+        assert_eq!(is_door(&variants[0]), !was_door);
+        assert_eq!(is_door(arena.base()), was_door);
+        assert_eq!(is_door(&variants[1]), was_door);
+    }
+}
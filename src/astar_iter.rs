@@ -0,0 +1,154 @@
+use alloc::{
+    boxed::Box,
+    collections::{BinaryHeap, VecDeque},
+    vec,
+};
+use core::{cmp::Reverse, fmt, iter::FusedIterator, num::NonZeroU16};
+
+use crate::{
+    graph::StaticGraph,
+    logic::{CollectionState, EvaluateLogic},
+    traversal::TraversalCore,
+};
+
+/// Heuristic value for nodes that can't reach the goal at all, even ignoring logic.
+pub const UNREACHABLE: u16 = u16::MAX;
+
+/// A goal-directed, best-first graph walker. Nodes are expanded in order of the hops it took to
+/// reach them plus a per-node estimate of the hops remaining to the goal, so a single-target query
+/// on a large graph explores far less of it than a plain BFS would.
+///
+/// The heuristic is just a slice indexed by node. It must never overestimate the remaining hops
+/// (and shouldn't decrease by more than one across any edge) for nodes to be yielded with their
+/// shortest cost. A hop distance to the goal that ignores logic satisfies both since logic can
+/// only remove edges. Codegen can emit these tables for well-known goals, or they can be built at
+/// runtime with `hop_distance_heuristic`.
+pub struct AStarIter<'graph, 'h, const M: usize, const N: usize, T = ()> {
+    pub core: TraversalCore<'graph, M, N, T>,
+    pub heuristic: &'h [u16],
+    pub open: BinaryHeap<Reverse<(u32, u16)>>,
+    // Best known number of hops from the root to each node.
+    cost: Box<[u16]>,
+}
+
+impl<'graph, 'h, const M: usize, const N: usize, T> AStarIter<'graph, 'h, M, N, T> {
+    pub(crate) fn new(
+        graph: &'graph StaticGraph<M, N, T>,
+        heuristic: &'h [u16],
+        state: CollectionState,
+    ) -> Self {
+        let core = TraversalCore::new(graph, 1, state);
+        let mut astar_iter = AStarIter {
+            heuristic,
+            open: BinaryHeap::new(),
+            cost: vec![u16::MAX; M].into_boxed_slice(),
+            core,
+        };
+        let root = astar_iter.core.root;
+        astar_iter.cost[root as usize] = 0;
+        astar_iter.push(root, 0);
+
+        astar_iter
+    }
+
+    /// The fewest hops from the root to `node` found so far, if it's been reached.
+    pub fn cost(&self, node: u16) -> Option<u16> {
+        match self.cost[node as usize] {
+            u16::MAX => None,
+            c => Some(c),
+        }
+    }
+
+    /// Returns whether `target` is reachable, expanding nodes until it's yielded or the search is
+    /// exhausted.
+    pub fn search(&mut self, target: u16) -> bool {
+        self.any(|n| u16::from(n) == target)
+    }
+
+    fn push(&mut self, node: u16, cost: u16) {
+        let h = self.heuristic.get(node as usize).copied().unwrap_or(0);
+        if h != UNREACHABLE {
+            self.open.push(Reverse((cost as u32 + h as u32, node)));
+        }
+    }
+
+    /// Relaxes a node's accessible outgoing edges, pushing any neighbor we found a cheaper path
+    /// to. Unlike our other walkers a node may be pushed more than once, in which case the stale
+    /// entries are skipped when popped.
+    fn visit_neighbors_out(&mut self, node: u16) {
+        let next_cost = self.cost[node as usize] + 1;
+        let (edge_pointers, edge_offset) = self.core.graph.get_neighbors_out(NonZeroU16::new(node));
+        for (i, &n) in edge_pointers.iter().enumerate() {
+            let node_index = u16::from(n);
+            if !self.core.check_edge(edge_offset + i as u16)
+                || next_cost >= self.cost[node_index as usize]
+            {
+                continue;
+            }
+            if !self.core.visited.test_set_visited(node_index) {
+                self.core.visited_count += 1;
+            }
+            self.cost[node_index as usize] = next_cost;
+            self.push(node_index, next_cost);
+        }
+    }
+}
+
+impl<const M: usize, const N: usize, T> Iterator for AStarIter<'_, '_, M, N, T> {
+    type Item = NonZeroU16;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(Reverse((f, node))) = self.open.pop() {
+            let h = self.heuristic.get(node as usize).copied().unwrap_or(0) as u32;
+            // A cheaper path to this node was found after this entry was pushed.
+            if f - h > self.cost[node as usize] as u32 {
+                continue;
+            }
+            self.visit_neighbors_out(node);
+            return NonZeroU16::new(node);
+        }
+
+        None
+    }
+}
+
+impl<const M: usize, const N: usize, T> FusedIterator for AStarIter<'_, '_, M, N, T> {}
+
+impl<const M: usize, const N: usize, T> EvaluateLogic for AStarIter<'_, '_, M, N, T> {
+    fn collection_state(&self) -> &CollectionState {
+        &self.core.collection_state
+    }
+}
+
+impl<const M: usize, const N: usize, T> fmt::Debug for AStarIter<'_, '_, M, N, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AStarIter")
+            .field("core", &self.core)
+            .field("open", &self.open.len())
+            .finish()
+    }
+}
+
+/// Build a heuristic table holding every node's hop distance to `goal`, ignoring logic, with a
+/// backwards breadth-first search. Nodes that can't reach the goal get `UNREACHABLE`.
+pub fn hop_distance_heuristic<const M: usize, const N: usize, T>(
+    graph: &StaticGraph<M, N, T>,
+    goal: u16,
+) -> Box<[u16]> {
+    let reverse = graph.reverse_adjacency();
+    let mut distances = vec![UNREACHABLE; M].into_boxed_slice();
+    let mut queue = VecDeque::new();
+    distances[goal as usize] = 0;
+    queue.push_back(goal);
+    while let Some(node) = queue.pop_front() {
+        let next = distances[node as usize] + 1;
+        for (src, _) in reverse.neighbors_in(node) {
+            if distances[src as usize] == UNREACHABLE {
+                distances[src as usize] = next;
+                queue.push_back(src);
+            }
+        }
+    }
+
+    distances
+}
@@ -0,0 +1,180 @@
+// The simulated workloads behind our end-to-end benches, public so a downstream world model can
+// be benchmarked with exactly the same scenarios as the generated one. See benches/scenarios.rs.
+use alloc::vec::Vec;
+
+use crate::{
+    graph::StaticGraph,
+    logic::{CollectionState, EventFlags, Item, SettingsState, TierMask},
+    rng::SplitMix64,
+    scratch::TraversalScratch,
+    tracker::TrackerSession,
+};
+
+const PROGRESSION: [Item; 4] = [Item::Boots, Item::Gloves, Item::Flute, Item::Hammer];
+
+/// Every combination of progression items, ordered so each state differs from the one before it
+/// by a single item like a player collecting or losing one item at a time.
+pub fn state_changes() -> Vec<CollectionState> {
+    (0..1u8 << PROGRESSION.len())
+        .map(|i| {
+            // Gray code, so neighbouring states differ by one bit.
+            let bits = i ^ (i >> 1);
+            let mut state = CollectionState::NOTHING;
+            for (bit, &item) in PROGRESSION.iter().enumerate() {
+                state.set(item, bits & (1 << bit) != 0);
+            }
+            state
+        })
+        .collect()
+}
+
+/// Search the whole graph again for every state, the way a tracker without incremental updates
+/// would after every change. Returns the total number of nodes reached so the work can't be
+/// optimized away.
+pub fn reachability_sweep<const M: usize, const N: usize, T>(
+    graph: &StaticGraph<M, N, T>,
+    states: &[CollectionState],
+    scratch: &mut TraversalScratch,
+) -> usize {
+    states
+        .iter()
+        .map(|state| graph.dfs_iter_with_state_in(state.clone(), scratch).count())
+        .sum()
+}
+
+/// A tracker session taking `iterations` random collects, uncollects and undos, reading the
+/// reachable checks after each one like a tracker UI redrawing. Returns the total number of
+/// reachable checks seen.
+pub fn tracker_workload<const M: usize, const N: usize, T>(
+    graph: &StaticGraph<M, N, T>,
+    iterations: usize,
+    seed: u64,
+) -> usize {
+    let mut rng = SplitMix64::new(seed);
+    let mut session = TrackerSession::new(graph, CollectionState::NOTHING);
+    let mut seen = 0;
+    for _ in 0..iterations {
+        let item = PROGRESSION[rng.below(PROGRESSION.len())];
+        match rng.below(4) {
+            0 => {
+                session.undo();
+            }
+            _ if session.collection_state().has(item) => session.uncollect(item),
+            _ => {
+                session.collect(item);
+            }
+        }
+        seen += session.reachable_checks().len();
+    }
+
+    seen
+}
+
+/// A pool of `size` items where half are progression, with copies of each progression item, and
+/// the rest are junk.
+pub fn item_pool(size: usize) -> Vec<Item> {
+    (0..size)
+        .map(|i| match i % 8 {
+            i @ 0..=3 => PROGRESSION[i],
+            _ => Item::Rupees,
+        })
+        .collect()
+}
+
+/// Assumed fill `pool` into the graph's item locations. We place progression items one at a time
+/// in a random order, each somewhere reachable while assuming every item still to be placed is
+/// collected along with whatever's already placed where we can reach it, then scatter the junk
+/// over what's left. Returns `(location, item)` for every item, or `None` if we ran out of
+/// locations.
+pub fn assumed_fill<const M: usize, const N: usize, T>(
+    graph: &StaticGraph<M, N, T>,
+    pool: &[Item],
+    seed: u64,
+) -> Option<Vec<(u16, Item)>> {
+    let mut rng = SplitMix64::new(seed);
+    let (mut progression, junk): (Vec<Item>, Vec<Item>) =
+        pool.iter().partition(|i| i.is_progression());
+    rng.shuffle(&mut progression);
+    let mut placed: Vec<(u16, Item)> = Vec::with_capacity(pool.len());
+    while let Some(item) = progression.pop() {
+        let mut assumed = CollectionState::NOTHING;
+        progression.iter().for_each(|&i| {
+            assumed.set(i, true);
+        });
+        let mut session = TrackerSession::new(graph, assumed);
+        while let Some(&(_, found)) = placed
+            .iter()
+            .find(|&&(l, i)| session.is_reachable(l) && !session.collection_state().has(i))
+        {
+            session.collect(found);
+        }
+        let open: Vec<u16> = session
+            .reachable_checks()
+            .into_iter()
+            .filter(|&l| placed.iter().all(|&(p, _)| p != l))
+            .collect();
+        if open.is_empty() {
+            return None;
+        }
+        placed.push((open[rng.below(open.len())], item));
+    }
+
+    let mut empty: Vec<u16> = graph
+        .nodes()
+        .filter(|&n| graph.item_data(n).is_some() && placed.iter().all(|&(p, _)| p != n))
+        .collect();
+    if empty.len() < junk.len() {
+        return None;
+    }
+    rng.shuffle(&mut empty);
+    placed.extend(empty.into_iter().zip(junk));
+
+    Some(placed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::new_static_graph;
+
+    #[test]
+    fn simulated_workloads() {
+        let graph = new_static_graph();
+        let states = state_changes();
+        assert_eq!(states.len(), 16);
+        let items = |s: &CollectionState| PROGRESSION.iter().filter(|&&i| s.has(i)).count();
+        assert!(states
+            .windows(2)
+            .all(|w| items(&w[0]).abs_diff(items(&w[1])) == 1));
+        let expected: usize = states
+            .iter()
+            .map(|s| graph.dfs_iter_with_state(s.clone()).count())
+            .sum();
+        let mut scratch = TraversalScratch::new();
+        assert_eq!(reachability_sweep(&graph, &states, &mut scratch), expected);
+
+        assert_eq!(
+            tracker_workload(&graph, 50, 3),
+            tracker_workload(&graph, 50, 3)
+        );
+
+        let pool = item_pool(200);
+        assert_eq!(pool.iter().filter(|i| i.is_progression()).count(), 100);
+        let placement = assumed_fill(&graph, &pool, 9).unwrap();
+        assert_eq!(placement.len(), pool.len());
+        let mut locations: Vec<u16> = placement.iter().map(|&(l, _)| l).collect();
+        locations.sort_unstable();
+        locations.dedup();
+        assert_eq!(locations.len(), pool.len());
+        // Starting from nothing, we can pick up every progression item.
+        let mut session = TrackerSession::new(&graph, CollectionState::NOTHING);
+        while let Some(&(_, item)) = placement.iter().find(|&&(l, i)| {
+            i.is_progression() && session.is_reachable(l) && !session.collection_state().has(i)
+        }) {
+            session.collect(item);
+        }
+        assert!(PROGRESSION
+            .iter()
+            .all(|&i| session.collection_state().has(i)));
+    }
+}
@@ -1,143 +1,124 @@
-// A quick copy of DfsIter but using a queue for breadth-first search.
-use alloc::boxed::Box;
-use core::num::NonZeroU16;
+// The breadth-first counterpart to DfsIter, sharing its TraversalCore but using a queue.
+use alloc::{boxed::Box, collections::VecDeque};
+use core::{fmt, iter::FusedIterator, num::NonZeroU16};
 
 use crate::{
     constants::*,
-    graph::{AccessCache, StaticGraph, VisitedCache},
-    logic::{CollectionState, Requirement, RequirementNode, REQ_CONTAINER},
+    gen::SEARCH_QUEUE_SIZE,
+    graph::{VisitedCache, VisitedSet},
+    logic::{CollectionState, EvaluateLogic},
+    traversal::TraversalCore,
 };
 
 /// Our main traversal data structure for simulating access checking. We model a search
 /// with the Iterator trait where the `.next()` method returns the next node in the search or None
 /// if the search has been exhausted.
-pub struct BfsIter<'graph, const M: usize, const N: usize> {
-    pub graph: &'graph StaticGraph<M, N>,
-    pub root: u16,
-    pub search_queue: BfsQueue,
-    pub collection_state: CollectionState,
-    pub visited: VisitedCache<VISITED_BITFIELD_LEN>,
-    pub edge_access: AccessCache<ACCESS_BITFIELD_LEN>,
-}
-
-impl<const M: usize, const N: usize> BfsIter<'_, M, N> {
-    const BITMASK_CUR: u64 = 0x80000000_00000000;
-
-    /// To evaluate our logic expressions we recursively evaluate the conditions one holds. If the
-    /// condition is false, we check for an OR child and repeat if present or return `false` if not
-    /// (ideally short-circuiting as soon as possible.) If the condition is true, we check for an
-    /// AND child and repeat if present or return `true` if not. Eventually we reach a node whose
-    /// evaluation gives us our final true or false. This function takes the root node of a tree
-    /// and proceeds as such.
-    pub fn eval_logic_tree(&self, mut req_index: u16) -> bool {
-        let mut req_node: RequirementNode;
-        loop {
-            req_node = REQ_CONTAINER[req_index];
-            match self.eval_requirement(req_node.req) {
-                true => match req_node.and {
-                    Some(n) => req_index = u16::from(n),
-                    None => break true,
-                },
-                false => match req_node.or {
-                    Some(n) => req_index = u16::from(n),
-                    None => break false,
-                },
-            }
-        }
-    }
-
-    /// Our logic evaluator. Here we're merely checking collection state, but the graph walking
-    /// data structure that solves for reachability etc will also implement more complex methods
-    /// that will run their own graph operations with a shared reference to the graph we're working
-    /// with.
-    pub const fn eval_requirement(&self, req: Requirement) -> bool {
-        match req {
-            Requirement::Open => true,
-            Requirement::Boots => self.collection_state.boots,
-            Requirement::Gloves => self.collection_state.gloves,
-            Requirement::Flute => self.collection_state.flute,
-            Requirement::Hammer => self.collection_state.hammer,
-            Requirement::Locked => false,
-        }
-    }
+pub struct BfsIter<
+    'graph,
+    const M: usize,
+    const N: usize,
+    T = (),
+    const Q: usize = SEARCH_QUEUE_SIZE,
+    V = VisitedCache<VISITED_BITFIELD_LEN>,
+> {
+    pub core: TraversalCore<'graph, M, N, T, V>,
+    pub search_queue: BfsQueue<Q>,
+}
 
-    /// Instead of determining whether an edge can be traversed during a traversal, we can
-    /// pre-compute our logic to a large extent. This is somewhat complicated by things like small
-    /// keys or logical requirements that may have a dependency on the graph state and other
-    /// logical constraints that may change or not have been computed yet.
-    ///
-    /// Another approach here would be to evaluate a set of requirements with static inputs once
-    /// and apply a pre-computed bitmask.
+impl<const M: usize, const N: usize, T, const Q: usize, V: VisitedSet> BfsIter<'_, M, N, T, Q, V> {
+    /// Re-evaluate which edges can be traversed under the current collection state.
     pub fn evaluate_logical_access(&mut self) {
-        // SAFETY: We have to statically ensure that this iterator has exactly the same amount of
-        // elements as our self.edge_access array. In a library we might use a debug assertion.
-        let edge_logic = unsafe {
-            self.graph
-                .edge_data
-                // Also tried nightly, safe .array_chunks iterator method but can't remember if
-                // it's faster or anything. Not too worried about, all the logic evaluation code
-                // could be massively improved.
-                .as_chunks_unchecked::<CHUNK_SIZE>()
-                .iter()
-                .enumerate()
-        };
-        edge_logic.for_each(|i| {
-            let mut bit_cursor: u64 = Self::BITMASK_CUR;
-            let (idx, logic_array) = i;
-            self.edge_access[idx] =
-                logic_array
-                    .iter()
-                    .fold(0u64, |acc, d| match self.eval_logic_tree(*d) {
-                        true => {
-                            let c = acc | bit_cursor;
-                            bit_cursor >>= 1;
-                            c
-                        }
-                        false => {
-                            bit_cursor >>= 1;
-                            acc
-                        }
-                    });
-        });
+        self.core.evaluate_logical_access();
     }
 
     /// Returns whether a node is reachable or not, checking previous traversals first.
     pub fn search(&mut self, node: u16) -> bool {
-        match self.visited.check_visited(node) {
+        match self.core.visited.check_visited(node) {
             true => true,
             false => self.any(|n| u16::from(n) == node),
         }
     }
 
     pub fn clear(&mut self) {
-        *self.visited = [0u64; VISITED_BITFIELD_LEN];
+        self.core.reset_visited();
         self.search_queue.clear();
-        self.search_queue.push_back(self.root);
-        self.visited.mark_visited(self.root);
+        self.search_queue.push_back(self.core.root);
+    }
+
+    /// The node `.next()` will return, without expanding it or advancing the search.
+    pub fn peek(&self) -> Option<NonZeroU16> {
+        self.search_queue.peek()
+    }
+
+    /// The nodes waiting to be expanded, in the order we'll yield them. They've already been
+    /// marked visited, so this is also the boundary between what we've reached and what we
+    /// haven't.
+    pub fn frontier(&self) -> impl Iterator<Item = NonZeroU16> + '_ {
+        self.search_queue.iter()
+    }
+
+    /// The number of nodes waiting to be expanded.
+    pub fn frontier_width(&self) -> usize {
+        self.search_queue.len()
     }
 
     /// Visits a node's unvisited, accessible, outgoing neighbors and pushes them onto the BFS
     /// queue.
     pub fn visit_neighbors_out(&mut self, node: Option<NonZeroU16>) {
-        let (edge_pointers, edge_offset) = self.graph.get_neighbors_out(node);
-        edge_pointers
-            .iter()
-            .enumerate()
-            .filter(|(i, _)| {
-                let edge_index = edge_offset + *i as u16;
-                self.edge_access.check_access(edge_index)
-            })
-            .for_each(|(_, &n)| {
-                let node_index = u16::from(n);
-                match self.visited.test_set_visited(node_index) {
-                    false => self.search_queue.push_back(node_index),
-                    true => (),
-                };
-            });
-    }
-}
-
-impl<const M: usize, const N: usize> Iterator for BfsIter<'_, M, N> {
+        let search_queue = &mut self.search_queue;
+        self.core
+            .visit_neighbors_out(node, |n| search_queue.push_back(n));
+    }
+}
+
+impl<const M: usize, const N: usize, T, const Q: usize, V> EvaluateLogic
+    for BfsIter<'_, M, N, T, Q, V>
+{
+    fn collection_state(&self) -> &CollectionState {
+        &self.core.collection_state
+    }
+}
+
+/// See `DfsIter`'s `Clone` impl.
+impl<const M: usize, const N: usize, T, const Q: usize, V: Clone> Clone
+    for BfsIter<'_, M, N, T, Q, V>
+{
+    fn clone(&self) -> Self {
+        BfsIter {
+            core: self.core.clone(),
+            search_queue: self.search_queue.clone(),
+        }
+    }
+}
+
+impl<const M: usize, const N: usize, T, const Q: usize, V: fmt::Debug> fmt::Debug
+    for BfsIter<'_, M, N, T, Q, V>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BfsIter")
+            .field("core", &self.core)
+            .field("search_queue", &self.search_queue)
+            .finish()
+    }
+}
+
+impl<const M: usize, const N: usize, T, const Q: usize, V> fmt::Display
+    for BfsIter<'_, M, N, T, Q, V>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "BfsIter {{ root: {}, queue: {}, visited: {} }}",
+            self.core.root,
+            self.search_queue.len(),
+            self.core.visited_count
+        )
+    }
+}
+
+impl<const M: usize, const N: usize, T, const Q: usize, V: VisitedSet> Iterator
+    for BfsIter<'_, M, N, T, Q, V>
+{
     // Returns a node's index. In a library we would also generate code such that every node
     // corresponds to a named variant of a u16-backed enum but with an iterator we only care about
     // the index.
@@ -147,61 +128,148 @@ impl<const M: usize, const N: usize> Iterator for BfsIter<'_, M, N> {
     fn next(&mut self) -> Option<Self::Item> {
         let next_node = self.search_queue.pop_front();
         self.visit_neighbors_out(next_node);
+        event!(
+            TRACE,
+            node = next_node.map_or(0, u16::from),
+            frontier = self.search_queue.len(),
+            "bfs expand"
+        );
 
         next_node
     }
+
+    // Every node still on the queue will be yielded and at most every node we haven't marked
+    // visited yet might be.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let pending = self.search_queue.len();
+
+        (pending, Some(pending + self.core.unvisited()))
+    }
+}
+
+// Once the queue is exhausted, popping keeps returning None which maps to the terminal node
+// and visits nothing.
+impl<const M: usize, const N: usize, T, const Q: usize, V: VisitedSet> FusedIterator
+    for BfsIter<'_, M, N, T, Q, V>
+{
 }
 
 /// A minimal, branchless, cache-efficient circular queue with push_back and pop_front operations.
-// The max length must be measured/computed such that it's never greater than or equal to the queue
-// size plus one. This lets us save time by avoiding masking it.
-pub struct BfsQueue {
-    buf: Box<[Option<NonZeroU16>; SEARCH_QUEUE_SIZE]>,
+// The build script measures the default size on the generated graph, and other graphs can pick
+// their own `Q` (a power of two), see `StaticGraph::bfs_iter_sized`. A search wider than the ring
+// spills into a growable overflow queue rather than wrapping over nodes it hasn't popped yet, so
+// `Q` only decides how much we allocate up front and never whether the search is right.
+#[derive(Clone)]
+pub struct BfsQueue<const Q: usize = SEARCH_QUEUE_SIZE> {
+    buf: Box<[Option<NonZeroU16>; Q]>,
     ptr: usize,
     len: usize,
+    // Everything pushed while the ring was full, or since, in order. Every node here was pushed
+    // after every node in the ring, so we only pop from it once the ring is empty.
+    overflow: VecDeque<u16>,
 }
 
-impl Default for BfsQueue {
+impl<const Q: usize> Default for BfsQueue<Q> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl BfsQueue {
+impl<const Q: usize> BfsQueue<Q> {
     pub fn new() -> Self {
+        const { assert!(Q.is_power_of_two()) };
         BfsQueue {
-            buf: Box::new([NonZeroU16::new(0); SEARCH_QUEUE_SIZE]),
+            buf: Box::new([NonZeroU16::new(0); Q]),
             ptr: 0,
             len: 0,
+            overflow: VecDeque::new(),
         }
     }
 
     #[inline]
     pub fn push_back(&mut self, n: u16) {
-        debug_assert!(self.len < (SEARCH_QUEUE_SIZE - 1));
-        let offset = (self.ptr + self.len) & (SEARCH_QUEUE_SIZE - 1);
+        if self.len == self.capacity() || !self.overflow.is_empty() {
+            self.overflow.push_back(n);
+            return;
+        }
+        let offset = (self.ptr + self.len) & (Q - 1);
         self.buf[offset] = NonZeroU16::new(n);
         self.len += 1;
     }
 
     #[inline]
     pub fn pop_front(&mut self) -> Option<NonZeroU16> {
-        self.ptr &= SEARCH_QUEUE_SIZE - 1;
+        self.ptr &= Q - 1;
         let ret = self.buf[self.ptr].take();
         self.ptr += 1;
-        self.len -= 1;
+        // Popping an empty ring takes a None out of the buffer and leaves the length at zero.
+        self.len = self.len.saturating_sub(1);
 
-        ret
+        match ret {
+            None => self.overflow.pop_front().and_then(NonZeroU16::new),
+            some => some,
+        }
+    }
+
+    /// The node at the front of the queue, without popping it.
+    #[inline]
+    pub fn peek(&self) -> Option<NonZeroU16> {
+        match self.len {
+            0 => self.overflow.front().copied().and_then(NonZeroU16::new),
+            _ => self.buf[self.ptr & (Q - 1)],
+        }
     }
 
     #[inline]
     pub fn clear(&mut self) {
+        // Popping an empty ring relies on every slot outside the queue being None.
+        for i in 0..self.len {
+            self.buf[(self.ptr + i) & (Q - 1)] = None;
+        }
         self.ptr = 0;
         self.len = 0;
+        self.overflow.clear();
+    }
+
+    /// The number of nodes currently in the queue.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len + self.overflow.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The most nodes the ring can hold. We keep one slot spare like the stack does, so it's one
+    /// less than `Q`.
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        Q - 1
+    }
+
+    /// How many more nodes we can push before the queue spills into its overflow.
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.capacity().saturating_sub(self.len())
+    }
+
+    /// The queued nodes from front to back, without popping them.
+    pub fn iter(&self) -> impl Iterator<Item = NonZeroU16> + '_ {
+        let ring = (0..self.len).filter_map(|i| self.buf[(self.ptr + i) & (Q - 1)]);
+
+        ring.chain(self.overflow.iter().copied().filter_map(NonZeroU16::new))
+    }
+}
+
+impl<const Q: usize> fmt::Debug for BfsQueue<Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
     }
 }
 
-impl Iterator for BfsQueue {
+impl<const Q: usize> Iterator for BfsQueue<Q> {
     type Item = NonZeroU16;
 
     #[inline]
@@ -209,3 +277,208 @@ impl Iterator for BfsQueue {
         self.pop_front()
     }
 }
+
+impl<const Q: usize> FusedIterator for BfsQueue<Q> {}
+
+/// A bucket queue for searches where edges cost small whole numbers, e.g. frames rounded to a
+/// few buckets or zero for moving around a room and one for a loading zone. Nodes are popped in
+/// order of the distance they were pushed with, so a Dijkstra-style search can run on it without
+/// a heap.
+///
+/// There's one `BfsQueue` ring per distance and `B` of them reused cyclically (Dial's algorithm),
+/// so every distance pushed has to be within `B - 1` of the distance we're popping at, which
+/// holds whenever no edge costs more than that. Larger weights can be divided into buckets of
+/// some width first like delta-stepping does, in which case nodes come out in order of bucket
+/// rather than exact distance. `B` has to be a power of two and so does `Q`, which caps how many
+/// nodes any one distance can hold.
+#[derive(Clone)]
+pub struct BucketQueue<const B: usize, const Q: usize = SEARCH_QUEUE_SIZE> {
+    buckets: Box<[BfsQueue<Q>; B]>,
+    // The distance we're popping at. Every queued node is at this distance or up to B - 1 more.
+    cursor: u32,
+    len: usize,
+}
+
+impl<const B: usize, const Q: usize> Default for BucketQueue<B, Q> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const B: usize, const Q: usize> BucketQueue<B, Q> {
+    pub fn new() -> Self {
+        const { assert!(B.is_power_of_two()) };
+        BucketQueue {
+            buckets: Box::new(core::array::from_fn(|_| BfsQueue::new())),
+            cursor: 0,
+            len: 0,
+        }
+    }
+
+    /// Queue `n` at distance `dist`, which mustn't be less than the distance we're popping at or
+    /// `B` or more past it.
+    #[inline]
+    pub fn push(&mut self, n: u16, dist: u32) {
+        debug_assert!(dist >= self.cursor && dist - self.cursor < B as u32);
+        self.buckets[dist as usize & (B - 1)].push_back(n);
+        self.len += 1;
+    }
+
+    /// The nearest queued node and its distance. Nodes at the same distance come out in the
+    /// order they were pushed.
+    #[inline]
+    pub fn pop(&mut self) -> Option<(u32, NonZeroU16)> {
+        if self.len == 0 {
+            return None;
+        }
+        // Something is queued within B distances of the cursor, so this always stops.
+        while self.buckets[self.cursor as usize & (B - 1)].is_empty() {
+            self.cursor += 1;
+        }
+        self.len -= 1;
+
+        self.buckets[self.cursor as usize & (B - 1)]
+            .pop_front()
+            .map(|n| (self.cursor, n))
+    }
+
+    /// The distance we're popping at, which no node queued from here on can be nearer than.
+    #[inline]
+    pub fn current(&self) -> u32 {
+        self.cursor
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.buckets.iter_mut().for_each(BfsQueue::clear);
+        self.cursor = 0;
+        self.len = 0;
+    }
+
+    /// The number of nodes queued across every distance.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<const B: usize, const Q: usize> fmt::Debug for BucketQueue<B, Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Buckets from the cursor on, labelled with the distance they hold.
+        let mut map = f.debug_map();
+        for d in self.cursor..self.cursor + B as u32 {
+            let bucket = &self.buckets[d as usize & (B - 1)];
+            if !bucket.is_empty() {
+                map.entry(&d, bucket);
+            }
+        }
+        map.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::graph::{new_static_graph, StaticGraph};
+
+    #[test]
+    fn frontier_is_what_comes_next() {
+        let graph = new_static_graph();
+        let mut bfs_iter = graph.bfs_iter();
+        assert_eq!(bfs_iter.frontier_width(), 1);
+        let mut max_width = 0;
+        while bfs_iter.frontier_width() > 0 {
+            let frontier: Vec<_> = bfs_iter.frontier().collect();
+            assert_eq!(frontier.len(), bfs_iter.frontier_width());
+            assert!(frontier
+                .iter()
+                .all(|n| bfs_iter.core.visited.check_visited(n.get())));
+            max_width = max_width.max(frontier.len());
+            // Inspecting doesn't consume anything and the front is always yielded next.
+            assert_eq!(bfs_iter.peek(), frontier.first().copied());
+            assert_eq!(bfs_iter.next(), frontier.first().copied());
+        }
+        assert!(max_width > 1);
+        assert_eq!(bfs_iter.peek(), None);
+        assert_eq!(bfs_iter.next(), None);
+
+        let mut queue: BfsQueue = BfsQueue::new();
+        assert_eq!(queue.peek(), None);
+        assert!(queue.is_empty());
+        assert_eq!(queue.remaining(), queue.capacity());
+        queue.push_back(3);
+        queue.push_back(7);
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.remaining(), SEARCH_QUEUE_SIZE - 3);
+        assert_eq!(queue.peek(), NonZeroU16::new(3));
+        queue.pop_front();
+        assert_eq!(queue.peek(), NonZeroU16::new(7));
+        queue.pop_front();
+        assert_eq!(queue.peek(), None);
+
+        // Pushing past the ring spills over without losing the order.
+        let mut queue: BfsQueue<4> = BfsQueue::new();
+        (1..=10).for_each(|n| queue.push_back(n));
+        assert_eq!((queue.len(), queue.remaining()), (10, 0));
+        assert!(queue.iter().map(u16::from).eq(1..=10));
+        queue.pop_front();
+        queue.push_back(11);
+        assert!(queue.by_ref().map(u16::from).eq(2..=11));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn wider_than_the_queue() {
+        // The root, 13 children and 13 grandchildren under each: far more than the default queue.
+        let mut edges = Vec::new();
+        for child in 2..=14 {
+            edges.push(alloc::format!("1 {}", child));
+            for grandchild in 0..13 {
+                edges.push(alloc::format!(
+                    "{} {}",
+                    child,
+                    15 + (child - 2) * 13 + grandchild
+                ));
+            }
+        }
+        let graph = StaticGraph::<256, 256>::from_edge_list(edges).unwrap();
+        assert_eq!(graph.node_count(), 183);
+        assert_eq!(graph.bfs_iter().count(), 183);
+        assert_eq!(graph.dfs_iter().count(), 183);
+        assert_eq!(graph.bfs_iter().last(), NonZeroU16::new(183));
+    }
+
+    #[test]
+    fn bucket_queue_order() {
+        let mut queue: BucketQueue<4> = BucketQueue::new();
+        assert_eq!(queue.pop(), None);
+        queue.push(5, 2);
+        queue.push(6, 0);
+        queue.push(7, 3);
+        queue.push(8, 0);
+        assert_eq!(queue.len(), 4);
+        assert_eq!(queue.pop(), Some((0, NonZeroU16::new(6).unwrap())));
+        assert_eq!(queue.pop(), Some((0, NonZeroU16::new(8).unwrap())));
+        assert_eq!(queue.pop(), Some((2, NonZeroU16::new(5).unwrap())));
+        // Distances wrap around onto the buckets we've drained.
+        queue.push(9, 5);
+        queue.push(10, 2);
+        assert_eq!(queue.pop(), Some((2, NonZeroU16::new(10).unwrap())));
+        assert_eq!(queue.pop(), Some((3, NonZeroU16::new(7).unwrap())));
+        assert_eq!(queue.current(), 3);
+        assert_eq!(queue.pop(), Some((5, NonZeroU16::new(9).unwrap())));
+        assert_eq!(queue.pop(), None);
+        assert!(queue.is_empty());
+
+        queue.push(1, 7);
+        queue.clear();
+        assert_eq!((queue.pop(), queue.current()), (None, 0));
+    }
+}
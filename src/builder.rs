@@ -0,0 +1,696 @@
+// Editing a graph as plain vectors and laying it back out into a `StaticGraph`'s arrays.
+use alloc::{sync::Arc, vec::Vec};
+use core::{fmt, num::NonZeroU16, ptr};
+
+use crate::{
+    graph::{
+        new_static_graph, DoorNodeData, EdgeData, GraphError, ItemNodeData, NodeData,
+        PlaceNodeData, StaticGraph, TrickMask,
+    },
+    logic::{ReqIndex, RequirementNode, REQ_CONTAINER, REQ_CONTAINER_LEN},
+    region::Region,
+};
+
+/// One node of a `GraphBuilder`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BuilderNode {
+    pub data: NodeData,
+    /// The node's region id, or 0 if it isn't in one. See `Region::id`.
+    pub region: u16,
+    /// `(destination, edge)` for every edge leaving the node, in the order they're laid out.
+    pub edges: Vec<(u16, EdgeData)>,
+}
+
+/// A graph we can edit freely before freezing it into a `StaticGraph`. A `StaticGraph`'s edges are
+/// packed into one array so adding or removing one shifts everything after it; here every node
+/// keeps its own list instead. The usual way to get one is to start from the generated model,
+/// change a few edges and freeze it again:
+///
+/// ```ignore
+/// let mut builder = GraphBuilder::generated();
+/// builder.remove_edge(12, 13);
+/// let warp = EdgeData { req, kind: EdgeType::Warp, weight: 1, tricks: TrickMask::NONE };
+/// builder.add_edge(12, 40, warp);
+/// let graph: GenGraph = builder.freeze()?;
+/// ```
+///
+/// Freezing lays the tables out exactly the way the build script does, so freezing a builder
+/// made from a graph without editing it gives back identical arrays, padding and all. Tests can
+/// check this with `StaticGraph::table_mismatch`.
+///
+/// Removing a node leaves a hole so every other node keeps its index while we're editing.
+/// Freezing closes the holes up and drops every edge into them; `frozen_index` says where a node
+/// ends up.
+#[derive(Clone, Debug, Default)]
+pub struct GraphBuilder {
+    // `None` where a node was removed.
+    nodes: Vec<Option<BuilderNode>>,
+    place_data: &'static [PlaceNodeData],
+    item_data: &'static [ItemNodeData],
+    door_data: &'static [DoorNodeData],
+    tricks: &'static [&'static str],
+    // See `StaticGraph::req_trees`.
+    req_trees: Option<Arc<[RequirementNode]>>,
+}
+
+impl GraphBuilder {
+    /// A builder without any nodes or wide metadata.
+    pub fn new() -> Self {
+        GraphBuilder::default()
+    }
+
+    /// A builder holding the generated model from gen.rs.
+    pub fn generated() -> Self {
+        new_static_graph().to_builder()
+    }
+
+    /// The number of nodes, not counting removed ones.
+    pub fn node_count(&self) -> u16 {
+        self.nodes().count() as u16
+    }
+
+    /// The highest node index in use, including removed nodes. New nodes are numbered after it.
+    pub fn max_index(&self) -> u16 {
+        self.nodes.len() as u16
+    }
+
+    /// The number of edges, including any into removed nodes that freezing will drop.
+    pub fn edge_count(&self) -> usize {
+        self.nodes().map(|(_, n)| n.edges.len()).sum()
+    }
+
+    /// `(index, node)` for every node that hasn't been removed, in index order.
+    pub fn nodes(&self) -> impl Iterator<Item = (u16, &BuilderNode)> + '_ {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, n)| Some((i as u16 + 1, n.as_ref()?)))
+    }
+
+    /// Get a node by its index, which like in a `StaticGraph` starts at 1. Removed nodes aren't
+    /// there.
+    pub fn node(&self, node: u16) -> Option<&BuilderNode> {
+        self.nodes.get((node as usize).wrapping_sub(1))?.as_ref()
+    }
+
+    pub fn node_mut(&mut self, node: u16) -> Option<&mut BuilderNode> {
+        self.nodes
+            .get_mut((node as usize).wrapping_sub(1))?
+            .as_mut()
+    }
+
+    /// Add a node without any edges and return its index.
+    pub fn add_node(&mut self, data: NodeData, region: Option<Region>) -> u16 {
+        self.nodes.push(Some(BuilderNode {
+            data,
+            region: region.map_or(0, Region::id),
+            edges: Vec::new(),
+        }));
+
+        self.max_index()
+    }
+
+    /// Remove a node and everything leaving it, returning it if it was there. Edges into it stay
+    /// until we freeze, which drops them.
+    pub fn remove_node(&mut self, node: u16) -> Option<BuilderNode> {
+        self.nodes.get_mut((node as usize).wrapping_sub(1))?.take()
+    }
+
+    /// Add an edge after every edge already leaving `from`.
+    ///
+    /// Panics if `from` isn't in the builder. `to` doesn't have to be there yet, but freezing
+    /// fails if it still isn't.
+    pub fn add_edge(&mut self, from: u16, to: u16, edge: EdgeData) {
+        match self.node_mut(from) {
+            Some(node) => node.edges.push((to, edge)),
+            None => panic!("no node {} to add an edge to", from),
+        }
+    }
+
+    /// Remove the first edge from `from` to `to`, returning its data if there was one.
+    pub fn remove_edge(&mut self, from: u16, to: u16) -> Option<EdgeData> {
+        let edges = &mut self.node_mut(from)?.edges;
+        let i = edges.iter().position(|&(dest, _)| dest == to)?;
+
+        Some(edges.remove(i).1)
+    }
+
+    /// Keep only the edges `keep` returns true for, given each edge's source, destination and
+    /// data, e.g. to drop every edge needing a trick a seed's settings turn off.
+    pub fn retain_edges<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(u16, u16, &EdgeData) -> bool,
+    {
+        for (i, node) in self.nodes.iter_mut().enumerate() {
+            if let Some(node) = node {
+                let from = i as u16 + 1;
+                node.edges.retain(|(to, edge)| keep(from, *to, edge));
+            }
+        }
+    }
+
+    /// Drop every edge with the locked requirement, returning how many there were. See
+    /// `StaticGraph::lock_dead_edges`.
+    pub fn remove_locked_edges(&mut self) -> usize {
+        let before = self.edge_count();
+        self.retain_edges(|_, _, edge| edge.req != ReqIndex::LOCKED);
+
+        before - self.edge_count()
+    }
+
+    /// Get the first edge from `from` to `to`.
+    pub fn edge_mut(&mut self, from: u16, to: u16) -> Option<&mut EdgeData> {
+        self.node_mut(from)?
+            .edges
+            .iter_mut()
+            .find(|(dest, _)| *dest == to)
+            .map(|(_, edge)| edge)
+    }
+
+    /// Append every node and edge of `other`, e.g. a dungeon or custom area modeled on its own,
+    /// and add the `connections` between it and what's already here. Node `n` of `other` becomes
+    /// node `n + offset`, where `offset` is our `max_index` before splicing, and we return the
+    /// offset so callers can find their nodes again.
+    ///
+    /// Wide metadata lives in one set of tables per graph, so `other`'s nodes only keep theirs if
+    /// `other` uses the same tables we do, or we don't have any yet and take `other`'s. Otherwise
+    /// they keep their types but have no wide metadata. Trick annotations work the same way with
+    /// the graphs' lists of trick names. Requirement trees `other` has of its own are added after
+    /// ours, unless they're the same trees, and its edges are pointed at the copies.
+    ///
+    /// Panics if a connection names a node that isn't on the side it says, or if `other`'s trees
+    /// don't fit after ours.
+    pub fn splice<const M: usize, const N: usize, T>(
+        &mut self,
+        other: &StaticGraph<M, N, T>,
+        connections: &[Connection],
+    ) -> u16 {
+        let offset = self.max_index();
+        assert!(
+            offset as usize + other.node_count() as usize <= u16::MAX as usize,
+            "too many nodes to splice"
+        );
+        let mut spliced = other.to_builder();
+        if self.nodes.is_empty() && self.has_no_metadata() {
+            (self.place_data, self.item_data, self.door_data) =
+                (spliced.place_data, spliced.item_data, spliced.door_data);
+        }
+        let same_metadata = ptr::eq(self.place_data, spliced.place_data)
+            && ptr::eq(self.item_data, spliced.item_data)
+            && ptr::eq(self.door_data, spliced.door_data);
+        if self.tricks.is_empty() {
+            self.tricks = spliced.tricks;
+        }
+        let same_tricks = ptr::eq(self.tricks, spliced.tricks);
+        let theirs = other.req_trees();
+        let tree_offset = match theirs.len() > REQ_CONTAINER_LEN && self.req_trees() != theirs {
+            true => {
+                assert!(
+                    self.req_trees().len() + theirs.len() <= u16::MAX as usize,
+                    "too many requirement trees to splice"
+                );
+                self.append_req_trees(theirs) - REQ_CONTAINER_LEN as u16
+            }
+            false => 0,
+        };
+        let trees = self.req_trees();
+        for node in spliced.nodes.iter_mut().flatten() {
+            for (to, edge) in node.edges.iter_mut() {
+                *to += offset;
+                if edge.req.get() as usize >= REQ_CONTAINER_LEN {
+                    edge.req = ReqIndex::new_in(edge.req.get() + tree_offset, trees).unwrap();
+                }
+                if !same_tricks {
+                    // Their trick bits name tricks we don't have.
+                    edge.tricks = TrickMask::NONE;
+                }
+            }
+            if !same_metadata {
+                // Past the end of every table, so the metadata accessors find nothing.
+                node.data.data_index = u16::MAX;
+            }
+        }
+        self.nodes.append(&mut spliced.nodes);
+
+        let (ours, theirs) = (1..=offset, 1..=other.node_count());
+        for &connection in connections {
+            match connection {
+                Connection::Into { from, to, edge } => {
+                    assert!(ours.contains(&from) && theirs.contains(&to));
+                    self.add_edge(from, to + offset, edge);
+                }
+                Connection::OutOf { from, to, edge } => {
+                    assert!(theirs.contains(&from) && ours.contains(&to));
+                    self.add_edge(from + offset, to, edge);
+                }
+            }
+        }
+
+        offset
+    }
+
+    /// The requirement trees our edges' requirements index into. See `StaticGraph::req_trees`.
+    pub fn req_trees(&self) -> &[RequirementNode] {
+        match &self.req_trees {
+            Some(trees) => trees,
+            None => &REQ_CONTAINER.0,
+        }
+    }
+
+    /// Use `trees` for our edges' requirements, e.g. the nodes of a `ReqBuilder` started from
+    /// `REQ_TREES` with rules of our own added to it.
+    ///
+    /// Panics if `trees` doesn't start with the library's trees, since every graph's requirements
+    /// index into those the same way.
+    pub fn set_req_trees(&mut self, trees: &[RequirementNode]) {
+        assert!(
+            trees.starts_with(&REQ_CONTAINER.0),
+            "requirement trees have to start with the library's"
+        );
+        self.req_trees = (trees.len() > REQ_CONTAINER_LEN).then(|| Arc::from(trees));
+    }
+
+    // Add the trees `other` has past the library's to ours and return where they start now.
+    fn append_req_trees(&mut self, other: &[RequirementNode]) -> u16 {
+        let mut trees = self.req_trees().to_vec();
+        let start = trees.len() as u16;
+        let moved = |link: Option<NonZeroU16>| match link {
+            Some(l) if l.get() as usize >= REQ_CONTAINER_LEN => {
+                NonZeroU16::new(l.get() - REQ_CONTAINER_LEN as u16 + start)
+            }
+            link => link,
+        };
+        trees.extend(
+            other[REQ_CONTAINER_LEN..]
+                .iter()
+                .map(|node| RequirementNode {
+                    req: node.req,
+                    and: moved(node.and),
+                    or: moved(node.or),
+                }),
+        );
+        self.set_req_trees(&trees);
+
+        start
+    }
+
+    fn has_no_metadata(&self) -> bool {
+        self.place_data.is_empty() && self.item_data.is_empty() && self.door_data.is_empty()
+    }
+
+    /// The index `node` will have once we freeze, after the nodes removed before it are closed
+    /// up, or `None` if it's been removed.
+    pub fn frozen_index(&self, node: u16) -> Option<u16> {
+        self.node(node)?;
+        Some(self.nodes[..node as usize].iter().flatten().count() as u16)
+    }
+
+    /// Lay the builder out as a `StaticGraph`. Removed nodes are closed up and edges into them
+    /// dropped, edges are numbered from 1 in node order, the terminal node and unused edge 0 are
+    /// left at their defaults and every table is padded the same way gen.rs is.
+    pub fn freeze<const M: usize, const N: usize, T: Default>(
+        &self,
+    ) -> Result<StaticGraph<M, N, T>, GraphError> {
+        let nodes = self.node_count() as usize;
+        // Edges into removed nodes get dropped, so we only know how many edges fit once we lay
+        // them out below.
+        let mut graph = StaticGraph::with_capacity_hint(nodes, 0)?;
+        // Where each index ends up, with 0 for the terminal node and removed nodes.
+        let mut frozen = Vec::with_capacity(self.nodes.len() + 1);
+        frozen.push(0u16);
+        let mut next_node = 0;
+        for node in &self.nodes {
+            next_node += node.is_some() as u16;
+            frozen.push(if node.is_some() { next_node } else { 0 });
+        }
+
+        graph.node_count = nodes as u16;
+        graph.place_data = self.place_data;
+        graph.item_data = self.item_data;
+        graph.door_data = self.door_data;
+        graph.tricks = self.tricks;
+        graph.req_trees = self.req_trees.clone();
+        let node_data = Arc::make_mut(&mut graph.node_data);
+        let region_of = Arc::make_mut(&mut graph.region_of);
+        let edge_data = Arc::make_mut(&mut graph.edge_data);
+        let edge_kinds = Arc::make_mut(&mut graph.edge_kinds);
+        let edge_weights = Arc::make_mut(&mut graph.edge_weights);
+        let edge_tricks = Arc::make_mut(&mut graph.edge_tricks);
+        let mut next_edge = 1;
+        for (i, (_, node)) in self.nodes().enumerate() {
+            graph.node_pointers.0[i + 1] = NonZeroU16::new(next_edge as u16).unwrap();
+            node_data[i + 1] = node.data.clone();
+            region_of[i + 1] = node.region;
+            for &(to, edge) in &node.edges {
+                let to = match frozen.get(to as usize) {
+                    Some(0) if to != 0 => continue,
+                    // The terminal node or a node we never had.
+                    Some(0) | None => return Err(GraphError::BadEdgePointer(next_edge as u16)),
+                    Some(&to) => to,
+                };
+                if next_edge + 1 > N {
+                    return Err(GraphError::TooLarge);
+                }
+                graph.edge_pointers.0[next_edge] = NonZeroU16::new(to).unwrap();
+                edge_data[next_edge] = edge.req;
+                edge_kinds[next_edge] = edge.kind;
+                edge_weights[next_edge] = edge.weight;
+                edge_tricks[next_edge] = edge.tricks.0;
+                next_edge += 1;
+            }
+        }
+        graph.node_pointers.0[nodes + 1] = NonZeroU16::new(next_edge as u16).unwrap();
+        graph.edge_count = next_edge as u16 - 1;
+        graph.validate()?;
+
+        Ok(graph)
+    }
+}
+
+/// An edge between a builder and a graph being spliced into it. See `GraphBuilder::splice`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Connection {
+    /// From a node already in the builder to a node of the spliced graph, numbered as it is in
+    /// that graph.
+    Into { from: u16, to: u16, edge: EdgeData },
+    /// From a node of the spliced graph, numbered as it is in that graph, back out to a node
+    /// already in the builder.
+    OutOf { from: u16, to: u16, edge: EdgeData },
+}
+
+impl<const M: usize, const N: usize, T> StaticGraph<M, N, T> {
+    /// Copy this graph's nodes, edges and metadata into a `GraphBuilder`. Payloads aren't copied.
+    pub fn to_builder(&self) -> GraphBuilder {
+        let nodes = self
+            .nodes()
+            .map(|node| {
+                let (edge_pointers, edge_offset) = self.get_neighbors_out(NonZeroU16::new(node));
+                Some(BuilderNode {
+                    data: self.node_data[node as usize].clone(),
+                    region: self.region_of[node as usize],
+                    edges: edge_pointers
+                        .iter()
+                        .enumerate()
+                        .map(|(i, &dest)| (u16::from(dest), self.edge_data(edge_offset + i as u16)))
+                        .collect(),
+                })
+            })
+            .collect();
+
+        GraphBuilder {
+            nodes,
+            place_data: self.place_data,
+            item_data: self.item_data,
+            door_data: self.door_data,
+            tricks: self.tricks,
+            req_trees: self.req_trees.clone(),
+        }
+    }
+
+    /// Find the first place this graph's tables differ from `other`'s, including padding and the
+    /// unused entries at index 0. Payloads aren't compared and the wide metadata tables only
+    /// match if they're the same tables.
+    ///
+    /// This is stricter than `structurally_equal` and meant for tests that check something
+    /// reproduces a graph exactly, e.g. a `GraphBuilder` round trip.
+    pub fn table_mismatch<U>(&self, other: &StaticGraph<M, N, U>) -> Option<TableMismatch> {
+        fn first<A: PartialEq>(table: &'static str, a: &[A], b: &[A]) -> Option<TableMismatch> {
+            let index = a.iter().zip(b).position(|(a, b)| a != b)?;
+            Some(TableMismatch { table, index })
+        }
+        let same = |table: &'static str, same: bool| match same {
+            true => None,
+            false => Some(TableMismatch { table, index: 0 }),
+        };
+
+        same("node_count", self.node_count == other.node_count)
+            .or_else(|| same("edge_count", self.edge_count == other.edge_count))
+            .or_else(|| {
+                first(
+                    "node_pointers",
+                    &self.node_pointers.0[..],
+                    &other.node_pointers.0[..],
+                )
+            })
+            .or_else(|| first("node_data", &self.node_data[..], &other.node_data[..]))
+            .or_else(|| {
+                first(
+                    "edge_pointers",
+                    &self.edge_pointers.0[..],
+                    &other.edge_pointers.0[..],
+                )
+            })
+            .or_else(|| first("edge_data", &self.edge_data[..], &other.edge_data[..]))
+            .or_else(|| first("edge_kinds", &self.edge_kinds[..], &other.edge_kinds[..]))
+            .or_else(|| {
+                first(
+                    "edge_weights",
+                    &self.edge_weights[..],
+                    &other.edge_weights[..],
+                )
+            })
+            .or_else(|| first("edge_tricks", &self.edge_tricks[..], &other.edge_tricks[..]))
+            .or_else(|| first("region_of", &self.region_of[..], &other.region_of[..]))
+            .or_else(|| same("place_data", ptr::eq(self.place_data, other.place_data)))
+            .or_else(|| same("item_data", ptr::eq(self.item_data, other.item_data)))
+            .or_else(|| same("door_data", ptr::eq(self.door_data, other.door_data)))
+            .or_else(|| same("tricks", ptr::eq(self.tricks, other.tricks)))
+            .or_else(|| first("req_trees", self.req_trees(), other.req_trees()))
+            .or_else(|| {
+                same(
+                    "req_trees",
+                    self.req_trees().len() == other.req_trees().len(),
+                )
+            })
+    }
+}
+
+/// Where two graphs' tables first differ. See `StaticGraph::table_mismatch`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TableMismatch {
+    pub table: &'static str,
+    pub index: usize,
+}
+
+impl fmt::Display for TableMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}] differs", self.table, self.index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        constants::*,
+        diff::EdgeChange,
+        graph::{EdgeType, NodeType},
+        logic::{CollectionState, ReqIndex},
+    };
+
+    type Graph = StaticGraph<NUM_VERTICES_PADDED, NUM_EDGES_PADDED>;
+
+    #[test]
+    fn builder_round_trip() {
+        let graph = new_static_graph();
+        let mut builder = GraphBuilder::generated();
+        assert_eq!(builder.node_count(), graph.node_count());
+        assert_eq!(builder.edge_count(), graph.edge_count() as usize);
+        let frozen: Graph = builder.freeze().unwrap();
+        assert_eq!(frozen.table_mismatch(&graph), None);
+        assert!(frozen.is_validated());
+
+        // Tweak one edge and it's the only change.
+        let (from, to) = (1, builder.node(1).unwrap().edges[0].0);
+        builder.edge_mut(from, to).unwrap().req = ReqIndex::LOCKED;
+        let tweaked: Graph = builder.freeze().unwrap();
+        assert_eq!(
+            tweaked.table_mismatch(&graph),
+            Some(TableMismatch {
+                table: "edge_data",
+                index: 1
+            })
+        );
+        assert_eq!(graph.diff(&tweaked).changes.len(), 1);
+
+        // Moving an edge to the end of its node's list reorders it and nothing else.
+        let edge = builder.remove_edge(from, to).unwrap();
+        builder.add_edge(from, to, edge);
+        let moved: Graph = builder.freeze().unwrap();
+        assert!(moved.diff(&tweaked).is_empty());
+        assert_eq!(
+            moved.to_builder().node(from).unwrap().edges.last(),
+            Some(&(to, edge))
+        );
+
+        // New nodes go after the generated ones and edges can point at them.
+        let node = builder.add_node(
+            NodeData {
+                node_type: NodeType::Place,
+                data_index: 0,
+            },
+            Some(Region::DarkWorld),
+        );
+        assert_eq!(node, graph.node_count() + 1);
+        let warp = EdgeData {
+            req: ReqIndex::OPEN,
+            kind: EdgeType::Warp,
+            weight: 1,
+            tricks: TrickMask::NONE,
+        };
+        builder.add_edge(from, node, warp);
+        let grown: Graph = builder.freeze().unwrap();
+        assert_eq!(grown.region_of(node), Some(Region::DarkWorld));
+        assert!(graph.diff(&grown).changes.contains(&EdgeChange::Added {
+            from,
+            to: node,
+            req: ReqIndex::OPEN
+        }));
+        assert!(grown.dfs_iter().any(|n| u16::from(n) == node));
+
+        builder.add_edge(node, node + 1, warp);
+        assert_eq!(
+            builder
+                .freeze::<NUM_VERTICES_PADDED, NUM_EDGES_PADDED, ()>()
+                .err(),
+            Some(GraphError::BadEdgePointer(graph.edge_count() + 2))
+        );
+        assert_eq!(
+            builder.freeze::<64, 64, ()>().err(),
+            Some(GraphError::TooLarge)
+        );
+    }
+
+    #[test]
+    fn splice_requirement_trees() {
+        type Small = StaticGraph<64, 64>;
+        let ours = Small::from_edge_list(["1 2 boots & flute", "2 1"]).unwrap();
+        let theirs = Small::from_edge_list(["1 2 hammer & gloves", "2 3 boots & flute"]).unwrap();
+        let mut builder = ours.to_builder();
+        let edge = EdgeData {
+            req: ReqIndex::OPEN,
+            kind: EdgeType::Normal,
+            weight: 1,
+            tricks: TrickMask::NONE,
+        };
+        let offset = builder.splice(
+            &theirs,
+            &[Connection::Into {
+                from: 2,
+                to: 1,
+                edge,
+            }],
+        );
+        let spliced: Small = builder.freeze().unwrap();
+        // Their trees went in after ours and every edge still needs what it did.
+        let needs = |graph: &Small, from: u16, to: u16| {
+            let req = graph.to_builder().edge_mut(from, to).unwrap().req;
+            graph.requirement_tree(req).to_string()
+        };
+        assert_eq!(needs(&spliced, 1, 2), needs(&ours, 1, 2));
+        assert_eq!(needs(&spliced, 1 + offset, 2 + offset), "Hammer & Gloves");
+        assert_eq!(needs(&spliced, 2 + offset, 3 + offset), needs(&ours, 1, 2));
+        let both = ours.req_trees().len() + theirs.req_trees().len() - REQ_CONTAINER_LEN;
+        assert_eq!(spliced.req_trees().len(), both);
+
+        // Without the trees the edges using them are rejected.
+        builder.set_req_trees(&REQ_CONTAINER.0);
+        assert!(matches!(
+            builder.freeze::<64, 64, ()>(),
+            Err(GraphError::BadRequirement(_))
+        ));
+    }
+
+    #[test]
+    fn splice_graphs() {
+        let graph = new_static_graph();
+        let dungeon =
+            StaticGraph::<64, 64>::from_edge_list(["1 2", "2 3 flute", "3 1", "2 4"]).unwrap();
+        let open = EdgeData {
+            req: ReqIndex::OPEN,
+            kind: EdgeType::Door,
+            weight: 1,
+            tricks: TrickMask::NONE,
+        };
+        let mut builder = GraphBuilder::generated();
+        let offset = builder.splice(
+            &dungeon,
+            &[
+                Connection::Into {
+                    from: 1,
+                    to: 1,
+                    edge: open,
+                },
+                Connection::OutOf {
+                    from: 4,
+                    to: 1,
+                    edge: open,
+                },
+            ],
+        );
+        assert_eq!(offset, graph.node_count());
+        let spliced: Graph = builder.freeze().unwrap();
+        assert_eq!(spliced.node_count(), graph.node_count() + 4);
+        assert_eq!(
+            spliced.edge_count(),
+            graph.edge_count() + dungeon.edge_count() + 2
+        );
+
+        // The dungeon comes through intact, just renumbered, and is reachable from the root.
+        let inside: Vec<u16> = (1..=4).map(|n| n + offset).collect();
+        assert_eq!(
+            spliced.isomorphism(&inside, &dungeon, &[1, 2, 3, 4]),
+            Some(alloc::vec![1, 2, 3, 4])
+        );
+        let reached: Vec<u16> = spliced
+            .dfs_iter_with_state(CollectionState::ALL_ITEMS)
+            .map(u16::from)
+            .collect();
+        assert!(inside.iter().all(|n| reached.contains(n)));
+        assert_eq!(spliced.node_name(offset + 1), None);
+        assert_eq!(spliced.node_name(1), graph.node_name(1));
+
+        // Splicing into an empty builder is just a copy, metadata and all.
+        let mut empty = GraphBuilder::new();
+        assert_eq!(empty.splice(&graph, &[]), 0);
+        let copy: Graph = empty.freeze().unwrap();
+        assert_eq!(copy.table_mismatch(&graph), None);
+    }
+
+    #[test]
+    fn remove_and_compact() {
+        type Small = StaticGraph<64, 64>;
+        let graph = Small::from_edge_list(["1 2", "2 3", "3 4", "1 4", "2 4 locked"]).unwrap();
+        let mut builder = graph.to_builder();
+        assert!(builder.remove_node(3).is_some());
+        assert!(builder.remove_node(3).is_none());
+        assert_eq!(builder.node_count(), 3);
+        assert_eq!(builder.max_index(), 4);
+        // The edge into the removed node is still here until we freeze.
+        assert_eq!(builder.edge_count(), 4);
+        builder.retain_edges(|_, _, edge| edge.req != ReqIndex::LOCKED);
+        assert_eq!(builder.edge_count(), 3);
+        assert_eq!(builder.frozen_index(3), None);
+        assert_eq!(builder.frozen_index(4), Some(3));
+
+        let compacted: Small = builder.freeze().unwrap();
+        let expected = Small::from_edge_list(["1 2", "1 3"]).unwrap();
+        assert!(compacted.structurally_equal(&expected));
+        assert!(compacted.is_validated());
+
+        // New nodes still go after the removed one's index.
+        let node = builder.add_node(NodeData::DEFAULT, None);
+        assert_eq!(node, 5);
+        let walk = EdgeData {
+            req: ReqIndex::OPEN,
+            kind: EdgeType::Normal,
+            weight: 1,
+            tricks: TrickMask::NONE,
+        };
+        builder.add_edge(node, 3, walk);
+        builder.add_edge(4, node, walk);
+        let grown: Small = builder.freeze().unwrap();
+        let expected = Small::from_edge_list(["1 2", "1 3", "3 4"]).unwrap();
+        assert!(grown.structurally_equal(&expected));
+    }
+}
@@ -0,0 +1,283 @@
+use alloc::{sync::Arc, vec, vec::Vec};
+use core::{fmt, num::NonZeroU16};
+
+use crate::{
+    graph::{GraphError, StaticGraph},
+    logic::{ReqIndex, Requirement, RequirementNode, REQ_CONTAINER, REQ_CONTAINER_LEN},
+    parser::MAX_OPERATORS,
+};
+
+/// A minimal binary encoding of a graph's structure and logic so a graph can be built at runtime,
+/// e.g. by a web tracker that downloads its world model. Everything is a little-endian u16:
+///
+/// ```text
+/// magic ("SG" then format version 1), node count, edge count,
+/// node pointers for node indexes 0..=node_count + 1,
+/// edge pointers for edge indexes 1..=edge_count,
+/// requirement indexes for edge indexes 1..=edge_count,
+/// then only if the graph has requirement trees past the library's:
+/// tree node count, (requirement id, and link, or link) for each of those nodes
+/// ```
+///
+/// Tree nodes are numbered on from the library's, a link of 0 means none and links can only point
+/// at earlier nodes, so reading untrusted trees can't make evaluation loop. No path through a tree
+/// can be longer than one built from an expression with `parser::MAX_OPERATORS` operators, so
+/// displaying one can't overflow the stack either.
+///
+/// Only the hot arrays are encoded. A graph read from bytes has default node data, no wide
+/// metadata or regions and normal edges with a weight of one.
+pub const BYTES_MAGIC: [u8; 4] = [b'S', b'G', 1, 0];
+
+/// Why `StaticGraph::from_bytes` rejected its input.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BytesError {
+    /// The input doesn't start with `BYTES_MAGIC`.
+    BadMagic,
+    /// The input is shorter or longer than its header says it should be.
+    BadLength,
+    /// The graph has more nodes or edges than this `StaticGraph` can hold.
+    TooLarge,
+    /// A node pointer is out of order or points past the last edge.
+    BadNodePointer(u16),
+    /// An edge points at a node that isn't in the graph.
+    BadEdgePointer(u16),
+    /// An edge's requirement index is past the graph's requirement trees.
+    BadRequirement(u16),
+    /// A requirement tree node has an unknown requirement, a link that doesn't point at an
+    /// earlier node or a path through it longer than any expression builds.
+    BadTree(u16),
+}
+
+impl fmt::Display for BytesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BytesError::BadMagic => write!(f, "not a sparsegraph byte encoding"),
+            BytesError::BadLength => write!(f, "length doesn't match the header"),
+            BytesError::TooLarge => write!(f, "graph is too large"),
+            BytesError::BadNodePointer(n) => write!(f, "bad pointer for node {}", n),
+            BytesError::BadEdgePointer(e) => write!(f, "edge {} points outside the graph", e),
+            BytesError::BadRequirement(e) => write!(f, "edge {} has a bad requirement", e),
+            BytesError::BadTree(n) => write!(f, "requirement tree node {} is malformed", n),
+        }
+    }
+}
+
+impl From<GraphError> for BytesError {
+    fn from(e: GraphError) -> Self {
+        match e {
+            GraphError::TooLarge => BytesError::TooLarge,
+            GraphError::BadNodePointer(n) => BytesError::BadNodePointer(n),
+            GraphError::BadEdgePointer(e) => BytesError::BadEdgePointer(e),
+            GraphError::BadRequirement(e) => BytesError::BadRequirement(e),
+        }
+    }
+}
+
+impl<const M: usize, const N: usize, T> StaticGraph<M, N, T> {
+    /// Encode this graph's structure and logic. See `BYTES_MAGIC` for the format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let nodes = self.node_count as usize;
+        let edges = self.edge_count as usize;
+        let mut bytes = Vec::with_capacity(8 + (nodes + 2 + edges * 2) * 2);
+        bytes.extend_from_slice(&BYTES_MAGIC);
+        bytes.extend_from_slice(&self.node_count.to_le_bytes());
+        bytes.extend_from_slice(&self.edge_count.to_le_bytes());
+        (0..nodes + 2).for_each(|i| {
+            bytes.extend_from_slice(&u16::from(self.node_pointers[i as u16]).to_le_bytes())
+        });
+        (1..=edges).for_each(|i| {
+            bytes.extend_from_slice(&u16::from(self.edge_pointers[i as u16]).to_le_bytes())
+        });
+        (1..=edges).for_each(|i| bytes.extend_from_slice(&self.edge_data[i].get().to_le_bytes()));
+        let extra = &self.req_trees()[REQ_CONTAINER_LEN..];
+        if !extra.is_empty() {
+            bytes.extend_from_slice(&(extra.len() as u16).to_le_bytes());
+            for node in extra {
+                let link = |l: Option<NonZeroU16>| l.map_or(0, u16::from);
+                for word in [node.req.id(), link(node.and), link(node.or)] {
+                    bytes.extend_from_slice(&word.to_le_bytes());
+                }
+            }
+        }
+
+        bytes
+    }
+}
+
+impl<const M: usize, const N: usize, T: Default> StaticGraph<M, N, T> {
+    /// Build a graph from bytes produced by `to_bytes`, checking that it's well formed.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BytesError> {
+        if bytes.len() < 8 || bytes[..4] != BYTES_MAGIC {
+            return Err(BytesError::BadMagic);
+        }
+        let words: Vec<u16> = bytes[4..]
+            .chunks(2)
+            .map(|c| match c {
+                [lo, hi] => Ok(u16::from_le_bytes([*lo, *hi])),
+                _ => Err(BytesError::BadLength),
+            })
+            .collect::<Result<_, _>>()?;
+        let (node_count, edge_count) = (words[0], words[1]);
+        let (nodes, edges) = (node_count as usize, edge_count as usize);
+        let trees_start = 2 + nodes + 2 + edges * 2;
+        let tree_words = match words.get(trees_start..) {
+            Some([]) => &[][..],
+            Some([count, tree_words @ ..]) if tree_words.len() == *count as usize * 3 => tree_words,
+            _ => return Err(BytesError::BadLength),
+        };
+        let mut graph = StaticGraph::with_capacity_hint(nodes, edges)?;
+        let node_words = &words[2..nodes + 4];
+        let edge_words = &words[nodes + 4..nodes + 4 + edges];
+        let req_words = &words[nodes + 4 + edges..trees_start];
+        if !tree_words.is_empty() {
+            graph.req_trees = Some(read_trees(tree_words)?);
+        }
+
+        graph.node_count = node_count;
+        graph.edge_count = edge_count;
+        // The terminal node has no edges and every pointer has to fall within the edge array,
+        // whose end has to fit in a pointer too.
+        let end = edge_count.checked_add(1).ok_or(BytesError::TooLarge)?;
+        let mut previous = 1;
+        for (i, &p) in node_words.iter().enumerate() {
+            let last = i == nodes + 1;
+            if p < previous || p > end || (i == 0 && p != 1) || (last && p != end) {
+                return Err(BytesError::BadNodePointer(i as u16));
+            }
+            previous = p;
+            graph.node_pointers.0[i] = NonZeroU16::new(p).unwrap();
+        }
+        // Padding nodes have no edges either.
+        graph.node_pointers.0[nodes + 2..].fill(NonZeroU16::new(end).unwrap());
+        for (i, (&dest, &req)) in edge_words.iter().zip(req_words.iter()).enumerate() {
+            let edge = i + 1;
+            graph.edge_pointers.0[edge] = NonZeroU16::new(dest)
+                .filter(|_| dest <= node_count)
+                .ok_or(BytesError::BadEdgePointer(edge as u16))?;
+            Arc::make_mut(&mut graph.edge_data)[edge] = ReqIndex::new_in(req, graph.req_trees())
+                .ok_or(BytesError::BadRequirement(edge as u16))?;
+        }
+        graph.validate()?;
+
+        Ok(graph)
+    }
+}
+
+// The library's trees followed by the ones encoded in `words`, checking every link points back
+// and no path is longer than an expression could make it.
+fn read_trees(words: &[u16]) -> Result<Arc<[RequirementNode]>, BytesError> {
+    let mut trees = REQ_CONTAINER.0.to_vec();
+    // The longest path from each node, counting the node. The library's trees link forwards as
+    // well as back, but there are only a handful of them.
+    let mut lengths = vec![1; trees.len()];
+    for _ in 0..trees.len() {
+        for (i, node) in trees.iter().enumerate() {
+            lengths[i] = path_length(node, &lengths);
+        }
+    }
+    for node in words.chunks(3) {
+        let idx = u16::try_from(trees.len()).map_err(|_| BytesError::BadTree(u16::MAX))?;
+        let link = |l: u16| match l < idx {
+            true => Ok(NonZeroU16::new(l)),
+            false => Err(BytesError::BadTree(idx)),
+        };
+        let node = RequirementNode {
+            req: Requirement::from_id(node[0]).ok_or(BytesError::BadTree(idx))?,
+            and: link(node[1])?,
+            or: link(node[2])?,
+        };
+        let length = path_length(&node, &lengths);
+        if length > MAX_OPERATORS + 1 {
+            return Err(BytesError::BadTree(idx));
+        }
+        trees.push(node);
+        lengths.push(length);
+    }
+
+    Ok(trees.into())
+}
+
+fn path_length(node: &RequirementNode, lengths: &[usize]) -> usize {
+    let links = [node.and, node.or].into_iter().flatten();
+    1 + links.map(|l| lengths[l.get() as usize]).max().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{constants::*, graph::new_static_graph};
+
+    #[test]
+    fn bytes_round_trip() {
+        let graph = new_static_graph();
+        let bytes = graph.to_bytes();
+        let read =
+            StaticGraph::<NUM_VERTICES_PADDED, NUM_EDGES_PADDED>::from_bytes(&bytes).unwrap();
+        assert_eq!(read.to_bytes(), bytes);
+        for node in 1..=NUM_VERTICES as u16 {
+            let n = NonZeroU16::new(node);
+            assert_eq!(graph.get_neighbors_out(n), read.get_neighbors_out(n));
+        }
+        let expected: Vec<u16> = graph.dfs_iter().map(u16::from).collect();
+        let actual: Vec<u16> = read.dfs_iter().map(u16::from).collect();
+        assert_eq!(expected, actual);
+
+        let from_bytes = StaticGraph::<NUM_VERTICES_PADDED, NUM_EDGES_PADDED>::from_bytes;
+        assert_eq!(from_bytes(&bytes[1..]).err(), Some(BytesError::BadMagic));
+        assert_eq!(
+            from_bytes(&bytes[..bytes.len() - 2]).err(),
+            Some(BytesError::BadLength)
+        );
+        let mut bad_req = bytes.clone();
+        let last = bad_req.len() - 2;
+        bad_req[last] = 0xFF;
+        assert_eq!(
+            from_bytes(&bad_req).err(),
+            Some(BytesError::BadRequirement(NUM_EDGES as u16))
+        );
+        assert_eq!(
+            StaticGraph::<64, 64>::from_bytes(&bytes).err(),
+            Some(BytesError::TooLarge)
+        );
+
+        // A tree node linking to itself or a later node could make evaluating it loop forever.
+        let trees = StaticGraph::<64, 64>::from_edge_list(["1 2 boots & flute"]).unwrap();
+        let last_node = trees.req_trees().len() as u16 - 1;
+        let mut bad_link = trees.to_bytes();
+        let last = bad_link.len() - 2;
+        bad_link[last..].copy_from_slice(&last_node.to_le_bytes());
+        assert_eq!(
+            StaticGraph::<64, 64>::from_bytes(&bad_link).err(),
+            Some(BytesError::BadTree(last_node))
+        );
+        // So would a chain of trees too long to display.
+        let mut chain = StaticGraph::<64, 64>::from_edge_list(["1 2"])
+            .unwrap()
+            .to_bytes();
+        let count = MAX_OPERATORS as u16 + 2;
+        chain.extend_from_slice(&count.to_le_bytes());
+        for i in 0..count {
+            let link = match i {
+                0 => 0,
+                _ => REQ_CONTAINER_LEN as u16 + i - 1,
+            };
+            for word in [Requirement::Boots.id(), link, 0] {
+                chain.extend_from_slice(&word.to_le_bytes());
+            }
+        }
+        let last_node = REQ_CONTAINER_LEN as u16 + count - 1;
+        assert_eq!(
+            StaticGraph::<64, 64>::from_bytes(&chain).err(),
+            Some(BytesError::BadTree(last_node))
+        );
+
+        // Big enough for 65535 edges, but the pointer past the last of them wouldn't fit in a u16.
+        let mut too_many_edges = BYTES_MAGIC.to_vec();
+        too_many_edges.extend_from_slice(&[0, 0, 0xFF, 0xFF, 1, 0, 0, 0]);
+        too_many_edges.resize(too_many_edges.len() + 0xFFFF * 4, 0);
+        assert_eq!(
+            StaticGraph::<4, 0x10000>::from_bytes(&too_many_edges).err(),
+            Some(BytesError::TooLarge)
+        );
+    }
+}
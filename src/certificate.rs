@@ -0,0 +1,318 @@
+// Proofs that a node is reachable which can be checked without searching the graph, e.g. to test
+// our logic against another randomizer's engine.
+use alloc::{vec, vec::Vec};
+use core::{fmt, num::NonZeroU16};
+
+use crate::{
+    edge_iter::TraversedEdge,
+    graph::StaticGraph,
+    logic::{CollectionState, EvaluateLogic, ReqTreeView, Requirement},
+    parser::{requirement, write_requirement_name},
+    traversal::WalkOrder,
+};
+
+/// One edge of a certificate's path and the requirements that open it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CertifiedEdge {
+    pub src: u16,
+    /// The edge's index into the graph's edge arrays.
+    pub edge: u16,
+    pub dst: u16,
+    /// Requirements that hold under the certified state and are enough on their own to satisfy
+    /// the edge's requirement tree. Open edges have none.
+    pub leaves: Vec<Requirement>,
+}
+
+/// A path from the root to a node with the requirements that open each edge along it. See
+/// `StaticGraph::certify` and `verify_certificate`.
+///
+/// Its `Display` is a plain text encoding that `parse_certificate` reads back, one edge per line
+/// after the target, with leaves named as `parse_requirement` names them:
+///
+/// ```text
+/// target 9
+/// 1 -> 4 via 2: open
+/// 4 -> 9 via 7: hammer & normal
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Certificate {
+    pub target: u16,
+    pub edges: Vec<CertifiedEdge>,
+}
+
+/// Why a certificate didn't check out. Edges are numbered from zero in path order and lines from
+/// one.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CertificateError {
+    /// A line of the text encoding that isn't a target or an edge.
+    Malformed(usize),
+    /// The path doesn't start at the root, an edge doesn't start where the last one ended or the
+    /// path ends somewhere other than the target.
+    Disconnected(usize),
+    /// The graph has no such edge between those nodes.
+    NotAnEdge(usize),
+    /// One of the edge's leaves doesn't hold under the state.
+    UnmetLeaf(usize),
+    /// The edge's leaves aren't enough to satisfy its requirement.
+    Insufficient(usize),
+}
+
+impl fmt::Display for CertificateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CertificateError::Malformed(line) => write!(f, "can't read line {}", line),
+            CertificateError::Disconnected(e) => write!(f, "path breaks at edge {}", e),
+            CertificateError::NotAnEdge(e) => write!(f, "edge {} isn't in the graph", e),
+            CertificateError::UnmetLeaf(e) => write!(f, "edge {} has an unmet leaf", e),
+            CertificateError::Insufficient(e) => write!(f, "edge {} isn't opened", e),
+        }
+    }
+}
+
+impl fmt::Display for Certificate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "target {}", self.target)?;
+        for e in self.edges.iter() {
+            write!(f, "{} -> {} via {}: ", e.src, e.dst, e.edge)?;
+            match e.leaves.split_first() {
+                None => write!(f, "open")?,
+                Some((first, rest)) => {
+                    write_requirement_name(f, *first)?;
+                    for leaf in rest {
+                        write!(f, " & ")?;
+                        write_requirement_name(f, *leaf)?;
+                    }
+                }
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Read a certificate back from its `Display` encoding. Blank lines are skipped.
+pub fn parse_certificate(text: &str) -> Result<Certificate, CertificateError> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty());
+    let target = match lines.next() {
+        Some((at, line)) => line
+            .strip_prefix("target ")
+            .and_then(|n| n.trim().parse().ok())
+            .ok_or(CertificateError::Malformed(at))?,
+        None => return Err(CertificateError::Malformed(1)),
+    };
+    let edges = lines
+        .map(|(at, line)| parse_edge(line).ok_or(CertificateError::Malformed(at)))
+        .collect::<Result<_, _>>()?;
+
+    Ok(Certificate { target, edges })
+}
+
+fn parse_edge(line: &str) -> Option<CertifiedEdge> {
+    let (path, leaves) = line.split_once(':')?;
+    let mut words = path.split_whitespace();
+    let src = words.next()?.parse().ok()?;
+    let dst = words.nth(1)?.parse().ok()?;
+    let edge = words.nth(1)?.parse().ok()?;
+    let leaves = leaves
+        .split('&')
+        .map(|word| requirement(word.trim()))
+        .filter(|&req| req != Some(Requirement::Open))
+        .collect::<Option<_>>()?;
+
+    Some(CertifiedEdge {
+        src,
+        edge,
+        dst,
+        leaves,
+    })
+}
+
+impl<const M: usize, const N: usize, T> StaticGraph<M, N, T> {
+    /// A certificate that `target` is reachable from the root under `state`, or `None` if it
+    /// isn't. The path is a shortest one and each edge's leaves are a minimal set of the
+    /// requirements in its tree that `state` meets, so a certificate only claims what it needs.
+    pub fn certify(&self, target: u16, state: &CollectionState) -> Option<Certificate> {
+        let mut parent = vec![None; self.node_count as usize + 1];
+        if target != 1 {
+            self.edges_from(1, WalkOrder::BreadthFirst, state.clone())
+                .find(|&e| {
+                    parent[e.dst as usize] = Some(e);
+                    e.dst == target
+                })?;
+        }
+        let mut edges = Vec::new();
+        let mut node = target;
+        while let Some(TraversedEdge { src, edge, dst }) = parent[node as usize] {
+            edges.push(CertifiedEdge {
+                src,
+                edge,
+                dst,
+                leaves: minimal_leaves(state, self.requirement_tree(self.edge_data(edge).req)),
+            });
+            node = src;
+        }
+        edges.reverse();
+
+        Some(Certificate { target, edges })
+    }
+}
+
+/// Check `certificate` against `graph` and `state` without searching: the path has to be made of
+/// real edges from the root to the target and every edge's leaves have to hold under `state` and
+/// satisfy the edge's requirement by themselves.
+pub fn verify_certificate<const M: usize, const N: usize, T>(
+    graph: &StaticGraph<M, N, T>,
+    state: &CollectionState,
+    certificate: &Certificate,
+) -> Result<(), CertificateError> {
+    let mut at = 1;
+    for (i, e) in certificate.edges.iter().enumerate() {
+        if e.src != at {
+            return Err(CertificateError::Disconnected(i));
+        }
+        if e.dst == 0 || e.dst > graph.node_count {
+            return Err(CertificateError::NotAnEdge(i));
+        }
+        let (edge_pointers, edge_offset) = graph.get_neighbors_out(NonZeroU16::new(e.src));
+        let is_edge = e
+            .edge
+            .checked_sub(edge_offset)
+            .and_then(|i| edge_pointers.get(i as usize))
+            .is_some_and(|&dst| dst.get() == e.dst);
+        if !is_edge {
+            return Err(CertificateError::NotAnEdge(i));
+        }
+        if !e.leaves.iter().all(|&leaf| state.eval_requirement(leaf)) {
+            return Err(CertificateError::UnmetLeaf(i));
+        }
+        let granted = Granted {
+            state,
+            leaves: &e.leaves,
+        };
+        if !graph
+            .requirement_tree(graph.edge_data(e.edge).req)
+            .eval(&granted)
+        {
+            return Err(CertificateError::Insufficient(i));
+        }
+        at = e.dst;
+    }
+    match at == certificate.target {
+        true => Ok(()),
+        false => Err(CertificateError::Disconnected(certificate.edges.len())),
+    }
+}
+
+// Evaluates trees as if only `leaves` held, along with `Open`.
+struct Granted<'a> {
+    state: &'a CollectionState,
+    leaves: &'a [Requirement],
+}
+
+impl EvaluateLogic for Granted<'_> {
+    fn collection_state(&self) -> &CollectionState {
+        self.state
+    }
+
+    fn eval_requirement(&self, req: Requirement) -> bool {
+        req == Requirement::Open || self.leaves.contains(&req)
+    }
+}
+
+// Every requirement in `req`'s tree that `state` meets, less any the tree can do without. We
+// drop leaves one at a time while the rest still satisfy the tree, so no single leaf is spare.
+fn minimal_leaves(state: &CollectionState, req: ReqTreeView) -> Vec<Requirement> {
+    let mut leaves: Vec<Requirement> = req
+        .requirements()
+        .into_iter()
+        .filter(|&leaf| leaf != Requirement::Open && state.eval_requirement(leaf))
+        .collect();
+
+    let mut i = 0;
+    while i < leaves.len() {
+        let leaf = leaves.remove(i);
+        let granted = Granted {
+            state,
+            leaves: &leaves,
+        };
+        if !req.eval(&granted) {
+            leaves.insert(i, leaf);
+            i += 1;
+        }
+    }
+
+    leaves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::new_static_graph;
+
+    #[test]
+    fn certificates() {
+        let graph = new_static_graph();
+        let state = CollectionState::ALL_ITEMS;
+        let reached: Vec<u16> = graph
+            .bfs_iter_with_state(state.clone())
+            .map(u16::from)
+            .collect();
+        // Every search allocates its caches, so a sample of targets keeps this quick.
+        for &node in reached.iter().step_by(reached.len() / 16 + 1) {
+            let certificate = graph.certify(node, &state).unwrap();
+            assert_eq!(verify_certificate(&graph, &state, &certificate), Ok(()));
+            let text = certificate.to_string();
+            assert_eq!(parse_certificate(&text), Ok(certificate));
+        }
+
+        // Something behind an item, certified with the item and checked without it.
+        let without_gloves = CollectionState::default();
+        let reached_without: Vec<u16> = graph
+            .bfs_iter_with_state(without_gloves.clone())
+            .map(u16::from)
+            .collect();
+        let target = reached
+            .iter()
+            .copied()
+            .find(|n| !reached_without.contains(n))
+            .unwrap();
+        assert_eq!(graph.certify(target, &without_gloves), None);
+        let certificate = graph.certify(target, &state).unwrap();
+        assert!(certificate
+            .edges
+            .iter()
+            .any(|e| e.leaves.contains(&Requirement::Gloves)));
+        assert!(matches!(
+            verify_certificate(&graph, &without_gloves, &certificate),
+            Err(CertificateError::UnmetLeaf(_))
+        ));
+
+        // Claiming less than an edge needs, or a path with a gap, doesn't verify.
+        let mut forged = certificate.clone();
+        let gloves = forged
+            .edges
+            .iter()
+            .position(|e| e.leaves.contains(&Requirement::Gloves))
+            .unwrap();
+        forged.edges[gloves].leaves.clear();
+        assert_eq!(
+            verify_certificate(&graph, &state, &forged),
+            Err(CertificateError::Insufficient(gloves))
+        );
+        let mut forged = certificate.clone();
+        forged.edges.remove(0);
+        assert_eq!(
+            verify_certificate(&graph, &state, &forged),
+            Err(CertificateError::Disconnected(0))
+        );
+        assert_eq!(
+            parse_certificate("target 3\n1 -> 2 via x: open"),
+            Err(CertificateError::Malformed(2))
+        );
+    }
+}
@@ -0,0 +1,282 @@
+// Structure-only reachability answered without searching, for ruling out targets before a
+// logic-aware search.
+use alloc::{boxed::Box, vec, vec::Vec};
+use core::num::NonZeroU16;
+
+use crate::graph::StaticGraph;
+
+/// Which nodes can reach which ignoring logic, as if every edge were open. Logic can only ever
+/// remove edges, so a node this says is out of reach is out of reach under every collection
+/// state and a logic-aware search for it can be skipped. See `StaticGraph::closure`.
+///
+/// Storing a whole reachability matrix for tens of thousands of nodes would take hundreds of
+/// megabytes, so we compress it the usual way. Every strongly connected component collapses to
+/// one node, leaving a DAG, and we number the DAG in postorder along a spanning forest so each
+/// component reaches one interval of numbers through its tree. What it reaches through other
+/// edges is stored as a few more intervals, merged wherever they touch. Most components in a
+/// world model need only a handful, so a query is a couple of lookups and a short binary search.
+#[derive(Clone, Debug)]
+pub struct Closure {
+    // The component of every node, indexed by node.
+    component: Box<[u16]>,
+    // Every component's postorder number in the spanning forest.
+    post: Box<[u16]>,
+    // Each component's intervals are `intervals[offsets[c]..offsets[c + 1]]`, sorted and disjoint.
+    offsets: Box<[u32]>,
+    intervals: Box<[(u16, u16)]>,
+}
+
+impl Closure {
+    /// Whether `to` is reachable from `from` along any edges at all. Every node reaches itself.
+    pub fn reaches(&self, from: u16, to: u16) -> bool {
+        let (from, to) = (self.component[from as usize], self.component[to as usize]);
+        if from == to {
+            return true;
+        }
+        let post = self.post[to as usize];
+        let intervals = self.intervals_of(from);
+        match intervals.partition_point(|&(start, _)| start <= post) {
+            0 => false,
+            i => intervals[i - 1].1 >= post,
+        }
+    }
+
+    /// Whether `a` and `b` can each reach the other.
+    pub fn strongly_connected(&self, a: u16, b: u16) -> bool {
+        self.component[a as usize] == self.component[b as usize]
+    }
+
+    /// How many strongly connected components the graph has.
+    pub fn component_count(&self) -> usize {
+        self.post.len()
+    }
+
+    /// How many intervals we store in total, a measure of how well the closure compressed.
+    pub fn interval_count(&self) -> usize {
+        self.intervals.len()
+    }
+
+    fn intervals_of(&self, component: u16) -> &[(u16, u16)] {
+        let c = component as usize;
+        &self.intervals[self.offsets[c] as usize..self.offsets[c + 1] as usize]
+    }
+}
+
+impl<const M: usize, const N: usize, T> StaticGraph<M, N, T> {
+    /// Precompute which nodes can reach which ignoring logic. See `Closure`.
+    pub fn closure(&self) -> Closure {
+        let (component, component_count) = self.strong_components();
+
+        // Tarjan's algorithm finishes a component only after everything it reaches, so every
+        // edge between components goes from a higher index to a lower one.
+        let mut dag: Vec<(u16, u16)> = self
+            .edges()
+            .map(|(src, _, dst)| (component[src as usize], component[dst as usize]))
+            .filter(|&(a, b)| a != b)
+            .collect();
+        dag.sort_unstable();
+        dag.dedup();
+        let mut succ_offsets = vec![0u32; component_count + 1];
+        dag.iter()
+            .for_each(|&(a, _)| succ_offsets[a as usize + 1] += 1);
+        for c in 1..succ_offsets.len() {
+            succ_offsets[c] += succ_offsets[c - 1];
+        }
+
+        // Any predecessor will do as a component's parent in the spanning forest, we take the
+        // first one.
+        let mut parent = vec![u16::MAX; component_count];
+        dag.iter().for_each(|&(a, b)| {
+            if parent[b as usize] == u16::MAX {
+                parent[b as usize] = a;
+            }
+        });
+        let mut child_offsets = vec![0u32; component_count + 1];
+        parent
+            .iter()
+            .filter(|&&p| p != u16::MAX)
+            .for_each(|&p| child_offsets[p as usize + 1] += 1);
+        for c in 1..child_offsets.len() {
+            child_offsets[c] += child_offsets[c - 1];
+        }
+        let mut children = vec![0u16; child_offsets[component_count] as usize];
+        let mut cursor = child_offsets.clone();
+        for (c, &p) in parent.iter().enumerate() {
+            if p != u16::MAX {
+                children[cursor[p as usize] as usize] = c as u16;
+                cursor[p as usize] += 1;
+            }
+        }
+
+        // Postorder numbers, and the lowest number in each subtree so every subtree is one
+        // interval.
+        let mut post = vec![0u16; component_count];
+        let mut lowest = vec![0u16; component_count];
+        let mut next_post = 0u16;
+        let mut stack: Vec<(u16, u32)> = Vec::new();
+        for root in (0..component_count).filter(|&c| parent[c] == u16::MAX) {
+            stack.push((root as u16, child_offsets[root]));
+            lowest[root] = next_post;
+            while let Some((c, next_child)) = stack.last_mut() {
+                let c = *c as usize;
+                match *next_child < child_offsets[c + 1] {
+                    true => {
+                        let child = children[*next_child as usize];
+                        *next_child += 1;
+                        lowest[child as usize] = next_post;
+                        stack.push((child, child_offsets[child as usize]));
+                    }
+                    false => {
+                        post[c] = next_post;
+                        next_post += 1;
+                        stack.pop();
+                    }
+                }
+            }
+        }
+
+        // Successors have lower indexes, so going up from zero we've always merged theirs first.
+        let mut offsets = vec![0u32; component_count + 1];
+        let mut intervals: Vec<(u16, u16)> = Vec::new();
+        let mut merging: Vec<(u16, u16)> = Vec::new();
+        for c in 0..component_count {
+            merging.clear();
+            merging.push((lowest[c], post[c]));
+            for &(_, succ) in &dag[succ_offsets[c] as usize..succ_offsets[c + 1] as usize] {
+                let s = succ as usize;
+                merging.extend_from_slice(&intervals[offsets[s] as usize..offsets[s + 1] as usize]);
+            }
+            merging.sort_unstable();
+            let mut current = merging[0];
+            for &(start, end) in &merging[1..] {
+                match start <= current.1.saturating_add(1) {
+                    true => current.1 = current.1.max(end),
+                    false => {
+                        intervals.push(current);
+                        current = (start, end);
+                    }
+                }
+            }
+            intervals.push(current);
+            offsets[c + 1] = intervals.len() as u32;
+        }
+
+        Closure {
+            component: component.into_boxed_slice(),
+            post: post.into_boxed_slice(),
+            offsets: offsets.into_boxed_slice(),
+            intervals: intervals.into_boxed_slice(),
+        }
+    }
+
+    // The strongly connected component of every node ignoring logic and how many there are, with
+    // an iterative Tarjan's algorithm since the recursion could be as deep as the graph is big.
+    fn strong_components(&self) -> (Vec<u16>, usize) {
+        let len = self.node_count as usize + 1;
+        // Zero marks a node we haven't reached yet.
+        let mut index = vec![0u32; len];
+        let mut low = vec![0u32; len];
+        let mut on_stack = vec![false; len];
+        let mut component = vec![0u16; len];
+        let mut stack: Vec<u16> = Vec::new();
+        let mut frames: Vec<(u16, usize)> = Vec::new();
+        let (mut next_index, mut component_count) = (1u32, 0usize);
+        for root in self.nodes() {
+            if index[root as usize] != 0 {
+                continue;
+            }
+            index[root as usize] = next_index;
+            low[root as usize] = next_index;
+            next_index += 1;
+            stack.push(root);
+            on_stack[root as usize] = true;
+            frames.push((root, 0));
+            while let Some(&mut (node, ref mut next_edge)) = frames.last_mut() {
+                let (edge_pointers, _) = self.get_neighbors_out(NonZeroU16::new(node));
+                let v = node as usize;
+                if let Some(&w) = edge_pointers.get(*next_edge) {
+                    *next_edge += 1;
+                    let w = u16::from(w);
+                    match index[w as usize] {
+                        0 => {
+                            index[w as usize] = next_index;
+                            low[w as usize] = next_index;
+                            next_index += 1;
+                            stack.push(w);
+                            on_stack[w as usize] = true;
+                            frames.push((w, 0));
+                        }
+                        i if on_stack[w as usize] => low[v] = low[v].min(i),
+                        _ => (),
+                    }
+                    continue;
+                }
+
+                frames.pop();
+                if low[v] == index[v] {
+                    loop {
+                        // The node itself is still on the stack.
+                        let w = stack.pop().unwrap();
+                        on_stack[w as usize] = false;
+                        component[w as usize] = component_count as u16;
+                        if w == node {
+                            break;
+                        }
+                    }
+                    component_count += 1;
+                }
+                if let Some(&(p, _)) = frames.last() {
+                    low[p as usize] = low[p as usize].min(low[v]);
+                }
+            }
+        }
+
+        (component, component_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{graph::new_static_graph, logic::CollectionState};
+    use std::collections::VecDeque;
+
+    #[test]
+    fn structural_closure() {
+        let graph = new_static_graph();
+        let closure = graph.closure();
+        let n = graph.node_count() as usize;
+        assert!(closure.component_count() <= n);
+
+        let mut adjacency = vec![Vec::new(); n + 1];
+        graph
+            .edges()
+            .for_each(|(src, _, dst)| adjacency[src as usize].push(dst));
+        // Check a spread of sources against a plain search ignoring logic.
+        for from in (1..=n as u16).step_by(n / 24 + 1) {
+            let mut reached = vec![false; n + 1];
+            let mut queue = VecDeque::from([from]);
+            reached[from as usize] = true;
+            while let Some(node) = queue.pop_front() {
+                for &next in adjacency[node as usize].iter() {
+                    if !reached[next as usize] {
+                        reached[next as usize] = true;
+                        queue.push_back(next);
+                    }
+                }
+            }
+            for to in 1..=n as u16 {
+                assert_eq!(
+                    closure.reaches(from, to),
+                    reached[to as usize],
+                    "{from} {to}"
+                );
+            }
+        }
+
+        // It never rules out anything a logic-aware search reaches.
+        assert!(graph
+            .bfs_iter_with_state(CollectionState::ALL_ITEMS)
+            .all(|node| closure.reaches(1, u16::from(node))));
+    }
+}
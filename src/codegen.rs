@@ -0,0 +1,368 @@
+// The tables our build script writes out as src/gen.rs, read back from a graph so other front-ends
+// can produce the same module without the build script. Writing `GenTables` for the generated
+// model reproduces src/gen.rs byte for byte, which the tests hold us to so the committed module
+// can't drift from what the library thinks it should contain.
+use alloc::{collections::VecDeque, vec, vec::Vec};
+use core::{fmt, num::NonZeroU16};
+
+use crate::{
+    graph::{DoorNodeData, EdgeType, ItemNodeData, NodeData, NodeType, PlaceNodeData, StaticGraph},
+    logic::{Item, Requirement, RequirementNode, Tier, REQ_CONTAINER_LEN},
+};
+
+const AUTOGEN_WARNING: &str =
+    "// THIS IS AN AUTOMATICALLY GENERATED MODULE. ANY CHANGES WILL BE OVERWRITTEN.";
+const IMPORTS: &str = r#"use core::num::NonZeroU16;
+use crate::{graph::{DoorFlags, DoorNodeData, EdgeType, ItemNodeData, NodeData, NodeType, PlaceNodeData}, logic::Item, constants::{NUM_VERTICES_PADDED, NUM_EDGES_PADDED}, raw::nonzero};"#;
+
+/// Every array and constant in a generated model module, padding included. `Display` writes them
+/// out as Rust in the same layout as src/gen.rs, so the result can replace it as long as the
+/// graph is sized `NUM_VERTICES_PADDED` by `NUM_EDGES_PADDED`.
+///
+/// The search sizes are worked out the way the build script does it, so they fit every search
+/// from the root under any combination of the requirements the graph's edges check.
+#[derive(Clone, Debug)]
+pub struct GenTables {
+    pub node_pointers: Vec<u16>,
+    pub node_data: Vec<NodeData>,
+    pub edge_pointers: Vec<u16>,
+    pub edge_data: Vec<u16>,
+    pub place_data: &'static [PlaceNodeData],
+    pub item_data: &'static [ItemNodeData],
+    pub door_data: &'static [DoorNodeData],
+    pub region_of: Vec<u16>,
+    pub edge_kinds: Vec<EdgeType>,
+    pub edge_weights: Vec<u8>,
+    pub tricks: &'static [&'static str],
+    pub edge_tricks: Vec<u8>,
+    pub search_stack_size: usize,
+    pub search_queue_size: usize,
+}
+
+impl GenTables {
+    /// Panics if the graph has requirement trees of its own, e.g. from `from_edge_list`, since a
+    /// generated module's edges can only use the library's. See `StaticGraph::req_trees`.
+    pub fn from_graph<const M: usize, const N: usize, T>(graph: &StaticGraph<M, N, T>) -> Self {
+        assert!(
+            graph.req_trees().len() == REQ_CONTAINER_LEN,
+            "a generated module can't hold a graph's own requirement trees"
+        );
+        let (search_stack_size, search_queue_size) = search_sizes(graph);
+
+        GenTables {
+            node_pointers: graph
+                .node_pointers
+                .0
+                .iter()
+                .map(|&n| u16::from(n))
+                .collect(),
+            node_data: graph.node_data.to_vec(),
+            edge_pointers: graph
+                .edge_pointers
+                .0
+                .iter()
+                .map(|&n| u16::from(n))
+                .collect(),
+            edge_data: graph.edge_data.iter().map(|req| req.get()).collect(),
+            place_data: graph.place_data,
+            item_data: graph.item_data,
+            door_data: graph.door_data,
+            region_of: graph.region_of.to_vec(),
+            edge_kinds: graph.edge_kinds.to_vec(),
+            edge_weights: graph.edge_weights.to_vec(),
+            tricks: graph.tricks,
+            edge_tricks: graph.edge_tricks.to_vec(),
+            search_stack_size,
+            search_queue_size,
+        }
+    }
+}
+
+impl fmt::Display for GenTables {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The build script puts any problems it found with the model between the imports and the
+        // tables as `compile_error!`s. A graph we could build has none.
+        writeln!(f, "{}\n{}\n", AUTOGEN_WARNING, IMPORTS)?;
+        let nonzero = |f: &mut fmt::Formatter<'_>, n: &u16| write!(f, "nonzero({})", n);
+        let number = |f: &mut fmt::Formatter<'_>, n: &dyn fmt::Display| write!(f, "{}", n);
+
+        write!(
+            f,
+            "pub(crate) const NODE_POINTERS: [NonZeroU16; NUM_VERTICES_PADDED] = "
+        )?;
+        write_array(f, &self.node_pointers, nonzero)?;
+        write!(
+            f,
+            "\npub(crate) const NODE_DATA: [NodeData; NUM_VERTICES_PADDED] = "
+        )?;
+        write_array(f, &self.node_data, |f, data| {
+            write!(
+                f,
+                "NodeData {{ node_type: NodeType::{}, data_index: {} }}",
+                node_type(&data.node_type),
+                data.data_index
+            )
+        })?;
+        write!(
+            f,
+            "\npub(crate) const EDGE_POINTERS: [NonZeroU16; NUM_EDGES_PADDED] = "
+        )?;
+        write_array(f, &self.edge_pointers, nonzero)?;
+        write!(
+            f,
+            "\npub(crate) const EDGE_DATA: [u16; NUM_EDGES_PADDED] = "
+        )?;
+        write_array(f, &self.edge_data, |f, n| number(f, n))?;
+        write!(
+            f,
+            "\npub(crate) static PLACE_DATA: [PlaceNodeData; {}] = ",
+            self.place_data.len()
+        )?;
+        write_array(f, self.place_data, |f, place| {
+            write!(
+                f,
+                "PlaceNodeData {{ name: {:?}, region: {} }}",
+                place.name, place.region
+            )
+        })?;
+        write!(
+            f,
+            "\npub(crate) static ITEM_DATA: [ItemNodeData; {}] = ",
+            self.item_data.len()
+        )?;
+        write_array(f, self.item_data, |f, item| {
+            write!(
+                f,
+                "ItemNodeData {{ name: {:?}, region: {}, vanilla_item: Item::{} }}",
+                item.name,
+                item.region,
+                item_name(item.vanilla_item)
+            )
+        })?;
+        write!(
+            f,
+            "\npub(crate) static DOOR_DATA: [DoorNodeData; {}] = ",
+            self.door_data.len()
+        )?;
+        write_array(f, self.door_data, |f, door| {
+            write!(
+                f,
+                "DoorNodeData {{ name: {:?}, region: {}, flags: DoorFlags({}) }}",
+                door.name, door.region, door.flags.0
+            )
+        })?;
+        write!(
+            f,
+            "\npub(crate) const REGION_OF: [u16; NUM_VERTICES_PADDED] = "
+        )?;
+        write_array(f, &self.region_of, |f, n| number(f, n))?;
+        write!(
+            f,
+            "\npub(crate) const EDGE_KINDS: [EdgeType; NUM_EDGES_PADDED] = "
+        )?;
+        write_array(f, &self.edge_kinds, |f, &kind| {
+            write!(f, "EdgeType::{}", edge_kind(kind))
+        })?;
+        write!(
+            f,
+            "\npub(crate) const EDGE_WEIGHTS: [u8; NUM_EDGES_PADDED] = "
+        )?;
+        write_array(f, &self.edge_weights, |f, n| number(f, n))?;
+        write!(
+            f,
+            "\npub(crate) static TRICKS: [&str; {}] = ",
+            self.tricks.len()
+        )?;
+        write_array(f, self.tricks, |f, name| write!(f, "{:?}", name))?;
+        write!(
+            f,
+            "\npub(crate) const EDGE_TRICKS: [u8; NUM_EDGES_PADDED] = "
+        )?;
+        write_array(f, &self.edge_tricks, |f, n| number(f, n))?;
+        writeln!(
+            f,
+            "\npub const SEARCH_STACK_SIZE: usize = {};",
+            self.search_stack_size
+        )?;
+        writeln!(
+            f,
+            "pub const SEARCH_QUEUE_SIZE: usize = {};",
+            self.search_queue_size
+        )
+    }
+}
+
+// A whole array initializer, brackets to semicolon.
+fn write_array<V>(
+    f: &mut fmt::Formatter<'_>,
+    values: &[V],
+    mut write_value: impl FnMut(&mut fmt::Formatter<'_>, &V) -> fmt::Result,
+) -> fmt::Result {
+    write!(f, "[")?;
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write_value(f, value)?;
+    }
+
+    write!(f, "];")
+}
+
+fn node_type(node_type: &NodeType) -> &'static str {
+    match node_type {
+        NodeType::Place => "Place",
+        NodeType::Item => "Item",
+        NodeType::Door => "Door",
+    }
+}
+
+fn edge_kind(kind: EdgeType) -> &'static str {
+    match kind {
+        EdgeType::Normal => "Normal",
+        EdgeType::Door => "Door",
+        EdgeType::Warp => "Warp",
+        EdgeType::Shortcut => "Shortcut",
+    }
+}
+
+fn item_name(item: Item) -> &'static str {
+    match item {
+        Item::Boots => "Boots",
+        Item::Gloves => "Gloves",
+        Item::Flute => "Flute",
+        Item::Hammer => "Hammer",
+        Item::Rupees => "Rupees",
+    }
+}
+
+// Search from the root under every combination of the requirements our edges check and size the
+// DFS stack and BFS queue to the most either ever held, plus the slot both keep free. This is the
+// build script's `search_sizes` over the graph instead of its own copy of the tables, so the two
+// have to change together.
+fn search_sizes<const M: usize, const N: usize, T>(graph: &StaticGraph<M, N, T>) -> (usize, usize) {
+    // Requirements no edge checks can't change where a search goes.
+    let mut checked = Vec::new();
+    let mut roots: Vec<u16> = graph.edge_data.iter().map(|req| req.get()).collect();
+    roots.sort_unstable();
+    roots.dedup();
+    let trees = graph.req_trees();
+    for root in roots {
+        checked_requirements(trees, root, &mut checked);
+    }
+    let always = [Requirement::Open, Requirement::Tier(Tier::Normal)].map(|r| r.id());
+    checked.retain(|id| !always.contains(id));
+    checked.sort_unstable();
+
+    let edges = |node: u16| {
+        let (edge_pointers, edge_offset) = graph.get_neighbors_out(NonZeroU16::new(node));
+        edge_pointers
+            .iter()
+            .enumerate()
+            .map(move |(i, &dest)| (edge_offset + i as u16, u16::from(dest)))
+    };
+    let nodes = graph.node_count as usize + 1;
+    let (mut stack_max, mut queue_max) = (0, 0);
+    for combo in 0..1u32 << checked.len() {
+        let state = checked
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| combo & 1 << i != 0)
+            .fold(0, |state, (_, &id)| state | 1 << id);
+        let open = |edge: u16| eval_state(trees, graph.edge_data[edge as usize].get(), state);
+
+        let mut visited = vec![false; nodes];
+        let mut stack = vec![1];
+        visited[1] = true;
+        while let Some(node) = stack.pop() {
+            for (edge, dest) in edges(node) {
+                if open(edge) && !visited[dest as usize] {
+                    visited[dest as usize] = true;
+                    stack.push(dest);
+                }
+            }
+            stack_max = stack_max.max(stack.len());
+        }
+
+        let mut visited = vec![false; nodes];
+        let mut queue = VecDeque::from([1]);
+        visited[1] = true;
+        while let Some(node) = queue.pop_front() {
+            for (edge, dest) in edges(node) {
+                if open(edge) && !visited[dest as usize] {
+                    visited[dest as usize] = true;
+                    queue.push_back(dest);
+                }
+            }
+            queue_max = queue_max.max(queue.len());
+        }
+    }
+
+    (
+        (stack_max + 1).next_power_of_two(),
+        (queue_max + 1).next_power_of_two(),
+    )
+}
+
+fn checked_requirements(trees: &[RequirementNode], idx: u16, ids: &mut Vec<u16>) {
+    let tree = &trees[idx as usize];
+    // A negation checks the same bit as the requirement it negates.
+    let id = match tree.req {
+        Requirement::Not(negated) => negated.requirement().id(),
+        req => req.id(),
+    };
+    if !ids.contains(&id) {
+        ids.push(id);
+    }
+    for link in [tree.and, tree.or].into_iter().flatten() {
+        checked_requirements(trees, link.get(), ids);
+    }
+}
+
+// Evaluate a requirement tree where bit `n` of `state` says whether the requirement with id `n`
+// is satisfied.
+fn eval_state(trees: &[RequirementNode], mut idx: u16, state: u32) -> bool {
+    loop {
+        let tree = &trees[idx as usize];
+        let satisfied = match tree.req {
+            Requirement::Open | Requirement::Tier(Tier::Normal) => true,
+            Requirement::Not(negated) => state & (1 << negated.requirement().id()) == 0,
+            req => state & (1 << req.id()) != 0,
+        };
+        let next = match satisfied {
+            true => tree.and,
+            false => tree.or,
+        };
+        match next {
+            Some(n) => idx = n.get(),
+            None => break satisfied,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        gen::{SEARCH_QUEUE_SIZE, SEARCH_STACK_SIZE},
+        graph::new_static_graph,
+    };
+
+    #[test]
+    fn generated_module_round_trips() {
+        let graph = new_static_graph();
+        let tables = GenTables::from_graph(&graph);
+        assert_eq!(
+            (tables.search_stack_size, tables.search_queue_size),
+            (SEARCH_STACK_SIZE, SEARCH_QUEUE_SIZE)
+        );
+
+        // Compare line by line so a mismatch doesn't print all of both modules.
+        let module = std::format!("{}", tables);
+        let golden = include_str!("gen.rs");
+        for (i, (ours, theirs)) in module.lines().zip(golden.lines()).enumerate() {
+            assert!(ours == theirs, "line {} of src/gen.rs differs", i + 1);
+        }
+        assert_eq!(module.len(), golden.len());
+    }
+}
@@ -0,0 +1,179 @@
+// Differential testing against another logic engine, for checking a world model ported from an
+// existing randomizer answers the same way the randomizer does.
+use alloc::vec::Vec;
+
+use crate::{
+    graph::StaticGraph,
+    logic::{CollectionState, Item},
+    rng::SplitMix64,
+};
+
+const PROGRESSION: [Item; 4] = [Item::Boots, Item::Gloves, Item::Flute, Item::Hammer];
+
+/// Another implementation of the same logic that only knows nodes by name, e.g. bindings to the
+/// randomizer a world model was ported from.
+///
+/// `compare_reachability` asks about every named node for the same item set before moving on to
+/// the next set, so an engine that solves a whole state at once can solve on the first question
+/// and answer the rest from that.
+pub trait ReferenceEngine {
+    /// Whether the node called `node` is reachable from the start with exactly `items` collected.
+    fn is_reachable(&mut self, node: &str, items: &[Item]) -> bool;
+}
+
+/// A node the reference engine disagrees with us about. See `compare_reachability`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    /// The items collected when we disagreed.
+    pub items: Vec<Item>,
+    pub node: &'static str,
+    /// Whether we think the node is reachable. The reference thinks otherwise.
+    pub ours: bool,
+}
+
+/// What `compare_reachability` found.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CompatReport {
+    /// How many item sets we compared under.
+    pub states: usize,
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl CompatReport {
+    pub fn is_empty(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Ask `engine` about every named node under `rounds` random item sets and report everywhere its
+/// answer differs from ours. `base` supplies the tiers, settings and events we search with, and
+/// each round collects each progression item with even odds on top of it. The same `seed` always
+/// draws the same item sets so a mismatch can be replayed.
+///
+/// Nodes without a name are skipped since the engine couldn't be asked about them.
+pub fn compare_reachability<const M: usize, const N: usize, T>(
+    graph: &StaticGraph<M, N, T>,
+    engine: &mut impl ReferenceEngine,
+    base: &CollectionState,
+    rounds: usize,
+    seed: u64,
+) -> CompatReport {
+    let named: Vec<(u16, &'static str)> = graph
+        .nodes()
+        .filter_map(|n| graph.node_name(n).map(|name| (n, name)))
+        .collect();
+    let mut rng = SplitMix64::new(seed);
+    let mut report = CompatReport::default();
+    for _ in 0..rounds {
+        let bits = rng.next_u64();
+        let mut state = base.clone();
+        let items: Vec<Item> = PROGRESSION
+            .iter()
+            .enumerate()
+            .filter(|&(i, &item)| {
+                state.set(item, bits & (1 << i) != 0);
+                state.has(item)
+            })
+            .map(|(_, &item)| item)
+            .collect();
+
+        let mut search = graph.bfs_iter_with_state(state);
+        search.by_ref().for_each(drop);
+        for &(node, name) in named.iter() {
+            let ours = search.core.visited.check_visited(node);
+            if engine.is_reachable(name, &items) != ours {
+                report.mismatches.push(Mismatch {
+                    items: items.clone(),
+                    node: name,
+                    ours,
+                });
+            }
+        }
+        report.states += 1;
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{graph::new_static_graph, logic::EvaluateLogic};
+    use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+    type Adjacency = BTreeMap<&'static str, Vec<(&'static str, crate::logic::ReqIndex)>>;
+
+    // A naive engine over adjacency lists by name, working out reachability from scratch for every
+    // item set. It can be told to ignore an item to act like a port with a bug in it.
+    struct Naive {
+        start: &'static str,
+        edges: Adjacency,
+        ignore: Option<Item>,
+        cached: Option<(Vec<Item>, BTreeSet<&'static str>)>,
+    }
+
+    impl ReferenceEngine for Naive {
+        fn is_reachable(&mut self, node: &str, items: &[Item]) -> bool {
+            if self.cached.as_ref().map(|(i, _)| i.as_slice()) != Some(items) {
+                let mut state = CollectionState::default();
+                PROGRESSION.iter().for_each(|&item| {
+                    state.set(item, items.contains(&item) && Some(item) != self.ignore);
+                });
+                let mut reached = BTreeSet::from([self.start]);
+                let mut queue = VecDeque::from([self.start]);
+                while let Some(at) = queue.pop_front() {
+                    for &(dst, req) in self.edges.get(at).into_iter().flatten() {
+                        if state.eval_logic_tree(req) && reached.insert(dst) {
+                            queue.push_back(dst);
+                        }
+                    }
+                }
+                self.cached = Some((items.to_vec(), reached));
+            }
+
+            self.cached.as_ref().unwrap().1.contains(node)
+        }
+    }
+
+    #[test]
+    fn differential() {
+        let graph = new_static_graph();
+        // Names are unique in the example model.
+        let mut edges = Adjacency::new();
+        for (src, edge, dst) in graph.edges() {
+            edges
+                .entry(graph.node_name(src).unwrap())
+                .or_default()
+                .push((graph.node_name(dst).unwrap(), graph.edge_data(edge).req));
+        }
+        let start = graph.node_name(1).unwrap();
+        let mut faithful = Naive {
+            start,
+            edges: edges.clone(),
+            ignore: None,
+            cached: None,
+        };
+        let base = CollectionState::default();
+        let report = compare_reachability(&graph, &mut faithful, &base, 16, 7);
+        assert_eq!(report.states, 16);
+        assert!(report.is_empty(), "{:?}", report.mismatches.first());
+
+        // A port that forgot about the hammer thinks less is reachable whenever we have it.
+        let mut buggy = Naive {
+            start,
+            edges,
+            ignore: Some(Item::Hammer),
+            cached: None,
+        };
+        let report = compare_reachability(&graph, &mut buggy, &base, 16, 7);
+        assert!(!report.is_empty());
+        assert!(report
+            .mismatches
+            .iter()
+            .all(|m| m.ours && m.items.contains(&Item::Hammer)));
+        assert_eq!(
+            report,
+            compare_reachability(&graph, &mut buggy, &base, 16, 7)
+        );
+    }
+}
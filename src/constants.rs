@@ -16,19 +16,32 @@ pub const NUM_EDGES: usize = (NUM_VERTICES * 2) + (NUM_VERTICES >> 2) + 500;
 pub const NUM_VERTICES_PADDED: usize = u16::MAX as usize + 1;
 pub const NUM_EDGES_PADDED: usize = u16::MAX as usize + 1;
 
-// These could be const generics which could trade uglier library code for a nicer public
-// interface.
-pub const VISITED_BITFIELD_LEN: usize = 32768 >> 6;
-pub const ACCESS_BITFIELD_LEN: usize = NUM_EDGES_PADDED >> 6;
+/// How many words a bitfield with one bit for each of `bits` indexes needs.
+pub const fn bitfield_len(bits: usize) -> usize {
+    bits.div_ceil(64)
+}
 
-// These should be a power of two. We use a runtime bitmask to avoid branches on our stack and
-// queue.
-pub const SEARCH_STACK_SIZE: usize = 4096;
-pub const SEARCH_QUEUE_SIZE: usize = 256;
+// Walkers keep a visited bit for every node index and an access bit for every edge index. They
+// allocate just what their graph's `M` and `N` need, see `StaticGraph::VISITED_LEN` and
+// `ACCESS_LEN`, but the cache types are all parameterized by these lengths instead. Naming a
+// cache type after its graph would need generic_const_exprs (still too incomplete to lean on), so
+// these are the most any cache can hold, and `StaticGraph::FITS_CACHES` makes building a walker
+// for a graph with bigger tables a compile error.
+pub const VISITED_BITFIELD_LEN: usize = bitfield_len(NUM_VERTICES_PADDED);
+pub const ACCESS_BITFIELD_LEN: usize = bitfield_len(NUM_EDGES_PADDED);
+// Two bits per node. See `ColorCache`.
+pub const COLOR_BITFIELD_LEN: usize = bitfield_len(2 * NUM_VERTICES_PADDED);
+
+// The generator never gives a node more than this many outgoing edges, so the build script, the
+// model linter and the edge list importer all reject nodes with more.
+pub const MAX_OUT_DEGREE: usize = 13;
+
+// The default DfsStack and BfsQueue sizes depend on how deep and wide searches of the generated
+// graph get, so the build script measures them and writes them to gen.rs as SEARCH_STACK_SIZE
+// and SEARCH_QUEUE_SIZE. They're only what we allocate up front: a search of another graph that
+// gets deeper or wider spills into a growable overflow rather than losing nodes.
 
 const _: () = {
     assert!(NUM_VERTICES_PADDED.is_power_of_two());
     assert!(NUM_EDGES_PADDED.is_power_of_two());
-    assert!(SEARCH_STACK_SIZE.is_power_of_two());
-    assert!(SEARCH_QUEUE_SIZE.is_power_of_two());
 };
@@ -0,0 +1,214 @@
+// Checking that searches visit nodes in exactly the same order everywhere. Seed generators
+// embedding us need a seed to produce the same playthrough on every platform, so anything that
+// changes a visit order, down to the order edges leaving one node are pushed in, changes seeds.
+// The test vectors below pin our orders down: a consumer can run them on each platform they ship
+// to, and our own tests fail if a change to the walkers moves them. New walkers, parallel ones
+// included, should get vectors of their own.
+use core::num::NonZeroU16;
+
+use crate::{
+    bytes::BytesError,
+    graph::StaticGraph,
+    logic::{CollectionState, EventFlags, SettingsState, TierMask},
+    traversal::WalkOrder,
+};
+
+// FNV-1a, which is tiny, has no platform-dependent state and is stable by definition.
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Hashing the order a search yields nodes in. Two searches with the same fingerprint visited the
+/// same nodes in the same order, with overwhelming probability.
+pub trait TraversalFingerprint: Iterator<Item = NonZeroU16> + Sized {
+    /// Run the search to the end and hash every node it yields, in order, as little-endian u16s
+    /// with 64-bit FNV-1a. The result doesn't depend on the platform or on `usize`'s width.
+    fn traversal_fingerprint(self) -> u64 {
+        self.fold(FNV_OFFSET, |hash, node| {
+            u16::from(node)
+                .to_le_bytes()
+                .iter()
+                .fold(hash, |hash, &byte| {
+                    (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+                })
+        })
+    }
+}
+
+impl<I: Iterator<Item = NonZeroU16>> TraversalFingerprint for I {}
+
+/// A graph in the `to_bytes` format, a collection state and a search order, along with how many
+/// nodes a search from the root visits and the fingerprint of the order it visits them in.
+#[derive(Clone, Debug)]
+pub struct TestVector {
+    pub name: &'static str,
+    pub graph: &'static [u8],
+    pub state: CollectionState,
+    pub order: WalkOrder,
+    pub visited: usize,
+    pub fingerprint: u64,
+}
+
+/// Big enough for every graph in `TEST_VECTORS`.
+pub type VectorGraph = StaticGraph<64, 64>;
+
+impl TestVector {
+    /// Search the vector's graph and return how many nodes we visited and the fingerprint of the
+    /// order we visited them in.
+    pub fn run(&self) -> Result<(usize, u64), BytesError> {
+        let graph = VectorGraph::from_bytes(self.graph)?;
+        let visited = self.search(&graph).count();
+
+        Ok((visited, self.search(&graph).traversal_fingerprint()))
+    }
+
+    /// Whether searching the vector's graph here gives the expected order.
+    pub fn check(&self) -> bool {
+        self.run() == Ok((self.visited, self.fingerprint))
+    }
+
+    fn search<'graph>(
+        &self,
+        graph: &'graph VectorGraph,
+    ) -> impl Iterator<Item = NonZeroU16> + 'graph {
+        let state = self.state.clone();
+        let (dfs, bfs) = match self.order {
+            WalkOrder::DepthFirst => (Some(graph.dfs_iter_with_state(state)), None),
+            WalkOrder::BreadthFirst => (None, Some(graph.bfs_iter_with_state(state))),
+        };
+
+        dfs.into_iter().flatten().chain(bfs.into_iter().flatten())
+    }
+}
+
+const HAMMER: CollectionState = CollectionState {
+    hammer: true,
+    ..CollectionState::NOTHING
+};
+
+// A diamond whose sides need different items, with a locked shortcut and a way back to the root:
+//
+// 1 2, 1 3 hammer, 2 4 boots | hammer, 3 5, 4 5 gloves, 5 6, 2 6 locked, 6 1
+const DIAMOND: &[u8] = &[
+    83, 71, 1, 0, 6, 0, 8, 0, 1, 0, 1, 0, 3, 0, 5, 0, 6, 0, 7, 0, 8, 0, 9, 0, 2, 0, 3, 0, 4, 0, 6,
+    0, 5, 0, 5, 0, 6, 0, 1, 0, 0, 0, 3, 0, 2, 0, 1, 0, 0, 0, 4, 0, 0, 0, 0, 0,
+];
+
+// Two rows of six nodes joined at every rung, with one rung needing each progression item, so
+// the searches branch at every node and the order depends on how ties are broken.
+//
+// i i+1 along both rows (1-6 and 7-12), i i+6 and i+6 i for every rung, where the rungs at 2, 3,
+// 4 and 5 need boots | hammer, gloves, flute and hammer.
+const LADDER: &[u8] = &[
+    83, 71, 1, 0, 12, 0, 22, 0, 1, 0, 1, 0, 3, 0, 5, 0, 7, 0, 9, 0, 11, 0, 12, 0, 14, 0, 16, 0, 18,
+    0, 20, 0, 22, 0, 23, 0, 2, 0, 7, 0, 3, 0, 8, 0, 4, 0, 9, 0, 5, 0, 10, 0, 6, 0, 11, 0, 12, 0, 8,
+    0, 1, 0, 9, 0, 2, 0, 10, 0, 3, 0, 11, 0, 4, 0, 12, 0, 5, 0, 6, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0,
+    4, 0, 0, 0, 6, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 4, 0, 0, 0, 6, 0, 0, 0, 3, 0,
+    0, 0,
+];
+
+/// Our canned vectors. Every one of them has to pass on every platform we support.
+pub static TEST_VECTORS: [TestVector; 6] = [
+    TestVector {
+        name: "diamond, depth first, everything",
+        graph: DIAMOND,
+        state: CollectionState::ALL_ITEMS,
+        order: WalkOrder::DepthFirst,
+        visited: 6,
+        fingerprint: 0x0742_358d_9dc9_990a,
+    },
+    TestVector {
+        name: "diamond, breadth first, everything",
+        graph: DIAMOND,
+        state: CollectionState::ALL_ITEMS,
+        order: WalkOrder::BreadthFirst,
+        visited: 6,
+        fingerprint: 0xdf03_4f85_08c7_7cd2,
+    },
+    TestVector {
+        name: "diamond, depth first, nothing",
+        graph: DIAMOND,
+        state: CollectionState::NOTHING,
+        order: WalkOrder::DepthFirst,
+        visited: 2,
+        fingerprint: 0xad31_9677_479e_1db6,
+    },
+    TestVector {
+        name: "ladder, depth first, everything",
+        graph: LADDER,
+        state: CollectionState::ALL_ITEMS,
+        order: WalkOrder::DepthFirst,
+        visited: 12,
+        fingerprint: 0x9d92_ea95_f83a_1a41,
+    },
+    TestVector {
+        name: "ladder, breadth first, everything",
+        graph: LADDER,
+        state: CollectionState::ALL_ITEMS,
+        order: WalkOrder::BreadthFirst,
+        visited: 12,
+        fingerprint: 0x77ef_cb9f_ca6b_8429,
+    },
+    TestVector {
+        name: "ladder, depth first, hammer",
+        graph: LADDER,
+        state: HAMMER,
+        order: WalkOrder::DepthFirst,
+        visited: 12,
+        fingerprint: 0xd592_28b7_6d6f_78d1,
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::new_static_graph;
+
+    #[test]
+    fn test_vectors() {
+        let diamond = VectorGraph::from_edge_list([
+            "1 2",
+            "1 3 hammer",
+            "2 4 boots | hammer",
+            "3 5",
+            "4 5 gloves",
+            "5 6",
+            "2 6 locked",
+            "6 1",
+        ])
+        .unwrap();
+        assert_eq!(diamond.to_bytes(), DIAMOND);
+        let mut ladder = std::vec::Vec::new();
+        for i in 1..=5 {
+            ladder.push(std::format!("{} {}", i, i + 1));
+            ladder.push(std::format!("{} {}", i + 6, i + 7));
+        }
+        let rungs = ["", "boots | hammer", "gloves", "flute", "hammer", ""];
+        for (i, req) in (1..=6).zip(rungs) {
+            ladder.push(std::format!("{} {} {}", i, i + 6, req));
+            ladder.push(std::format!("{} {} {}", i + 6, i, req));
+        }
+        assert_eq!(
+            VectorGraph::from_edge_list(ladder).unwrap().to_bytes(),
+            LADDER
+        );
+
+        for vector in TEST_VECTORS.iter() {
+            assert!(vector.check(), "{}", vector.name);
+        }
+
+        // The generated model is too big to keep as bytes, but it's compiled in, so we pin its
+        // orders here too.
+        let graph = new_static_graph();
+        let state = CollectionState::ALL_ITEMS;
+        assert_eq!(
+            graph
+                .dfs_iter_with_state(state.clone())
+                .traversal_fingerprint(),
+            0xafe3_f21a_df04_3b7b
+        );
+        assert_eq!(
+            graph.bfs_iter_with_state(state).traversal_fingerprint(),
+            0x60b5_603a_b37f_8203
+        );
+    }
+}
@@ -1,13 +1,17 @@
-use alloc::boxed::Box;
+use alloc::{boxed::Box, vec, vec::Vec};
 use core::{
+    fmt,
+    iter::FusedIterator,
     num::NonZeroU16,
     ops::{Deref, Index},
 };
 
 use crate::{
     constants::*,
-    graph::{AccessCache, StaticGraph, VisitedCache},
-    logic::{CollectionState, Requirement, RequirementNode, REQ_CONTAINER},
+    gen::SEARCH_STACK_SIZE,
+    graph::{StaticGraph, VisitedCache, VisitedSet},
+    logic::{CollectionState, EvaluateLogic},
+    traversal::TraversalCore,
 };
 
 /// Our main traversal data structure for simulating access checking. We model a search
@@ -18,126 +22,101 @@ use crate::{
 /// for item location access checking with depth first search e.g. one where the .next() method
 /// ignores logical constraints for checking node connectedness or a breadth first search for
 /// narrower searches where the target is probably closer to the root.
-pub struct DfsIter<'graph, const M: usize, const N: usize> {
-    pub graph: &'graph StaticGraph<M, N>,
-    pub root: u16,
-    pub search_stack: DfsStack,
-    pub collection_state: CollectionState,
-    pub visited: VisitedCache<VISITED_BITFIELD_LEN>,
-    pub edge_access: AccessCache<ACCESS_BITFIELD_LEN>,
-}
-
-impl<const M: usize, const N: usize> DfsIter<'_, M, N> {
-    const BITMASK_CUR: u64 = 0x80000000_00000000;
-
-    /// To evaluate our logic expressions we recursively evaluate the conditions one holds. If the
-    /// condition is false, we check for an OR child and repeat if present or return `false` if not
-    /// (ideally short-circuiting as soon as possible.) If the condition is true, we check for an
-    /// AND child and repeat if present or return `true` if not. Eventually we reach a node whose
-    /// evaluation gives us our final true or false. This function takes the root node of a tree
-    /// and proceeds as such.
-    pub fn eval_logic_tree(&self, mut req_index: u16) -> bool {
-        let mut req_node: RequirementNode;
-        loop {
-            req_node = REQ_CONTAINER[req_index];
-            match self.eval_requirement(req_node.req) {
-                true => match req_node.and {
-                    Some(n) => req_index = u16::from(n),
-                    None => break true,
-                },
-                false => match req_node.or {
-                    Some(n) => req_index = u16::from(n),
-                    None => break false,
-                },
-            }
-        }
-    }
-
-    /// Our logic evaluator. Here we're merely checking collection state, but the graph walking
-    /// data structure that solves for reachability etc will also implement more complex methods
-    /// that will run their own graph operations with a shared reference to the graph we're working
-    /// with.
-    pub const fn eval_requirement(&self, req: Requirement) -> bool {
-        match req {
-            Requirement::Open => true,
-            Requirement::Boots => self.collection_state.boots,
-            Requirement::Gloves => self.collection_state.gloves,
-            Requirement::Flute => self.collection_state.flute,
-            Requirement::Hammer => self.collection_state.hammer,
-            Requirement::Locked => false,
-        }
-    }
+pub struct DfsIter<
+    'graph,
+    const M: usize,
+    const N: usize,
+    T = (),
+    const S: usize = SEARCH_STACK_SIZE,
+    V = VisitedCache<VISITED_BITFIELD_LEN>,
+> {
+    pub core: TraversalCore<'graph, M, N, T, V>,
+    pub search_stack: DfsStack<S>,
+}
 
-    /// Instead of determining whether an edge can be traversed during a traversal, we can
-    /// pre-compute our logic to a large extent. This is somewhat complicated by things like small
-    /// keys or logical requirements that may have a dependency on the graph state and other
-    /// logical constraints that may change or not have been computed yet.
-    ///
-    /// Another approach here would be to evaluate a set of requirements with static inputs once
-    /// and apply a pre-computed bitmask.
+impl<const M: usize, const N: usize, T, const S: usize, V: VisitedSet> DfsIter<'_, M, N, T, S, V> {
+    /// Re-evaluate which edges can be traversed under the current collection state.
     pub fn evaluate_logical_access(&mut self) {
-        // SAFETY: We have to statically ensure that this iterator has exactly the same amount of
-        // elements as our self.edge_access array. In a library we might use a debug assertion.
-        let edge_logic = unsafe {
-            self.graph
-                .edge_data
-                // Also tried nightly, safe .array_chunks iterator method but can't remember if
-                // it's faster or anything. Not too worried about, all the logic evaluation code
-                // could be massively improved.
-                .as_chunks_unchecked::<CHUNK_SIZE>()
-                .iter()
-                .enumerate()
-        };
-        edge_logic.for_each(|i| {
-            let mut bit_cursor: u64 = Self::BITMASK_CUR;
-            let (idx, logic_array) = i;
-            self.edge_access[idx] =
-                logic_array
-                    .iter()
-                    .fold(0u64, |acc, d| match self.eval_logic_tree(*d) {
-                        true => {
-                            let c = acc | bit_cursor;
-                            bit_cursor >>= 1;
-                            c
-                        }
-                        false => {
-                            bit_cursor >>= 1;
-                            acc
-                        }
-                    });
-        });
+        self.core.evaluate_logical_access();
     }
 
     /// Returns whether a node is reachable or not, checking previous traversals first.
     pub fn search(&mut self, node: u16) -> bool {
-        match self.visited.check_visited(node) {
+        match self.core.visited.check_visited(node) {
             true => true,
             false => self.any(|n| u16::from(n) == node),
         }
     }
 
+    /// The node `.next()` will return, without expanding it or advancing the search.
+    pub fn peek(&self) -> Option<NonZeroU16> {
+        self.search_stack.peek()
+    }
+
+    /// The nodes waiting to be expanded, in the order we'll yield them. Like `BfsIter::frontier`
+    /// they've already been marked visited.
+    pub fn frontier(&self) -> impl Iterator<Item = NonZeroU16> + '_ {
+        self.search_stack.iter()
+    }
+
     /// Visits a node's unvisited, accessible, outgoing neighbors and pushes them onto the DFS
     /// stack.
     pub fn visit_neighbors_out(&mut self, node: Option<NonZeroU16>) {
-        let (edge_pointers, edge_offset) = self.graph.get_neighbors_out(node);
-        edge_pointers
-            .iter()
-            .enumerate()
-            .filter(|(i, _)| {
-                let edge_index = edge_offset + *i as u16;
-                self.edge_access.check_access(edge_index)
-            })
-            .for_each(|(_, &n)| {
-                let node_index = u16::from(n);
-                match self.visited.test_set_visited(node_index) {
-                    false => self.search_stack.push(node_index),
-                    true => (),
-                };
-            });
-    }
-}
-
-impl<const M: usize, const N: usize> Iterator for DfsIter<'_, M, N> {
+        let search_stack = &mut self.search_stack;
+        self.core
+            .visit_neighbors_out(node, |n| search_stack.push(n));
+    }
+}
+
+impl<const M: usize, const N: usize, T, const S: usize, V> EvaluateLogic
+    for DfsIter<'_, M, N, T, S, V>
+{
+    fn collection_state(&self) -> &CollectionState {
+        &self.core.collection_state
+    }
+}
+
+/// A clone is a snapshot of the search that can be resumed independently of the original, e.g.
+/// to speculatively try collecting an item on one branch while the other carries on as before.
+impl<const M: usize, const N: usize, T, const S: usize, V: Clone> Clone
+    for DfsIter<'_, M, N, T, S, V>
+{
+    fn clone(&self) -> Self {
+        DfsIter {
+            core: self.core.clone(),
+            search_stack: self.search_stack.clone(),
+        }
+    }
+}
+
+impl<const M: usize, const N: usize, T, const S: usize, V: fmt::Debug> fmt::Debug
+    for DfsIter<'_, M, N, T, S, V>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DfsIter")
+            .field("core", &self.core)
+            .field("search_stack", &self.search_stack)
+            .finish()
+    }
+}
+
+impl<const M: usize, const N: usize, T, const S: usize, V> fmt::Display
+    for DfsIter<'_, M, N, T, S, V>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "DfsIter {{ root: {}, stack: {}, visited: {} }}",
+            self.core.root,
+            self.search_stack.len(),
+            self.core.visited_count
+        )
+    }
+}
+
+impl<const M: usize, const N: usize, T, const S: usize, V: VisitedSet> Iterator
+    for DfsIter<'_, M, N, T, S, V>
+{
     // Returns a node's index. In a library we would also generate code such that every node
     // corresponds to a named variant of a u16-backed enum but with an iterator we only care about
     // the index.
@@ -147,55 +126,260 @@ impl<const M: usize, const N: usize> Iterator for DfsIter<'_, M, N> {
     fn next(&mut self) -> Option<Self::Item> {
         let next_node = self.search_stack.pop();
         self.visit_neighbors_out(next_node);
+        event!(
+            TRACE,
+            node = next_node.map_or(0, u16::from),
+            frontier = self.search_stack.len(),
+            "dfs expand"
+        );
 
         next_node
     }
+
+    // Every node still on the stack will be yielded and at most every node we haven't marked
+    // visited yet might be.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let pending = self.search_stack.len();
+
+        (pending, Some(pending + self.core.unvisited()))
+    }
+}
+
+// Once the stack is exhausted, popping keeps returning None which maps to the terminal node
+// and visits nothing.
+impl<const M: usize, const N: usize, T, const S: usize, V: VisitedSet> FusedIterator
+    for DfsIter<'_, M, N, T, S, V>
+{
+}
+
+/// A depth-first search that won't follow a path more than `max_depth` edges from the root. This
+/// answers "what is reachable within N transitions" directly.
+///
+/// A node can first be reached by a long path and later by a shorter one, in which case we expand
+/// it again from the shallower depth so nothing within the limit is missed. Each node is still
+/// only yielded once. Remembering those depths takes a `u16` for every node on top of the usual
+/// traversal caches, so this needs more memory than a plain `DfsIter`, not less.
+pub struct DepthLimitedDfsIter<'graph, const M: usize, const N: usize, T = ()> {
+    pub core: TraversalCore<'graph, M, N, T>,
+    pub max_depth: u16,
+    search_stack: Vec<(u16, u16)>,
+    // The shallowest depth each node has been expanded at so far.
+    depth: Box<[u16]>,
+}
+
+impl<'graph, const M: usize, const N: usize, T> DepthLimitedDfsIter<'graph, M, N, T> {
+    pub(crate) fn new(
+        graph: &'graph StaticGraph<M, N, T>,
+        root: u16,
+        max_depth: u16,
+        state: CollectionState,
+    ) -> Self {
+        DepthLimitedDfsIter {
+            core: TraversalCore::new(graph, root, state),
+            max_depth,
+            search_stack: vec![(root, 0)],
+            depth: vec![u16::MAX; M].into_boxed_slice(),
+        }
+    }
+
+    /// The fewest edges from the root we've reached `node` with so far, if we've reached it.
+    pub fn depth(&self, node: u16) -> Option<u16> {
+        match self.depth[node as usize] {
+            u16::MAX => None,
+            d => Some(d),
+        }
+    }
+
+    /// Returns whether `node` is reachable within `max_depth` edges of the root.
+    pub fn search(&mut self, node: u16) -> bool {
+        match self.core.visited.check_visited(node) {
+            true => true,
+            false => self.any(|n| u16::from(n) == node),
+        }
+    }
+}
+
+impl<const M: usize, const N: usize, T> Iterator for DepthLimitedDfsIter<'_, M, N, T> {
+    type Item = NonZeroU16;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((node, depth)) = self.search_stack.pop() {
+            // Already expanded from at least as shallow a depth.
+            if depth >= self.depth[node as usize] {
+                continue;
+            }
+            let first_visit = self.depth[node as usize] == u16::MAX;
+            self.depth[node as usize] = depth;
+            if depth < self.max_depth {
+                let (edge_pointers, edge_offset) =
+                    self.core.graph.get_neighbors_out(NonZeroU16::new(node));
+                edge_pointers
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| self.core.check_edge(edge_offset + *i as u16))
+                    .filter(|(_, &n)| depth + 1 < self.depth[u16::from(n) as usize])
+                    .for_each(|(_, &n)| self.search_stack.push((u16::from(n), depth + 1)));
+            }
+            if first_visit {
+                if !self.core.visited.test_set_visited(node) {
+                    self.core.visited_count += 1;
+                }
+                return NonZeroU16::new(node);
+            }
+        }
+
+        None
+    }
+}
+
+impl<const M: usize, const N: usize, T> FusedIterator for DepthLimitedDfsIter<'_, M, N, T> {}
+
+impl<const M: usize, const N: usize, T> fmt::Debug for DepthLimitedDfsIter<'_, M, N, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DepthLimitedDfsIter")
+            .field("core", &self.core)
+            .field("max_depth", &self.max_depth)
+            .field("search_stack", &self.search_stack.len())
+            .finish()
+    }
+}
+
+/// Iterative deepening: runs depth-limited searches from `root` with limits 0, 1, 2... up to
+/// `max_depth` and returns the first limit at which `target` is found, which is also the fewest
+/// edges it takes to reach it. This repeats work at every limit but never holds more than a
+/// single path's worth of siblings on the stack.
+pub fn iddfs<const M: usize, const N: usize, T>(
+    graph: &StaticGraph<M, N, T>,
+    state: &CollectionState,
+    root: u16,
+    target: u16,
+    max_depth: u16,
+) -> Option<u16> {
+    (0..=max_depth).find(|&limit| {
+        graph
+            .dfs_iter_depth_limited_with_state(root, limit, state.clone())
+            .search(target)
+    })
 }
 
 /// A branchless DFS stack. We use a massively oversized stack and keep a None value at the 0th
 /// index to get some optimizations here
-pub struct DfsStack {
-    buf: Box<[Option<NonZeroU16>; SEARCH_STACK_SIZE]>,
+///
+/// `S` has to be a power of two since we mask the stack pointer with it. The default is measured
+/// on the generated graph by the build script and other graphs can pick their own, see
+/// `StaticGraph::dfs_iter_sized`. A search deeper than that spills into a growable overflow stack
+/// instead of wrapping over the bottom of the stack, so `S` only decides how much we allocate up
+/// front.
+#[derive(Clone)]
+pub struct DfsStack<const S: usize = SEARCH_STACK_SIZE> {
+    buf: Box<[Option<NonZeroU16>; S]>,
     ptr: usize,
+    // Everything pushed while the buffer was full, or since. It's all above what's in the buffer,
+    // so we pop from here first.
+    overflow: Vec<u16>,
 }
 
-impl Default for DfsStack {
+impl<const S: usize> Default for DfsStack<S> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl DfsStack {
+impl<const S: usize> DfsStack<S> {
     pub fn new() -> Self {
+        const { assert!(S.is_power_of_two()) };
         DfsStack {
-            buf: Box::new([NonZeroU16::new(0); SEARCH_STACK_SIZE]),
+            buf: Box::new([NonZeroU16::new(0); S]),
             ptr: 0,
+            overflow: Vec::new(),
         }
     }
 
     #[inline]
     pub fn push(&mut self, n: u16) {
-        debug_assert!(self.ptr < (SEARCH_STACK_SIZE - 1));
-        self.ptr = (self.ptr + 1) & (SEARCH_STACK_SIZE - 1);
+        if self.ptr == self.capacity() || !self.overflow.is_empty() {
+            self.overflow.push(n);
+            return;
+        }
+        self.ptr = (self.ptr + 1) & (S - 1);
         self.buf[self.ptr] = NonZeroU16::new(n);
     }
 
     #[inline]
     pub fn pop(&mut self) -> Option<NonZeroU16> {
-        self.ptr &= SEARCH_STACK_SIZE - 1;
+        if let Some(n) = self.overflow.pop() {
+            return NonZeroU16::new(n);
+        }
+        self.ptr &= S - 1;
         let s = self.buf[self.ptr];
-        self.ptr -= 1;
+        // Popping an empty stack reads the None at the 0th index and leaves the pointer there.
+        self.ptr = self.ptr.saturating_sub(1);
 
         s
     }
 
+    /// The node on top of the stack, without popping it.
+    #[inline]
+    pub fn peek(&self) -> Option<NonZeroU16> {
+        match self.overflow.last() {
+            Some(&n) => NonZeroU16::new(n),
+            None => self.buf[self.ptr & (S - 1)],
+        }
+    }
+
     #[inline]
     pub fn clear(&mut self) {
         self.ptr = 0;
+        self.overflow.clear();
+    }
+
+    /// The number of nodes currently on the stack.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.ptr + self.overflow.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The most nodes the buffer can hold. One slot less than `S` since the 0th is always None.
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        S - 1
+    }
+
+    /// How many more nodes we can push before the stack spills into its overflow.
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.capacity().saturating_sub(self.len())
+    }
+
+    /// The stacked nodes from top to bottom, without popping them.
+    pub fn iter(&self) -> impl Iterator<Item = NonZeroU16> + '_ {
+        let overflow = self
+            .overflow
+            .iter()
+            .rev()
+            .copied()
+            .filter_map(NonZeroU16::new);
+
+        overflow.chain(self.buf[1..=self.ptr].iter().rev().flatten().copied())
+    }
+}
+
+impl<const S: usize> fmt::Debug for DfsStack<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The 0th element is always None so the live stack starts at index one.
+        f.debug_list()
+            .entries(self.buf[1..=self.ptr].iter().flatten())
+            .entries(self.overflow.iter().copied().filter_map(NonZeroU16::new))
+            .finish()
     }
 }
 
-impl Iterator for DfsStack {
+impl<const S: usize> Iterator for DfsStack<S> {
     type Item = NonZeroU16;
 
     #[inline]
@@ -203,3 +387,53 @@ impl Iterator for DfsStack {
         self.pop()
     }
 }
+
+impl<const S: usize> FusedIterator for DfsStack<S> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::new_static_graph;
+
+    #[test]
+    fn peek_matches_next() {
+        let graph = new_static_graph();
+        let mut dfs_iter = graph.dfs_iter();
+        let mut count = 0;
+        while let Some(next) = dfs_iter.peek() {
+            let visited = dfs_iter.core.visited_count;
+            assert_eq!(dfs_iter.peek(), Some(next));
+            assert_eq!(dfs_iter.core.visited_count, visited);
+            assert_eq!(dfs_iter.next(), Some(next));
+            count += 1;
+        }
+        assert_eq!(dfs_iter.next(), None);
+        assert_eq!(count, graph.dfs_iter().count());
+
+        let mut stack: DfsStack = DfsStack::new();
+        assert_eq!(stack.peek(), None);
+        assert!(stack.is_empty());
+        assert_eq!(stack.remaining(), stack.capacity());
+        stack.push(3);
+        stack.push(7);
+        assert_eq!(stack.len(), 2);
+        assert_eq!(stack.remaining(), SEARCH_STACK_SIZE - 3);
+        assert_eq!(stack.peek(), NonZeroU16::new(7));
+        assert!(stack.iter().eq([7, 3].map(|n| NonZeroU16::new(n).unwrap())));
+        stack.pop();
+        assert_eq!(stack.peek(), NonZeroU16::new(3));
+        stack.pop();
+        assert_eq!(stack.peek(), None);
+
+        // Pushing past the buffer spills over without losing the order.
+        let mut stack: DfsStack<4> = DfsStack::new();
+        (1..=10).for_each(|n| stack.push(n));
+        assert_eq!((stack.len(), stack.remaining()), (10, 0));
+        assert!(stack.iter().map(u16::from).eq((1..=10).rev()));
+        stack.pop();
+        stack.push(11);
+        assert_eq!(stack.peek(), NonZeroU16::new(11));
+        assert!(stack.by_ref().map(u16::from).eq((1..=9).chain([11]).rev()));
+        assert!(stack.is_empty());
+    }
+}
@@ -0,0 +1,415 @@
+use alloc::vec::Vec;
+use core::{fmt, num::NonZeroU16};
+
+use crate::{graph::StaticGraph, logic::ReqIndex};
+
+/// A single structural difference between two graphs. See `StaticGraph::diff`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EdgeChange {
+    Added {
+        from: u16,
+        to: u16,
+        req: ReqIndex,
+    },
+    Removed {
+        from: u16,
+        to: u16,
+        req: ReqIndex,
+    },
+    /// The edge still leaves the same node with the same requirement but leads somewhere else,
+    /// e.g. after shuffling doors.
+    Retargeted {
+        from: u16,
+        old_to: u16,
+        new_to: u16,
+        req: ReqIndex,
+    },
+    /// The edge connects the same nodes but with a different requirement.
+    Requirement {
+        from: u16,
+        to: u16,
+        old_req: ReqIndex,
+        new_req: ReqIndex,
+    },
+}
+
+impl EdgeChange {
+    /// The node the changed edge leaves from.
+    pub fn from(&self) -> u16 {
+        match *self {
+            EdgeChange::Added { from, .. }
+            | EdgeChange::Removed { from, .. }
+            | EdgeChange::Retargeted { from, .. }
+            | EdgeChange::Requirement { from, .. } => from,
+        }
+    }
+}
+
+impl fmt::Display for EdgeChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EdgeChange::Added { from, to, req } => write!(f, "+ {} -> {} (req {})", from, to, req),
+            EdgeChange::Removed { from, to, req } => {
+                write!(f, "- {} -> {} (req {})", from, to, req)
+            }
+            EdgeChange::Retargeted {
+                from,
+                old_to,
+                new_to,
+                req,
+            } => write!(f, "~ {} -> {} => {} (req {})", from, old_to, new_to, req),
+            EdgeChange::Requirement {
+                from,
+                to,
+                old_req,
+                new_req,
+            } => write!(f, "~ {} -> {} (req {} => {})", from, to, old_req, new_req),
+        }
+    }
+}
+
+/// Everything that changed going from one graph to another, ordered by the node the changed
+/// edges leave from.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GraphDiff {
+    pub old_node_count: u16,
+    pub new_node_count: u16,
+    pub changes: Vec<EdgeChange>,
+}
+
+impl GraphDiff {
+    pub fn is_empty(&self) -> bool {
+        self.old_node_count == self.new_node_count && self.changes.is_empty()
+    }
+}
+
+/// One line per change, like a (very) simple unified diff.
+impl fmt::Display for GraphDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.old_node_count != self.new_node_count {
+            writeln!(
+                f,
+                "nodes: {} => {}",
+                self.old_node_count, self.new_node_count
+            )?;
+        }
+        self.changes
+            .iter()
+            .try_for_each(|change| writeln!(f, "{}", change))
+    }
+}
+
+impl<const M: usize, const N: usize, T> StaticGraph<M, N, T> {
+    /// Compare this graph's structure and logic against `other`, e.g. a shuffled or hand-edited
+    /// model against its vanilla baseline.
+    ///
+    /// Edges are compared per source node since edge indexes shift as soon as one edge is added
+    /// or removed anywhere before them. For every node we first match edges going to the same
+    /// place (reporting any change in requirement), then pair up what's left with the same
+    /// requirement as retargeted edges, in edge order. Anything still unmatched was added or
+    /// removed.
+    pub fn diff<const M2: usize, const N2: usize, U>(
+        &self,
+        other: &StaticGraph<M2, N2, U>,
+    ) -> GraphDiff {
+        let mut changes = Vec::new();
+        for node in 1..=self.node_count.max(other.node_count) {
+            let old = self.edges_out_of(node);
+            let new = other.edges_out_of(node);
+            let mut old_left: Vec<(u16, ReqIndex)> = Vec::new();
+            let mut new_used = alloc::vec![false; new.len()];
+            for &(to, req) in &old {
+                let same_target = (0..new.len())
+                    .filter(|&j| !new_used[j] && new[j].0 == to)
+                    .min_by_key(|&j| new[j].1 != req);
+                match same_target {
+                    Some(j) => {
+                        new_used[j] = true;
+                        if new[j].1 != req {
+                            changes.push(EdgeChange::Requirement {
+                                from: node,
+                                to,
+                                old_req: req,
+                                new_req: new[j].1,
+                            });
+                        }
+                    }
+                    None => old_left.push((to, req)),
+                }
+            }
+            for (to, req) in old_left {
+                match (0..new.len()).find(|&j| !new_used[j] && new[j].1 == req) {
+                    Some(j) => {
+                        new_used[j] = true;
+                        changes.push(EdgeChange::Retargeted {
+                            from: node,
+                            old_to: to,
+                            new_to: new[j].0,
+                            req,
+                        });
+                    }
+                    None => changes.push(EdgeChange::Removed {
+                        from: node,
+                        to,
+                        req,
+                    }),
+                }
+            }
+            new.iter()
+                .zip(new_used)
+                .filter(|(_, used)| !used)
+                .for_each(|(&(to, req), _)| {
+                    changes.push(EdgeChange::Added {
+                        from: node,
+                        to,
+                        req,
+                    })
+                });
+        }
+
+        GraphDiff {
+            old_node_count: self.node_count,
+            new_node_count: other.node_count,
+            changes,
+        }
+    }
+
+    /// Whether `other` is the same graph as this one as far as traversals can tell: the same
+    /// number of nodes, and every node has the same edges leaving it, going to the same places
+    /// with the same requirements in the same order. Metadata like node data, edge kinds and
+    /// weights, regions and payloads isn't compared, since not every format we round-trip
+    /// through keeps it.
+    ///
+    /// This is what regression tests want when they check a regenerated, re-imported or rebuilt
+    /// graph against a golden one. `diff` says what changed if this fails.
+    pub fn structurally_equal<const M2: usize, const N2: usize, U>(
+        &self,
+        other: &StaticGraph<M2, N2, U>,
+    ) -> bool {
+        self.node_count == other.node_count
+            && self.edge_count == other.edge_count
+            && self
+                .nodes()
+                .all(|node| self.edges_out_of(node) == other.edges_out_of(node))
+    }
+
+    /// Find a relabeling of `nodes` that turns the subgraph they induce in this graph into the one
+    /// `other_nodes` induce in `other`, i.e. one that maps every edge between two of `nodes` to an
+    /// edge with the same requirement between the corresponding `other_nodes` and vice versa.
+    /// Edges leaving either set are ignored. Returns what each of `nodes` maps to, in order.
+    ///
+    /// This lets tests compare a subgraph against a golden one without caring how either was
+    /// numbered, e.g. after a builder or codegen change reorders nodes. We backtrack over every
+    /// candidate pairing so this is only meant for small subgraphs of a few dozen nodes.
+    pub fn isomorphism<const M2: usize, const N2: usize, U>(
+        &self,
+        nodes: &[u16],
+        other: &StaticGraph<M2, N2, U>,
+        other_nodes: &[u16],
+    ) -> Option<Vec<u16>> {
+        if nodes.len() != other_nodes.len() {
+            return None;
+        }
+        let ours = InducedSubgraph::new(self, nodes);
+        let theirs = InducedSubgraph::new(other, other_nodes);
+        let mut mapping = Vec::with_capacity(nodes.len());
+        let mut used = alloc::vec![false; nodes.len()];
+        match ours.extend_mapping(&theirs, &mut mapping, &mut used) {
+            true => Some(mapping.into_iter().map(|i| other_nodes[i]).collect()),
+            false => None,
+        }
+    }
+
+    // `(destination, requirement)` for every edge leaving `node`, or nothing if the graph doesn't
+    // have that node.
+    fn edges_out_of(&self, node: u16) -> Vec<(u16, ReqIndex)> {
+        if node > self.node_count {
+            return Vec::new();
+        }
+        let (edge_pointers, edge_offset) = self.get_neighbors_out(NonZeroU16::new(node));
+        edge_pointers
+            .iter()
+            .enumerate()
+            .map(|(i, &dest)| (u16::from(dest), self.edge_data[edge_offset as usize + i]))
+            .collect()
+    }
+}
+
+// The edges between a set of nodes, numbered by their position in the set.
+struct InducedSubgraph {
+    // The requirements of the edges from `i` to `j` at `i * len + j`, sorted so parallel edges
+    // compare as a multiset.
+    edges: Vec<Vec<ReqIndex>>,
+    // `(out-degree, in-degree)` within the subgraph, which rules out most pairings up front.
+    degrees: Vec<(usize, usize)>,
+}
+
+impl InducedSubgraph {
+    fn new<const M: usize, const N: usize, T>(graph: &StaticGraph<M, N, T>, nodes: &[u16]) -> Self {
+        let len = nodes.len();
+        let mut edges = alloc::vec![Vec::new(); len * len];
+        let mut degrees = alloc::vec![(0, 0); len];
+        for (i, &node) in nodes.iter().enumerate() {
+            for (to, req) in graph.edges_out_of(node) {
+                if let Some(j) = nodes.iter().position(|&n| n == to) {
+                    edges[i * len + j].push(req);
+                    degrees[i].0 += 1;
+                    degrees[j].1 += 1;
+                }
+            }
+        }
+        edges.iter_mut().for_each(|reqs| reqs.sort_unstable());
+
+        InducedSubgraph { edges, degrees }
+    }
+
+    fn between(&self, from: usize, to: usize) -> &[ReqIndex] {
+        &self.edges[from * self.degrees.len() + to]
+    }
+
+    // Map the next of our nodes to each unused node of `other` that agrees with everything mapped
+    // so far, and recurse. Leaves `mapping` complete if we find a full match.
+    fn extend_mapping(&self, other: &Self, mapping: &mut Vec<usize>, used: &mut [bool]) -> bool {
+        let i = mapping.len();
+        if i == self.degrees.len() {
+            return true;
+        }
+        for j in 0..used.len() {
+            if used[j] || self.degrees[i] != other.degrees[j] {
+                continue;
+            }
+            mapping.push(j);
+            let consistent = mapping.iter().enumerate().all(|(k, &m)| {
+                self.between(i, k) == other.between(j, m)
+                    && self.between(k, i) == other.between(m, j)
+            });
+            if consistent {
+                used[j] = true;
+                if self.extend_mapping(other, mapping, used) {
+                    return true;
+                }
+                used[j] = false;
+            }
+            mapping.pop();
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        constants::*,
+        door::DoorShuffle,
+        graph::{new_static_graph, new_static_graph_open},
+        logic::CollectionState,
+    };
+    use alloc::sync::Arc;
+
+    #[test]
+    fn graph_diff() {
+        let graph = new_static_graph();
+        assert!(graph.diff(&graph).is_empty());
+        assert_eq!(std::format!("{}", graph.diff(&graph)), "");
+
+        // Shuffling doors only changes edges leaving doors, and mostly retargets them. A door
+        // whose edges have different requirements can also trade a destination between them.
+        let mut shuffled = new_static_graph();
+        shuffled
+            .shuffle_doors(&DoorShuffle::new(1, CollectionState::default()))
+            .unwrap();
+        let diff = graph.diff(&shuffled);
+        assert!(!diff.is_empty());
+        assert!(diff
+            .changes
+            .iter()
+            .all(|c| graph.door_data(c.from()).is_some()));
+        assert!(diff
+            .changes
+            .iter()
+            .any(|c| matches!(c, EdgeChange::Retargeted { .. })));
+        assert!(diff.changes.windows(2).all(|w| w[0].from() <= w[1].from()));
+
+        // Opening all logic only changes requirements.
+        let open = new_static_graph_open();
+        let diff = graph.diff(&open);
+        let locked = (0..graph.edge_count as usize + 1)
+            .filter(|&e| graph.edge_data[e] != ReqIndex::OPEN)
+            .count();
+        assert_eq!(diff.changes.len(), locked);
+        assert!(diff.changes.iter().all(|c| matches!(
+            c,
+            EdgeChange::Requirement {
+                new_req: ReqIndex::OPEN,
+                ..
+            }
+        )));
+
+        // An edge moved to a different requirement and destination is a removal and an addition.
+        let mut edited = new_static_graph();
+        let (edge_pointers, offset) = edited.get_neighbors_out(NonZeroU16::new(1));
+        let (old_to, old_req) = (
+            u16::from(edge_pointers[0]),
+            edited.edge_data[offset as usize],
+        );
+        let new_req = ReqIndex::new((old_req.get() + 1) % 7).unwrap();
+        Arc::make_mut(&mut edited.edge_data)[offset as usize] = new_req;
+        edited.edge_pointers.0[offset as usize] = NonZeroU16::new(old_to + 1).unwrap();
+        let diff = graph.diff(&edited);
+        assert_eq!(diff.changes.len(), 2);
+        assert!(diff.changes.contains(&EdgeChange::Removed {
+            from: 1,
+            to: old_to,
+            req: old_req
+        }));
+        assert!(diff.changes.contains(&EdgeChange::Added {
+            from: 1,
+            to: old_to + 1,
+            req: new_req
+        }));
+        assert_eq!(
+            std::format!("{}", diff.changes[0]),
+            std::format!("- 1 -> {} (req {})", old_to, old_req)
+        );
+    }
+
+    #[test]
+    fn structural_equality() {
+        let graph = new_static_graph();
+        assert!(graph.structurally_equal(&graph));
+        let read =
+            StaticGraph::<NUM_VERTICES_PADDED, NUM_EDGES_PADDED>::from_bytes(&graph.to_bytes())
+                .unwrap();
+        assert!(read.structurally_equal(&graph));
+        assert!(!graph.structurally_equal(&new_static_graph_open()));
+
+        // The same triangle numbered differently.
+        let small =
+            StaticGraph::<8, 64>::from_edge_list(["1 2 flute", "2 3", "3 1 locked"]).unwrap();
+        let relabeled =
+            StaticGraph::<8, 64>::from_edge_list(["3 1 flute", "1 2", "2 3 locked"]).unwrap();
+        assert!(!small.structurally_equal(&relabeled));
+        assert_eq!(
+            small.isomorphism(&[1, 2, 3], &relabeled, &[1, 2, 3]),
+            Some(alloc::vec![3, 1, 2])
+        );
+        let reversed =
+            StaticGraph::<8, 64>::from_edge_list(["1 2 locked", "2 3", "3 1 flute"]).unwrap();
+        assert_eq!(small.isomorphism(&[1, 2, 3], &reversed, &[1, 2, 3]), None);
+        assert_eq!(small.isomorphism(&[1, 2], &small, &[1, 2, 3]), None);
+
+        // A piece of the generated graph matches itself listed backwards.
+        let nodes: Vec<u16> = graph.bfs_iter().take(12).map(u16::from).collect();
+        let backwards: Vec<u16> = nodes.iter().rev().copied().collect();
+        let mapping = graph.isomorphism(&nodes, &graph, &backwards).unwrap();
+        for (i, &node) in nodes.iter().enumerate() {
+            for (to, req) in graph.edges_out_of(node) {
+                if let Some(j) = nodes.iter().position(|&n| n == to) {
+                    assert!(graph.edges_out_of(mapping[i]).contains(&(mapping[j], req)));
+                }
+            }
+        }
+    }
+}
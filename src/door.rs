@@ -0,0 +1,340 @@
+use alloc::{vec, vec::Vec};
+use core::{fmt, num::NonZeroU16};
+
+use crate::{
+    graph::{DoorFlags, StaticGraph},
+    logic::CollectionState,
+    rng::SplitMix64,
+    tracker::TrackerSession,
+};
+
+/// Where a door leads: an edge leaving a door node and the node at the other end.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DoorTransition {
+    pub edge: u16,
+    pub door: u16,
+    pub target: u16,
+}
+
+/// Settings for `StaticGraph::shuffle_doors`.
+///
+/// Doors only trade destinations with doors of the same shape, which for us is their
+/// `DoorFlags`, so e.g. a big key door always still leads somewhere a big key door did. In
+/// coupled mode, two doors leading into each other stay paired: if door A ends up leading to
+/// door B then B leads back to A, as you'd expect walking back through a door. Transitions
+/// without a partner going the other way are shuffled on their own either way.
+#[derive(Clone, Debug)]
+pub struct DoorShuffle {
+    goal: u16,
+    state: CollectionState,
+    seed: u64,
+    coupled: bool,
+    max_attempts: u32,
+}
+
+impl DoorShuffle {
+    pub const DEFAULT_MAX_ATTEMPTS: u32 = 16;
+
+    /// Shuffle doors such that `goal` stays reachable under `state`.
+    pub fn new(goal: u16, state: CollectionState) -> Self {
+        DoorShuffle {
+            goal,
+            state,
+            seed: 0,
+            coupled: false,
+            max_attempts: Self::DEFAULT_MAX_ATTEMPTS,
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn coupled(mut self, coupled: bool) -> Self {
+        self.coupled = coupled;
+        self
+    }
+
+    /// How many shuffles to try before giving up. At least one is always tried.
+    pub fn with_max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts;
+        self
+    }
+}
+
+/// Why `StaticGraph::shuffle_doors` couldn't produce a valid graph. This is the reason the last
+/// attempt failed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DoorError {
+    /// The node was reachable with every item before the shuffle but isn't anymore.
+    Disconnected(u16),
+    /// The goal isn't reachable under the shuffle's collection state.
+    Unbeatable,
+}
+
+impl fmt::Display for DoorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DoorError::Disconnected(n) => write!(f, "node {} became unreachable", n),
+            DoorError::Unbeatable => write!(f, "goal is unreachable"),
+        }
+    }
+}
+
+// Two doors leading into each other, as `[there, back]`.
+type DoorPair = [DoorTransition; 2];
+
+// The transitions that trade destinations with each other.
+enum DoorGroup {
+    OneWay(Vec<DoorTransition>),
+    TwoWay(Vec<DoorPair>),
+}
+
+impl<const M: usize, const N: usize, T> StaticGraph<M, N, T> {
+    /// Every door node, in index order.
+    pub fn door_nodes(&self) -> impl Iterator<Item = u16> + '_ {
+        (1..=self.node_count).filter(|&n| self.door_data(n).is_some())
+    }
+
+    /// Every edge leaving a door node, ordered by edge index.
+    pub fn door_transitions(&self) -> Vec<DoorTransition> {
+        self.door_nodes()
+            .flat_map(|door| {
+                let (edge_pointers, edge_offset) = self.get_neighbors_out(NonZeroU16::new(door));
+                edge_pointers
+                    .iter()
+                    .enumerate()
+                    .map(move |(i, &dest)| DoorTransition {
+                        edge: edge_offset + i as u16,
+                        door,
+                        target: u16::from(dest),
+                    })
+            })
+            .collect()
+    }
+
+    /// Permute where doors lead and rewrite the edge arrays to match, retrying with a new
+    /// permutation until every node reachable with all items before the shuffle still is and
+    /// the goal is reachable under the shuffle's state. Only edge destinations change, so the
+    /// requirement on each edge stays with the door it leaves from. Returns every door transition
+    /// after the shuffle. If no attempt is valid the graph is left as it was.
+    pub fn shuffle_doors(
+        &mut self,
+        shuffle: &DoorShuffle,
+    ) -> Result<Vec<DoorTransition>, DoorError> {
+        let original = self.door_transitions();
+        let groups = self.door_groups(&original, shuffle.coupled);
+        let before = TrackerSession::new(self, CollectionState::ALL_ITEMS);
+        let reachable: Vec<u16> = (1..=self.node_count)
+            .filter(|&n| before.is_reachable(n))
+            .collect();
+
+        let mut rng = SplitMix64::new(shuffle.seed);
+        let mut error = DoorError::Unbeatable;
+        for _ in 0..shuffle.max_attempts.max(1) {
+            groups.iter().for_each(|g| self.permute_doors(g, &mut rng));
+            match self.validate_doors(&reachable, shuffle) {
+                Ok(()) => return Ok(self.door_transitions()),
+                Err(e) => error = e,
+            }
+            original
+                .iter()
+                .for_each(|t| self.set_edge_target(t.edge, t.target));
+        }
+
+        Err(error)
+    }
+
+    fn door_groups(&self, transitions: &[DoorTransition], coupled: bool) -> Vec<DoorGroup> {
+        let flags = |door: u16| self.door_data(door).map_or(DoorFlags::NONE, |d| d.flags);
+        let mut paired = vec![false; transitions.len()];
+        let mut two_way: Vec<((DoorFlags, DoorFlags), Vec<DoorPair>)> = Vec::new();
+        if coupled {
+            for (i, &there) in transitions.iter().enumerate() {
+                let back = transitions.iter().enumerate().find(|&(j, t)| {
+                    j > i && !paired[j] && t.door == there.target && t.target == there.door
+                });
+                let Some((j, &back)) = back else {
+                    continue;
+                };
+                if paired[i] || there.door == there.target {
+                    continue;
+                }
+                paired[i] = true;
+                paired[j] = true;
+                // Order each pair so the side with the smaller flags comes first and pairs of the
+                // same shape line up.
+                let pair = match flags(there.door).0 <= flags(back.door).0 {
+                    true => [there, back],
+                    false => [back, there],
+                };
+                let shape = (flags(pair[0].door), flags(pair[1].door));
+                match two_way.iter_mut().find(|(s, _)| *s == shape) {
+                    Some((_, group)) => group.push(pair),
+                    None => two_way.push((shape, vec![pair])),
+                }
+            }
+        }
+        let mut one_way: Vec<(DoorFlags, Vec<DoorTransition>)> = Vec::new();
+        for (&t, _) in transitions.iter().zip(paired.iter()).filter(|(_, &p)| !p) {
+            let shape = flags(t.door);
+            match one_way.iter_mut().find(|(s, _)| *s == shape) {
+                Some((_, group)) => group.push(t),
+                None => one_way.push((shape, vec![t])),
+            }
+        }
+
+        one_way
+            .into_iter()
+            .map(|(_, g)| DoorGroup::OneWay(g))
+            .chain(two_way.into_iter().map(|(_, g)| DoorGroup::TwoWay(g)))
+            .collect()
+    }
+
+    fn permute_doors(&mut self, group: &DoorGroup, rng: &mut SplitMix64) {
+        match group {
+            DoorGroup::OneWay(transitions) => {
+                let mut targets: Vec<u16> = transitions.iter().map(|t| t.target).collect();
+                rng.shuffle(&mut targets);
+                transitions
+                    .iter()
+                    .zip(targets)
+                    .for_each(|(t, target)| self.set_edge_target(t.edge, target));
+            }
+            // Keep the first side of every pair and deal out the second sides.
+            DoorGroup::TwoWay(pairs) => {
+                let mut order: Vec<usize> = (0..pairs.len()).collect();
+                rng.shuffle(&mut order);
+                for (i, &j) in order.iter().enumerate() {
+                    let (there, back) = (pairs[i][0], pairs[j][1]);
+                    self.set_edge_target(there.edge, back.door);
+                    self.set_edge_target(back.edge, there.door);
+                }
+            }
+        }
+    }
+
+    // We only ask whether particular nodes are reachable, never in what order, and a tracker
+    // session has that answer for every node as soon as it's built.
+    fn validate_doors(&self, reachable: &[u16], shuffle: &DoorShuffle) -> Result<(), DoorError> {
+        let after = TrackerSession::new(self, CollectionState::ALL_ITEMS);
+        if let Some(&n) = reachable.iter().find(|&&n| !after.is_reachable(n)) {
+            return Err(DoorError::Disconnected(n));
+        }
+
+        match TrackerSession::new(self, shuffle.state.clone()).is_reachable(shuffle.goal) {
+            true => Ok(()),
+            false => Err(DoorError::Unbeatable),
+        }
+    }
+
+    fn set_edge_target(&mut self, edge: u16, target: u16) {
+        self.edge_pointers.0[edge as usize] = NonZeroU16::new(target).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        graph::new_static_graph,
+        logic::{EventFlags, SettingsState, TierMask},
+    };
+
+    fn reachable<const M: usize, const N: usize>(
+        graph: &StaticGraph<M, N>,
+        state: &CollectionState,
+        node: u16,
+    ) -> bool {
+        TrackerSession::new(graph, state.clone()).is_reachable(node)
+    }
+
+    #[test]
+    fn door_shuffle() {
+        let graph = new_static_graph();
+        let original = graph.door_transitions();
+        assert!(original
+            .iter()
+            .all(|t| graph.door_data(t.door).is_some() && t.target != 0));
+        let state = CollectionState::default();
+        let goal = graph
+            .dfs_iter_with_state(state.clone())
+            .last()
+            .map(u16::from)
+            .unwrap();
+        let two_way = |transitions: &[DoorTransition]| {
+            transitions
+                .iter()
+                .filter(|t| {
+                    transitions
+                        .iter()
+                        .any(|u| u.door == t.target && u.target == t.door)
+                })
+                .count()
+        };
+        let shapes = |transitions: &[DoorTransition]| {
+            let mut shapes: Vec<(u8, u16)> = transitions
+                .iter()
+                .map(|t| (graph.door_data(t.door).unwrap().flags.0, t.target))
+                .collect();
+            shapes.sort_unstable();
+            shapes
+        };
+
+        for coupled in [false, true] {
+            let mut shuffled_graph = new_static_graph();
+            let shuffle = DoorShuffle::new(goal, state.clone())
+                .with_seed(7)
+                .coupled(coupled);
+            let shuffled = shuffled_graph.shuffle_doors(&shuffle).unwrap();
+            assert!(shuffled.iter().zip(original.iter()).any(|(a, b)| a != b));
+            // Only destinations move, and only between doors of the same shape.
+            assert!(shuffled
+                .iter()
+                .zip(original.iter())
+                .all(|(a, b)| (a.edge, a.door) == (b.edge, b.door)));
+            assert_eq!(shapes(&shuffled), shapes(&original));
+            assert!(reachable(&shuffled_graph, &state, goal));
+        }
+
+        // Our generated graph has no two-way doors, so pair up doors that lead into each other
+        // first. Coupled shuffling has to keep them paired.
+        let mut paired_graph = new_static_graph();
+        let mut used: Vec<u16> = Vec::new();
+        for t in original
+            .iter()
+            .filter(|t| graph.door_data(t.target).is_some())
+        {
+            let back = original
+                .iter()
+                .find(|u| u.door == t.target && u.door != t.door && !used.contains(&u.edge));
+            match back {
+                Some(u) if !used.contains(&t.edge) => {
+                    paired_graph.set_edge_target(u.edge, t.door);
+                    used.extend([t.edge, u.edge]);
+                }
+                _ => (),
+            }
+        }
+        let paired = paired_graph.door_transitions();
+        assert!(two_way(&paired) > 0);
+        let shuffle = DoorShuffle::new(1, state.clone())
+            .with_seed(7)
+            .coupled(true);
+        let shuffled = paired_graph.shuffle_doors(&shuffle).unwrap();
+        assert_ne!(shuffled, paired);
+        assert!(two_way(&shuffled) >= two_way(&paired));
+        assert_eq!(shapes(&shuffled), shapes(&paired));
+
+        // A goal we can't reach leaves the graph untouched.
+        let mut graph = new_static_graph();
+        let nothing = CollectionState::NOTHING;
+        let unreachable = (1..=graph.node_count)
+            .find(|&n| !reachable(&graph, &nothing, n))
+            .unwrap();
+        let shuffle = DoorShuffle::new(unreachable, nothing).with_max_attempts(2);
+        assert_eq!(graph.shuffle_doors(&shuffle), Err(DoorError::Unbeatable));
+        assert_eq!(graph.door_transitions(), original);
+    }
+}
@@ -0,0 +1,188 @@
+// Searches that yield the edges they traverse rather than the nodes they reach.
+use alloc::{collections::VecDeque, vec, vec::Vec};
+use core::{fmt, iter::FusedIterator, num::NonZeroU16};
+
+use crate::{
+    graph::StaticGraph,
+    logic::{CollectionState, EvaluateLogic},
+    traversal::{TraversalCore, WalkOrder},
+};
+
+/// An edge a search went through to reach a node for the first time.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TraversedEdge {
+    pub src: u16,
+    /// The edge's index into the graph's edge arrays.
+    pub edge: u16,
+    pub dst: u16,
+}
+
+/// A breadth- or depth-first search yielding every edge it relaxes, in the order it relaxes them.
+/// Each node other than the root is reached through exactly one of these, so collecting them
+/// gives the search's traversal tree.
+pub struct EdgeIter<'graph, const M: usize, const N: usize, T = ()> {
+    pub core: TraversalCore<'graph, M, N, T>,
+    order: WalkOrder,
+    frontier: VecDeque<u16>,
+    // Edges relaxed from the last node we expanded that we haven't yielded yet.
+    relaxed: VecDeque<TraversedEdge>,
+}
+
+impl<'graph, const M: usize, const N: usize, T> StaticGraph<M, N, T> {
+    /// A depth-first search from the root yielding the edges it takes. See `EdgeIter`.
+    pub fn dfs_edges(&'graph self) -> EdgeIter<'graph, M, N, T> {
+        self.edges_with_state(WalkOrder::DepthFirst, CollectionState::default())
+    }
+
+    /// A breadth-first search from the root yielding the edges it takes. See `EdgeIter`.
+    pub fn bfs_edges(&'graph self) -> EdgeIter<'graph, M, N, T> {
+        self.edges_with_state(WalkOrder::BreadthFirst, CollectionState::default())
+    }
+
+    /// Same as `.dfs_edges()` or `.bfs_edges()` but evaluates the graph's logic against the given
+    /// collection state instead of the default one.
+    pub fn edges_with_state(
+        &'graph self,
+        order: WalkOrder,
+        state: CollectionState,
+    ) -> EdgeIter<'graph, M, N, T> {
+        self.edges_from(1, order, state)
+    }
+
+    /// Same as `.edges_with_state()` but searching from `root` instead of the graph's root.
+    pub fn edges_from(
+        &'graph self,
+        root: u16,
+        order: WalkOrder,
+        state: CollectionState,
+    ) -> EdgeIter<'graph, M, N, T> {
+        EdgeIter {
+            core: TraversalCore::new(self, root, state),
+            order,
+            frontier: VecDeque::from([root]),
+            relaxed: VecDeque::new(),
+        }
+    }
+
+    /// The nodes along a path from `from` to `to` under `state` with as few edges as possible,
+    /// both ends included, or `None` if `to` isn't reachable from `from`.
+    pub fn shortest_path(
+        &'graph self,
+        from: u16,
+        to: u16,
+        state: CollectionState,
+    ) -> Option<Vec<u16>> {
+        // Breadth-first, so the first edge into `to` ends a shortest path.
+        let mut parent = vec![0u16; self.node_count as usize + 1];
+        if from != to {
+            self.edges_from(from, WalkOrder::BreadthFirst, state)
+                .find(|e| {
+                    parent[e.dst as usize] = e.src;
+                    e.dst == to
+                })?;
+        }
+        let mut path = vec![to];
+        while let Some(&node) = path.last().filter(|&&n| n != from) {
+            path.push(parent[node as usize]);
+        }
+        path.reverse();
+
+        Some(path)
+    }
+}
+
+impl<const M: usize, const N: usize, T> EvaluateLogic for EdgeIter<'_, M, N, T> {
+    fn collection_state(&self) -> &CollectionState {
+        &self.core.collection_state
+    }
+}
+
+impl<const M: usize, const N: usize, T> Iterator for EdgeIter<'_, M, N, T> {
+    type Item = TraversedEdge;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.relaxed.is_empty() {
+            let src = match self.order {
+                WalkOrder::BreadthFirst => self.frontier.pop_front(),
+                WalkOrder::DepthFirst => self.frontier.pop_back(),
+            }?;
+            let (frontier, relaxed) = (&mut self.frontier, &mut self.relaxed);
+            self.core
+                .visit_edges_out(NonZeroU16::new(src), |edge, dst| {
+                    frontier.push_back(dst);
+                    relaxed.push_back(TraversedEdge { src, edge, dst });
+                });
+        }
+
+        self.relaxed.pop_front()
+    }
+
+    // Every relaxed edge will be yielded and at most one more per node we haven't visited yet.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let pending = self.relaxed.len();
+
+        (pending, Some(pending + self.core.unvisited()))
+    }
+}
+
+impl<const M: usize, const N: usize, T> FusedIterator for EdgeIter<'_, M, N, T> {}
+
+impl<const M: usize, const N: usize, T> fmt::Debug for EdgeIter<'_, M, N, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EdgeIter")
+            .field("core", &self.core)
+            .field("order", &self.order)
+            .field("frontier", &self.frontier)
+            .field("relaxed", &self.relaxed)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::graph::new_static_graph;
+
+    #[test]
+    fn traversal_tree_edges() {
+        let graph = new_static_graph();
+        let reachable = graph.dfs_iter().count();
+        for edges in [graph.dfs_edges(), graph.bfs_edges()] {
+            let edges: Vec<TraversedEdge> = edges.collect();
+            // Every node but the root is reached exactly once.
+            assert_eq!(edges.len(), reachable - 1);
+            let mut reached = alloc::vec![false; graph.node_count as usize + 1];
+            reached[1] = true;
+            for e in &edges {
+                // Sources are always reached before the edges leaving them are relaxed.
+                assert!(reached[e.src as usize]);
+                assert!(!reached[e.dst as usize]);
+                reached[e.dst as usize] = true;
+                let (dests, offset) = graph.get_neighbors_out(NonZeroU16::new(e.src));
+                assert_eq!(u16::from(dests[(e.edge - offset) as usize]), e.dst);
+            }
+        }
+        // Breadth-first edges come out grouped by the order their sources were reached.
+        let bfs: Vec<u16> = graph.bfs_edges().map(|e| e.dst).collect();
+        let nodes: Vec<u16> = graph.bfs_iter().skip(1).map(u16::from).collect();
+        assert_eq!(bfs, nodes);
+
+        // Paths start and end where we asked, only follow real edges, and going by way of another
+        // node is never shorter.
+        let state = CollectionState::default();
+        let (&far, &mid) = (nodes.last().unwrap(), &nodes[nodes.len() / 2]);
+        let path = graph.shortest_path(1, far, state.clone()).unwrap();
+        assert_eq!((path[0], path[path.len() - 1]), (1, far));
+        assert!(path.windows(2).all(|w| graph
+            .get_neighbors_out(NonZeroU16::new(w[0]))
+            .0
+            .contains(&NonZeroU16::new(w[1]).unwrap())));
+        let to_mid = graph.shortest_path(1, mid, state.clone()).unwrap();
+        assert!(to_mid.len() <= path.len());
+        let from_mid = graph.shortest_path(mid, far, state.clone());
+        assert!(from_mid.map_or(true, |p| p[0] == mid && path.len() < to_mid.len() + p.len()));
+        assert_eq!(graph.shortest_path(far, far, state), Some(alloc::vec![far]));
+    }
+}
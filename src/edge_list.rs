@@ -0,0 +1,293 @@
+use alloc::{boxed::Box, collections::BTreeSet, sync::Arc, vec::Vec};
+use core::{fmt, num::NonZeroU16};
+
+use crate::{
+    constants::MAX_OUT_DEGREE,
+    graph::StaticGraph,
+    logic::{ReqBuilder, ReqIndex, REQ_CONTAINER_LEN, REQ_TREES},
+    parser::{parse_requirement, ParseError},
+};
+
+/// How many requirement tree nodes an edge list graph can have, the library's included.
+pub const MAX_EDGE_LIST_TREES: usize = 4096;
+
+/// Why `StaticGraph::from_edge_list` rejected its input. Lines are numbered from 1.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EdgeListError {
+    /// The line doesn't start with a source and a destination.
+    Malformed { line: usize },
+    /// A node isn't a number or is zero, which is reserved for the terminal node.
+    BadNode { line: usize },
+    /// The requirement expression doesn't parse.
+    Requirement { line: usize, error: ParseError },
+    /// The graph's requirement trees already have `MAX_EDGE_LIST_TREES` nodes.
+    TooManyTrees { line: usize },
+    /// The same source and destination were already connected on an earlier line.
+    Duplicate { line: usize, from: u16, to: u16 },
+    /// The node already has `MAX_OUT_DEGREE` outgoing edges.
+    DegreeTooHigh { line: usize, node: u16 },
+    /// The graph has more nodes or edges than this `StaticGraph` can hold.
+    TooLarge,
+}
+
+impl fmt::Display for EdgeListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EdgeListError::Malformed { line } => {
+                write!(f, "line {}: expected a source and a destination", line)
+            }
+            EdgeListError::BadNode { line } => write!(f, "line {}: bad node", line),
+            EdgeListError::Requirement { line, error } => write!(f, "line {}: {}", line, error),
+            EdgeListError::TooManyTrees { line } => write!(
+                f,
+                "line {}: more than {} requirement tree nodes",
+                line, MAX_EDGE_LIST_TREES
+            ),
+            EdgeListError::Duplicate { line, from, to } => {
+                write!(f, "line {}: duplicate edge from {} to {}", line, from, to)
+            }
+            EdgeListError::DegreeTooHigh { line, node } => write!(
+                f,
+                "line {}: node {} has more than {} outgoing edges",
+                line, node, MAX_OUT_DEGREE
+            ),
+            EdgeListError::TooLarge => write!(f, "graph is too large"),
+        }
+    }
+}
+
+fn parse_node(word: &str, line: usize) -> Result<u16, EdgeListError> {
+    word.parse()
+        .ok()
+        .filter(|&n| n != 0)
+        .ok_or(EdgeListError::BadNode { line })
+}
+
+impl<const M: usize, const N: usize, T: Default> StaticGraph<M, N, T> {
+    /// Build a graph from a plain edge list, one edge per line:
+    ///
+    /// ```text
+    /// # src dst requirement
+    /// 1 2
+    /// 2 3 boots | hammer
+    /// 3 1 gloves and hammer
+    /// ```
+    ///
+    /// Nodes are numbered from 1, which is the root, and the graph has as many nodes as the
+    /// highest one mentioned. Fields are separated by any amount of whitespace. The requirement is
+    /// anything `parser::parse_requirement` accepts and defaults to open. Each one is built into
+    /// the graph's own requirement trees, after the library's, sharing every subtree the trees
+    /// already have. `#` starts a comment. This takes lines rather than a reader so it works
+    /// without std, e.g. with `str::lines` or `BufRead::lines` after handling IO errors.
+    ///
+    /// Like a graph read with `from_bytes` the result has default node data, no wide metadata or
+    /// regions and normal edges with a weight of one.
+    pub fn from_edge_list<I>(lines: I) -> Result<Self, EdgeListError>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut edges: Vec<(u16, u16, ReqIndex)> = Vec::new();
+        let mut trees = Box::new(ReqBuilder::<MAX_EDGE_LIST_TREES>::from_trees(&REQ_TREES));
+        // Every `(from, to)` we've seen, and how many edges leave each node so far.
+        let mut connected = BTreeSet::new();
+        let mut degrees: Vec<usize> = Vec::new();
+        let mut node_count = 0u16;
+        for (i, text) in lines.into_iter().enumerate() {
+            let line = i + 1;
+            let text = text.as_ref();
+            let text = text.split('#').next().unwrap_or_default().trim();
+            if text.is_empty() {
+                continue;
+            }
+            // The two nodes, then whatever's left is the requirement. Any run of whitespace
+            // separates them, e.g. tab-aligned columns.
+            let (from, rest) = text
+                .split_once(char::is_whitespace)
+                .ok_or(EdgeListError::Malformed { line })?;
+            let rest = rest.trim_start();
+            let (to, req) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            let (from, to) = (parse_node(from, line)?, parse_node(to, line)?);
+            let req = match req.trim() {
+                "" => ReqIndex::OPEN,
+                expr => parse_requirement(expr)
+                    .map_err(|error| EdgeListError::Requirement { line, error })?
+                    .build(&mut trees)
+                    .and_then(|idx| ReqIndex::new_in(idx, trees.nodes()))
+                    .ok_or(EdgeListError::TooManyTrees { line })?,
+            };
+            if !connected.insert((from, to)) {
+                return Err(EdgeListError::Duplicate { line, from, to });
+            }
+            if degrees.len() <= from as usize {
+                degrees.resize(from as usize + 1, 0);
+            }
+            if degrees[from as usize] == MAX_OUT_DEGREE {
+                return Err(EdgeListError::DegreeTooHigh { line, node: from });
+            }
+            degrees[from as usize] += 1;
+            node_count = node_count.max(from).max(to);
+            edges.push((from, to, req));
+        }
+        let (nodes, edge_count) = (node_count as usize, edges.len());
+        let mut graph = StaticGraph::with_capacity_hint(nodes, edge_count)
+            .map_err(|_| EdgeListError::TooLarge)?;
+        // Stable, so edges leaving the same node keep the order they were written in.
+        edges.sort_by_key(|&(from, ..)| from);
+
+        graph.node_count = node_count;
+        graph.edge_count = edge_count as u16;
+        if trees.len() > REQ_CONTAINER_LEN {
+            graph.req_trees = Some(Arc::from(trees.nodes()));
+        }
+        // Node i's edges start after every edge leaving a lower node. The terminal node and
+        // padding nodes have none.
+        let end = NonZeroU16::new(edge_count as u16 + 1).unwrap();
+        for node in 1..=node_count {
+            let start = edges.partition_point(|&(from, ..)| from < node) + 1;
+            graph.node_pointers.0[node as usize] = NonZeroU16::new(start as u16).unwrap();
+        }
+        graph.node_pointers.0[nodes + 1..].fill(end);
+        let edge_data = Arc::make_mut(&mut graph.edge_data);
+        for (i, &(_, to, req)) in edges.iter().enumerate() {
+            graph.edge_pointers.0[i + 1] = NonZeroU16::new(to).unwrap();
+            edge_data[i + 1] = req;
+        }
+        // We checked the size above and laid the pointers out in order ourselves.
+        graph
+            .validate()
+            .expect("edge list graphs are laid out in order");
+
+        Ok(graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{constants::*, logic::CollectionState};
+
+    type Graph = StaticGraph<NUM_VERTICES_PADDED, NUM_EDGES_PADDED>;
+
+    const EDGES: &str = "
+        # A small loop with a locked shortcut.
+        1 2
+        2 3 boots | hammer
+        3 1 gloves and hammer
+        1 3   locked
+        2  4	flute   # trailing comment
+    ";
+
+    #[test]
+    fn import_edge_list() {
+        let graph = Graph::from_edge_list(EDGES.lines()).unwrap();
+        assert_eq!(graph.node_count, 4);
+        assert_eq!(graph.edge_count, 5);
+        let out = |n: u16| graph.get_neighbors_out(NonZeroU16::new(n)).0.to_vec();
+        assert_eq!(
+            out(1),
+            [NonZeroU16::new(2).unwrap(), NonZeroU16::new(3).unwrap()]
+        );
+        assert_eq!(
+            out(2),
+            [NonZeroU16::new(3).unwrap(), NonZeroU16::new(4).unwrap()]
+        );
+        assert!(out(4).is_empty());
+        let reqs: Vec<u16> = (1..=5).map(|e| graph.edge_data[e].get()).collect();
+        assert_eq!(reqs, [0, 1, 2, 6, 5]);
+        // The default state has boots, hammer and flute but no gloves.
+        let reached: Vec<u16> = graph
+            .dfs_iter_with_state(CollectionState::default())
+            .map(u16::from)
+            .collect();
+        assert_eq!(reached.len(), 4);
+
+        let from_edge_list = |text: &str| Graph::from_edge_list(text.lines());
+        assert_eq!(from_edge_list("1  2").unwrap().edge_count, 1);
+        assert_eq!(
+            from_edge_list("1").err(),
+            Some(EdgeListError::Malformed { line: 1 })
+        );
+        assert_eq!(
+            from_edge_list("1 0").err(),
+            Some(EdgeListError::BadNode { line: 1 })
+        );
+        assert_eq!(
+            from_edge_list("\n1 2 sword").err(),
+            Some(EdgeListError::Requirement {
+                line: 2,
+                error: ParseError::UnknownName(0)
+            })
+        );
+        assert_eq!(
+            from_edge_list("1 2\n1 2 hammer").err(),
+            Some(EdgeListError::Duplicate {
+                line: 2,
+                from: 1,
+                to: 2
+            })
+        );
+        let fan_out = (2..=MAX_OUT_DEGREE + 2).map(|n| std::format!("1 {}", n));
+        assert_eq!(
+            Graph::from_edge_list(fan_out).err(),
+            Some(EdgeListError::DegreeTooHigh {
+                line: MAX_OUT_DEGREE + 1,
+                node: 1
+            })
+        );
+        assert_eq!(
+            StaticGraph::<64, 64>::from_edge_list(["1 100"]).err(),
+            Some(EdgeListError::TooLarge)
+        );
+
+        // A long list, which checking every edge against every earlier one made crawl, still
+        // catches a duplicate at its very end.
+        let ladder: Vec<std::string::String> = (1..40_000u16)
+            .map(|n| std::format!("{} {}", n, n + 1))
+            .collect();
+        let graph = Graph::from_edge_list(&ladder).unwrap();
+        assert_eq!(graph.edge_count as usize, ladder.len());
+        let mut doubled = ladder.clone();
+        doubled.push("39999 40000".into());
+        assert_eq!(
+            Graph::from_edge_list(&doubled).err(),
+            Some(EdgeListError::Duplicate {
+                line: doubled.len(),
+                from: 39999,
+                to: 40000
+            })
+        );
+    }
+
+    #[test]
+    fn requirements_outside_the_library() {
+        let lines = [
+            "1 2 boots & flute",
+            "2 3 hammer | gloves",
+            "3 4 gloves & boots",
+            "4 5 (boots | flute) & (hammer | gloves) & normal",
+            "5 6 boots & flute",
+        ];
+        let graph = Graph::from_edge_list(lines).unwrap();
+        assert!(graph.req_trees().len() > REQ_CONTAINER_LEN);
+        assert!(graph.is_monotone());
+        // The same expression written again shares its tree.
+        assert_eq!(graph.edge_data[1], graph.edge_data[5]);
+        for state in crate::parser::all_states() {
+            let memo = graph.req_memo(&state);
+            for (edge, line) in (1..).zip(lines) {
+                let expr = parse_requirement(line.splitn(3, ' ').nth(2).unwrap()).unwrap();
+                assert_eq!(
+                    memo.get(graph.edge_data[edge]),
+                    expr.eval(&state),
+                    "{}",
+                    line
+                );
+            }
+        }
+        // And they survive the byte encoding.
+        let read = Graph::from_bytes(&graph.to_bytes()).unwrap();
+        assert_eq!(read.req_trees(), graph.req_trees());
+        assert_eq!(read.to_bytes(), graph.to_bytes());
+    }
+}
@@ -0,0 +1,689 @@
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    fmt::{self, Write},
+    num::NonZeroU16,
+};
+
+use crate::{
+    graph::{EdgeType, NodeType, StaticGraph, TrickMask, VisitedSet},
+    logic::{EvaluateLogic, ReqIndex, Requirement},
+    traversal::{LogicMode, TraversalCore},
+};
+
+// Every format below wants the same things: a node's name, type and region, and each edge's
+// endpoints, requirement, kind and weight. These get them for any graph so the writers can stick
+// to formatting.
+
+fn node_name<const M: usize, const N: usize, T>(
+    graph: &StaticGraph<M, N, T>,
+    node: u16,
+) -> &'static str {
+    graph.node_name(node).unwrap_or("")
+}
+
+fn node_type<const M: usize, const N: usize, T>(
+    graph: &StaticGraph<M, N, T>,
+    node: u16,
+) -> &'static str {
+    match graph.node_data(node).node_type {
+        NodeType::Place => "Place",
+        NodeType::Item => "Item",
+        NodeType::Door => "Door",
+    }
+}
+
+fn region_name<const M: usize, const N: usize, T>(
+    graph: &StaticGraph<M, N, T>,
+    node: u16,
+) -> &'static str {
+    graph.region_of(node).map_or("", |r| r.name())
+}
+
+fn edge_kind(kind: EdgeType) -> &'static str {
+    match kind {
+        EdgeType::Normal => "Normal",
+        EdgeType::Door => "Door",
+        EdgeType::Warp => "Warp",
+        EdgeType::Shortcut => "Shortcut",
+    }
+}
+
+/// Escapes text for XML attributes and content, or for JSON and DOT strings. Names come from the
+/// world model so they should be plain, but nothing stops a model author from using quotes.
+struct Escaped<'a> {
+    text: &'a str,
+    xml: bool,
+}
+
+impl fmt::Display for Escaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in self.text.chars() {
+            match (self.xml, c) {
+                (true, '&') => write!(f, "&amp;")?,
+                (true, '<') => write!(f, "&lt;")?,
+                (true, '>') => write!(f, "&gt;")?,
+                (true, '"') => write!(f, "&quot;")?,
+                (true, c) if c.is_control() => write!(f, "&#{};", c as u32)?,
+                (false, '"') => write!(f, "\\\"")?,
+                (false, '\\') => write!(f, "\\\\")?,
+                (false, '\n') => write!(f, "\\n")?,
+                (false, c) if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+                (_, c) => write!(f, "{}", c)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn xml(text: &str) -> Escaped<'_> {
+    Escaped { text, xml: true }
+}
+
+fn quoted(text: &str) -> Escaped<'_> {
+    Escaped { text, xml: false }
+}
+
+/// The graph in Graphviz's DOT format. Nodes are labelled with their names and shaped by type,
+/// and edges are labelled with any requirement other than open.
+pub fn dot<const M: usize, const N: usize, T>(graph: &StaticGraph<M, N, T>) -> Dot<'_, M, N, T> {
+    Dot { graph }
+}
+
+pub struct Dot<'graph, const M: usize, const N: usize, T = ()> {
+    graph: &'graph StaticGraph<M, N, T>,
+}
+
+impl<const M: usize, const N: usize, T> fmt::Display for Dot<'_, M, N, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let graph = self.graph;
+        writeln!(f, "digraph model {{")?;
+        for node in 1..=graph.node_count {
+            let shape = match graph.node_data(node).node_type {
+                NodeType::Place => "ellipse",
+                NodeType::Item => "box",
+                NodeType::Door => "diamond",
+            };
+            writeln!(
+                f,
+                "    {} [label=\"{}\" shape={}];",
+                node,
+                quoted(node_name(graph, node)),
+                shape
+            )?;
+        }
+        for (src, edge, dest) in graph.edges() {
+            let req = graph.edge_data(edge).req;
+            match req == ReqIndex::OPEN {
+                true => writeln!(f, "    {} -> {};", src, dest)?,
+                false => writeln!(
+                    f,
+                    "    {} -> {} [label=\"{}\"];",
+                    src,
+                    dest,
+                    graph.requirement_tree(req)
+                )?,
+            }
+        }
+
+        writeln!(f, "}}")
+    }
+}
+
+/// The graph as GraphML, e.g. for Gephi or Cytoscape. Nodes carry their name, type and region
+/// and edges their requirement expression, kind and weight.
+pub fn graphml<const M: usize, const N: usize, T>(
+    graph: &StaticGraph<M, N, T>,
+) -> GraphMl<'_, M, N, T> {
+    GraphMl { graph }
+}
+
+pub struct GraphMl<'graph, const M: usize, const N: usize, T = ()> {
+    graph: &'graph StaticGraph<M, N, T>,
+}
+
+impl<const M: usize, const N: usize, T> fmt::Display for GraphMl<'_, M, N, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let graph = self.graph;
+        writeln!(f, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            f,
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+        )?;
+        for (id, kind, name, ty) in [
+            ("name", "node", "name", "string"),
+            ("type", "node", "type", "string"),
+            ("region", "node", "region", "string"),
+            ("req", "edge", "requirement", "string"),
+            ("kind", "edge", "kind", "string"),
+            ("weight", "edge", "weight", "int"),
+        ] {
+            writeln!(
+                f,
+                r#"  <key id="{}" for="{}" attr.name="{}" attr.type="{}"/>"#,
+                id, kind, name, ty
+            )?;
+        }
+        writeln!(f, r#"  <graph id="model" edgedefault="directed">"#)?;
+        for node in 1..=graph.node_count {
+            writeln!(f, r#"    <node id="n{}">"#, node)?;
+            writeln!(
+                f,
+                r#"      <data key="name">{}</data>"#,
+                xml(node_name(graph, node))
+            )?;
+            writeln!(
+                f,
+                r#"      <data key="type">{}</data>"#,
+                node_type(graph, node)
+            )?;
+            writeln!(
+                f,
+                r#"      <data key="region">{}</data>"#,
+                xml(region_name(graph, node))
+            )?;
+            writeln!(f, "    </node>")?;
+        }
+        for (src, edge, dest) in graph.edges() {
+            let data = graph.edge_data(edge);
+            writeln!(
+                f,
+                r#"    <edge id="e{}" source="n{}" target="n{}">"#,
+                edge, src, dest
+            )?;
+            writeln!(
+                f,
+                r#"      <data key="req">{}</data>"#,
+                xml(&graph.requirement_tree(data.req).to_string())
+            )?;
+            writeln!(
+                f,
+                r#"      <data key="kind">{}</data>"#,
+                edge_kind(data.kind)
+            )?;
+            writeln!(f, r#"      <data key="weight">{}</data>"#, data.weight)?;
+            writeln!(f, "    </edge>")?;
+        }
+        writeln!(f, "  </graph>")?;
+
+        writeln!(f, "</graphml>")
+    }
+}
+
+/// The graph as a JSON adjacency document for web tooling:
+///
+/// ```text
+/// {"nodes": [{"id": 1, "name": "...", "type": "Place", "region": "..."}, ...],
+///  "edges": [{"id": 1, "source": 1, "target": 2, "req": "Open", "kind": "Normal", "weight": 1}, ...]}
+/// ```
+///
+/// Nodes outside any region have an empty region name.
+pub fn json<const M: usize, const N: usize, T>(graph: &StaticGraph<M, N, T>) -> Json<'_, M, N, T> {
+    Json { graph }
+}
+
+pub struct Json<'graph, const M: usize, const N: usize, T = ()> {
+    graph: &'graph StaticGraph<M, N, T>,
+}
+
+impl<const M: usize, const N: usize, T> fmt::Display for Json<'_, M, N, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let graph = self.graph;
+        write!(f, "{{\"nodes\":[")?;
+        for node in 1..=graph.node_count {
+            if node > 1 {
+                write!(f, ",")?;
+            }
+            write!(
+                f,
+                "{{\"id\":{},\"name\":\"{}\",\"type\":\"{}\",\"region\":\"{}\"}}",
+                node,
+                quoted(node_name(graph, node)),
+                node_type(graph, node),
+                quoted(region_name(graph, node))
+            )?;
+        }
+        write!(f, "],\"edges\":[")?;
+        for (i, (src, edge, dest)) in graph.edges().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            let data = graph.edge_data(edge);
+            write!(
+                f,
+                "{{\"id\":{},\"source\":{},\"target\":{},\"req\":\"{}\",\"kind\":\"{}\",\"weight\":{}}}",
+                edge,
+                src,
+                dest,
+                graph.requirement_tree(data.req),
+                edge_kind(data.kind),
+                data.weight
+            )?;
+        }
+
+        write!(f, "]}}")
+    }
+}
+
+/// Where a search is up to, drawn over the part of the graph it's explored: the nodes it's
+/// visited, the ones still in its frontier, and every edge leaving a visited node, with the
+/// closed ones labelled by the requirements the search's collection state is missing. Meant for
+/// working out why a search, e.g. one sphere of a playthrough, didn't reach what we expected.
+///
+/// `frontier` is the walker's, e.g. `BfsIter::frontier` or `DfsIter::frontier`. Whether an edge
+/// is open comes from the walker's own access cache where it's been evaluated, so edges a
+/// `TrackerSession` opened with a trick show up as open.
+pub fn traversal_overlay<'graph, const M: usize, const N: usize, T, V: VisitedSet>(
+    core: &TraversalCore<'graph, M, N, T, V>,
+    frontier: impl IntoIterator<Item = NonZeroU16>,
+) -> TraversalOverlay<'graph, M, N, T> {
+    let graph = core.graph;
+    let state = &core.collection_state;
+    let visited: Vec<u16> = (1..=graph.node_count)
+        .filter(|&n| core.visited.check_visited(n))
+        .collect();
+    let mut edges = Vec::new();
+    for &src in visited.iter() {
+        let (edge_pointers, edge_offset) = graph.get_neighbors_out(NonZeroU16::new(src));
+        for (i, &dest) in edge_pointers.iter().enumerate() {
+            let edge = edge_offset + i as u16;
+            let req = graph.edge_data[edge as usize];
+            let open = match core.logic_mode {
+                LogicMode::Lazy if !core.edge_evaluated.check_access(edge) => {
+                    graph.requirement_tree(req).eval(state)
+                }
+                _ => core.edge_access.check_access(edge),
+            };
+            let missing = match open {
+                true => Vec::new(),
+                false => graph
+                    .requirement_tree(req)
+                    .requirements()
+                    .into_iter()
+                    .filter(|&r| !state.eval_requirement(r))
+                    .collect(),
+            };
+            edges.push(OverlayEdge {
+                src,
+                edge,
+                dest: u16::from(dest),
+                open,
+                missing,
+            });
+        }
+    }
+
+    TraversalOverlay {
+        graph,
+        visited,
+        frontier: frontier.into_iter().map(u16::from).collect(),
+        edges,
+    }
+}
+
+/// A snapshot of a search taken by `traversal_overlay`, to write out with `dot` or `json`.
+pub struct TraversalOverlay<'graph, const M: usize, const N: usize, T = ()> {
+    graph: &'graph StaticGraph<M, N, T>,
+    visited: Vec<u16>,
+    frontier: Vec<u16>,
+    edges: Vec<OverlayEdge>,
+}
+
+struct OverlayEdge {
+    src: u16,
+    edge: u16,
+    dest: u16,
+    open: bool,
+    // The requirements in the edge's tree our state doesn't satisfy, if it's closed.
+    missing: Vec<Requirement>,
+}
+
+// Closed edges are colored by the first requirement they're missing, so everything one item
+// would open stands out together.
+const MISSING_COLORS: [&str; 8] = [
+    "red",
+    "blue",
+    "purple",
+    "darkorange",
+    "brown",
+    "magenta",
+    "darkgreen",
+    "navy",
+];
+
+impl<'graph, const M: usize, const N: usize, T> TraversalOverlay<'graph, M, N, T> {
+    /// The snapshot in Graphviz's DOT format. Visited nodes are filled green and frontier nodes
+    /// orange, nodes we haven't reached are dashed, and closed edges are dashed, colored by
+    /// what they're missing and labelled with it.
+    pub fn dot(&self) -> OverlayDot<'_, 'graph, M, N, T> {
+        OverlayDot(self)
+    }
+
+    /// The snapshot as JSON for a web viewer:
+    ///
+    /// ```text
+    /// {"nodes": [{"id": 1, "name": "...", "state": "visited"|"frontier"|"unreached"}, ...],
+    ///  "edges": [{"id": 1, "source": 1, "target": 2, "open": false, "missing": ["Hammer"]}, ...]}
+    /// ```
+    pub fn json(&self) -> OverlayJson<'_, 'graph, M, N, T> {
+        OverlayJson(self)
+    }
+
+    // Every node in the snapshot with its state: the visited ones in index order, then the
+    // unreached ends of closed edges in the order we found them.
+    fn nodes(&self) -> impl Iterator<Item = (u16, &'static str)> + '_ {
+        let visited = self
+            .visited
+            .iter()
+            .map(|&n| match self.frontier.contains(&n) {
+                true => (n, "frontier"),
+                false => (n, "visited"),
+            });
+        let mut unreached: Vec<u16> = self
+            .edges
+            .iter()
+            .map(|e| e.dest)
+            .filter(|n| self.visited.binary_search(n).is_err())
+            .collect();
+        unreached.sort_unstable();
+        unreached.dedup();
+
+        visited.chain(unreached.into_iter().map(|n| (n, "unreached")))
+    }
+}
+
+pub struct OverlayDot<'a, 'graph, const M: usize, const N: usize, T = ()>(
+    &'a TraversalOverlay<'graph, M, N, T>,
+);
+
+impl<const M: usize, const N: usize, T> fmt::Display for OverlayDot<'_, '_, M, N, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let overlay = self.0;
+        writeln!(f, "digraph traversal {{")?;
+        for (node, state) in overlay.nodes() {
+            let style = match state {
+                "visited" => "style=filled fillcolor=palegreen",
+                "frontier" => "style=filled fillcolor=orange",
+                _ => "style=dashed",
+            };
+            writeln!(
+                f,
+                "    {} [label=\"{}\" {}];",
+                node,
+                quoted(node_name(overlay.graph, node)),
+                style
+            )?;
+        }
+        for edge in overlay.edges.iter() {
+            match edge.missing.first() {
+                _ if edge.open => writeln!(f, "    {} -> {};", edge.src, edge.dest)?,
+                None => writeln!(f, "    {} -> {} [style=dashed];", edge.src, edge.dest)?,
+                Some(first) => {
+                    let color = MISSING_COLORS[first.id() as usize % MISSING_COLORS.len()];
+                    write!(
+                        f,
+                        "    {} -> {} [style=dashed color={} label=\"",
+                        edge.src, edge.dest, color
+                    )?;
+                    for (i, req) in edge.missing.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{}", req)?;
+                    }
+                    writeln!(f, "\"];")?;
+                }
+            }
+        }
+
+        writeln!(f, "}}")
+    }
+}
+
+pub struct OverlayJson<'a, 'graph, const M: usize, const N: usize, T = ()>(
+    &'a TraversalOverlay<'graph, M, N, T>,
+);
+
+impl<const M: usize, const N: usize, T> fmt::Display for OverlayJson<'_, '_, M, N, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let overlay = self.0;
+        write!(f, "{{\"nodes\":[")?;
+        for (i, (node, state)) in overlay.nodes().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(
+                f,
+                "{{\"id\":{},\"name\":\"{}\",\"state\":\"{}\"}}",
+                node,
+                quoted(node_name(overlay.graph, node)),
+                state
+            )?;
+        }
+        write!(f, "],\"edges\":[")?;
+        for (i, edge) in overlay.edges.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(
+                f,
+                "{{\"id\":{},\"source\":{},\"target\":{},\"open\":{},\"missing\":[",
+                edge.edge, edge.src, edge.dest, edge.open
+            )?;
+            for (j, req) in edge.missing.iter().enumerate() {
+                if j > 0 {
+                    write!(f, ",")?;
+                }
+                write!(f, "\"{}\"", req)?;
+            }
+            write!(f, "]}}")?;
+        }
+
+        write!(f, "]}}")
+    }
+}
+
+/// A Markdown description of the model as it was compiled, for reviewing changes to a plain text
+/// model: every node with its type and region, then each of its outgoing edges with the
+/// requirement expression, kind, weight and any tricks that open it.
+///
+/// `names` are the names the model calls its requirement trees by, indexed like `ReqIndex`, e.g.
+/// the names `static_graph!` accepts in `req=`. A tree with a name is shown as its name followed
+/// by its expression, anything else as just the expression.
+pub fn describe_model<const M: usize, const N: usize, T>(
+    graph: &StaticGraph<M, N, T>,
+    names: &[&str],
+) -> String {
+    let mut text = String::new();
+    // Writing to a `String` never fails.
+    let _ = write_description(&mut text, graph, names);
+
+    text
+}
+
+fn write_description<const M: usize, const N: usize, T>(
+    text: &mut String,
+    graph: &StaticGraph<M, N, T>,
+    names: &[&str],
+) -> fmt::Result {
+    writeln!(text, "# World model")?;
+    writeln!(text)?;
+    writeln!(
+        text,
+        "{} nodes and {} edges. Node 1 is the root.",
+        graph.node_count, graph.edge_count
+    )?;
+    for node in 1..=graph.node_count {
+        writeln!(text)?;
+        write!(
+            text,
+            "## {}. {} ({}",
+            node,
+            node_name(graph, node),
+            node_type(graph, node)
+        )?;
+        match region_name(graph, node) {
+            "" => writeln!(text, ")")?,
+            region => writeln!(text, ", {})", region)?,
+        }
+        writeln!(text)?;
+        let (edge_pointers, edge_offset) = graph.get_neighbors_out(NonZeroU16::new(node));
+        if edge_pointers.is_empty() {
+            writeln!(text, "No outgoing edges.")?;
+        }
+        for (i, &dest) in edge_pointers.iter().enumerate() {
+            let data = graph.edge_data(edge_offset + i as u16);
+            let dest = u16::from(dest);
+            write!(text, "- to {}. {}: ", dest, node_name(graph, dest))?;
+            match names.get(data.req.get() as usize) {
+                Some(name) => write!(text, "`{}` ({})", name, graph.requirement_tree(data.req))?,
+                None => write!(text, "{}", graph.requirement_tree(data.req))?,
+            }
+            write!(text, ", {}, weight {}", edge_kind(data.kind), data.weight)?;
+            let mut tricks = graph
+                .tricks()
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| data.tricks.intersects(TrickMask::single(i)));
+            if let Some((_, first)) = tricks.next() {
+                write!(text, ", or with {}", first)?;
+                tricks.try_for_each(|(_, trick)| write!(text, " or {}", trick))?;
+            }
+            writeln!(text)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        graph::new_static_graph,
+        logic::{CollectionState, ReqTreeView},
+    };
+
+    #[test]
+    fn export_formats() {
+        let req = |i| ReqTreeView::new(ReqIndex::new(i).unwrap()).to_string();
+        assert_eq!(req(0), "Open");
+        assert_eq!(req(1), "Locked");
+        assert_eq!(req(2), "Boots | Hammer");
+        assert_eq!(req(5), "Gloves & Hammer");
+        assert_eq!(
+            crate::logic::format(&crate::logic::REQ_CONTAINER.0, ReqIndex::new(2).unwrap()),
+            req(2)
+        );
+        // A graph's own trees are formatted from the graph's array.
+        let own = StaticGraph::<64, 64>::from_edge_list(["1 2 flute & (boots | gloves)"]).unwrap();
+        let own_req = own.edge_data(1).req;
+        assert!(own_req.get() as usize >= crate::logic::REQ_CONTAINER.0.len());
+        assert_eq!(
+            crate::logic::format(own.req_trees(), own_req),
+            own.requirement_tree(own_req).to_string()
+        );
+        assert_eq!(
+            crate::logic::format(own.req_trees(), own_req),
+            "Flute & (Boots | Gloves)"
+        );
+        assert_eq!(std::format!("{}", quoted("a \"b\"\\")), "a \\\"b\\\"\\\\");
+        assert_eq!(
+            std::format!("{}", xml("<a & \"b\">")),
+            "&lt;a &amp; &quot;b&quot;&gt;"
+        );
+
+        let graph = new_static_graph();
+        let edge_count = graph.edge_count as usize;
+        let node_count = graph.node_count as usize;
+
+        let dot = std::format!("{}", dot(&graph));
+        assert!(dot.starts_with("digraph model {\n    1 [label=\"place_1\" shape=ellipse];\n"));
+        assert!(dot.ends_with("}\n"));
+        assert_eq!(dot.matches(" -> ").count(), edge_count);
+        let labelled = (1..=edge_count)
+            .filter(|&e| graph.edge_data(e as u16).req != ReqIndex::OPEN)
+            .count();
+        assert_eq!(dot.matches("[label=").count(), node_count + labelled);
+
+        let graphml = std::format!("{}", graphml(&graph));
+        assert!(graphml.starts_with("<?xml"));
+        assert!(graphml.ends_with("</graphml>\n"));
+        assert_eq!(graphml.matches("<node id=").count(), node_count);
+        assert_eq!(graphml.matches("<edge id=").count(), edge_count);
+        assert!(graphml.contains("<data key=\"req\">Boots | Hammer</data>"));
+        assert!(graphml.contains("<data key=\"req\">Gloves &amp; Hammer</data>"));
+        // Every `&` starts an entity, so the output parses as XML.
+        assert!(graphml
+            .match_indices('&')
+            .all(|(i, _)| ["&amp;", "&lt;", "&gt;", "&quot;", "&#"]
+                .iter()
+                .any(|entity| graphml[i..].starts_with(entity))));
+        assert!(graphml.contains("<data key=\"region\">Light World</data>"));
+
+        let json = std::format!("{}", json(&graph));
+        assert!(json.starts_with("{\"nodes\":[{\"id\":1,\"name\":\"place_1\",\"type\":\"Place\""));
+        assert!(json.ends_with("]}"));
+        assert_eq!(json.matches("\"source\":").count(), edge_count);
+        assert_eq!(json.matches("\"type\":").count(), node_count);
+        // Every bracket and brace is balanced, and none show up inside strings.
+        assert_eq!(json.matches('{').count(), json.matches('}').count());
+        assert_eq!(json.matches('[').count(), json.matches(']').count());
+
+        let state = CollectionState {
+            hammer: false,
+            ..CollectionState::default()
+        };
+        let mut bfs = graph.bfs_iter_with_state(state.clone());
+        bfs.by_ref().take(200).for_each(drop);
+        let overlay = traversal_overlay(&bfs.core, bfs.frontier());
+        assert_eq!(overlay.visited.len(), bfs.core.visited_count as usize);
+        assert_eq!(overlay.frontier.len(), bfs.frontier_width());
+        let out_degree: usize = overlay
+            .visited
+            .iter()
+            .map(|&n| graph.get_neighbors_out(NonZeroU16::new(n)).0.len())
+            .sum();
+        assert_eq!(overlay.edges.len(), out_degree);
+        for edge in overlay.edges.iter() {
+            let req = graph.edge_data(edge.edge).req;
+            assert_eq!(edge.open, state.eval_logic_tree(req));
+            assert!(edge.missing.iter().all(|&r| !state.eval_requirement(r)));
+        }
+        assert!(overlay
+            .edges
+            .iter()
+            .any(|e| e.missing.contains(&Requirement::Hammer)));
+
+        let dot = std::format!("{}", overlay.dot());
+        assert!(dot.starts_with("digraph traversal {\n"));
+        assert_eq!(
+            dot.matches("fillcolor=orange").count(),
+            bfs.frontier_width()
+        );
+        assert_eq!(dot.matches(" -> ").count(), overlay.edges.len());
+        let json = std::format!("{}", overlay.json());
+        assert_eq!(
+            json.matches("\"state\":\"visited\"").count(),
+            overlay.visited.len() - overlay.frontier.len()
+        );
+        assert_eq!(
+            json.matches("\"open\":false").count(),
+            dot.matches("dashed color").count() + dot.matches("[style=dashed];").count()
+        );
+        assert_eq!(json.matches('{').count(), json.matches('}').count());
+
+        let docs = describe_model(&graph, &["open", "locked", "boots_or_hammer"]);
+        assert!(docs.starts_with("# World model\n\n"));
+        assert!(docs.contains("\n## 1. place_1 (Place, Light World)\n\n- to "));
+        assert_eq!(docs.matches("\n## ").count(), node_count);
+        assert_eq!(docs.matches("\n- to ").count(), edge_count);
+        assert!(docs.contains(": `boots_or_hammer` (Boots | Hammer), "));
+        assert!(docs.contains(": Gloves & Hammer, "));
+        let tricked = graph.trick_edges(TrickMask(!0)).count();
+        assert_eq!(docs.matches(", or with ").count(), tricked);
+    }
+}
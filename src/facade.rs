@@ -0,0 +1,139 @@
+// Naming graphs without their const parameters. Code that only ever handles the generated world
+// can use the aliases, and code that handles any world can take a `&dyn Graph`.
+use alloc::{collections::VecDeque, vec, vec::Vec};
+use core::{num::NonZeroU16, ops::RangeInclusive};
+
+use crate::{
+    bfs_iter::BfsIter,
+    constants::*,
+    dfs_iter::DfsIter,
+    graph::{NodeData, StaticGraph},
+    logic::{CollectionState, EvaluateLogic, ReqIndex, RequirementNode},
+    tracker::TrackerSession,
+};
+
+/// The graph of the generated world, e.g. from `new_static_graph`.
+pub type GenGraph<T = ()> = StaticGraph<NUM_VERTICES_PADDED, NUM_EDGES_PADDED, T>;
+
+pub type GenDfsIter<'graph, T = ()> = DfsIter<'graph, NUM_VERTICES_PADDED, NUM_EDGES_PADDED, T>;
+
+pub type GenBfsIter<'graph, T = ()> = BfsIter<'graph, NUM_VERTICES_PADDED, NUM_EDGES_PADDED, T>;
+
+pub type GenTrackerSession<'graph, T = ()> =
+    TrackerSession<'graph, NUM_VERTICES_PADDED, NUM_EDGES_PADDED, T>;
+
+/// What application code usually needs from a graph, for passing any `StaticGraph` around as a
+/// `&dyn Graph` without carrying its sizes through every signature. The walkers stay on
+/// `StaticGraph` since their speed comes from knowing the sizes; `reachable` here is a plain
+/// search for when that doesn't matter.
+pub trait Graph {
+    /// The number of nodes, not counting the terminal node or padding.
+    fn node_count(&self) -> u16;
+
+    /// The number of edges, not counting the terminal edge or padding.
+    fn edge_count(&self) -> u16;
+
+    /// A node's destinations and the index of its first edge. See
+    /// `StaticGraph::get_neighbors_out`.
+    fn neighbors_out(&self, node: u16) -> (&[NonZeroU16], u16);
+
+    fn node_data(&self, node: u16) -> &NodeData;
+
+    /// The requirement tree an edge needs to be satisfied to be traversed.
+    fn requirement_of(&self, edge: u16) -> ReqIndex;
+
+    /// The requirement trees `requirement_of` indexes into. See `StaticGraph::req_trees`.
+    fn req_trees(&self) -> &[RequirementNode];
+
+    fn node_name(&self, node: u16) -> Option<&'static str>;
+
+    /// Every valid node index.
+    fn nodes(&self) -> RangeInclusive<u16> {
+        1..=self.node_count()
+    }
+
+    /// Every node reachable from the root under `state`, in breadth-first order.
+    fn reachable(&self, state: &CollectionState) -> Vec<u16> {
+        let mut seen = vec![false; self.node_count() as usize + 1];
+        let mut order = Vec::new();
+        let mut queue = VecDeque::from([1u16]);
+        seen[1] = true;
+        while let Some(node) = queue.pop_front() {
+            order.push(node);
+            let (neighbors, edge_offset) = self.neighbors_out(node);
+            for (i, &dest) in neighbors.iter().enumerate() {
+                let dest = u16::from(dest);
+                let edge = edge_offset + i as u16;
+                if !seen[dest as usize]
+                    && state.eval_logic_tree_in(self.req_trees(), self.requirement_of(edge))
+                {
+                    seen[dest as usize] = true;
+                    queue.push_back(dest);
+                }
+            }
+        }
+
+        order
+    }
+}
+
+impl<const M: usize, const N: usize, T> Graph for StaticGraph<M, N, T> {
+    fn node_count(&self) -> u16 {
+        self.node_count
+    }
+
+    fn edge_count(&self) -> u16 {
+        self.edge_count
+    }
+
+    fn neighbors_out(&self, node: u16) -> (&[NonZeroU16], u16) {
+        self.get_neighbors_out(NonZeroU16::new(node))
+    }
+
+    fn node_data(&self, node: u16) -> &NodeData {
+        &self.node_data[node as usize]
+    }
+
+    fn requirement_of(&self, edge: u16) -> ReqIndex {
+        self.edge_data[edge as usize]
+    }
+
+    fn req_trees(&self) -> &[RequirementNode] {
+        StaticGraph::req_trees(self)
+    }
+
+    fn node_name(&self, node: u16) -> Option<&'static str> {
+        StaticGraph::node_name(self, node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::new_static_graph;
+
+    #[test]
+    fn graph_behind_dyn() {
+        let generated: GenGraph = new_static_graph();
+        let graph: &dyn Graph = &generated;
+        assert_eq!(graph.node_count(), NUM_VERTICES as u16);
+        assert_eq!(graph.edge_count(), NUM_EDGES as u16);
+        for (src, edge, dest) in generated.edges() {
+            let (neighbors, edge_offset) = graph.neighbors_out(src);
+            assert_eq!(u16::from(neighbors[(edge - edge_offset) as usize]), dest);
+            assert_eq!(graph.requirement_of(edge), generated.edge_data(edge).req);
+        }
+        assert_eq!(graph.node_name(1), generated.node_name(1));
+
+        for state in [CollectionState::default(), CollectionState::ALL_ITEMS] {
+            let mut reached = graph.reachable(&state);
+            let mut expected: Vec<u16> = generated
+                .bfs_iter_with_state(state)
+                .map(u16::from)
+                .collect();
+            reached.sort_unstable();
+            expected.sort_unstable();
+            assert_eq!(reached, expected);
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use alloc::{boxed::Box, collections::VecDeque, vec, vec::Vec};
+use core::{num::NonZeroU16, ops::Index};
+
+use crate::{
+    constants::ACCESS_BITFIELD_LEN,
+    graph::{AccessCache, StaticGraph},
+    logic::CollectionState,
+};
+
+/// Per-edge capacities stored parallel to a graph's edge arrays. These aren't part of the graph
+/// since most consumers never need them, but they let us model transitions that can only be used
+/// a limited number of times (single-use portals, paths gated by a consumable, etc.) A capacity of
+/// zero means the edge can't carry any flow at all.
+pub struct EdgeCapacities<const N: usize>(Box<[u16; N]>);
+
+impl<const N: usize> EdgeCapacities<N> {
+    /// Get capacities where every edge can be used `capacity` times.
+    pub fn uniform(capacity: u16) -> Self {
+        EdgeCapacities(Box::new([capacity; N]))
+    }
+
+    pub fn set(&mut self, edge: u16, capacity: u16) {
+        self.0[edge as usize] = capacity;
+    }
+}
+
+impl<const N: usize> Index<u16> for EdgeCapacities<N> {
+    type Output = u16;
+
+    fn index(&self, idx: u16) -> &Self::Output {
+        &self.0[idx as usize]
+    }
+}
+
+/// The result of `max_flow`.
+#[derive(Clone, Debug, Default)]
+pub struct MaxFlow {
+    /// The total flow from the source to the sink.
+    pub value: u32,
+    /// Every edge carrying a non-zero flow as `(edge index, flow)`, ordered by edge index.
+    pub edge_flows: Vec<(u16, u32)>,
+}
+
+// A residual arc. Arcs are stored in pairs so the reverse of arc `i` is always `i ^ 1`.
+struct Arc {
+    to: u16,
+    cap: u32,
+    edge: u16,
+}
+
+/// Computes the maximum flow from `source` to `sink` with Dinic's algorithm, using only the edges
+/// accessible under `state`. With a uniform capacity of one this is the number of edge-disjoint
+/// routes between two nodes, e.g. how many separate trips a route needing several single-use
+/// transitions can make.
+///
+/// A source or sink that isn't a node in the graph can't carry any flow, so either gives an empty
+/// result like `source == sink` does.
+pub fn max_flow<const M: usize, const N: usize, T>(
+    graph: &StaticGraph<M, N, T>,
+    capacities: &EdgeCapacities<N>,
+    state: &CollectionState,
+    source: u16,
+    sink: u16,
+) -> MaxFlow {
+    let in_graph = |node: u16| (1..=graph.node_count).contains(&node);
+    if source == sink || !in_graph(source) || !in_graph(sink) {
+        return MaxFlow::default();
+    }
+    let mut access =
+        AccessCache::<ACCESS_BITFIELD_LEN>::with_len(StaticGraph::<M, N, T>::ACCESS_LEN);
+    graph.compute_access_mask(state, &mut access);
+    let node_slots = graph.node_count as usize + 1;
+    let mut arcs: Vec<Arc> = Vec::new();
+    let mut adjacency: Vec<Vec<u32>> = (0..node_slots).map(|_| Vec::new()).collect();
+    for node in 1..=graph.node_count {
+        let (edge_pointers, edge_offset) = graph.get_neighbors_out(NonZeroU16::new(node));
+        for (i, &dest) in edge_pointers.iter().enumerate() {
+            let edge = edge_offset + i as u16;
+            let cap = capacities[edge];
+            if cap == 0 || !access.check_access(edge) {
+                continue;
+            }
+            let dest = u16::from(dest);
+            adjacency[node as usize].push(arcs.len() as u32);
+            arcs.push(Arc {
+                to: dest,
+                cap: cap as u32,
+                edge,
+            });
+            adjacency[dest as usize].push(arcs.len() as u32);
+            arcs.push(Arc {
+                to: node,
+                cap: 0,
+                edge,
+            });
+        }
+    }
+    let original: Vec<u32> = arcs.iter().map(|a| a.cap).collect();
+
+    let mut value = 0u32;
+    let mut level = vec![u32::MAX; node_slots];
+    let mut current = vec![0usize; node_slots];
+    loop {
+        // Build the level graph with a BFS over arcs with remaining capacity.
+        level.iter_mut().for_each(|l| *l = u32::MAX);
+        level[source as usize] = 0;
+        let mut queue = VecDeque::from([source]);
+        while let Some(node) = queue.pop_front() {
+            for &a in &adjacency[node as usize] {
+                let arc = &arcs[a as usize];
+                if arc.cap > 0 && level[arc.to as usize] == u32::MAX {
+                    level[arc.to as usize] = level[node as usize] + 1;
+                    queue.push_back(arc.to);
+                }
+            }
+        }
+        if level[sink as usize] == u32::MAX {
+            break;
+        }
+
+        // Find blocking flow with an iterative DFS, remembering which arc each node is at so
+        // dead ends are never retried in this phase.
+        current.iter_mut().for_each(|c| *c = 0);
+        let mut path: Vec<u32> = Vec::new();
+        let mut node = source;
+        loop {
+            if node == sink {
+                let bottleneck = path.iter().map(|&a| arcs[a as usize].cap).min().unwrap();
+                for &a in &path {
+                    arcs[a as usize].cap -= bottleneck;
+                    arcs[a as usize ^ 1].cap += bottleneck;
+                }
+                value += bottleneck;
+                path.clear();
+                node = source;
+                continue;
+            }
+            let next = adjacency[node as usize][current[node as usize]..]
+                .iter()
+                .position(|&a| {
+                    let arc = &arcs[a as usize];
+                    arc.cap > 0 && level[arc.to as usize] == level[node as usize] + 1
+                });
+            match next {
+                Some(offset) => {
+                    current[node as usize] += offset;
+                    let a = adjacency[node as usize][current[node as usize]];
+                    path.push(a);
+                    node = arcs[a as usize].to;
+                }
+                None => {
+                    // Dead end, so retreat and skip the arc that led here.
+                    level[node as usize] = u32::MAX;
+                    match path.pop() {
+                        Some(a) => {
+                            node = arcs[a as usize ^ 1].to;
+                            current[node as usize] += 1;
+                        }
+                        None => break,
+                    }
+                }
+            }
+        }
+    }
+
+    let mut edge_flows: Vec<(u16, u32)> = arcs
+        .iter()
+        .zip(original.iter())
+        .step_by(2)
+        .filter(|(arc, &cap)| arc.cap < cap)
+        .map(|(arc, &cap)| (arc.edge, cap - arc.cap))
+        .collect();
+    edge_flows.sort_unstable();
+
+    MaxFlow { value, edge_flows }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{constants::*, graph::new_static_graph_open};
+
+    #[test]
+    fn unit_capacity_flow() {
+        let graph = new_static_graph_open();
+        let capacities = EdgeCapacities::<NUM_EDGES_PADDED>::uniform(1);
+        let state = CollectionState::default();
+        let sink = 500;
+        let flow = max_flow(&graph, &capacities, &state, 1, sink);
+        // With unit capacities the flow can't exceed the root's out-degree.
+        let root_degree = graph.get_neighbors_out(NonZeroU16::new(1)).0.len() as u32;
+        assert!(flow.value >= 1 && flow.value <= root_degree);
+        assert!(flow.edge_flows.iter().all(|&(_, f)| f == 1));
+
+        // Flow is conserved at every node other than the source and sink.
+        let reverse = graph.reverse_adjacency();
+        let flow_on = |edge: u16| {
+            flow.edge_flows
+                .binary_search_by_key(&edge, |&(e, _)| e)
+                .map_or(0, |i| flow.edge_flows[i].1)
+        };
+        for node in 2..=NUM_VERTICES as u16 {
+            if node == sink {
+                continue;
+            }
+            let (edge_pointers, offset) = graph.get_neighbors_out(NonZeroU16::new(node));
+            let out: u32 = (0..edge_pointers.len() as u16)
+                .map(|i| flow_on(offset + i))
+                .sum();
+            let inflow: u32 = reverse.neighbors_in(node).map(|(_, e)| flow_on(e)).sum();
+            assert_eq!(out, inflow);
+        }
+    }
+
+    #[test]
+    fn nodes_outside_the_graph() {
+        let graph = new_static_graph_open();
+        let capacities = EdgeCapacities::<NUM_EDGES_PADDED>::uniform(1);
+        let state = CollectionState::default();
+        let outside = NUM_VERTICES as u16 + 1;
+        for (source, sink) in [(1, outside), (outside, 1), (0, 1), (1, u16::MAX)] {
+            let flow = max_flow(&graph, &capacities, &state, source, sink);
+            assert_eq!(flow.value, 0);
+            assert!(flow.edge_flows.is_empty());
+        }
+    }
+}
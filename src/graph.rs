@@ -1,16 +1,32 @@
-use alloc::boxed::Box;
+use alloc::{boxed::Box, sync::Arc, vec, vec::Vec};
 use core::{
+    fmt, iter,
     num::NonZeroU16,
-    ops::{Deref, DerefMut, Index, IndexMut, Range},
+    ops::{Deref, DerefMut, Index, IndexMut, Range, RangeInclusive},
+    slice,
+    sync::atomic::{AtomicU64, Ordering},
 };
 
 use crate::{
+    astar_iter::AStarIter,
     bfs_iter::{BfsIter, BfsQueue},
     constants::*,
-    dfs_iter::{DfsIter, DfsStack},
-    logic::CollectionState,
+    dfs_iter::{DepthLimitedDfsIter, DfsIter, DfsStack},
+    facade::GenGraph,
+    gen::{SEARCH_QUEUE_SIZE, SEARCH_STACK_SIZE},
+    logic::{
+        CollectionState, EvaluateLogic, Item, ReqIndex, ReqMemo, ReqTreeView, RequirementNode,
+        SettingsState, TierMask, REQ_CONTAINER, REQ_CONTAINER_LEN,
+    },
+    priority_iter::PriorityIter,
+    raw::{self, PointersInBounds},
+    region::{Region, RegionGraph},
+    traversal::{LogicMode, TraversalCore},
+    zero_one_iter::ZeroOneIter,
 };
 
+pub use crate::types::{DoorFlags, EdgeType, NodeData, NodeType, TrickMask};
+
 /// Our main graph representation. Primarily represented by an offset array where the value for
 /// vertex Vx at index x is an index into our outgoing edges array. Combined with the value at
 /// V(x+1) we can get a slice &[NonZeroU16] containing indexes for connected nodes or an empty
@@ -27,14 +43,51 @@ use crate::{
 /// by hopefully fitting as much as possible into cache lines and possibly being able to elide
 /// most bounds checks where we might be doing hundreds of thousands of array accesses or more.
 /// Despite being "static" in size, this graph representation allows
-pub struct StaticGraph<const M: usize, const N: usize> {
+///
+/// A graph is `Send + Sync` whenever its payload is. Walking it only ever reads it, so any number
+/// of threads can search one graph at once through a shared reference, and every walker owns its
+/// own buffers so it can be handed to another thread mid-search.
+pub struct StaticGraph<const M: usize, const N: usize, T = ()> {
     pub(crate) node_pointers: NodeIndexArray<M>,
-    pub(crate) node_data: Box<[NodeData; M]>,
+    // Everything but the pointer arrays is behind an Arc so variants of one world, e.g. candidate
+    // door shuffles, can share it. See `arena::GraphArena`. Writes copy it first if it's shared.
+    pub(crate) node_data: Arc<[NodeData; M]>,
     pub(crate) edge_pointers: EdgeIndexArray<N>,
-    pub(crate) edge_data: Box<[u16; N]>,
+    pub(crate) edge_data: Arc<[ReqIndex; N]>,
+    // The requirement trees `edge_data` indexes into when they aren't `REQ_CONTAINER`. See
+    // `req_trees`.
+    pub(crate) req_trees: Option<Arc<[RequirementNode]>>,
+    pub(crate) edge_kinds: Arc<[EdgeType; N]>,
+    pub(crate) edge_weights: Arc<[u8; N]>,
+    // A `TrickMask` per edge, indexing into `tricks`.
+    pub(crate) edge_tricks: Arc<[u8; N]>,
+    pub(crate) place_data: &'static [PlaceNodeData],
+    pub(crate) item_data: &'static [ItemNodeData],
+    pub(crate) door_data: &'static [DoorNodeData],
+    pub(crate) tricks: &'static [&'static str],
+    pub(crate) region_of: Arc<[u16; M]>,
+    // Arbitrary per-node application data (placement results, prices, UI state...) indexed like
+    // node_data. This takes up no space at all with the default `()` payload.
+    pub(crate) payload: Box<[T]>,
+    pub(crate) node_count: u16,
+    pub(crate) edge_count: u16,
+    // Set when the pointer arrays passed `validate` since they last changed, which lets
+    // `get_neighbors_out` skip bounds checks.
+    pub(crate) validated: Option<PointersInBounds>,
 }
 
-impl<'graph, const M: usize, const N: usize> StaticGraph<M, N> {
+impl<'graph, const M: usize, const N: usize, T> StaticGraph<M, N, T> {
+    /// The number of words a visited bitfield needs for this graph's node indexes.
+    pub const VISITED_LEN: usize = bitfield_len(M);
+    /// The number of words an access bitfield needs for this graph's edge indexes.
+    pub const ACCESS_LEN: usize = bitfield_len(N);
+    // Walkers evaluate this for the graph they're built for, so one too big for any cache fails
+    // to compile instead of panicking when the walker allocates. See constants.rs.
+    pub(crate) const FITS_CACHES: () = assert!(
+        Self::VISITED_LEN <= VISITED_BITFIELD_LEN && Self::ACCESS_LEN <= ACCESS_BITFIELD_LEN,
+        "graph is too big for the traversal caches"
+    );
+
     // This can be any index into node_pointers for a node with no outgoing neighbors. It should be
     // zero because we use it as an alternative value for when the search stack/queue pops None
     // in our .next implementations which the compiler should be able to trivially map to zero.
@@ -44,50 +97,234 @@ impl<'graph, const M: usize, const N: usize> StaticGraph<M, N> {
 
     /// This gives us a data structure implementing Iterator that traverses the graph with a depth-
     /// first search.
-    pub fn dfs_iter(&'graph self) -> DfsIter<'graph, M, N> {
+    pub fn dfs_iter(&'graph self) -> DfsIter<'graph, M, N, T> {
+        self.dfs_iter_with_state(CollectionState::default())
+    }
+
+    /// Same as `.dfs_iter()` but evaluates the graph's logic against the given collection state
+    /// instead of the default one.
+    pub fn dfs_iter_with_state(&'graph self, state: CollectionState) -> DfsIter<'graph, M, N, T> {
         let mut dfs_iter = DfsIter {
-            graph: self,
-            root: 1,
+            core: TraversalCore::new(self, 1, state),
+            search_stack: DfsStack::new(),
+        };
+        dfs_iter.search_stack.push(dfs_iter.core.root);
+
+        dfs_iter
+    }
+
+    /// Same as `.dfs_iter_with_state()` but with a search stack of `S` nodes instead of the size
+    /// measured on the generated graph, for graphs whose searches run much deeper or shallower.
+    pub fn dfs_iter_sized<const S: usize>(
+        &'graph self,
+        state: CollectionState,
+    ) -> DfsIter<'graph, M, N, T, S> {
+        let mut dfs_iter = DfsIter {
+            core: TraversalCore::new(self, 1, state),
+            search_stack: DfsStack::new(),
+        };
+        dfs_iter.search_stack.push(dfs_iter.core.root);
+
+        dfs_iter
+    }
+
+    /// Same as `.dfs_iter_with_state()` but keeps visited nodes in `visited` instead of a new
+    /// `VisitedCache`, e.g. a `StampedVisited` that can be taken back out of `core.visited` and
+    /// lent to the next search without clearing it.
+    pub fn dfs_iter_with_visited<V: VisitedSet>(
+        &'graph self,
+        state: CollectionState,
+        visited: V,
+    ) -> DfsIter<'graph, M, N, T, SEARCH_STACK_SIZE, V> {
+        let mut dfs_iter = DfsIter {
+            core: TraversalCore::with_visited(self, 1, state, LogicMode::Eager, visited),
+            search_stack: DfsStack::new(),
+        };
+        dfs_iter.search_stack.push(dfs_iter.core.root);
+
+        dfs_iter
+    }
+
+    /// Same as `.dfs_iter()` but evaluates each edge's logic the first time the search touches it
+    /// instead of evaluating every edge up front. See `LogicMode`.
+    pub fn dfs_iter_lazy(&'graph self) -> DfsIter<'graph, M, N, T> {
+        self.dfs_iter_lazy_with_state(CollectionState::default())
+    }
+
+    /// Same as `.dfs_iter_lazy()` but evaluates the graph's logic against the given collection
+    /// state instead of the default one.
+    pub fn dfs_iter_lazy_with_state(
+        &'graph self,
+        state: CollectionState,
+    ) -> DfsIter<'graph, M, N, T> {
+        let mut dfs_iter = DfsIter {
+            core: TraversalCore::with_logic_mode(self, 1, state, LogicMode::Lazy),
+            search_stack: DfsStack::new(),
+        };
+        dfs_iter.search_stack.push(dfs_iter.core.root);
+
+        dfs_iter
+    }
+
+    /// Same as `.dfs_iter_with_state()` but takes access precomputed for `state` instead of
+    /// evaluating the graph's logic, e.g. `ALL_ITEMS_ACCESS`. The bitfield has to match the
+    /// graph's current edge data and the given state for the search to make sense.
+    pub fn dfs_iter_with_access(
+        &'graph self,
+        state: CollectionState,
+        access: &[u64; ACCESS_BITFIELD_LEN],
+    ) -> DfsIter<'graph, M, N, T> {
+        let mut dfs_iter = DfsIter {
+            core: TraversalCore::with_access(self, 1, state, access),
             search_stack: DfsStack::new(),
-            collection_state: CollectionState::default(),
-            visited: VisitedCache::<VISITED_BITFIELD_LEN>::new(),
-            edge_access: AccessCache::<ACCESS_BITFIELD_LEN>::new(),
         };
-        dfs_iter.evaluate_logical_access();
-        dfs_iter.search_stack.push(dfs_iter.root);
-        dfs_iter.visited.mark_visited(dfs_iter.root);
+        dfs_iter.search_stack.push(dfs_iter.core.root);
 
         dfs_iter
     }
 
+    /// A depth-first search from `root` that never follows a path longer than `max_depth` edges.
+    /// See `DepthLimitedDfsIter`.
+    pub fn dfs_iter_depth_limited(
+        &'graph self,
+        root: u16,
+        max_depth: u16,
+    ) -> DepthLimitedDfsIter<'graph, M, N, T> {
+        self.dfs_iter_depth_limited_with_state(root, max_depth, CollectionState::default())
+    }
+
+    /// Same as `.dfs_iter_depth_limited()` but evaluates the graph's logic against the given
+    /// collection state instead of the default one.
+    pub fn dfs_iter_depth_limited_with_state(
+        &'graph self,
+        root: u16,
+        max_depth: u16,
+        state: CollectionState,
+    ) -> DepthLimitedDfsIter<'graph, M, N, T> {
+        DepthLimitedDfsIter::new(self, root, max_depth, state)
+    }
+
     /// This gives us a data structure implementing Iterator that traverses the graph with a depth-
     /// first search.
-    pub fn bfs_iter(&'graph self) -> BfsIter<'graph, M, N> {
+    pub fn bfs_iter(&'graph self) -> BfsIter<'graph, M, N, T> {
+        self.bfs_iter_with_state(CollectionState::default())
+    }
+
+    /// Same as `.bfs_iter()` but evaluates the graph's logic against the given collection state
+    /// instead of the default one.
+    pub fn bfs_iter_with_state(&'graph self, state: CollectionState) -> BfsIter<'graph, M, N, T> {
         let mut bfs_iter = BfsIter {
-            graph: self,
-            root: 1,
+            core: TraversalCore::new(self, 1, state),
             search_queue: BfsQueue::new(),
-            collection_state: CollectionState::default(),
-            visited: VisitedCache::<VISITED_BITFIELD_LEN>::new(),
-            edge_access: AccessCache::<ACCESS_BITFIELD_LEN>::new(),
         };
-        bfs_iter.evaluate_logical_access();
-        bfs_iter.search_queue.push_back(bfs_iter.root);
-        bfs_iter.visited.mark_visited(bfs_iter.root);
+        bfs_iter.search_queue.push_back(bfs_iter.core.root);
 
         bfs_iter
     }
 
-    /// Get a new zeroed graph.
-    pub fn new_zeroed() -> Self {
-        StaticGraph {
-            // SAFETY: Not zero.
-            node_pointers: NodeIndexArray(Box::new([unsafe { NonZeroU16::new_unchecked(1) }; M])),
-            node_data: Box::new([NodeData::DEFAULT; M]),
-            // SAFETY: Not zero.
-            edge_pointers: EdgeIndexArray(Box::new([unsafe { NonZeroU16::new_unchecked(1) }; N])),
-            edge_data: Box::new([0u16; N]),
-        }
+    /// Same as `.bfs_iter_with_state()` but with a search queue of `Q` nodes instead of the size
+    /// measured on the generated graph, for graphs whose searches get much wider or narrower.
+    pub fn bfs_iter_sized<const Q: usize>(
+        &'graph self,
+        state: CollectionState,
+    ) -> BfsIter<'graph, M, N, T, Q> {
+        let mut bfs_iter = BfsIter {
+            core: TraversalCore::new(self, 1, state),
+            search_queue: BfsQueue::new(),
+        };
+        bfs_iter.search_queue.push_back(bfs_iter.core.root);
+
+        bfs_iter
+    }
+
+    /// Same as `.bfs_iter_with_state()` but keeps visited nodes in `visited`. See
+    /// `.dfs_iter_with_visited()`.
+    pub fn bfs_iter_with_visited<V: VisitedSet>(
+        &'graph self,
+        state: CollectionState,
+        visited: V,
+    ) -> BfsIter<'graph, M, N, T, SEARCH_QUEUE_SIZE, V> {
+        let mut bfs_iter = BfsIter {
+            core: TraversalCore::with_visited(self, 1, state, LogicMode::Eager, visited),
+            search_queue: BfsQueue::new(),
+        };
+        bfs_iter.search_queue.push_back(bfs_iter.core.root);
+
+        bfs_iter
+    }
+
+    /// Same as `.bfs_iter()` but evaluates each edge's logic the first time the search touches it
+    /// instead of evaluating every edge up front. See `LogicMode`.
+    pub fn bfs_iter_lazy(&'graph self) -> BfsIter<'graph, M, N, T> {
+        self.bfs_iter_lazy_with_state(CollectionState::default())
+    }
+
+    /// Same as `.bfs_iter_lazy()` but evaluates the graph's logic against the given collection
+    /// state instead of the default one.
+    pub fn bfs_iter_lazy_with_state(
+        &'graph self,
+        state: CollectionState,
+    ) -> BfsIter<'graph, M, N, T> {
+        let mut bfs_iter = BfsIter {
+            core: TraversalCore::with_logic_mode(self, 1, state, LogicMode::Lazy),
+            search_queue: BfsQueue::new(),
+        };
+        bfs_iter.search_queue.push_back(bfs_iter.core.root);
+
+        bfs_iter
+    }
+
+    /// This gives us a data structure implementing Iterator that expands nodes best-first towards
+    /// a goal using a per-node heuristic. See `AStarIter`.
+    pub fn astar_iter<'h>(&'graph self, heuristic: &'h [u16]) -> AStarIter<'graph, 'h, M, N, T> {
+        self.astar_iter_with_state(heuristic, CollectionState::default())
+    }
+
+    /// Same as `.astar_iter()` but evaluates the graph's logic against the given collection
+    /// state instead of the default one.
+    pub fn astar_iter_with_state<'h>(
+        &'graph self,
+        heuristic: &'h [u16],
+        state: CollectionState,
+    ) -> AStarIter<'graph, 'h, M, N, T> {
+        AStarIter::new(self, heuristic, state)
+    }
+
+    /// This gives us a data structure implementing Iterator that yields nodes in order of how many
+    /// loading zones it takes to reach them. See `ZeroOneIter`.
+    pub fn zero_one_iter(&'graph self) -> ZeroOneIter<'graph, M, N, T> {
+        self.zero_one_iter_with_state(CollectionState::default())
+    }
+
+    /// Same as `.zero_one_iter()` but evaluates the graph's logic against the given collection
+    /// state instead of the default one.
+    pub fn zero_one_iter_with_state(
+        &'graph self,
+        state: CollectionState,
+    ) -> ZeroOneIter<'graph, M, N, T> {
+        ZeroOneIter::new(self, state)
+    }
+
+    /// This gives us a data structure implementing Iterator that expands whichever reached node
+    /// `key` rates highest first. See `PriorityIter`.
+    pub fn priority_iter<F>(&'graph self, key: F) -> PriorityIter<'graph, M, N, T, F>
+    where
+        F: Fn(u16, &NodeData) -> u32,
+    {
+        self.priority_iter_with_state(CollectionState::default(), key)
+    }
+
+    /// Same as `.priority_iter()` but evaluates the graph's logic against the given collection
+    /// state instead of the default one.
+    pub fn priority_iter_with_state<F>(
+        &'graph self,
+        state: CollectionState,
+        key: F,
+    ) -> PriorityIter<'graph, M, N, T, F>
+    where
+        F: Fn(u16, &NodeData) -> u32,
+    {
+        PriorityIter::new(self, state, key)
     }
 
     /// Get a slice containing a node's outgoing edges and the index of the first edge.
@@ -99,55 +336,689 @@ impl<'graph, const M: usize, const N: usize> StaticGraph<M, N> {
         // 2. That no two nodes point to the same edge sub-slice.
         // 3. That every value in self.node_pointers is greater than or equal to every value
         //    preceding it.
+        //
+        // `validate` checks all three, and graphs that haven't passed it (or every graph with the
+        // `checked` feature) take a bounds-checked path that gives malformed nodes no edges.
         let node_index = n.map_or(self.terminal(), u16::from);
-        let start = self.node_pointers[node_index];
-        let end = self.node_pointers[(node_index + 1) & (NUM_VERTICES_PADDED - 1) as u16];
+        let start = u16::from(self.node_pointers[node_index]);
+        let end =
+            u16::from(self.node_pointers[(node_index + 1) & (NUM_VERTICES_PADDED - 1) as u16]);
 
         // This generates better code than the safe version where we avoid a branch by computing
-        // one side of the range arithmetically.
-        //
-        // SAFETY: We statically ensure that no value in node_pointers indexes out of range and
-        // that every value is greater than or equal to values at lower indexes.
-        debug_assert!(start <= end);
-        (
-            unsafe {
-                self.edge_pointers
-                    .get_unchecked(u16::from(start) as usize..u16::from(end) as usize)
-            },
-            u16::from(start),
-        )
+        // one side of the range arithmetically. Pointers are only in order up to the end of the
+        // last node, so padding nodes can get an empty slice from a start past their end.
+        #[cfg(not(feature = "checked"))]
+        if let Some(in_bounds) = self.validated {
+            let edges = raw::edge_range(&self.edge_pointers.0[..], start, end, in_bounds);
+            return (edges, start);
+        }
+
+        let edges = self.edge_pointers.get(start as usize..end as usize);
+        (edges.unwrap_or(&[]), start)
+    }
+
+    /// Check the invariants `get_neighbors_out` relies on: the terminal node has no edges, node
+    /// pointers never point past the last edge and never decrease up to the end of the last node,
+    /// every edge points at a node in the graph and uses one of the graph's requirement trees.
+    /// Once a graph passes, walking it skips bounds checks on the edge array unless the `checked`
+    /// feature is enabled. Every constructor validates the graph it builds, so this is only
+    /// needed after editing the pointer arrays directly.
+    pub fn validate(&mut self) -> Result<(), GraphError> {
+        self.validated = None;
+        if self.node_count as usize + 1 >= M || self.edge_count as usize >= N {
+            return Err(GraphError::TooLarge);
+        }
+        if self.node_pointers[0] != self.node_pointers[1] {
+            return Err(GraphError::BadNodePointer(0));
+        }
+        let end = self.edge_count + 1;
+        let mut previous = 1;
+        for (i, &p) in self.node_pointers.iter().enumerate() {
+            let p = u16::from(p);
+            let in_order = i > self.node_count as usize + 1 || p >= previous;
+            if !in_order || p > end {
+                return Err(GraphError::BadNodePointer(i as u16));
+            }
+            previous = p;
+        }
+        if let Some(edge) = (1..end).find(|&e| u16::from(self.edge_pointers[e]) > self.node_count) {
+            return Err(GraphError::BadEdgePointer(edge));
+        }
+        let trees = self.req_trees().len();
+        if let Some(edge) = (1..end).find(|&e| self.edge_data[e as usize].get() as usize >= trees) {
+            return Err(GraphError::BadRequirement(edge));
+        }
+        self.validated = raw::check_pointers(&self.node_pointers.0[..], N);
+
+        Ok(())
+    }
+
+    /// Whether the graph passed `validate` since its pointer arrays last changed.
+    pub fn is_validated(&self) -> bool {
+        self.validated.is_some()
     }
 
     pub const fn terminal(&self) -> u16 {
         Self::TERMINAL_NODE_INDEX as u16
     }
+
+    /// Get the user payload attached to a node. See `with_payload`.
+    pub fn payload(&self, node: u16) -> &T {
+        &self.payload[node as usize]
+    }
+
+    pub fn payload_mut(&mut self, node: u16) -> &mut T {
+        &mut self.payload[node as usize]
+    }
+
+    /// Get a node's type and the index of its wide metadata.
+    pub fn node_data(&self, node: u16) -> &NodeData {
+        &self.node_data[node as usize]
+    }
+
+    /// Get a mutable reference to a node's type and the index of its wide metadata. The wide
+    /// metadata accessors below check the index against the table for the node's type, so
+    /// retagging a node can make its metadata disappear but never reads out of bounds.
+    pub fn node_data_mut(&mut self, node: u16) -> &mut NodeData {
+        &mut Arc::make_mut(&mut self.node_data)[node as usize]
+    }
+
+    /// Iterate over `(node index, &NodeData)` for every node in the graph. This is also what
+    /// iterating over `&StaticGraph` does.
+    pub fn node_data_iter(&self) -> NodeDataIter<'_> {
+        (1..=self.node_count).zip(self.node_data[1..=self.node_count as usize].iter())
+    }
+
+    /// Every valid node index, i.e. everything but the terminal node and padding.
+    pub fn nodes(&self) -> RangeInclusive<u16> {
+        1..=self.node_count
+    }
+
+    /// The number of nodes in the graph, not counting the terminal node or padding.
+    pub fn node_count(&self) -> u16 {
+        self.node_count
+    }
+
+    /// The number of edges in the graph, not counting the terminal edge or padding.
+    pub fn edge_count(&self) -> u16 {
+        self.edge_count
+    }
+
+    /// The number of edges leaving `node`.
+    pub fn out_degree(&self, node: u16) -> u16 {
+        self.get_neighbors_out(NonZeroU16::new(node)).0.len() as u16
+    }
+
+    /// The most edges leaving any one node. Models are expected to keep this within
+    /// `MAX_OUT_DEGREE`, which the linters and `from_edge_list` enforce.
+    pub fn max_out_degree(&self) -> u16 {
+        self.nodes().map(|n| self.out_degree(n)).max().unwrap_or(0)
+    }
+
+    /// How many nodes have each out-degree, indexed by degree up to `max_out_degree`.
+    pub fn degree_histogram(&self) -> Vec<u16> {
+        let mut histogram = vec![0u16; self.max_out_degree() as usize + 1];
+        self.nodes()
+            .for_each(|n| histogram[self.out_degree(n) as usize] += 1);
+
+        histogram
+    }
+
+    /// `(source, edge index, destination)` for every edge in the graph, grouped by source in
+    /// node order, so callers don't need to walk the pointer arrays themselves.
+    pub fn edges(&self) -> impl Iterator<Item = (u16, u16, u16)> + '_ {
+        self.nodes().flat_map(move |src| {
+            let (edge_pointers, edge_offset) = self.get_neighbors_out(NonZeroU16::new(src));
+            edge_pointers
+                .iter()
+                .enumerate()
+                .map(move |(i, &dest)| (src, edge_offset + i as u16, u16::from(dest)))
+        })
+    }
+
+    /// Build the per-requirement edge masks for this graph's current edge data. For the generated
+    /// graph, `static_graph_req_masks` has the same masks computed at compile time.
+    pub fn req_masks(&self) -> Box<ReqMasks<ACCESS_BITFIELD_LEN>> {
+        Box::new(ReqMasks::with_trees(
+            &self.edge_data,
+            self.req_trees().len(),
+        ))
+    }
+
+    /// The requirement trees this graph's edges index into. That's `REQ_CONTAINER` unless the
+    /// graph brought trees of its own, e.g. from `from_edge_list`, which keep the library's trees
+    /// at their indexes and add the graph's after them.
+    pub fn req_trees(&self) -> &[RequirementNode] {
+        match &self.req_trees {
+            Some(trees) => trees,
+            None => &REQ_CONTAINER.0,
+        }
+    }
+
+    /// Every one of this graph's requirement trees evaluated once under `logic`.
+    pub fn req_memo(&self, logic: &impl EvaluateLogic) -> ReqMemo {
+        ReqMemo::with_trees(logic, self.req_trees())
+    }
+
+    /// Evaluate every edge's requirement under `state` into `out`, the same access bitfield a
+    /// traversal builds before it starts, for callers that only want to know which edges are
+    /// open. Bit `i` is edge `i` and `out` needs one bit per edge in the graph's arrays.
+    pub fn compute_access_mask<const L: usize>(
+        &self,
+        state: &CollectionState,
+        out: &mut AccessCache<L>,
+    ) {
+        assert!(
+            N <= out.len() * CHUNK_SIZE,
+            "access cache is too small for the edge array"
+        );
+        out.evaluate_logical_access(&self.edge_data, &self.req_memo(state));
+    }
+
+    /// Rewrite every requirement that can't be met under the given logic tiers and settings to
+    /// `ReqIndex::LOCKED`, returning how many edges we locked. An edge is dead if its requirement
+    /// is false even with every item and event, which covers everything a seed could collect
+    /// since more items and events never close an edge. Trees with a negation can, so we leave
+    /// those alone.
+    ///
+    /// Traversals skip locked edges without evaluating anything, and `GraphBuilder` can drop
+    /// them altogether with `remove_locked_edges` for a smaller graph.
+    pub fn lock_dead_edges(&mut self, tiers: TierMask, settings: SettingsState) -> u16 {
+        let memo = self.req_memo(&CollectionState {
+            tiers,
+            settings,
+            ..CollectionState::ALL_ITEMS
+        });
+        let mut locked = 0;
+        for edge in 1..=self.edge_count as usize {
+            let req = self.edge_data[edge];
+            if req != ReqIndex::LOCKED
+                && !memo.get(req)
+                && !self.requirement_tree(req).has_negation()
+            {
+                Arc::make_mut(&mut self.edge_data)[edge] = ReqIndex::LOCKED;
+                locked += 1;
+            }
+        }
+
+        locked
+    }
+
+    /// Get everything we know about an edge. Traversals only ever need the requirement, so the
+    /// kind and weight are stored in their own arrays and only read from here.
+    pub fn edge_data(&self, edge: u16) -> EdgeData {
+        EdgeData {
+            req: self.edge_data[edge as usize],
+            kind: self.edge_kinds[edge as usize],
+            weight: self.edge_weights[edge as usize],
+            tricks: TrickMask(self.edge_tricks[edge as usize]),
+        }
+    }
+
+    /// The names of the tricks this graph's edges can be opened by, in the order their bits are
+    /// in a `TrickMask`. Only `TrackerSession` takes tricks into account, see
+    /// `TrackerSession::enable_trick`; every other search goes by requirements alone.
+    pub fn tricks(&self) -> &'static [&'static str] {
+        self.tricks
+    }
+
+    /// The trick called `name`, case-insensitively, if the graph has one.
+    pub fn trick(&self, name: &str) -> Option<TrickMask> {
+        self.tricks
+            .iter()
+            .position(|trick| trick.eq_ignore_ascii_case(name))
+            .map(TrickMask::single)
+    }
+
+    /// Every edge that any of `tricks` opens, in index order.
+    pub fn trick_edges(&self, tricks: TrickMask) -> impl Iterator<Item = u16> + '_ {
+        (1..=self.edge_count)
+            .filter(move |&e| tricks.intersects(TrickMask(self.edge_tricks[e as usize])))
+    }
+
+    /// The root of an edge's requirement tree.
+    pub fn requirement_root(&self, edge: u16) -> ReqIndex {
+        self.edge_data[edge as usize]
+    }
+
+    /// A view of the requirement tree rooted at `idx` to walk or display it. See `ReqTreeView`.
+    pub fn requirement_tree(&self, idx: ReqIndex) -> ReqTreeView<'_> {
+        ReqTreeView::in_trees(self.req_trees(), idx)
+    }
+
+    /// Whether no edge checks a negated requirement, so collecting something never closes an
+    /// edge. Anything that assumes reachability only grows with the collection state, e.g.
+    /// `StateLattice`, has to check this first. Each distinct tree is only walked once.
+    pub fn is_monotone(&self) -> bool {
+        let mut negates = vec![None; self.req_trees().len()];
+        self.edge_data[1..=self.edge_count as usize]
+            .iter()
+            .all(|&req| {
+                !*negates[req.get() as usize]
+                    .get_or_insert_with(|| self.requirement_tree(req).has_negation())
+            })
+    }
+
+    /// Get the wide metadata for a node if it's a place.
+    pub fn place_data(&self, node: u16) -> Option<&PlaceNodeData> {
+        let data = &self.node_data[node as usize];
+        match data.node_type {
+            NodeType::Place => self.place_data.get(data.data_index as usize),
+            _ => None,
+        }
+    }
+
+    /// Get the wide metadata for a node if it's an item location.
+    pub fn item_data(&self, node: u16) -> Option<&ItemNodeData> {
+        let data = &self.node_data[node as usize];
+        match data.node_type {
+            NodeType::Item => self.item_data.get(data.data_index as usize),
+            _ => None,
+        }
+    }
+
+    /// Get the wide metadata for a node if it's a door.
+    pub fn door_data(&self, node: u16) -> Option<&DoorNodeData> {
+        let data = &self.node_data[node as usize];
+        match data.node_type {
+            NodeType::Door => self.door_data.get(data.data_index as usize),
+            _ => None,
+        }
+    }
+
+    /// Get a node's name from its wide metadata, whatever type of node it is.
+    pub fn node_name(&self, node: u16) -> Option<&'static str> {
+        match self.node_data[node as usize].node_type {
+            NodeType::Place => self.place_data(node).map(|d| d.name),
+            NodeType::Item => self.item_data(node).map(|d| d.name),
+            NodeType::Door => self.door_data(node).map(|d| d.name),
+        }
+    }
+
+    /// Get the region a node belongs to. The zero index and padding nodes aren't in any region.
+    pub fn region_of(&self, node: u16) -> Option<Region> {
+        Region::from_id(self.region_of[node as usize])
+    }
+
+    /// Iterate over every node in `region` in index order.
+    pub fn nodes_in_region(&self, region: Region) -> impl Iterator<Item = u16> + '_ {
+        (1..=self.node_count).filter(move |&n| self.region_of[n as usize] == region as u16)
+    }
+
+    /// Collapse the graph into a small meta-graph of regions, where each edge between two regions
+    /// carries every distinct requirement on the edges between their nodes. See `RegionGraph`.
+    pub fn condense_regions(&self) -> RegionGraph {
+        RegionGraph::new(self)
+    }
+
+    /// Build the incoming edges of every node. The graph itself only stores outgoing edges since
+    /// that's all a forward search needs, so this allocates and walks every edge once.
+    pub fn reverse_adjacency(&self) -> ReverseAdjacency {
+        let node_count = self.node_count as usize;
+        let mut offsets = vec![0u32; node_count + 2].into_boxed_slice();
+        for node in 1..=node_count {
+            let (edge_pointers, _) = self.get_neighbors_out(NonZeroU16::new(node as u16));
+            edge_pointers
+                .iter()
+                .for_each(|&dest| offsets[u16::from(dest) as usize + 1] += 1);
+        }
+        for i in 1..offsets.len() {
+            offsets[i] += offsets[i - 1];
+        }
+        let edge_total = offsets[node_count + 1] as usize;
+        let mut sources = vec![0u16; edge_total].into_boxed_slice();
+        let mut edges = vec![0u16; edge_total].into_boxed_slice();
+        let mut cursor = offsets.clone();
+        for node in 1..=node_count {
+            let (edge_pointers, edge_offset) = self.get_neighbors_out(NonZeroU16::new(node as u16));
+            edge_pointers.iter().enumerate().for_each(|(i, &dest)| {
+                let slot = &mut cursor[u16::from(dest) as usize];
+                sources[*slot as usize] = node as u16;
+                edges[*slot as usize] = edge_offset + i as u16;
+                *slot += 1;
+            });
+        }
+
+        ReverseAdjacency {
+            offsets,
+            sources,
+            edges,
+        }
+    }
+
+    /// Get a `Display`able dump of the outgoing edges of at most `limit` nodes starting at
+    /// `start`, one node per line along with each edge's requirement index.
+    pub fn dump_adjacency(
+        &'graph self,
+        start: u16,
+        limit: usize,
+    ) -> AdjacencyDump<'graph, M, N, T> {
+        AdjacencyDump {
+            graph: self,
+            start,
+            limit,
+        }
+    }
+}
+
+impl<const M: usize, const N: usize, T: Default> StaticGraph<M, N, T> {
+    /// A graph with no nodes or edges. Every node pointer is the terminal node's, so nothing has
+    /// any edges and the graph is validated and safe to walk, though a search never gets past
+    /// the root.
+    pub fn empty() -> Self {
+        let mut graph = Self::new_zeroed();
+        graph
+            .validate()
+            .expect("a graph without nodes is always laid out in order");
+
+        graph
+    }
+
+    /// An empty graph to lay out `nodes` nodes and `edges` edges in, which is how
+    /// `GraphBuilder::freeze`, `from_bytes` and `from_edge_list` start. A graph's capacity is
+    /// fixed by its const parameters at `M - 2` nodes, leaving room for the terminal node and the
+    /// pointer past the last node, and `N - 1` edges, so rather than allocate anything this
+    /// checks the hint fits and fails with `GraphError::TooLarge` if it doesn't. Pointers are
+    /// u16s, so a graph can't have more than 65534 of either however large its parameters are.
+    ///
+    /// The graph isn't validated. Whoever fills in its counts and pointer arrays has to call
+    /// `validate` once they're done.
+    pub fn with_capacity_hint(nodes: usize, edges: usize) -> Result<Self, GraphError> {
+        let max = u16::MAX as usize - 1;
+        match nodes + 2 > M || edges + 1 > N || nodes > max || edges > max {
+            true => Err(GraphError::TooLarge),
+            false => Ok(Self::new_zeroed()),
+        }
+    }
+
+    // Every table at its default and both counts zero. See `empty` and `with_capacity_hint`.
+    fn new_zeroed() -> Self {
+        StaticGraph {
+            node_pointers: NodeIndexArray(Box::new([raw::nonzero(1); M])),
+            node_data: Arc::new([NodeData::DEFAULT; M]),
+            edge_pointers: EdgeIndexArray(Box::new([raw::nonzero(1); N])),
+            edge_data: Arc::new([ReqIndex::OPEN; N]),
+            req_trees: None,
+            edge_kinds: Arc::new([EdgeType::Normal; N]),
+            edge_weights: Arc::new([1u8; N]),
+            edge_tricks: Arc::new([0u8; N]),
+            place_data: &[],
+            item_data: &[],
+            door_data: &[],
+            tricks: &[],
+            region_of: Arc::new([0u16; M]),
+            payload: (0..M).map(|_| T::default()).collect(),
+            node_count: 0,
+            edge_count: 0,
+            validated: None,
+        }
+    }
+}
+
+impl<const M: usize, const N: usize, T> StaticGraph<M, N, T> {
+    /// Move this graph's structure and metadata into a graph carrying a different payload,
+    /// starting every node's payload at its default value.
+    pub fn with_payload<U: Default>(self) -> StaticGraph<M, N, U> {
+        StaticGraph {
+            node_pointers: self.node_pointers,
+            node_data: self.node_data,
+            edge_pointers: self.edge_pointers,
+            edge_data: self.edge_data,
+            req_trees: self.req_trees,
+            edge_kinds: self.edge_kinds,
+            edge_weights: self.edge_weights,
+            edge_tricks: self.edge_tricks,
+            place_data: self.place_data,
+            item_data: self.item_data,
+            door_data: self.door_data,
+            tricks: self.tricks,
+            region_of: self.region_of,
+            payload: (0..M).map(|_| U::default()).collect(),
+            node_count: self.node_count,
+            edge_count: self.edge_count,
+            validated: self.validated,
+        }
+    }
+}
+
+/// `(node index, &NodeData)` for every node in a graph. See `StaticGraph::node_data_iter`.
+pub type NodeDataIter<'graph> = iter::Zip<RangeInclusive<u16>, slice::Iter<'graph, NodeData>>;
+
+impl<'graph, const M: usize, const N: usize, T> IntoIterator for &'graph StaticGraph<M, N, T> {
+    type Item = (u16, &'graph NodeData);
+    type IntoIter = NodeDataIter<'graph>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.node_data_iter()
+    }
+}
+
+impl<const M: usize, const N: usize, T> fmt::Debug for StaticGraph<M, N, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StaticGraph")
+            .field("nodes", &self.node_count)
+            .field("edges", &self.edge_count)
+            .field("places", &self.place_data.len())
+            .field("items", &self.item_data.len())
+            .field("doors", &self.door_data.len())
+            .finish()
+    }
+}
+
+impl<const M: usize, const N: usize, T> fmt::Display for StaticGraph<M, N, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "StaticGraph {{ nodes: {}, edges: {} }}",
+            self.node_count, self.edge_count
+        )
+    }
+}
+
+/// The incoming edges of every node in a graph, in the same offset array layout as the graph's
+/// outgoing edges. See `StaticGraph::reverse_adjacency`.
+pub struct ReverseAdjacency {
+    offsets: Box<[u32]>,
+    sources: Box<[u16]>,
+    edges: Box<[u16]>,
+}
+
+impl ReverseAdjacency {
+    /// Returns `(source node, edge index)` for every edge pointing at `node`.
+    pub fn neighbors_in(&self, node: u16) -> impl Iterator<Item = (u16, u16)> + '_ {
+        let start = self.offsets.get(node as usize).copied().unwrap_or(0) as usize;
+        let end = self
+            .offsets
+            .get(node as usize + 1)
+            .copied()
+            .unwrap_or(start as u32) as usize;
+        self.sources[start..end]
+            .iter()
+            .copied()
+            .zip(self.edges[start..end].iter().copied())
+    }
 }
 
+/// A bounded, human-readable dump of part of a graph's adjacency. See
+/// `StaticGraph::dump_adjacency`.
+pub struct AdjacencyDump<'graph, const M: usize, const N: usize, T = ()> {
+    graph: &'graph StaticGraph<M, N, T>,
+    start: u16,
+    limit: usize,
+}
+
+impl<const M: usize, const N: usize, T> fmt::Display for AdjacencyDump<'_, M, N, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let end = (self.start as usize)
+            .saturating_add(self.limit)
+            .min(self.graph.node_count as usize + 1);
+        for node in self.start as usize..end {
+            let (edge_pointers, edge_offset) =
+                self.graph.get_neighbors_out(NonZeroU16::new(node as u16));
+            write!(f, "{} ->", node)?;
+            for (i, dest) in edge_pointers.iter().enumerate() {
+                let edge_index = edge_offset as usize + i;
+                write!(f, " {} (req {})", dest, self.graph.edge_data[edge_index])?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+// The generated requirement indexes, checked against REQ_CONTAINER at compile time.
+const EDGE_REQS: [ReqIndex; NUM_EDGES_PADDED] = ReqIndex::checked_array(&crate::gen::EDGE_DATA);
+
 /// Get a new fully-connected static graph from the automatically-generated module gen.rs.
-pub fn new_static_graph() -> StaticGraph<NUM_VERTICES_PADDED, NUM_EDGES_PADDED> {
+pub fn new_static_graph() -> GenGraph {
     use crate::gen::*;
-    StaticGraph {
+    let mut graph = StaticGraph {
         node_pointers: NodeIndexArray(Box::new(NODE_POINTERS)),
-        node_data: Box::new(NODE_DATA),
+        node_data: Arc::new(NODE_DATA),
         edge_pointers: EdgeIndexArray(Box::new(EDGE_POINTERS)),
-        edge_data: Box::new(EDGE_DATA),
-    }
+        edge_data: Arc::new(EDGE_REQS),
+        req_trees: None,
+        edge_kinds: Arc::new(EDGE_KINDS),
+        edge_weights: Arc::new(EDGE_WEIGHTS),
+        edge_tricks: Arc::new(EDGE_TRICKS),
+        place_data: &PLACE_DATA,
+        item_data: &ITEM_DATA,
+        door_data: &DOOR_DATA,
+        tricks: &TRICKS,
+        region_of: Arc::new(REGION_OF),
+        payload: Box::new([(); NUM_VERTICES_PADDED]),
+        node_count: NUM_VERTICES as u16,
+        edge_count: NUM_EDGES as u16,
+        validated: None,
+    };
+    // The build script lays these tables out, so this only fails if gen.rs was edited by hand.
+    graph.validate().expect("generated graph is malformed");
+
+    graph
+}
+
+/// Get the per-requirement edge masks for the graph returned by `new_static_graph`. These are
+/// computed at compile time.
+pub fn static_graph_req_masks() -> &'static ReqMasks<ACCESS_BITFIELD_LEN> {
+    &GEN_REQ_MASKS
 }
 
 /// Get a new fully-connected static graph from the automatically-generated module gen.rs with no
 /// logical constraints between connected nodes.
-pub fn new_static_graph_open() -> StaticGraph<NUM_VERTICES_PADDED, NUM_EDGES_PADDED> {
+pub fn new_static_graph_open() -> GenGraph {
     use crate::gen::*;
-    StaticGraph {
+    let mut graph = StaticGraph {
         node_pointers: NodeIndexArray(Box::new(NODE_POINTERS)),
-        node_data: Box::new(NODE_DATA),
+        node_data: Arc::new(NODE_DATA),
         edge_pointers: EdgeIndexArray(Box::new(EDGE_POINTERS)),
-        edge_data: Box::new([0u16; NUM_EDGES_PADDED]),
+        edge_data: Arc::new([ReqIndex::OPEN; NUM_EDGES_PADDED]),
+        req_trees: None,
+        edge_kinds: Arc::new(EDGE_KINDS),
+        edge_weights: Arc::new(EDGE_WEIGHTS),
+        edge_tricks: Arc::new(EDGE_TRICKS),
+        place_data: &PLACE_DATA,
+        item_data: &ITEM_DATA,
+        door_data: &DOOR_DATA,
+        tricks: &TRICKS,
+        region_of: Arc::new(REGION_OF),
+        payload: Box::new([(); NUM_VERTICES_PADDED]),
+        node_count: NUM_VERTICES as u16,
+        edge_count: NUM_EDGES as u16,
+        validated: None,
+    };
+    // The build script lays these tables out, so this only fails if gen.rs was edited by hand.
+    graph.validate().expect("generated graph is malformed");
+
+    graph
+}
+
+/// A compiled world model without any padding, e.g. from the `static_graph!` macro. Every table
+/// is laid out like its counterpart in gen.rs: index 0 of the node tables is the terminal node,
+/// index 0 of the edge tables is unused, and `node_pointers` has one more entry than there are
+/// nodes to end the last node's edges.
+pub struct ModelTables {
+    pub node_pointers: &'static [u16],
+    pub node_data: &'static [NodeData],
+    pub edge_pointers: &'static [u16],
+    pub edge_data: &'static [ReqIndex],
+    pub edge_kinds: &'static [EdgeType],
+    pub edge_weights: &'static [u8],
+    /// A `TrickMask` per edge, indexing into `tricks`.
+    pub edge_tricks: &'static [u8],
+    pub place_data: &'static [PlaceNodeData],
+    pub item_data: &'static [ItemNodeData],
+    pub door_data: &'static [DoorNodeData],
+    pub tricks: &'static [&'static str],
+    pub region_of: &'static [u16],
+    pub node_count: u16,
+    pub edge_count: u16,
+}
+
+impl<const M: usize, const N: usize> StaticGraph<M, N> {
+    /// Build a graph from compiled model tables, padding them out the same way gen.rs is padded.
+    ///
+    /// Panics if the tables don't fit in the graph or don't pass `validate`.
+    pub fn from_tables(tables: &ModelTables) -> Self {
+        let nodes = tables.node_count as usize + 1;
+        let edges = tables.edge_count as usize + 1;
+        let mut graph = StaticGraph::with_capacity_hint(nodes - 1, edges - 1)
+            .expect("model tables don't fit in the graph");
+        assert!(tables.node_pointers.len() == nodes + 1 && tables.edge_pointers.len() == edges);
+        for (i, &ptr) in tables.node_pointers.iter().enumerate() {
+            graph.node_pointers.0[i] = NonZeroU16::new(ptr).expect("node pointers can't be zero");
+        }
+        for (i, &dest) in tables.edge_pointers.iter().enumerate() {
+            graph.edge_pointers.0[i] = NonZeroU16::new(dest).expect("edges can't point at zero");
+        }
+        Arc::make_mut(&mut graph.node_data)[..nodes].clone_from_slice(&tables.node_data[..nodes]);
+        Arc::make_mut(&mut graph.edge_data)[..edges].copy_from_slice(&tables.edge_data[..edges]);
+        Arc::make_mut(&mut graph.edge_kinds)[..edges].copy_from_slice(&tables.edge_kinds[..edges]);
+        Arc::make_mut(&mut graph.edge_weights)[..edges]
+            .copy_from_slice(&tables.edge_weights[..edges]);
+        Arc::make_mut(&mut graph.edge_tricks)[..edges]
+            .copy_from_slice(&tables.edge_tricks[..edges]);
+        Arc::make_mut(&mut graph.region_of)[..nodes].copy_from_slice(&tables.region_of[..nodes]);
+        graph.place_data = tables.place_data;
+        graph.item_data = tables.item_data;
+        graph.door_data = tables.door_data;
+        graph.tricks = tables.tricks;
+        graph.node_count = tables.node_count;
+        graph.edge_count = tables.edge_count;
+        if let Err(e) = graph.validate() {
+            panic!("model tables are malformed: {}", e);
+        }
+
+        graph
+    }
+}
+
+/// Why `StaticGraph::validate` rejected a graph.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GraphError {
+    /// The graph has more nodes or edges than its arrays can hold.
+    TooLarge,
+    /// A node pointer is out of order or points past the last edge.
+    BadNodePointer(u16),
+    /// An edge points at a node that isn't in the graph.
+    BadEdgePointer(u16),
+    /// An edge's requirement index is past the graph's requirement trees.
+    BadRequirement(u16),
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphError::TooLarge => write!(f, "graph is too large"),
+            GraphError::BadNodePointer(n) => write!(f, "bad pointer for node {}", n),
+            GraphError::BadEdgePointer(e) => write!(f, "edge {} points outside the graph", e),
+            GraphError::BadRequirement(e) => write!(f, "edge {} has a bad requirement", e),
+        }
     }
 }
 
 #[repr(transparent)]
-pub(crate) struct NodeIndexArray<const M: usize>(Box<[NonZeroU16; M]>);
+pub(crate) struct NodeIndexArray<const M: usize>(pub(crate) Box<[NonZeroU16; M]>);
 
 impl<const M: usize> Index<u16> for NodeIndexArray<M> {
     type Output = NonZeroU16;
@@ -174,7 +1045,7 @@ impl<const M: usize> Deref for NodeIndexArray<M> {
 }
 
 #[repr(transparent)]
-pub(crate) struct EdgeIndexArray<const N: usize>(Box<[NonZeroU16; N]>);
+pub(crate) struct EdgeIndexArray<const N: usize>(pub(crate) Box<[NonZeroU16; N]>);
 
 impl<const N: usize> Index<u16> for EdgeIndexArray<N> {
     type Output = NonZeroU16;
@@ -208,42 +1079,68 @@ impl<const N: usize> Deref for EdgeIndexArray<N> {
     }
 }
 
-/// In many cases when we're traversing the graph we don't need to concern ourselves with a node's
-/// full metadata. We may not even care what type of node it is. We don't use this struct in our
-/// demonstration but the intention is that every node has a type and associated "wide" metadata
-/// (generated from our plain text world model at compile time, not hard coded) which are stored in
-/// separate arrays by node type and only accessed when needed.
-///
-/// We store these in an array separate from node pointers for the sake of cache efficiency; a
-/// traversing iterator can choose whether it cares about them or not.
-pub struct NodeData {
-    pub node_type: NodeType,
-    pub data_index: u16,
-}
-
-// We can also derive Copy for practically free but I want to avoid implicit copies of these types
-// for now. We always access the original owned version by reference.
-impl NodeData {
-    pub const fn default() -> Self {
-        NodeData {
-            node_type: NodeType::Place,
-            data_index: 0u16,
+impl fmt::Display for NodeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeType::Place => write!(f, "Place"),
+            NodeType::Item => write!(f, "Item"),
+            NodeType::Door => write!(f, "Door"),
         }
     }
 }
 
-impl NodeData {
-    pub const DEFAULT: NodeData = NodeData::default();
+/// Wide metadata for a `NodeType::Place` node. The 0th entry of the generated table is shared by
+/// the zero index and any padding nodes, which aren't part of the graph and have no region.
+#[derive(Debug)]
+pub struct PlaceNodeData {
+    pub name: &'static str,
+    pub region: u16,
+}
+
+/// Wide metadata for a `NodeType::Item` node, i.e. a location that holds an item.
+#[derive(Debug)]
+pub struct ItemNodeData {
+    pub name: &'static str,
+    pub region: u16,
+    pub vanilla_item: Item,
+}
+
+/// Wide metadata for a `NodeType::Door` node.
+#[derive(Debug)]
+pub struct DoorNodeData {
+    pub name: &'static str,
+    pub region: u16,
+    pub flags: DoorFlags,
 }
 
-pub enum NodeType {
-    Place, // ie: "Region" in ER, a logically distinct place where the player can just "be."
-    Item,
-    Door,
+/// The metadata of a single edge. See `StaticGraph::edge_data`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct EdgeData {
+    pub req: ReqIndex,
+    pub kind: EdgeType,
+    /// A small traversal cost for search modes that care about more than hop counts, e.g. to
+    /// prefer walking over a long way around through several doors.
+    pub weight: u8,
+    /// The tricks that open the edge whatever its requirement. See `StaticGraph::tricks`.
+    pub tricks: TrickMask,
 }
 
+impl fmt::Display for EdgeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EdgeType::Normal => write!(f, "Normal"),
+            EdgeType::Door => write!(f, "Door"),
+            EdgeType::Warp => write!(f, "Warp"),
+            EdgeType::Shortcut => write!(f, "Shortcut"),
+        }
+    }
+}
+
+/// One bit per edge index. `N` is the most words one can hold, and `new` allocates all of them,
+/// but a walker only allocates as many as its graph needs, see `with_len`.
+#[derive(Clone)]
 #[repr(transparent)]
-pub struct AccessCache<const N: usize>(Box<[u64; N]>);
+pub struct AccessCache<const N: usize>(Box<[u64]>);
 
 impl<const N: usize> Default for AccessCache<N> {
     fn default() -> Self {
@@ -255,7 +1152,26 @@ impl<const N: usize> AccessCache<N> {
     const BITMASK_CUR: u64 = 0x80000000_00000000;
 
     pub fn new() -> Self {
-        AccessCache(Box::new([0; N]))
+        Self::with_len(N)
+    }
+
+    /// A cache of `len` words, e.g. `StaticGraph::ACCESS_LEN` for one graph's edges.
+    pub fn with_len(len: usize) -> Self {
+        assert!(
+            len <= N,
+            "an access cache of {} words can't hold {}",
+            N,
+            len
+        );
+        AccessCache(vec![0; len].into_boxed_slice())
+    }
+
+    /// Reallocate to `len` words unless we already have that many, e.g. a cache lent from a
+    /// search of another graph. What we held is lost either way.
+    pub(crate) fn fit(&mut self, len: usize) {
+        if self.0.len() != len {
+            *self = Self::with_len(len);
+        }
     }
 
     pub fn check_access(&self, idx: u16) -> bool {
@@ -265,15 +1181,155 @@ impl<const N: usize> AccessCache<N> {
 
         (self[bitfield_index] & bitmask) != 0
     }
-}
 
-impl<const N: usize> Index<u16> for AccessCache<N> {
-    type Output = u64;
+    /// Overwrite access with a precomputed bitfield, e.g. one from `evaluate_access_const`.
+    /// Anything in `bitfield` past our own words is ignored.
+    pub fn copy_from(&mut self, bitfield: &[u64; N]) {
+        let len = self.0.len();
+        self.0.copy_from_slice(&bitfield[..len]);
+    }
 
-    fn index(&self, idx: u16) -> &Self::Output {
-        &self.0[idx as usize]
+    /// Mark every edge inaccessible.
+    pub fn clear(&mut self) {
+        raw::zero_words(&mut self.0[..]);
     }
-}
+
+    pub fn set_access(&mut self, idx: u16) {
+        let bit_index = (idx & 0x003F) as u32;
+        let bitfield_index = idx >> 6;
+        let bitmask = Self::BITMASK_CUR >> bit_index;
+
+        self[bitfield_index] |= bitmask;
+    }
+
+    /// Every accessible edge, in index order. See `VisitedCache::iter_ones`.
+    pub fn iter_ones(&self) -> Ones<'_> {
+        Ones::new(&self.0[..])
+    }
+
+    /// The number of accessible edges.
+    pub fn count(&self) -> u32 {
+        self.0.iter().map(|w| w.count_ones()).sum()
+    }
+
+    /// Also make every edge accessible in `other` accessible here.
+    pub fn union(&mut self, other: &Self) {
+        self.0
+            .iter_mut()
+            .zip(other.iter())
+            .for_each(|(a, b)| *a |= b);
+    }
+
+    /// Only keep the edges that are accessible in `other` too.
+    pub fn intersect(&mut self, other: &Self) {
+        self.0
+            .iter_mut()
+            .zip(other.iter())
+            .for_each(|(a, b)| *a &= b);
+    }
+
+    /// Drop every edge that's accessible in `other`.
+    pub fn difference(&mut self, other: &Self) {
+        self.0
+            .iter_mut()
+            .zip(other.iter())
+            .for_each(|(a, b)| *a &= !b);
+    }
+
+    /// Every edge whose access differs between `old` and `new`, in index order. Whichever side
+    /// has it accessible says whether it opened or closed, e.g. to resume a search from just the
+    /// edges an item opened or to show them to a player.
+    pub fn diff<'a>(old: &'a Self, new: &'a Self) -> impl Iterator<Item = u16> + 'a {
+        old.iter()
+            .zip(new.iter())
+            .enumerate()
+            .flat_map(|(w, (&old, &new))| {
+                let mut changed = old ^ new;
+                iter::from_fn(move || {
+                    (changed != 0).then(|| {
+                        let bit = changed.leading_zeros();
+                        changed &= !(Self::BITMASK_CUR >> bit);
+                        (w << 6) as u16 + bit as u16
+                    })
+                })
+            })
+    }
+
+    /// Instead of determining whether an edge can be traversed during a traversal, we can
+    /// pre-compute our logic to a large extent. This is somewhat complicated by things like small
+    /// keys or logical requirements that may have a dependency on the graph state and other
+    /// logical constraints that may change or not have been computed yet.
+    ///
+    /// Another approach here is to evaluate each requirement once and apply pre-computed
+    /// bitmasks, see `rebuild_from_masks`.
+    ///
+    /// There are far fewer trees than edges, so `memo` has each tree evaluated once up front. It
+    /// has to cover the trees `edge_data` indexes into, see `StaticGraph::req_memo`.
+    pub fn evaluate_logical_access<const E: usize>(
+        &mut self,
+        edge_data: &[ReqIndex; E],
+        memo: &ReqMemo,
+    ) {
+        debug_assert!(E <= self.0.len() * CHUNK_SIZE);
+        // Also tried nightly, safe .array_chunks iterator method but can't remember if it's
+        // faster or anything. Not too worried about, all the logic evaluation code could be
+        // massively improved.
+        let edge_logic = raw::exact_chunks::<CHUNK_SIZE, _>(edge_data)
+            .iter()
+            .enumerate();
+        edge_logic.for_each(|i| {
+            let mut bit_cursor: u64 = Self::BITMASK_CUR;
+            let (idx, logic_array) = i;
+            self[idx] = logic_array.iter().fold(0u64, |acc, &d| match memo.get(d) {
+                true => {
+                    let c = acc | bit_cursor;
+                    bit_cursor >>= 1;
+                    c
+                }
+                false => {
+                    bit_cursor >>= 1;
+                    acc
+                }
+            });
+        });
+        // Anything past a smaller graph's edges stays closed.
+        self.0[E / CHUNK_SIZE..].fill(0);
+    }
+
+    /// Rebuild access from precomputed per-requirement edge masks. Every requirement tree is
+    /// evaluated exactly once into `memo` and the masks of the satisfied ones are OR'd together,
+    /// so a state change costs a handful of 64-bit ops per word instead of a tree evaluation per
+    /// edge. `memo` has to cover the same trees as `masks`, see `StaticGraph::req_memo`.
+    pub fn rebuild_from_masks(&mut self, masks: &ReqMasks<N>, memo: &ReqMemo) {
+        self.0.iter_mut().enumerate().for_each(|(w, word)| {
+            *word = masks
+                .masks
+                .iter()
+                .chain(&masks.extra)
+                .zip(memo.satisfied())
+                .fold(0u64, |acc, (mask, &s)| match s {
+                    true => acc | mask[w],
+                    false => acc,
+                });
+        });
+    }
+}
+
+impl<const N: usize> fmt::Debug for AccessCache<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccessCache")
+            .field("accessible", &self.count())
+            .finish()
+    }
+}
+
+impl<const N: usize> Index<u16> for AccessCache<N> {
+    type Output = u64;
+
+    fn index(&self, idx: u16) -> &Self::Output {
+        &self.0[idx as usize]
+    }
+}
 
 impl<const N: usize> Index<usize> for AccessCache<N> {
     type Output = u64;
@@ -296,15 +1352,152 @@ impl<const N: usize> IndexMut<usize> for AccessCache<N> {
 }
 
 impl<const N: usize> Deref for AccessCache<N> {
-    type Target = [u64; N];
+    type Target = [u64];
 
     fn deref(&self) -> &Self::Target {
         self.0.deref()
     }
 }
 
+/// For every one of a graph's requirement trees (see `StaticGraph::req_trees`), a bitfield of the
+/// edges using it as their requirement, in the same layout as `AccessCache`. Since these only
+/// depend on the edge data they can be computed once per graph (at compile time for our generated
+/// one, see `StaticGraph::req_masks`) and combined for any collection state with
+/// `AccessCache::rebuild_from_masks`.
+pub struct ReqMasks<const W: usize> {
+    masks: [[u64; W]; REQ_CONTAINER_LEN],
+    // The same for every tree a graph adds after the library's. See `StaticGraph::req_trees`.
+    extra: Vec<[u64; W]>,
+}
+
+impl<const W: usize> ReqMasks<W> {
+    const BITMASK_CUR: u64 = 0x80000000_00000000;
+
+    /// Panics if an edge uses a tree past `REQ_CONTAINER`. See `with_trees`.
+    pub const fn from_edges<const E: usize>(edge_data: &[ReqIndex; E]) -> Self {
+        assert!(E <= W * CHUNK_SIZE);
+        let mut masks = [[0u64; W]; REQ_CONTAINER_LEN];
+        let mut edge = 0;
+        while edge < E {
+            let req = edge_data[edge].get() as usize;
+            masks[req][edge >> 6] |= Self::BITMASK_CUR >> (edge & 0x3F);
+            edge += 1;
+        }
+
+        ReqMasks {
+            masks,
+            extra: Vec::new(),
+        }
+    }
+
+    /// Same as `from_edges` for edges indexing into `trees` trees, e.g. a graph's own.
+    pub fn with_trees<const E: usize>(edge_data: &[ReqIndex; E], trees: usize) -> Self {
+        assert!(E <= W * CHUNK_SIZE);
+        let extra = trees.saturating_sub(REQ_CONTAINER_LEN);
+        let mut masks = ReqMasks {
+            masks: [[0u64; W]; REQ_CONTAINER_LEN],
+            extra: vec![[0u64; W]; extra],
+        };
+        for (edge, &req) in edge_data.iter().enumerate() {
+            let req = req.get() as usize;
+            let mask = match req.checked_sub(REQ_CONTAINER_LEN) {
+                None => &mut masks.masks[req],
+                Some(i) => &mut masks.extra[i],
+            };
+            mask[edge >> 6] |= Self::BITMASK_CUR >> (edge & 0x3F);
+        }
+
+        masks
+    }
+
+    /// The edges whose requirement is the tree rooted at `req`.
+    pub fn mask(&self, req: ReqIndex) -> &[u64; W] {
+        let req = req.get() as usize;
+        match req.checked_sub(REQ_CONTAINER_LEN) {
+            None => &self.masks[req],
+            Some(i) => &self.extra[i],
+        }
+    }
+}
+
+/// Evaluate access for every edge under a fixed collection state during const evaluation,
+/// producing a bitfield in the same layout as `AccessCache`.
+pub const fn evaluate_access_const<const W: usize, const E: usize>(
+    edge_data: &[ReqIndex; E],
+    state: &CollectionState,
+) -> [u64; W] {
+    assert!(E <= W * CHUNK_SIZE);
+    // Each tree only needs evaluating once.
+    let mut satisfied = [false; REQ_CONTAINER_LEN];
+    let mut req = 0;
+    while req < REQ_CONTAINER_LEN {
+        satisfied[req] = match ReqIndex::new(req as u16) {
+            Some(r) => state.eval_logic_tree_const(r),
+            None => false,
+        };
+        req += 1;
+    }
+    let mut access = [0u64; W];
+    let mut edge = 0;
+    while edge < E {
+        if satisfied[edge_data[edge].get() as usize] {
+            access[edge >> 6] |= 0x80000000_00000000 >> (edge & 0x3F);
+        }
+        edge += 1;
+    }
+
+    access
+}
+
+/// Access for the generated graph with every item collected, computed at compile time so
+/// connectivity checks don't pay for evaluating logic. See `StaticGraph::dfs_iter_with_access`.
+pub static ALL_ITEMS_ACCESS: [u64; ACCESS_BITFIELD_LEN] =
+    evaluate_access_const(&EDGE_REQS, &CollectionState::ALL_ITEMS);
+
+// Masks for the generated edge requirements, computed at compile time.
+static GEN_REQ_MASKS: ReqMasks<ACCESS_BITFIELD_LEN> = ReqMasks::from_edges(&EDGE_REQS);
+
+/// The index of every set bit in one of our bitfield caches, in ascending order. See
+/// `VisitedCache::iter_ones` and `AccessCache::iter_ones`.
+#[derive(Clone, Debug)]
+pub struct Ones<'a> {
+    words: iter::Enumerate<slice::Iter<'a, u64>>,
+    // The index of the word we're in and its bits we haven't returned yet.
+    word: (usize, u64),
+}
+
+impl<'a> Ones<'a> {
+    fn new(words: &'a [u64]) -> Self {
+        Ones {
+            words: words.iter().enumerate(),
+            word: (0, 0),
+        }
+    }
+}
+
+impl Iterator for Ones<'_> {
+    type Item = u16;
+
+    #[inline]
+    fn next(&mut self) -> Option<u16> {
+        while self.word.1 == 0 {
+            let (i, &bits) = self.words.next()?;
+            self.word = (i, bits);
+        }
+        // Bits are stored most significant first, so the lowest index is the highest bit.
+        let (i, bits) = self.word;
+        let bit = bits.leading_zeros();
+        self.word.1 &= !(0x80000000_00000000 >> bit);
+
+        Some(((i << 6) + bit as usize) as u16)
+    }
+}
+
+/// One bit per node index. Like `AccessCache`, `M` is the most words one can hold and a walker
+/// only allocates as many as its graph needs.
+#[derive(Clone)]
 #[repr(transparent)]
-pub struct VisitedCache<const M: usize>(Box<[u64; M]>);
+pub struct VisitedCache<const M: usize>(Box<[u64]>);
 
 impl<const M: usize> Default for VisitedCache<M> {
     fn default() -> Self {
@@ -316,35 +1509,80 @@ impl<const M: usize> VisitedCache<M> {
     const BITMASK_CUR: u64 = 0x80000000_00000000;
 
     pub fn new() -> Self {
-        VisitedCache(Box::new([0; M]))
+        Self::with_len(M)
+    }
+
+    /// A cache of `len` words, e.g. `StaticGraph::VISITED_LEN` for one graph's nodes.
+    pub fn with_len(len: usize) -> Self {
+        assert!(
+            len <= M,
+            "a visited cache of {} words can't hold {}",
+            M,
+            len
+        );
+        VisitedCache(vec![0; len].into_boxed_slice())
+    }
+
+    /// See `AccessCache::fit`.
+    pub(crate) fn fit(&mut self, len: usize) {
+        if self.0.len() != len {
+            *self = Self::with_len(len);
+        }
     }
 
     pub fn check_visited(&self, idx: u16) -> bool {
         let bit_index = idx as u32 & 0x003F;
-        let bitfield_index = (idx >> 6) & 0x1FF;
+        let bitfield_index = idx >> 6;
         let bitmask = Self::BITMASK_CUR >> bit_index;
 
         (self[bitfield_index] & bitmask) != 0
     }
 
+    /// Forget every visited node.
+    pub fn clear(&mut self) {
+        raw::zero_words(&mut self.0[..]);
+    }
+
     pub fn mark_visited(&mut self, idx: u16) {
         // https://godbolt.org/z/MePKean13
         let bit_index = idx as u32 & 0x003F;
-        let bitfield_index = (idx >> 6) & 0x1FF;
+        let bitfield_index = idx >> 6;
         let bitmask = Self::BITMASK_CUR >> bit_index;
 
         self[bitfield_index] |= bitmask;
     }
 
+    pub fn unmark_visited(&mut self, idx: u16) {
+        let bit_index = idx as u32 & 0x003F;
+        let bitfield_index = idx >> 6;
+        let bitmask = Self::BITMASK_CUR >> bit_index;
+
+        self[bitfield_index] &= !bitmask;
+    }
+
     pub fn test_set_visited(&mut self, idx: u16) -> bool {
         let bit_index = idx as u32 & 0x003F;
-        let bitfield_index = (idx >> 6) & 0x1FF;
+        let bitfield_index = idx >> 6;
         let bitmask = Self::BITMASK_CUR >> bit_index;
         let previously_visited = (self[bitfield_index] & bitmask) != 0;
         self[bitfield_index] |= bitmask;
 
         previously_visited
     }
+
+    /// Every visited node, in index order. This skips over empty words so it only costs
+    /// something for the nodes that were visited.
+    pub fn iter_ones(&self) -> Ones<'_> {
+        Ones::new(&self.0[..])
+    }
+}
+
+impl<const M: usize> fmt::Debug for VisitedCache<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VisitedCache")
+            .field("visited", &self.iter().map(|w| w.count_ones()).sum::<u32>())
+            .finish()
+    }
 }
 
 impl<const M: usize> Index<u16> for VisitedCache<M> {
@@ -362,7 +1600,7 @@ impl<const M: usize> IndexMut<u16> for VisitedCache<M> {
 }
 
 impl<const M: usize> Deref for VisitedCache<M> {
-    type Target = [u64; M];
+    type Target = [u64];
 
     fn deref(&self) -> &Self::Target {
         self.0.deref()
@@ -375,18 +1613,464 @@ impl<const M: usize> DerefMut for VisitedCache<M> {
     }
 }
 
+/// What a walker needs from whatever remembers which nodes it's visited, so it can be backed by
+/// either a `VisitedCache` or a `StampedVisited`. See `StaticGraph::dfs_iter_with_visited`.
+pub trait VisitedSet {
+    /// How many node indexes it has room for.
+    const NODES: usize;
+
+    fn check_visited(&self, idx: u16) -> bool;
+    fn mark_visited(&mut self, idx: u16);
+    fn unmark_visited(&mut self, idx: u16);
+    /// Mark a node visited, returning whether it already was.
+    fn test_set_visited(&mut self, idx: u16) -> bool;
+    /// Forget every node, ready for the next search.
+    fn reset(&mut self);
+}
+
+impl<const M: usize> VisitedSet for VisitedCache<M> {
+    const NODES: usize = M * CHUNK_SIZE;
+
+    fn check_visited(&self, idx: u16) -> bool {
+        VisitedCache::check_visited(self, idx)
+    }
+
+    fn mark_visited(&mut self, idx: u16) {
+        VisitedCache::mark_visited(self, idx)
+    }
+
+    fn unmark_visited(&mut self, idx: u16) {
+        VisitedCache::unmark_visited(self, idx)
+    }
+
+    fn test_set_visited(&mut self, idx: u16) -> bool {
+        VisitedCache::test_set_visited(self, idx)
+    }
+
+    fn reset(&mut self) {
+        self.clear();
+    }
+}
+
+/// A visited set that forgets everything in constant time. Every node has a u16 stamp and a node
+/// counts as visited if its stamp matches the current generation, so resetting is just moving on
+/// to the next generation. The stamps only need rewriting when the generation wraps around, once
+/// every 65535 searches.
+///
+/// That makes it much bigger than a `VisitedCache` and a little slower to test, but a walker that
+/// only looks at a handful of nodes no longer pays for clearing a bit for every node in the
+/// graph. On our generated graph (see the bfs bench) clearing a walker and searching 16 nodes
+/// took well under half as long with stamps. Lend one to walkers with
+/// `StaticGraph::dfs_iter_with_visited` and take it back from their `core.visited` afterwards.
+#[derive(Clone)]
+pub struct StampedVisited<const M: usize = NUM_VERTICES_PADDED> {
+    stamps: Box<[u16; M]>,
+    // Never zero, so a zeroed stamp is never visited.
+    generation: u16,
+}
+
+impl<const M: usize> Default for StampedVisited<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const M: usize> StampedVisited<M> {
+    pub fn new() -> Self {
+        StampedVisited {
+            stamps: Box::new([0; M]),
+            generation: 1,
+        }
+    }
+}
+
+impl<const M: usize> VisitedSet for StampedVisited<M> {
+    const NODES: usize = M;
+
+    #[inline]
+    fn check_visited(&self, idx: u16) -> bool {
+        self.stamps[idx as usize] == self.generation
+    }
+
+    #[inline]
+    fn mark_visited(&mut self, idx: u16) {
+        self.stamps[idx as usize] = self.generation;
+    }
+
+    fn unmark_visited(&mut self, idx: u16) {
+        self.stamps[idx as usize] = 0;
+    }
+
+    #[inline]
+    fn test_set_visited(&mut self, idx: u16) -> bool {
+        let stamp = &mut self.stamps[idx as usize];
+        let previously_visited = *stamp == self.generation;
+        *stamp = self.generation;
+
+        previously_visited
+    }
+
+    fn reset(&mut self) {
+        self.generation = match self.generation.checked_add(1) {
+            Some(g) => g,
+            // Stamps from the last time round would look visited again.
+            None => {
+                *self.stamps = [0; M];
+                1
+            }
+        };
+    }
+}
+
+impl<const M: usize> fmt::Debug for StampedVisited<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let visited = self.stamps.iter().filter(|&&s| s == self.generation);
+        f.debug_struct("StampedVisited")
+            .field("generation", &self.generation)
+            .field("visited", &visited.count())
+            .finish()
+    }
+}
+
+/// Where a depth-first search is with a node. See `ColorCache`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Color {
+    /// Not reached yet.
+    White,
+    /// Reached but not finished, i.e. on the current path from the root.
+    Gray,
+    /// Reached and every edge out of it explored.
+    Black,
+}
+
+/// Two bits per node for searches that need more than visited or not, like telling back edges
+/// from cross edges in a depth-first search. Nodes are packed like in `VisitedCache`, most
+/// significant bits first, but 32 to a word rather than 64.
+#[derive(Clone)]
+#[repr(transparent)]
+pub struct ColorCache<const L: usize>(Box<[u64]>);
+
+impl<const L: usize> Default for ColorCache<L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const L: usize> ColorCache<L> {
+    const MASK_CUR: u64 = 0xC0000000_00000000;
+
+    /// Every node starts white.
+    pub fn new() -> Self {
+        Self::with_len(L)
+    }
+
+    /// Every node starts white, in `len` words rather than all `L`. A graph's nodes need twice
+    /// its `StaticGraph::VISITED_LEN`.
+    pub fn with_len(len: usize) -> Self {
+        assert!(len <= L, "a color cache of {} words can't hold {}", L, len);
+        ColorCache(vec![0; len].into_boxed_slice())
+    }
+
+    /// Make every node white again.
+    pub fn clear(&mut self) {
+        raw::zero_words(&mut self.0[..]);
+    }
+
+    pub fn color(&self, idx: u16) -> Color {
+        let shift = (idx as u32 & 0x001F) * 2;
+        let bitfield_index = (idx >> 5) as usize;
+        match (self.0[bitfield_index] << shift) >> 62 {
+            0 => Color::White,
+            1 => Color::Gray,
+            _ => Color::Black,
+        }
+    }
+
+    pub fn set_color(&mut self, idx: u16, color: Color) {
+        let shift = (idx as u32 & 0x001F) * 2;
+        let bitfield_index = (idx >> 5) as usize;
+        let word = &mut self.0[bitfield_index];
+        *word = (*word & !(Self::MASK_CUR >> shift)) | ((color as u64) << (62 - shift));
+    }
+}
+
+impl<const L: usize> fmt::Debug for ColorCache<L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // A node is reached if either of its bits is set.
+        let reached = |w: &u64| ((w | (w << 1)) & 0xAAAAAAAA_AAAAAAAA).count_ones();
+        f.debug_struct("ColorCache")
+            .field("reached", &self.0.iter().map(reached).sum::<u32>())
+            .finish()
+    }
+}
+
+/// A `VisitedCache` that threads can mark through a shared reference, for searches that split
+/// one frontier between threads. Marking is a single `fetch_or` so exactly one thread sees any
+/// node as newly visited.
+pub struct AtomicVisitedCache<const M: usize>(Box<[AtomicU64]>);
+
+impl<const M: usize> Default for AtomicVisitedCache<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const M: usize> AtomicVisitedCache<M> {
+    const BITMASK_CUR: u64 = 0x80000000_00000000;
+
+    pub fn new() -> Self {
+        Self::with_len(M)
+    }
+
+    /// See `VisitedCache::with_len`.
+    pub fn with_len(len: usize) -> Self {
+        assert!(
+            len <= M,
+            "a visited cache of {} words can't hold {}",
+            M,
+            len
+        );
+        AtomicVisitedCache((0..len).map(|_| AtomicU64::new(0)).collect())
+    }
+
+    pub fn check_visited(&self, idx: u16) -> bool {
+        let bit_index = idx as u32 & 0x003F;
+        let bitfield_index = idx >> 6;
+        let bitmask = Self::BITMASK_CUR >> bit_index;
+
+        (self.0[bitfield_index as usize].load(Ordering::Relaxed) & bitmask) != 0
+    }
+
+    pub fn mark_visited(&self, idx: u16) {
+        self.test_set_visited(idx);
+    }
+
+    pub fn test_set_visited(&self, idx: u16) -> bool {
+        let bit_index = idx as u32 & 0x003F;
+        let bitfield_index = idx >> 6;
+        let bitmask = Self::BITMASK_CUR >> bit_index;
+        // Relaxed is enough, the bit is all we're synchronizing on. Whoever waits for the other
+        // threads to finish (e.g. joining them) sees every mark.
+        let previous = self.0[bitfield_index as usize].fetch_or(bitmask, Ordering::Relaxed);
+
+        (previous & bitmask) != 0
+    }
+
+    /// The nodes marked so far, once no other thread is marking any more.
+    pub fn into_visited(self) -> VisitedCache<M> {
+        VisitedCache(
+            self.0
+                .into_vec()
+                .into_iter()
+                .map(AtomicU64::into_inner)
+                .collect(),
+        )
+    }
+}
+
+impl<const M: usize> fmt::Debug for AtomicVisitedCache<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let visited: u32 = self
+            .0
+            .iter()
+            .map(|w| w.load(Ordering::Relaxed).count_ones())
+            .sum();
+        f.debug_struct("AtomicVisitedCache")
+            .field("visited", &visited)
+            .finish()
+    }
+}
+
+// Fails to compile if a field ever takes away the thread safety promised on `StaticGraph`, e.g.
+// an `Rc` or a `Cell` in a graph or a walker.
+const _: () = {
+    const fn send<S: Send>() {}
+    const fn send_sync<S: Send + Sync>() {}
+
+    fn thread_safe<'a, const M: usize, const N: usize, T: Send + Sync + 'a>() {
+        send_sync::<StaticGraph<M, N, T>>();
+        send_sync::<crate::arena::GraphArena<M, N, T>>();
+        send_sync::<crate::overlay::OverlayGraph<'a, M, N, T>>();
+        send::<TraversalCore<'a, M, N, T>>();
+        send::<DfsIter<'a, M, N, T>>();
+        send::<DepthLimitedDfsIter<'a, M, N, T>>();
+        send::<BfsIter<'a, M, N, T>>();
+        send::<AStarIter<'a, 'a, M, N, T>>();
+        send::<crate::edge_iter::EdgeIter<'a, M, N, T>>();
+        send::<crate::tracker::TrackerSession<'a, M, N, T>>();
+        send::<crate::scratch::Lent<'a, DfsIter<'a, M, N, T>>>();
+        send::<crate::scratch::Lent<'a, BfsIter<'a, M, N, T>>>();
+    }
+
+    send_sync::<crate::scratch::TraversalScratch>();
+    send_sync::<AtomicVisitedCache<VISITED_BITFIELD_LEN>>();
+    send_sync::<ReverseAdjacency>();
+    send_sync::<RegionGraph>();
+};
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::logic::{EventFlags, Requirement, SettingFlag};
     use std::collections::HashSet;
 
     #[test]
     pub fn new_graph() {
-        let _zero_graph: StaticGraph<NUM_VERTICES_PADDED, NUM_EDGES_PADDED> =
-            StaticGraph::new_zeroed();
+        let empty: StaticGraph<NUM_VERTICES_PADDED, NUM_EDGES_PADDED> = StaticGraph::empty();
+        assert!(empty.is_validated());
+        assert_eq!(empty.node_count(), 0);
+        assert!(empty.get_neighbors_out(NonZeroU16::new(1)).0.is_empty());
+        type Small = StaticGraph<8, 8>;
+        assert!(Small::with_capacity_hint(6, 7).is_ok());
+        assert_eq!(
+            Small::with_capacity_hint(7, 7).err(),
+            Some(GraphError::TooLarge)
+        );
+        assert_eq!(
+            Small::with_capacity_hint(6, 8).err(),
+            Some(GraphError::TooLarge)
+        );
         let _static_graph = new_static_graph();
     }
 
+    #[test]
+    fn wide_node_data() {
+        let graph = new_static_graph();
+        assert!(graph.place_data(1).is_some());
+        assert!(graph.item_data(1).is_none());
+        assert!(graph.place_data(0).is_some_and(|p| p.region == 0));
+        let items = (1..=NUM_VERTICES as u16)
+            .filter_map(|n| graph.item_data(n).map(|d| (n, d)))
+            .collect::<Vec<_>>();
+        assert_eq!(items.len(), graph.item_data.len());
+        assert!(items.iter().any(|(_, d)| d.vanilla_item == Item::Hammer));
+        let (node, data) = items[0];
+        assert_eq!(data.name, std::format!("item_{}", node));
+    }
+
+    #[test]
+    fn node_payload() {
+        let graph = new_static_graph();
+        assert_eq!(graph.payload.len(), NUM_VERTICES_PADDED);
+        let plain_count = graph.bfs_iter().count();
+
+        let mut graph = graph.with_payload::<Option<Item>>();
+        let (node, _) = graph
+            .node_data_iter()
+            .find(|(_, d)| matches!(d.node_type, NodeType::Item))
+            .unwrap();
+        *graph.payload_mut(node) = Some(Item::Hammer);
+        assert_eq!(*graph.payload(node), Some(Item::Hammer));
+        assert_eq!(*graph.payload(1), None);
+        // Graphs with a payload can still be traversed.
+        assert_eq!(graph.bfs_iter().count(), plain_count);
+    }
+
+    #[test]
+    fn edge_metadata() {
+        let graph = new_static_graph();
+        let mut kinds = [0usize; 4];
+        for node in 1..=NUM_VERTICES as u16 {
+            let (edge_pointers, edge_offset) = graph.get_neighbors_out(NonZeroU16::new(node));
+            for (i, &dest) in edge_pointers.iter().enumerate() {
+                let edge = graph.edge_data(edge_offset + i as u16);
+                assert_eq!(edge.req, graph.edge_data[(edge_offset as usize) + i]);
+                assert!((1..=3).contains(&edge.weight));
+                let touches_door =
+                    graph.door_data(node).is_some() || graph.door_data(u16::from(dest)).is_some();
+                assert_eq!(edge.kind == EdgeType::Door, touches_door);
+                kinds[edge.kind as usize] += 1;
+            }
+        }
+        assert_eq!(kinds.iter().sum::<usize>(), NUM_EDGES);
+        assert!(kinds.iter().all(|&k| k > 0));
+    }
+
+    #[test]
+    fn node_data_access() {
+        let mut graph = new_static_graph();
+        assert_eq!(graph.node_data_iter().count(), NUM_VERTICES);
+        let items = graph
+            .node_data_iter()
+            .filter(|(_, d)| matches!(d.node_type, NodeType::Item))
+            .count();
+        assert_eq!(items, graph.item_data.len());
+
+        let (node, _) = graph
+            .node_data_iter()
+            .find(|(_, d)| matches!(d.node_type, NodeType::Item))
+            .unwrap();
+        graph.node_data_mut(node).node_type = NodeType::Door;
+        assert!(matches!(graph.node_data(node).node_type, NodeType::Door));
+        assert!(graph.item_data(node).is_none());
+    }
+
+    #[test]
+    fn enumerate_nodes_and_edges() {
+        let graph = new_static_graph();
+        assert_eq!(graph.nodes().count(), NUM_VERTICES);
+        let mut count = 0;
+        for (node, data) in &graph {
+            assert!(core::ptr::eq(data, graph.node_data(node)));
+            count += 1;
+        }
+        assert_eq!(count, NUM_VERTICES);
+
+        let edges: std::vec::Vec<(u16, u16, u16)> = graph.edges().collect();
+        assert_eq!(edges.len(), NUM_EDGES);
+        // Edge indexes run contiguously from 1 in source order.
+        assert!(edges.iter().enumerate().all(|(i, e)| e.1 as usize == i + 1));
+        assert!(edges.windows(2).all(|w| w[0].0 <= w[1].0));
+        assert!(edges
+            .iter()
+            .all(|&(_, edge, dest)| u16::from(graph.edge_pointers[edge]) == dest));
+    }
+
+    #[test]
+    fn degree_stats() {
+        let graph = new_static_graph();
+        assert_eq!(graph.node_count() as usize, NUM_VERTICES);
+        assert_eq!(graph.edge_count() as usize, NUM_EDGES);
+        let histogram = graph.degree_histogram();
+        assert_eq!(histogram.len(), graph.max_out_degree() as usize + 1);
+        assert!(graph.max_out_degree() as usize <= MAX_OUT_DEGREE);
+        assert!(*histogram.last().unwrap() > 0);
+        assert_eq!(histogram.iter().sum::<u16>() as usize, NUM_VERTICES);
+        let edges: usize = histogram
+            .iter()
+            .enumerate()
+            .map(|(d, &n)| d * n as usize)
+            .sum();
+        assert_eq!(edges, NUM_EDGES);
+    }
+
+    #[test]
+    fn req_index_bounds() {
+        use crate::logic::REQ_CONTAINER_LEN;
+        assert_eq!(ReqIndex::new(0), Some(ReqIndex::OPEN));
+        assert!(ReqIndex::new(REQ_CONTAINER_LEN as u16 - 1).is_some());
+        assert!(ReqIndex::new(REQ_CONTAINER_LEN as u16).is_none());
+    }
+
+    #[test]
+    fn debug_and_display() {
+        let graph = new_static_graph();
+        assert_eq!(
+            std::format!("{}", graph),
+            std::format!(
+                "StaticGraph {{ nodes: {}, edges: {} }}",
+                NUM_VERTICES,
+                NUM_EDGES
+            )
+        );
+        let dump = std::format!("{}", graph.dump_adjacency(1, 3));
+        assert_eq!(dump.lines().count(), 3);
+        assert!(dump.starts_with("1 ->"));
+        let dfs_iter = graph.dfs_iter();
+        assert!(std::format!("{:?}", dfs_iter).contains("visited: 1"));
+    }
+
     #[test]
     fn new_dfs_iterator() {
         let graph = new_static_graph();
@@ -397,7 +2081,7 @@ mod tests {
     fn test_connected_dfs() {
         let mut node_set = HashSet::with_capacity(NUM_VERTICES);
         let mut graph = new_static_graph_open();
-        graph.edge_data = Box::new([0u16; NUM_EDGES_PADDED]); // No logic
+        graph.edge_data = Arc::new([ReqIndex::OPEN; NUM_EDGES_PADDED]); // No logic
         let mut dfs_iter = graph.dfs_iter();
         for _ in 1..=NUM_VERTICES {
             let next_node = dfs_iter.next();
@@ -408,11 +2092,360 @@ mod tests {
         assert_eq!(None, dfs_iter.next());
     }
 
+    #[test]
+    fn const_access() {
+        let graph = new_static_graph();
+        let state = CollectionState::ALL_ITEMS;
+        let mut expected = AccessCache::<ACCESS_BITFIELD_LEN>::new();
+        graph.compute_access_mask(&state, &mut expected);
+        assert_eq!(*expected, ALL_ITEMS_ACCESS);
+        // The same mask a traversal starts from, for any state.
+        for state in [CollectionState::default(), CollectionState::ALL_ITEMS] {
+            graph.compute_access_mask(&state, &mut expected);
+            assert_eq!(
+                *expected,
+                *graph.dfs_iter_with_state(state).core.edge_access
+            );
+        }
+        const NOTHING: [u64; ACCESS_BITFIELD_LEN] =
+            evaluate_access_const(&EDGE_REQS, &CollectionState::NOTHING);
+        assert!(ALL_ITEMS_ACCESS
+            .iter()
+            .zip(NOTHING.iter())
+            .all(|(all, none)| all & none == *none));
+
+        let evaluated: HashSet<u16> = graph
+            .dfs_iter_with_state(state.clone())
+            .map(u16::from)
+            .collect();
+        let precomputed: HashSet<u16> = graph
+            .dfs_iter_with_access(state, &ALL_ITEMS_ACCESS)
+            .map(u16::from)
+            .collect();
+        assert_eq!(evaluated, precomputed);
+    }
+
+    #[test]
+    fn access_set_ops() {
+        let graph = new_static_graph();
+        let mut all = AccessCache::<ACCESS_BITFIELD_LEN>::new();
+        let mut some = AccessCache::<ACCESS_BITFIELD_LEN>::new();
+        graph.compute_access_mask(&CollectionState::ALL_ITEMS, &mut all);
+        graph.compute_access_mask(&CollectionState::default(), &mut some);
+        assert!(some.count() < all.count());
+
+        // Collecting more only opens edges, and the diff is exactly what opened.
+        let opened: Vec<u16> = AccessCache::diff(&some, &all).collect();
+        assert_eq!(opened.len() as u32, all.count() - some.count());
+        assert!(opened.windows(2).all(|w| w[0] < w[1]));
+        assert!(opened
+            .iter()
+            .all(|&e| all.check_access(e) && !some.check_access(e)));
+        assert_eq!(AccessCache::diff(&all, &some).collect::<Vec<_>>(), opened);
+
+        let mut union = some.clone();
+        union.union(&all);
+        assert_eq!(*union, *all);
+        let mut intersection = all.clone();
+        intersection.intersect(&some);
+        assert_eq!(*intersection, *some);
+        let mut difference = all.clone();
+        difference.difference(&some);
+        assert_eq!(difference.count() as usize, opened.len());
+        difference.union(&some);
+        assert_eq!(AccessCache::diff(&difference, &all).count(), 0);
+
+        let ones: Vec<u16> = difference.iter_ones().collect();
+        assert_eq!(ones.len() as u32, all.count());
+        assert!(ones.iter().all(|&e| all.check_access(e)));
+        assert!(ones.windows(2).all(|w| w[0] < w[1]));
+        let mut visited = VisitedCache::<VISITED_BITFIELD_LEN>::new();
+        assert_eq!(visited.iter_ones().next(), None);
+        [0, 1, 63, 64, 200]
+            .iter()
+            .for_each(|&n| visited.mark_visited(n));
+        assert_eq!(visited.iter_ones().collect::<Vec<_>>(), [0, 1, 63, 64, 200]);
+
+        visited.clear();
+        assert_eq!(visited.iter_ones().next(), None);
+        difference.clear();
+        assert_eq!(difference.count(), 0);
+    }
+
+    #[test]
+    fn cache_sizes() {
+        type Gen = StaticGraph<NUM_VERTICES_PADDED, NUM_EDGES_PADDED>;
+        assert_eq!(Gen::VISITED_LEN, VISITED_BITFIELD_LEN);
+        assert_eq!(Gen::ACCESS_LEN, ACCESS_BITFIELD_LEN);
+        assert_eq!(StaticGraph::<8, 64>::ACCESS_LEN, 1);
+
+        // Node bits past the first half of the index space don't land on other nodes.
+        let mut visited = VisitedCache::<VISITED_BITFIELD_LEN>::new();
+        visited.mark_visited(40_000);
+        assert!(!visited.check_visited(40_000 - 32_768));
+        assert!(visited.check_visited(40_000));
+
+        // Smaller graphs get caches sized for their own tables.
+        let small = StaticGraph::<64, 64>::from_edge_list(["1 2", "2 3 locked", "2 4"]).unwrap();
+        let mut dfs_iter = small.dfs_iter_with_state(CollectionState::ALL_ITEMS);
+        let reached: HashSet<u16> = dfs_iter.by_ref().map(u16::from).collect();
+        assert_eq!(reached, HashSet::from([1, 2, 4]));
+        assert!(!dfs_iter.core.edge_access.check_access(2));
+        assert_eq!(dfs_iter.core.visited.len(), 1);
+        assert_eq!(dfs_iter.core.edge_access.len(), 1);
+        assert_eq!(dfs_iter.core.edge_evaluated.len(), 1);
+
+        // Caches lent from a search of a bigger graph are cut down to size and grown back.
+        let mut scratch = crate::scratch::TraversalScratch::new();
+        assert_eq!(
+            small
+                .bfs_iter_with_state_in(CollectionState::ALL_ITEMS, &mut scratch)
+                .count(),
+            3
+        );
+        let graph = new_static_graph();
+        let lent = graph.bfs_iter_with_state_in(CollectionState::ALL_ITEMS, &mut scratch);
+        assert_eq!(lent.core.visited.len(), VISITED_BITFIELD_LEN);
+        assert_eq!(
+            lent.count(),
+            graph
+                .bfs_iter_with_state(CollectionState::ALL_ITEMS)
+                .count()
+        );
+    }
+
+    #[test]
+    fn stamped_visited() {
+        let graph = new_static_graph();
+        let mut stamped: StampedVisited = StampedVisited::new();
+        for state in crate::bench_support::state_changes() {
+            let mut dfs_iter = graph.dfs_iter_with_visited(state.clone(), stamped);
+            let reached: Vec<u16> = dfs_iter.by_ref().map(u16::from).collect();
+            assert_eq!(
+                reached,
+                graph
+                    .dfs_iter_with_state(state.clone())
+                    .map(u16::from)
+                    .collect::<Vec<_>>()
+            );
+            stamped = dfs_iter.core.visited;
+            assert!(reached.iter().all(|&n| stamped.check_visited(n)));
+
+            let mut bfs_iter = graph.bfs_iter_with_visited(state.clone(), stamped);
+            assert_eq!(bfs_iter.by_ref().count(), reached.len());
+            stamped = bfs_iter.core.visited;
+        }
+
+        // Wrapping around to the first generation again forgets every old stamp.
+        let mut stamped = StampedVisited::<64>::new();
+        stamped.generation = u16::MAX - 1;
+        stamped.mark_visited(3);
+        stamped.reset();
+        stamped.mark_visited(4);
+        stamped.reset();
+        assert_eq!(stamped.generation, 1);
+        assert!(!stamped.check_visited(3) && !stamped.check_visited(4));
+        assert!(!stamped.test_set_visited(4));
+        assert!(stamped.test_set_visited(4));
+    }
+
+    #[test]
+    fn requirement_trees() {
+        let graph = new_static_graph();
+        let states = [
+            CollectionState::default(),
+            CollectionState::ALL_ITEMS,
+            CollectionState {
+                hammer: false,
+                ..CollectionState::default()
+            },
+        ];
+        let mut roots: Vec<ReqIndex> = graph
+            .edges()
+            .map(|(_, e, _)| graph.requirement_root(e))
+            .collect();
+        roots.sort_unstable();
+        roots.dedup();
+        for &root in roots.iter() {
+            let tree = graph.requirement_tree(root);
+            let nodes: Vec<_> = tree.iter().collect();
+            assert_eq!(nodes[0], tree.root());
+            // Every link stays inside the tree and every node is listed once.
+            for node in nodes.iter() {
+                assert!(node
+                    .and
+                    .into_iter()
+                    .chain(node.or)
+                    .all(|i| nodes.iter().any(|n| n.index == i)));
+                assert_eq!(nodes.iter().filter(|n| n.index == node.index).count(), 1);
+            }
+            // Following the view's links evaluates the same as the evaluator does.
+            for state in states.iter() {
+                let mut node = tree.root();
+                let satisfied = loop {
+                    let next = match state.eval_requirement(node.req) {
+                        true => node.and.ok_or(true),
+                        false => node.or.ok_or(false),
+                    };
+                    match next {
+                        Ok(idx) => node = tree.node(idx),
+                        Err(satisfied) => break satisfied,
+                    }
+                };
+                assert_eq!(satisfied, state.eval_logic_tree(root));
+            }
+        }
+        let swordless = crate::parser::parse_requirement("swordless")
+            .unwrap()
+            .tree()
+            .unwrap();
+        assert_eq!(
+            graph.requirement_tree(swordless).requirements(),
+            [Requirement::Setting(SettingFlag::Swordless)]
+        );
+        assert_eq!(graph.requirement_root(1), graph.edge_data(1).req);
+
+        let hover = graph.trick("HOVER").unwrap();
+        assert_eq!(graph.tricks()[1], "hover");
+        assert!(graph.trick("moon_jump").is_none());
+        let trick_edges: Vec<u16> = graph.trick_edges(hover).collect();
+        assert!(!trick_edges.is_empty());
+        assert!((1..=graph.edge_count)
+            .all(|e| graph.edge_data(e).tricks.intersects(hover) == trick_edges.contains(&e)));
+    }
+
+    #[test]
+    fn lock_dead_edges() {
+        // The example model doesn't use settings, so put one on the first edge we can take.
+        let mut builder = new_static_graph().to_builder();
+        let to = builder.node(1).unwrap().edges[0].0;
+        let swordless = crate::parser::parse_requirement("swordless")
+            .unwrap()
+            .tree()
+            .unwrap();
+        builder.edge_mut(1, to).unwrap().req = swordless;
+        let original: GenGraph = builder.clone().freeze().unwrap();
+        let locked_reqs = |graph: &GenGraph| {
+            graph
+                .edges()
+                .filter(|&(_, e, _)| graph.edge_data(e).req == ReqIndex::LOCKED)
+                .count()
+        };
+
+        let mut graph: GenGraph = builder.freeze().unwrap();
+        assert_eq!(graph.lock_dead_edges(TierMask::ALL, SettingsState::ALL), 0);
+        let locked = graph.lock_dead_edges(TierMask::NORMAL, SettingsState::NONE);
+        assert_eq!(locked, 1);
+        assert_eq!(graph.edges().next(), Some((1, 1, to)));
+        assert_eq!(graph.edge_data(1).req, ReqIndex::LOCKED);
+        assert_eq!(locked_reqs(&graph), locked_reqs(&original) + 1);
+        assert_eq!(
+            graph.lock_dead_edges(TierMask::NORMAL, SettingsState::NONE),
+            0
+        );
+        // Nothing reachable without the setting changes.
+        for state in [CollectionState::default(), CollectionState::ALL_ITEMS] {
+            assert_eq!(
+                graph.dfs_iter_with_state(state.clone()).count(),
+                original.dfs_iter_with_state(state).count()
+            );
+        }
+
+        // Dropping the locked edges keeps the search the same with fewer edges.
+        let mut builder = graph.to_builder();
+        assert_eq!(builder.remove_locked_edges(), locked_reqs(&graph));
+        let lean: GenGraph = builder.freeze().unwrap();
+        assert!(lean.edge_count() < graph.edge_count());
+        assert_eq!(
+            lean.dfs_iter_with_state(CollectionState::ALL_ITEMS).count(),
+            graph
+                .dfs_iter_with_state(CollectionState::ALL_ITEMS)
+                .count()
+        );
+
+        // An entrance that closes once Agahnim is beaten is false with everything, but it's open
+        // before then so it isn't dead.
+        let mut builder = new_static_graph().to_builder();
+        builder.edge_mut(1, to).unwrap().req = ReqIndex::new(15).unwrap();
+        let mut graph: GenGraph = builder.freeze().unwrap();
+        assert!(!graph.is_monotone());
+        assert_eq!(graph.lock_dead_edges(TierMask::ALL, SettingsState::ALL), 0);
+    }
+
+    #[test]
+    fn batch_reachability() {
+        use crate::reachability::Reachability;
+        let graph = new_static_graph_open();
+        let locations = [1, 2, NUM_VERTICES as u16, NUM_VERTICES as u16 + 1];
+        let reachable = Reachability::batch(&graph, &CollectionState::default(), &locations);
+        assert_eq!(reachable.len(), 4);
+        assert_eq!(
+            reachable.iter().collect::<Vec<_>>(),
+            [true, true, true, false]
+        );
+    }
+
+    #[test]
+    fn iterator_size_hint() {
+        let graph = new_static_graph_open();
+        let mut dfs_iter = graph.dfs_iter();
+        assert_eq!(dfs_iter.size_hint(), (1, Some(NUM_VERTICES)));
+        dfs_iter.next();
+        let (lower, upper) = dfs_iter.size_hint();
+        assert!(lower >= 1 && upper == Some(NUM_VERTICES - 1));
+        assert_eq!(dfs_iter.by_ref().count(), NUM_VERTICES - 1);
+        assert_eq!(dfs_iter.size_hint(), (0, Some(0)));
+        assert_eq!(dfs_iter.next(), None);
+        assert_eq!(dfs_iter.next(), None);
+
+        let mut bfs_iter = graph.bfs_iter();
+        assert_eq!(bfs_iter.by_ref().count(), NUM_VERTICES);
+        assert_eq!(bfs_iter.size_hint(), (0, Some(0)));
+        assert_eq!(bfs_iter.next(), None);
+    }
+
+    #[test]
+    fn astar_shortest_hops() {
+        use crate::astar_iter::hop_distance_heuristic;
+        let graph = new_static_graph_open();
+        let goal = NUM_VERTICES as u16 - 7;
+        let heuristic = hop_distance_heuristic(&graph, goal);
+        let mut astar_iter = graph.astar_iter(&heuristic);
+        assert!(astar_iter.search(goal));
+        assert_eq!(astar_iter.cost(goal), Some(heuristic[1]));
+
+        let mut bfs_iter = graph.bfs_iter();
+        assert!(bfs_iter.search(goal));
+        assert!(astar_iter.core.visited_count < bfs_iter.core.visited_count);
+    }
+
+    #[test]
+    fn depth_limited_dfs() {
+        use crate::astar_iter::hop_distance_heuristic;
+        use crate::dfs_iter::iddfs;
+        let graph = new_static_graph_open();
+        assert_eq!(graph.dfs_iter_depth_limited(1, 0).count(), 1);
+        // With no heuristic A* yields every node along with its fewest hops from the root.
+        let no_heuristic = [0u16; NUM_VERTICES_PADDED];
+        let mut astar_iter = graph.astar_iter(&no_heuristic);
+        astar_iter.by_ref().for_each(drop);
+        let expected = (1..=NUM_VERTICES as u16)
+            .filter(|&n| astar_iter.cost(n).is_some_and(|c| c <= 2))
+            .count();
+        assert_eq!(graph.dfs_iter_depth_limited(1, 2).count(), expected);
+
+        let goal = 777;
+        let hops = hop_distance_heuristic(&graph, goal)[1];
+        let state = CollectionState::default();
+        assert_eq!(iddfs(&graph, &state, 1, goal, hops), Some(hops));
+        assert_eq!(iddfs(&graph, &state, 1, goal, hops - 1), None);
+    }
+
     #[test]
     fn test_connected_bfs() {
         let mut node_set = HashSet::with_capacity(NUM_VERTICES);
         let mut graph = new_static_graph_open();
-        graph.edge_data = Box::new([0u16; NUM_EDGES_PADDED]); // No logic
+        graph.edge_data = Arc::new([ReqIndex::OPEN; NUM_EDGES_PADDED]); // No logic
         let mut bfs_iter = graph.bfs_iter();
         for _ in 1..=NUM_VERTICES {
             let next_node = bfs_iter.next();
@@ -423,6 +2456,211 @@ mod tests {
         assert_eq!(None, bfs_iter.next());
     }
 
+    #[test]
+    fn sized_frontiers() {
+        let graph = new_static_graph();
+        let state = CollectionState::default();
+        let expected = graph.dfs_iter_with_state(state.clone()).count();
+        assert_eq!(
+            graph
+                .dfs_iter_sized::<{ SEARCH_STACK_SIZE * 2 }>(state.clone())
+                .count(),
+            expected
+        );
+        assert_eq!(
+            graph
+                .bfs_iter_sized::<{ SEARCH_QUEUE_SIZE * 2 }>(state.clone())
+                .count(),
+            expected
+        );
+
+        // A graph much smaller than the generated one gets by with tiny frontiers.
+        let small = StaticGraph::<NUM_VERTICES_PADDED, NUM_EDGES_PADDED>::from_edge_list([
+            "1 2", "1 3", "2 4", "3 4", "4 5",
+        ])
+        .unwrap();
+        assert_eq!(small.dfs_iter_sized::<4>(state.clone()).count(), 5);
+        assert_eq!(small.bfs_iter_sized::<4>(state).count(), 5);
+    }
+
+    #[test]
+    fn access_from_masks() {
+        let graph = new_static_graph();
+        let runtime_masks = graph.req_masks();
+        let states = [
+            CollectionState::default(),
+            CollectionState::NOTHING,
+            CollectionState {
+                boots: true,
+                hammer: true,
+                gloves: true,
+                flute: true,
+                tiers: TierMask::NORMAL,
+                settings: SettingsState::NONE,
+                events: EventFlags::NONE,
+            },
+        ];
+        for state in states {
+            // Every approach goes through a memo, so check it against walking each edge's tree.
+            let memo = graph.req_memo(&state);
+            let mut expected = AccessCache::<ACCESS_BITFIELD_LEN>::new();
+            expected.evaluate_logical_access(&graph.edge_data, &memo);
+            let mut from_masks = AccessCache::<ACCESS_BITFIELD_LEN>::new();
+            from_masks.rebuild_from_masks(static_graph_req_masks(), &memo);
+            assert_eq!(*expected, *from_masks);
+            from_masks.clear();
+            from_masks.rebuild_from_masks(&runtime_masks, &memo);
+            assert_eq!(*expected, *from_masks);
+            for edge in 1..=graph.edge_count() {
+                let req = graph.edge_data[edge as usize];
+                assert_eq!(memo.eval_logic_tree(req), state.eval_logic_tree(req));
+                assert_eq!(expected.check_access(edge), state.eval_logic_tree(req));
+            }
+        }
+    }
+
+    #[test]
+    fn lazy_logic() {
+        let graph = new_static_graph();
+        let eager: HashSet<u16> = graph.bfs_iter().map(u16::from).collect();
+        let lazy: HashSet<u16> = graph.bfs_iter_lazy().map(u16::from).collect();
+        assert_eq!(eager, lazy);
+        let lazy_dfs: HashSet<u16> = graph.dfs_iter_lazy().map(u16::from).collect();
+        assert_eq!(eager, lazy_dfs);
+
+        // A short search only evaluates the edges it touched, and agrees with the eager result
+        // on every one of them.
+        let mut eager_iter = graph.bfs_iter();
+        eager_iter.by_ref().for_each(drop);
+        let mut lazy_iter = graph.bfs_iter_lazy();
+        lazy_iter.by_ref().take(50).for_each(drop);
+        let core = &lazy_iter.core;
+        let evaluated = (0..NUM_EDGES as u16)
+            .filter(|&e| core.edge_evaluated.check_access(e))
+            .inspect(|&e| {
+                assert_eq!(
+                    core.edge_access.check_access(e),
+                    eager_iter.core.edge_access.check_access(e)
+                )
+            })
+            .count();
+        assert!(evaluated > 0 && evaluated < NUM_EDGES / 10);
+    }
+
+    #[test]
+    fn validate_pointers() {
+        let mut graph = new_static_graph();
+        assert!(graph.is_validated());
+        let mut zeroed: StaticGraph<NUM_VERTICES_PADDED, NUM_EDGES_PADDED> =
+            StaticGraph::with_capacity_hint(0, 0).unwrap();
+        assert!(!zeroed.is_validated());
+        assert_eq!(zeroed.validate(), Ok(()));
+
+        // A pointer past the last edge fails, and lookups go back to being bounds-checked.
+        let (edges, _) = graph.get_neighbors_out(NonZeroU16::new(5));
+        let degree = edges.len();
+        graph.node_pointers.0[6] = NonZeroU16::new(u16::MAX).unwrap();
+        assert_eq!(graph.validate(), Err(GraphError::BadNodePointer(6)));
+        assert!(!graph.is_validated());
+        assert!(graph.get_neighbors_out(NonZeroU16::new(5)).0.len() > degree);
+        assert!(graph.get_neighbors_out(NonZeroU16::new(6)).0.is_empty());
+
+        let mut graph = new_static_graph();
+        graph.edge_pointers.0[3] = NonZeroU16::new(graph.node_count + 1).unwrap();
+        assert_eq!(graph.validate(), Err(GraphError::BadEdgePointer(3)));
+        graph.edge_pointers.0[3] = NonZeroU16::new(1).unwrap();
+        assert_eq!(graph.validate(), Ok(()));
+    }
+
+    #[test]
+    fn shared_graph_across_threads() {
+        let graph = new_static_graph();
+        let states: Vec<CollectionState> = crate::bench_support::state_changes();
+        let expected: Vec<usize> = states
+            .iter()
+            .map(|s| graph.bfs_iter_with_state(s.clone()).count())
+            .collect();
+        // Half the threads start their own walkers, the rest are handed one we started here.
+        let counts: Vec<usize> = std::thread::scope(|scope| {
+            let handles: Vec<_> = states
+                .iter()
+                .enumerate()
+                .map(|(i, state)| {
+                    let graph = &graph;
+                    let mut dfs_iter = graph.dfs_iter_with_state(state.clone());
+                    let first = dfs_iter.next().map_or(0, |_| 1);
+                    let state = state.clone();
+                    scope.spawn(move || match i % 2 {
+                        0 => graph.bfs_iter_with_state(state).count(),
+                        _ => first + dfs_iter.count(),
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert_eq!(counts, expected);
+    }
+
+    #[test]
+    fn cloned_iterators() {
+        let graph = new_static_graph();
+        let mut dfs_iter = graph.dfs_iter();
+        dfs_iter.by_ref().take(100).for_each(drop);
+        let branch = dfs_iter.clone();
+        let rest: Vec<u16> = branch.map(u16::from).collect();
+        assert_eq!(rest, dfs_iter.by_ref().map(u16::from).collect::<Vec<u16>>());
+
+        let mut bfs_iter = graph.bfs_iter();
+        bfs_iter.by_ref().take(100).for_each(drop);
+        let mut branch = bfs_iter.clone();
+        // Changing the clone's caches leaves the original alone.
+        branch.core.visited.fill(0);
+        branch.core.edge_access.clear();
+        assert!(!branch.core.visited.check_visited(1));
+        assert!(bfs_iter.core.visited.check_visited(1));
+        assert!(bfs_iter.core.edge_access.iter().any(|&w| w != 0));
+        assert_eq!(branch.search_queue.len(), bfs_iter.search_queue.len());
+    }
+
+    #[cfg(feature = "macros")]
+    #[test]
+    fn static_graph_macro() {
+        const MODEL: ModelTables = crate::static_graph! { include = "model/example.model" };
+        let graph = StaticGraph::<NUM_VERTICES_PADDED, NUM_EDGES_PADDED>::from_tables(&MODEL);
+        assert_eq!((graph.node_count, graph.edge_count), (12, 15));
+        assert_eq!(graph.place_data(1).unwrap().name, "link_house");
+        assert_eq!(graph.door_data(6).unwrap().flags, DoorFlags::BIG_KEY);
+        assert_eq!(graph.item_data(11).unwrap().vanilla_item, Item::Flute);
+        assert_eq!(graph.region_of[7], 3);
+
+        // Everything but death mountain without gloves, which the default state doesn't have.
+        let mut reachable: Vec<u16> = graph.dfs_iter().map(u16::from).collect();
+        reachable.sort_unstable();
+        assert_eq!(reachable, (1..=9).collect::<Vec<u16>>());
+        assert_eq!(
+            graph
+                .dfs_iter_with_state(CollectionState::ALL_ITEMS)
+                .count(),
+            12
+        );
+
+        let (edges, offset) = graph.get_neighbors_out(NonZeroU16::new(2));
+        assert_eq!(edges.len(), 4);
+        let warp = graph.edge_data(offset + 3);
+        assert_eq!((warp.kind, warp.weight), (EdgeType::Warp, 1));
+        assert_eq!(graph.edge_data(offset + 1).kind, EdgeType::Door);
+        assert_eq!(graph.edge_data(offset + 1).weight, 2);
+
+        // Inline models work the same way.
+        const INLINE: ModelTables = crate::static_graph! {
+            model = "place a 1
+                     item b 1 rupees
+                     edge a b"
+        };
+        let graph = StaticGraph::<NUM_VERTICES_PADDED, NUM_EDGES_PADDED>::from_tables(&INLINE);
+        assert_eq!(graph.dfs_iter().count(), 2);
+    }
+
     // cargo +nightly test -- --nocapture > output_file
     //#[test]
     //fn manual_test() {
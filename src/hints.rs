@@ -0,0 +1,156 @@
+// Classifying regions for in-game hints, from how a placement's items get the player to the goal.
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{
+    graph::StaticGraph,
+    logic::{CollectionState, Item},
+    region::Region,
+    solver::FixedPoint,
+};
+
+/// The usual randomizer hint categories for a region.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HintKind {
+    /// The region holds an item that every way to the goal needs, often called "way of the hero".
+    Required,
+    /// The region holds progression we pick up on the way to the goal, but nothing we couldn't
+    /// do without.
+    Useful,
+    /// Nothing in the region helps, so players can skip it.
+    Barren,
+}
+
+impl fmt::Display for HintKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HintKind::Required => write!(f, "required"),
+            HintKind::Useful => write!(f, "useful"),
+            HintKind::Barren => write!(f, "barren"),
+        }
+    }
+}
+
+/// Label every region with item locations for hints, given `placements` as `(location, item)`
+/// and the node the player has to get to. Returns `None` if the placement can't reach the goal
+/// from `state` at all.
+///
+/// We play the seed through once, collecting everything we can reach until nothing changes, and
+/// a region is useful if we collected progression there. Then we take each of those placements
+/// away in turn and play through again: if the goal is out of reach without it the item is on
+/// every path to the goal and its region is required. Items with duplicates elsewhere are
+/// rarely required on their own, which is what hints want.
+pub fn classify_regions<const M: usize, const N: usize, T>(
+    graph: &StaticGraph<M, N, T>,
+    state: &CollectionState,
+    placements: &[(u16, Item)],
+    goal: u16,
+) -> Option<Vec<(Region, HintKind)>> {
+    let closure = FixedPoint::new().with_items(placements).solve(graph, state);
+    if !closure.is_reachable(goal) {
+        return None;
+    }
+    let collected: Vec<(u16, Item)> = placements
+        .iter()
+        .copied()
+        .filter(|&(l, item)| item.is_progression() && closure.is_reachable(l))
+        .collect();
+    let required: Vec<u16> = collected
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| {
+            let without: Vec<(u16, Item)> = collected
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .map(|(_, &p)| p)
+                .collect();
+            !FixedPoint::new()
+                .with_items(&without)
+                .solve(graph, state)
+                .is_reachable(goal)
+        })
+        .map(|(_, &(l, _))| l)
+        .collect();
+
+    let hints = Region::ALL
+        .into_iter()
+        .filter(|&region| {
+            graph
+                .nodes_in_region(region)
+                .any(|n| graph.item_data(n).is_some())
+        })
+        .map(|region| {
+            let in_region = |&l: &u16| graph.region_of(l) == Some(region);
+            let kind = match (
+                required.iter().any(in_region),
+                collected.iter().map(|(l, _)| l).any(in_region),
+            ) {
+                (true, _) => HintKind::Required,
+                (false, true) => HintKind::Useful,
+                (false, false) => HintKind::Barren,
+            };
+            (region, kind)
+        })
+        .collect();
+
+    Some(hints)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bench_support::{assumed_fill, item_pool},
+        graph::new_static_graph,
+        logic::{EventFlags, SettingsState, TierMask},
+    };
+
+    #[test]
+    fn region_hints() {
+        let graph = new_static_graph();
+        let nothing = CollectionState::NOTHING;
+        // One of each progression item, and a goal behind the gloves.
+        let placements = assumed_fill(&graph, &item_pool(8), 4).unwrap();
+        let without_gloves: Vec<u16> = graph
+            .dfs_iter_with_state(CollectionState::default())
+            .map(u16::from)
+            .collect();
+        let goal = graph
+            .dfs_iter_with_state(CollectionState::ALL_ITEMS)
+            .map(u16::from)
+            .find(|n| !without_gloves.contains(n))
+            .unwrap();
+
+        let hints = classify_regions(&graph, &nothing, &placements, goal).unwrap();
+        let kind_of = |location: u16| {
+            let region = graph.region_of(location).unwrap();
+            hints.iter().find(|&&(r, _)| r == region).unwrap().1
+        };
+        let gloves = placements
+            .iter()
+            .find(|&&(_, i)| i == Item::Gloves)
+            .unwrap()
+            .0;
+        assert_eq!(kind_of(gloves), HintKind::Required);
+        // Every progression item gets collected in a completable fill.
+        assert!(placements
+            .iter()
+            .filter(|&&(_, i)| i.is_progression())
+            .all(|&(l, _)| kind_of(l) != HintKind::Barren));
+        assert!(hints
+            .iter()
+            .filter(|&&(_, kind)| kind == HintKind::Barren)
+            .all(|&(region, _)| placements
+                .iter()
+                .all(|&(l, i)| !i.is_progression() || graph.region_of(l) != Some(region))));
+
+        // Without the gloves the goal is out of reach.
+        let no_gloves: Vec<(u16, Item)> = placements
+            .iter()
+            .copied()
+            .filter(|&(_, i)| i != Item::Gloves)
+            .collect();
+        assert_eq!(classify_regions(&graph, &nothing, &no_gloves, goal), None);
+    }
+}
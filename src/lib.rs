@@ -4,14 +4,71 @@
 #![allow(dead_code)]
 
 extern crate alloc;
+// Parallel placement needs threads, so it pulls in std.
+#[cfg(all(feature = "parallel", not(test)))]
+extern crate std;
+// Code generated by `static_graph!` refers to us by name, including in our own tests.
+#[cfg(all(feature = "macros", test))]
+extern crate self as sparsegraph;
 
+// Declared first so the instrumentation macros are visible to every other module.
+#[macro_use]
+mod trace;
+
+pub mod analysis;
+pub mod arena;
+pub mod astar_iter;
+pub mod bench_support;
 pub mod bfs_iter;
+pub mod builder;
+pub mod bytes;
+pub mod certificate;
+pub mod closure;
+pub mod codegen;
+pub mod compat;
 pub mod constants;
+pub mod determinism;
 pub mod dfs_iter;
+pub mod diff;
+pub mod door;
+pub mod edge_iter;
+pub mod edge_list;
+pub mod export;
+pub mod facade;
+pub mod flow;
 pub mod gen;
 pub mod graph;
+pub mod hints;
 pub mod logic;
+pub mod overlay;
+#[cfg(feature = "parallel")]
+pub mod par_bfs;
+pub mod parser;
+pub mod placement;
+pub mod priority_iter;
+mod raw;
+pub mod reachability;
+pub mod region;
+mod rng;
+pub mod scratch;
+pub mod solver;
+pub mod tracker;
+pub mod traversal;
+mod types;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod zero_one_iter;
 
+pub use astar_iter::*;
 pub use bfs_iter::*;
 pub use dfs_iter::*;
+pub use edge_iter::*;
+pub use facade::*;
 pub use graph::*;
+pub use priority_iter::*;
+pub use reachability::*;
+pub use region::*;
+#[cfg(feature = "macros")]
+pub use sparsegraph_macros::static_graph;
+pub use traversal::*;
+pub use zero_one_iter::*;
@@ -1,18 +1,107 @@
-use core::{num::NonZeroU16, ops::Index};
+use alloc::{collections::BTreeMap, string::String, vec, vec::Vec};
+use core::{fmt, num::NonZeroU16, ops::Index};
 
-// Also See: DfsIter's/BfsIter's eval_logic_tree, eval_requirement, and evaluate_logical_access
-// methods.
+pub use crate::types::{
+    EventId, Item, Negatable, ReqArray, ReqBuilder, Requirement, RequirementNode, SettingFlag,
+    Tier, REQ_CONTAINER_LEN, REQ_TREES,
+};
+
+// Also See: AccessCache's evaluate_logical_access method.
 
 /// Data structure modeling collection state. We could back this with a bitfield or something
 /// more efficient for many cases instead of effectively an array of bools, but this is sufficient
 /// for a demonstration. Generally speaking, a bitfield test is more expensive than a bool test and
 /// the difference is made up by whether we save time with cache vs memory access. So as usual, we
 /// would need to measure here.
+///
+/// The state also carries which logic tiers are enabled, the seed's settings and which events
+/// have happened, since every search that evaluates logic already takes one. None of them are
+/// collected but that keeps them per session like everything else here.
+#[derive(Clone, Debug)]
 pub struct CollectionState {
     pub boots: bool,
     pub hammer: bool,
     pub gloves: bool,
     pub flute: bool,
+    pub tiers: TierMask,
+    pub settings: SettingsState,
+    pub events: EventFlags,
+}
+
+/// The game mode flags a seed was generated with. See `SettingFlag`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SettingsState(u8);
+
+impl SettingsState {
+    pub const NONE: SettingsState = SettingsState(0);
+    pub const ALL: SettingsState = SettingsState((1 << SettingFlag::ALL.len()) - 1);
+
+    pub const fn has(&self, flag: SettingFlag) -> bool {
+        self.0 & (1 << flag as u8) != 0
+    }
+
+    /// The same settings with `flag` turned on or off.
+    pub const fn with(self, flag: SettingFlag, enabled: bool) -> SettingsState {
+        match enabled {
+            true => SettingsState(self.0 | 1 << flag as u8),
+            false => SettingsState(self.0 & !(1 << flag as u8)),
+        }
+    }
+}
+
+/// A set of enabled logic tiers. `Normal` is always in it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TierMask(u8);
+
+impl TierMask {
+    pub const NORMAL: TierMask = TierMask(1 << Tier::Normal as u8);
+    pub const ALL: TierMask = TierMask((1 << Tier::ALL.len()) - 1);
+
+    pub const fn contains(&self, tier: Tier) -> bool {
+        self.0 & (1 << tier as u8) != 0
+    }
+
+    /// The same mask with `tier` enabled.
+    pub const fn with(self, tier: Tier) -> TierMask {
+        TierMask(self.0 | 1 << tier as u8)
+    }
+
+    /// The same mask with `tier` disabled, unless it's `Normal`.
+    pub const fn without(self, tier: Tier) -> TierMask {
+        match tier {
+            Tier::Normal => self,
+            _ => TierMask(self.0 & !(1 << tier as u8)),
+        }
+    }
+
+    /// Every tier up to and including `tier`, the usual way difficulty settings stack.
+    pub const fn up_to(tier: Tier) -> TierMask {
+        TierMask((1 << (tier as u8 + 1)) - 1)
+    }
+}
+
+impl Default for TierMask {
+    fn default() -> Self {
+        TierMask::NORMAL
+    }
+}
+
+/// The events that have happened. See `EventId`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct EventFlags(u8);
+
+impl EventFlags {
+    pub const NONE: EventFlags = EventFlags(0);
+    pub const ALL: EventFlags = EventFlags((1 << EventId::ALL.len()) - 1);
+
+    pub const fn has(&self, event: EventId) -> bool {
+        self.0 & (1 << event as u8) != 0
+    }
+
+    /// The same flags with `event` happened.
+    pub const fn with(self, event: EventId) -> EventFlags {
+        EventFlags(self.0 | 1 << event as u8)
+    }
 }
 
 impl CollectionState {
@@ -22,62 +111,908 @@ impl CollectionState {
             gloves: false,
             hammer: true,
             flute: true,
+            tiers: TierMask::NORMAL,
+            settings: SettingsState::NONE,
+            events: EventFlags::NONE,
+        }
+    }
+
+    /// No items, no events, only normal logic and no settings, the state a seed starts from
+    /// before anything is collected.
+    pub const NOTHING: CollectionState = CollectionState {
+        boots: false,
+        gloves: false,
+        hammer: false,
+        flute: false,
+        tiers: TierMask::NORMAL,
+        settings: SettingsState::NONE,
+        events: EventFlags::NONE,
+    };
+
+    /// Every item collected, every tier and setting enabled and every event happened, e.g. for
+    /// validating that the graph is connected.
+    pub const ALL_ITEMS: CollectionState = CollectionState {
+        boots: true,
+        gloves: true,
+        hammer: true,
+        flute: true,
+        tiers: TierMask::ALL,
+        settings: SettingsState::ALL,
+        events: EventFlags::ALL,
+    };
+
+    /// Whether `other` has everything this state has: every item, tier, setting and event. Unless
+    /// logic negates something, whatever this state reaches `other` reaches too.
+    pub const fn is_subset_of(&self, other: &CollectionState) -> bool {
+        (!self.boots || other.boots)
+            && (!self.gloves || other.gloves)
+            && (!self.hammer || other.hammer)
+            && (!self.flute || other.flute)
+            && self.tiers.0 & !other.tiers.0 == 0
+            && self.settings.0 & !other.settings.0 == 0
+            && self.events.0 & !other.events.0 == 0
+    }
+
+    /// Returns whether `item` has been collected. Junk items never are since they have no effect
+    /// on logic.
+    pub const fn has(&self, item: Item) -> bool {
+        match item {
+            Item::Boots => self.boots,
+            Item::Gloves => self.gloves,
+            Item::Flute => self.flute,
+            Item::Hammer => self.hammer,
+            Item::Rupees => false,
+        }
+    }
+
+    /// Mark `item` collected or not. Returns whether the state changed.
+    pub fn set(&mut self, item: Item, collected: bool) -> bool {
+        let field = match item {
+            Item::Boots => &mut self.boots,
+            Item::Gloves => &mut self.gloves,
+            Item::Flute => &mut self.flute,
+            Item::Hammer => &mut self.hammer,
+            Item::Rupees => return false,
+        };
+        let changed = *field != collected;
+        *field = collected;
+
+        changed
+    }
+
+    /// The same evaluation as `EvaluateLogic::eval_logic_tree` but usable during const
+    /// evaluation, so access for a fixed state can be computed at compile time. See
+    /// `graph::evaluate_access_const`.
+    pub const fn eval_logic_tree_const(&self, mut req_index: ReqIndex) -> bool {
+        loop {
+            let req_node = &REQ_TREES.0[req_index.0 as usize];
+            let satisfied = self.eval_requirement_const(req_node.req);
+            let next = match satisfied {
+                true => req_node.and,
+                false => req_node.or,
+            };
+            match next {
+                Some(n) => req_index = ReqIndex(n.get()),
+                None => break satisfied,
+            }
+        }
+    }
+
+    const fn eval_requirement_const(&self, req: Requirement) -> bool {
+        match req {
+            Requirement::Open => true,
+            Requirement::Boots => self.boots,
+            Requirement::Gloves => self.gloves,
+            Requirement::Flute => self.flute,
+            Requirement::Hammer => self.hammer,
+            Requirement::Locked => false,
+            Requirement::Tier(tier) => self.tiers.contains(tier),
+            Requirement::Setting(flag) => self.settings.has(flag),
+            Requirement::Event(event) => self.events.has(event),
+            Requirement::Not(negated) => !self.eval_requirement_const(negated.requirement()),
+        }
+    }
+}
+
+/// Logic evaluation shared by everything that holds a collection state, including the state
+/// itself and our graph walkers, so each of them doesn't need its own copy of the evaluator.
+pub trait EvaluateLogic {
+    fn collection_state(&self) -> &CollectionState;
+
+    /// To evaluate our logic expressions we recursively evaluate the conditions one holds. If the
+    /// condition is false, we check for an OR child and repeat if present or return `false` if not
+    /// (ideally short-circuiting as soon as possible.) If the condition is true, we check for an
+    /// AND child and repeat if present or return `true` if not. Eventually we reach a node whose
+    /// evaluation gives us our final true or false. This function takes the root node of a tree
+    /// and proceeds as such.
+    fn eval_logic_tree(&self, req_index: ReqIndex) -> bool {
+        self.eval_logic_tree_in(&REQ_CONTAINER.0, req_index)
+    }
+
+    /// Evaluate a tree the same way from another array of trees, e.g. one a graph built from an
+    /// edge list brings along. See `StaticGraph::req_trees`.
+    fn eval_logic_tree_in(&self, trees: &[RequirementNode], mut req_index: ReqIndex) -> bool {
+        let mut req_node: RequirementNode;
+        loop {
+            req_node = trees[req_index.0 as usize];
+            match self.eval_requirement(req_node.req) {
+                true => match req_node.and {
+                    Some(n) => req_index = ReqIndex::link(n),
+                    None => break true,
+                },
+                false => match req_node.or {
+                    Some(n) => req_index = ReqIndex::link(n),
+                    None => break false,
+                },
+            }
+        }
+    }
+
+    /// Our logic evaluator. Here we're merely checking collection state, but the graph walking
+    /// data structure that solves for reachability etc will also implement more complex methods
+    /// that will run their own graph operations with a shared reference to the graph we're working
+    /// with.
+    fn eval_requirement(&self, req: Requirement) -> bool {
+        let state = self.collection_state();
+        match req {
+            Requirement::Open => true,
+            Requirement::Boots => state.boots,
+            Requirement::Gloves => state.gloves,
+            Requirement::Flute => state.flute,
+            Requirement::Hammer => state.hammer,
+            Requirement::Locked => false,
+            Requirement::Tier(tier) => state.tiers.contains(tier),
+            Requirement::Setting(flag) => state.settings.has(flag),
+            Requirement::Event(event) => state.events.has(event),
+            Requirement::Not(negated) => !self.eval_requirement(negated.requirement()),
+        }
+    }
+
+    /// Evaluate a tree from `trees` the same way, so anything that evaluates our binary trees can
+    /// evaluate n-ary ones too. See `NaryTrees`.
+    fn eval_nary(&self, trees: &NaryTrees, root: NaryIndex) -> bool {
+        let children = trees.children(root).iter();
+        match trees.node(root) {
+            NaryNode::Leaf(req) => self.eval_requirement(req),
+            NaryNode::All { .. } => children.copied().all(|c| self.eval_nary(trees, c)),
+            NaryNode::Any { .. } => children.copied().any(|c| self.eval_nary(trees, c)),
+        }
+    }
+}
+
+impl EvaluateLogic for CollectionState {
+    fn collection_state(&self) -> &CollectionState {
+        self
+    }
+}
+
+/// Every requirement tree evaluated once under one collection state. Lots of edges share a tree,
+/// so checking a whole edge array against this costs a lookup per edge instead of a tree walk.
+/// It evaluates logic itself, answering from the table, so it can stand in for whatever it was
+/// filled from.
+#[derive(Clone, Debug)]
+pub struct ReqMemo {
+    state: CollectionState,
+    satisfied: Vec<bool>,
+}
+
+impl ReqMemo {
+    /// Evaluate every tree in `REQ_CONTAINER`.
+    pub fn new(logic: &impl EvaluateLogic) -> Self {
+        let mut satisfied = vec![false; REQ_CONTAINER_LEN];
+        satisfied.iter_mut().enumerate().for_each(|(i, s)| {
+            // Every index here is in range of REQ_CONTAINER.
+            *s = logic.eval_logic_tree(ReqIndex(i as u16))
+        });
+
+        ReqMemo {
+            state: logic.collection_state().clone(),
+            satisfied,
+        }
+    }
+
+    /// Evaluate every tree in `trees` instead, e.g. a graph's. See `StaticGraph::req_memo`.
+    pub fn with_trees(logic: &impl EvaluateLogic, trees: &[RequirementNode]) -> Self {
+        let satisfied = (0..trees.len())
+            .map(|i| logic.eval_logic_tree_in(trees, ReqIndex(i as u16)))
+            .collect();
+
+        ReqMemo {
+            state: logic.collection_state().clone(),
+            satisfied,
+        }
+    }
+
+    pub fn get(&self, req: ReqIndex) -> bool {
+        self.satisfied[req.0 as usize]
+    }
+
+    /// Whether each tree is satisfied, indexed like the trees we evaluated.
+    pub fn satisfied(&self) -> &[bool] {
+        &self.satisfied
+    }
+}
+
+impl EvaluateLogic for ReqMemo {
+    fn collection_state(&self) -> &CollectionState {
+        &self.state
+    }
+
+    fn eval_logic_tree(&self, req_index: ReqIndex) -> bool {
+        self.get(req_index)
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Item::Boots => write!(f, "Boots"),
+            Item::Gloves => write!(f, "Gloves"),
+            Item::Flute => write!(f, "Flute"),
+            Item::Hammer => write!(f, "Hammer"),
+            Item::Rupees => write!(f, "Rupees"),
+        }
+    }
+}
+
+impl fmt::Display for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Requirement::Open => write!(f, "Open"),
+            Requirement::Boots => write!(f, "Boots"),
+            Requirement::Gloves => write!(f, "Gloves"),
+            Requirement::Flute => write!(f, "Flute"),
+            Requirement::Hammer => write!(f, "Hammer"),
+            Requirement::Locked => write!(f, "Locked"),
+            Requirement::Tier(tier) => write!(f, "{}", tier),
+            Requirement::Setting(flag) => write!(f, "{}", flag),
+            Requirement::Event(event) => write!(f, "{}", event),
+            Requirement::Not(negated) => write!(f, "!{}", negated.requirement()),
         }
     }
 }
 
-/// Edge traversal requirements that our graph walker is responsible for exhaustively implementing
-/// evaluation of. In the simplest case, these represent an item in collection state which we
-/// can quickly check for the presence of. But these can also check combinations including graph
-/// state and game configuration/settings state (e.g., we can program a CanWaterWalk requirement
-/// or check if we're dealing with OHKO mode.) Additionally, a production implementation would be
-/// slightly complicated by parameterized requirements (e.g. HasRupees(500), HasHealth(12))
+impl fmt::Display for Tier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Tier::Normal => write!(f, "Normal"),
+            Tier::Hard => write!(f, "Hard"),
+            Tier::Glitched => write!(f, "Glitched"),
+        }
+    }
+}
+
+impl fmt::Display for SettingFlag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingFlag::Swordless => write!(f, "Swordless"),
+            SettingFlag::Keysanity => write!(f, "Keysanity"),
+        }
+    }
+}
+
+impl fmt::Display for EventId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventId::Agahnim => write!(f, "Agahnim"),
+            EventId::CrystalSwitch => write!(f, "CrystalSwitch"),
+        }
+    }
+}
+
+/// A checked index to the root of a requirement tree in `REQ_CONTAINER`, or in a graph's own
+/// trees for a graph that brings some. See `StaticGraph::req_trees`. The only way to get one is
+/// through `ReqIndex::new`, `ReqIndex::new_in` (or the constants below) so a graph holding these
+/// can't point outside of its requirement array. Unlike the and/or links inside a tree this can't
+/// be a NonZero type since edges are allowed to use the open requirement at the 0th index.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct ReqIndex(u16);
+
+impl ReqIndex {
+    pub const OPEN: ReqIndex = ReqIndex(0);
+    pub const LOCKED: ReqIndex = ReqIndex(1);
+
+    /// Returns `None` if `idx` is out of range of `REQ_CONTAINER`.
+    pub const fn new(idx: u16) -> Option<Self> {
+        match (idx as usize) < REQ_CONTAINER_LEN {
+            true => Some(ReqIndex(idx)),
+            false => None,
+        }
+    }
+
+    /// Returns `None` if `idx` is out of range of `trees`.
+    pub fn new_in(idx: u16, trees: &[RequirementNode]) -> Option<Self> {
+        ((idx as usize) < trees.len()).then_some(ReqIndex(idx))
+    }
+
+    /// Check every raw requirement index in an array. This is a const fn so the generated edge
+    /// data can be validated at compile time, where it panics with the offending edge's index.
+    pub const fn checked_array<const N: usize>(raw: &[u16; N]) -> [ReqIndex; N] {
+        let mut checked = [ReqIndex::OPEN; N];
+        let mut i = 0;
+        while i < N {
+            checked[i] = match ReqIndex::new(raw[i]) {
+                Some(r) => r,
+                None => panic!("edge requirement index out of range of REQ_CONTAINER"),
+            };
+            i += 1;
+        }
+
+        checked
+    }
+
+    /// The index an and/or link inside a tree points to. Links are only ever created alongside
+    /// the tree they point into, so this doesn't need checking.
+    pub(crate) const fn link(idx: NonZeroU16) -> Self {
+        ReqIndex(idx.get())
+    }
+
+    pub const fn get(&self) -> u16 {
+        self.0
+    }
+}
+
+/// One node of a requirement tree with its links resolved to indexes. If `req` holds we go on to
+/// `and`, or the tree is satisfied if there's nothing there. Otherwise we go on to `or`, or the
+/// tree isn't satisfied.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ReqTreeNode {
+    pub index: ReqIndex,
+    pub req: Requirement,
+    pub and: Option<ReqIndex>,
+    pub or: Option<ReqIndex>,
+}
+
+/// The requirement tree rooted at one index, so tools can walk and display an edge's logic
+/// without knowing how `REQ_CONTAINER` encodes it. See `StaticGraph::requirement_tree`.
 ///
-/// We put Open and Locked to represent situations where traversal is always possible or we want to
-/// limit our graph operations to smaller subgraphs (e.g. single dungeons.) These are encoded here
-/// to hopefully avoid extra branches from encoding them as a separate enum higher in the main graph
-/// representation.
-#[derive(Copy, Clone)]
-#[repr(u16)]
-pub enum Requirement {
-    Open,
-    Boots,
-    Gloves,
-    Flute,
-    Hammer,
-    Locked,
-}
-
-/// Typically randomizers, whether they use a location list or graph world model, will encode their
-/// logical constraints as opaque functions that will take collection and world state as inputs.
-/// Our logic is modeled as plain text data which is transformed into simple tree-shaped and-or
-/// expressions that are evaluated transparently by our graph walker. They are then packed together
-/// into an array-like structure accessed by index where every edge only has to carry an index to
-/// a tree's root node.
+/// Trees share nodes, both with each other and within one tree where the AND and OR branches
+/// meet again, so iterating lists each node of this tree once even if it's linked from several
+/// places.
 ///
-/// This provides us a lot more flexibility across the board. We can "see" the requirements for
-/// any given path from one node to another (and reduce them to a simplified expression,) we can
-/// optimize similar and identical requirement trees by only including them in the backing
-/// structure once, and we can easily modify requirements, even allowing users to provide their
-/// own logic (encoded in plain text) to be placed into the backing structure and used at
-/// randomize time.
-#[derive(Copy, Clone)]
-#[repr(align(4))]
-pub struct RequirementNode {
-    pub req: Requirement,
-    pub and: Option<NonZeroU16>,
-    pub or: Option<NonZeroU16>,
+/// `Display` writes the tree the way `format` does.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ReqTreeView<'trees> {
+    trees: &'trees [RequirementNode],
+    root: ReqIndex,
+}
+
+impl ReqTreeView<'static> {
+    /// The tree rooted at `root` in `REQ_CONTAINER`.
+    pub fn new(root: ReqIndex) -> Self {
+        ReqTreeView::in_trees(&REQ_CONTAINER.0, root)
+    }
+}
+
+impl<'trees> ReqTreeView<'trees> {
+    /// The tree rooted at `root` in `trees`, e.g. a graph's.
+    pub const fn in_trees(trees: &'trees [RequirementNode], root: ReqIndex) -> Self {
+        ReqTreeView { trees, root }
+    }
+
+    pub fn root(&self) -> ReqTreeNode {
+        self.node(self.root)
+    }
+
+    /// Whether the tree is satisfied under `logic`.
+    pub fn eval(&self, logic: &impl EvaluateLogic) -> bool {
+        logic.eval_logic_tree_in(self.trees, self.root)
+    }
+
+    pub fn node(&self, idx: ReqIndex) -> ReqTreeNode {
+        let node = self.trees[idx.0 as usize];
+        ReqTreeNode {
+            index: idx,
+            req: node.req,
+            and: node.and.map(ReqIndex::link),
+            or: node.or.map(ReqIndex::link),
+        }
+    }
+
+    /// Every node of the tree, depth first from the root, following AND links before OR links.
+    pub fn iter(&self) -> ReqTreeIter<'trees> {
+        ReqTreeIter {
+            trees: self.trees,
+            stack: vec![self.root],
+            seen: Vec::new(),
+        }
+    }
+
+    /// Whether any node of the tree checks a negated requirement, which makes it non-monotone.
+    /// See `Requirement::Not`.
+    pub fn has_negation(&self) -> bool {
+        self.iter().any(|node| node.req.is_negated())
+    }
+
+    /// Every distinct requirement the tree checks, in the order `iter` first finds them.
+    pub fn requirements(&self) -> Vec<Requirement> {
+        let mut reqs = Vec::new();
+        for node in self.iter() {
+            if !reqs.contains(&node.req) {
+                reqs.push(node.req);
+            }
+        }
+
+        reqs
+    }
+}
+
+impl<'trees> IntoIterator for ReqTreeView<'trees> {
+    type Item = ReqTreeNode;
+    type IntoIter = ReqTreeIter<'trees>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl fmt::Display for ReqTreeView<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_requirement(f, self.trees, self.root)
+    }
+}
+
+/// Iterator over the nodes of a requirement tree. See `ReqTreeView::iter`.
+#[derive(Clone, Debug)]
+pub struct ReqTreeIter<'trees> {
+    trees: &'trees [RequirementNode],
+    stack: Vec<ReqIndex>,
+    // Trees are a handful of nodes, so a linear scan is plenty.
+    seen: Vec<ReqIndex>,
 }
 
-/// A generic newtype array wrapper that holds our RequirementNode trees.
+impl Iterator for ReqTreeIter<'_> {
+    type Item = ReqTreeNode;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(idx) = self.stack.pop() {
+            if self.seen.contains(&idx) {
+                continue;
+            }
+            self.seen.push(idx);
+            let node = ReqTreeView::in_trees(self.trees, idx).root();
+            self.stack.extend(node.or.into_iter().chain(node.and));
+            return Some(node);
+        }
+
+        None
+    }
+}
+
+/// An index to a node in an `NaryTrees`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[repr(transparent)]
-pub struct ReqArray<const N: usize>([RequirementNode; N]);
+pub struct NaryIndex(u16);
+
+impl NaryIndex {
+    pub const fn get(&self) -> u16 {
+        self.0
+    }
+}
+
+/// A node of an n-ary requirement tree. `All` and `Any` hold the range of their children in
+/// `NaryTrees`'s packed child array.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NaryNode {
+    Leaf(Requirement),
+    All { start: u16, len: u16 },
+    Any { start: u16, len: u16 },
+}
+
+/// Requirement logic as AND/OR nodes with any number of children, the way it's usually written
+/// for a real game. Our binary trees can only continue a node with one AND and one OR link, so
+/// something like `(boots & gloves) | (flute & hammer)` has to repeat nodes to fit, and bigger
+/// expressions repeat a lot more.
+///
+/// Nodes are interned as they're added so identical subtrees are stored once and the trees
+/// become a DAG, e.g. every expression mentioning `boots | hammer` shares one node for it.
+/// Children of every node are packed into one array and each node only holds its range in it.
+#[derive(Clone, Debug, Default)]
+pub struct NaryTrees {
+    nodes: Vec<NaryNode>,
+    children: Vec<NaryIndex>,
+    // Each node's kind and requirement or children, to find nodes we already have.
+    interned: BTreeMap<(u8, Vec<u16>), NaryIndex>,
+}
+
+impl NaryTrees {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of distinct nodes.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn node(&self, idx: NaryIndex) -> NaryNode {
+        self.nodes[idx.0 as usize]
+    }
+
+    /// The children of an `All` or `Any` node. Leaves have none.
+    pub fn children(&self, idx: NaryIndex) -> &[NaryIndex] {
+        match self.node(idx) {
+            NaryNode::Leaf(_) => &[],
+            NaryNode::All { start, len } | NaryNode::Any { start, len } => {
+                &self.children[start as usize..(start + len) as usize]
+            }
+        }
+    }
+
+    pub fn leaf(&mut self, req: Requirement) -> NaryIndex {
+        self.intern(0, &[req.id()], NaryNode::Leaf(req), &[])
+    }
+
+    /// A node satisfied when every one of `children` is. A single child is returned as is.
+    ///
+    /// Panics if `children` is empty.
+    pub fn all(&mut self, children: &[NaryIndex]) -> NaryIndex {
+        self.branch(1, children)
+    }
+
+    /// A node satisfied when any one of `children` is. A single child is returned as is.
+    ///
+    /// Panics if `children` is empty.
+    pub fn any(&mut self, children: &[NaryIndex]) -> NaryIndex {
+        self.branch(2, children)
+    }
+
+    fn branch(&mut self, kind: u8, children: &[NaryIndex]) -> NaryIndex {
+        assert!(!children.is_empty(), "a branch needs at least one child");
+        // Order doesn't change what a node means, so sorting lets `a & b` share with `b & a`.
+        let mut children = Vec::from(children);
+        children.sort_unstable();
+        children.dedup();
+        if let [child] = children[..] {
+            return child;
+        }
+        let (start, len) = (self.children.len() as u16, children.len() as u16);
+        let node = match kind {
+            1 => NaryNode::All { start, len },
+            _ => NaryNode::Any { start, len },
+        };
+        let key: Vec<u16> = children.iter().map(NaryIndex::get).collect();
+
+        self.intern(kind, &key, node, &children)
+    }
+
+    fn intern(
+        &mut self,
+        kind: u8,
+        key: &[u16],
+        node: NaryNode,
+        children: &[NaryIndex],
+    ) -> NaryIndex {
+        let next = NaryIndex(self.nodes.len() as u16);
+        let idx = *self.interned.entry((kind, Vec::from(key))).or_insert(next);
+        if idx == next {
+            self.nodes.push(node);
+            self.children.extend_from_slice(children);
+        }
+
+        idx
+    }
+}
+
+/// A set of requirements taken as met. Usually these are items, but a set also holds any
+/// tiers, settings or events a tree checks so it can describe everything the tree needs. See
+/// `satisfying_sets`.
+///
+/// A set never holds a negation. A negated requirement is met when the set doesn't hold the
+/// requirement it negates.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ItemSet(u16);
+
+impl ItemSet {
+    pub const EMPTY: ItemSet = ItemSet(0);
+
+    pub const fn contains(&self, req: Requirement) -> bool {
+        match req {
+            Requirement::Not(negated) => !self.contains(negated.requirement()),
+            req => self.0 & (1 << req.id()) != 0,
+        }
+    }
+
+    /// Panics if `req` is a negation.
+    pub fn insert(&mut self, req: Requirement) {
+        const { assert!(Requirement::COUNT <= u16::BITS as usize) };
+        assert!(!req.is_negated(), "a set can't hold a negation");
+        self.0 |= 1 << req.id();
+    }
+
+    pub const fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn is_subset(&self, other: ItemSet) -> bool {
+        self.0 & other.0 == self.0
+    }
+
+    /// The requirements in the set in order of `Requirement::id`.
+    pub fn iter(&self) -> impl Iterator<Item = Requirement> + '_ {
+        (0..Requirement::COUNT as u16)
+            .filter(|&id| self.0 & (1 << id) != 0)
+            .filter_map(Requirement::from_id)
+    }
+
+    /// Just the items in the set.
+    pub fn items(&self) -> impl Iterator<Item = Item> + '_ {
+        self.iter().filter_map(|req| req.item())
+    }
+
+    /// Whether the tree rooted at `req` is satisfied when exactly the requirements in the set
+    /// hold, along with `Open`.
+    pub fn satisfies(&self, req: ReqIndex) -> bool {
+        self.satisfies_in(&REQ_CONTAINER.0, req.get())
+    }
+
+    // The same for a tree in any array of nodes, e.g. a builder's.
+    fn satisfies_in(&self, nodes: &[RequirementNode], mut root: u16) -> bool {
+        loop {
+            let node = nodes[root as usize];
+            let satisfied = match node.req {
+                Requirement::Open => true,
+                Requirement::Locked => false,
+                leaf => self.contains(leaf),
+            };
+            let next = match satisfied {
+                true => node.and,
+                false => node.or,
+            };
+            match next {
+                Some(n) => root = n.get(),
+                None => break satisfied,
+            }
+        }
+    }
+}
+
+impl FromIterator<Requirement> for ItemSet {
+    fn from_iter<I: IntoIterator<Item = Requirement>>(iter: I) -> Self {
+        let mut set = ItemSet::EMPTY;
+        iter.into_iter().for_each(|req| set.insert(req));
+
+        set
+    }
+}
+
+impl fmt::Display for ItemSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut reqs = self.iter();
+        match reqs.next() {
+            None => write!(f, "{}", Requirement::Open),
+            Some(first) => {
+                write!(f, "{}", first)?;
+                reqs.try_for_each(|req| write!(f, " & {}", req))
+            }
+        }
+    }
+}
+
+/// Every minimal set of requirements that satisfies the tree rooted at `req`, meaning the set
+/// satisfies it and none of its subsets do, smallest sets first. For `hammer | boots & gloves`
+/// that's `Hammer` and then `Boots & Gloves`, which is what a hint wants to say and what a logic
+/// author wants to check an expression means. An open tree has just the empty set and a tree
+/// nothing satisfies has none.
+///
+/// We try every combination of the requirements the tree mentions, which is exponential in how
+/// many it mentions, but a tree only ever mentions a handful. A tree with a negation isn't
+/// satisfied by every superset of its minimal sets. See `non_monotone`.
+pub fn satisfying_sets(req: ReqIndex) -> impl Iterator<Item = ItemSet> {
+    let mut leaves: Vec<Requirement> = Vec::new();
+    for leaf in ReqTreeView::new(req).requirements() {
+        let leaf = match leaf {
+            Requirement::Not(negated) => negated.requirement(),
+            leaf => leaf,
+        };
+        if !matches!(leaf, Requirement::Open | Requirement::Locked) && !leaves.contains(&leaf) {
+            leaves.push(leaf);
+        }
+    }
+    let mut candidates: Vec<ItemSet> = (0..1u32 << leaves.len())
+        .map(|bits| {
+            (0..leaves.len())
+                .filter(|&i| bits & (1 << i) != 0)
+                .map(|i| leaves[i])
+                .collect()
+        })
+        .collect();
+    // Stable, so sets of one size stay in the order the tree mentions their requirements.
+    candidates.sort_by_key(ItemSet::len);
+    let mut minimal: Vec<ItemSet> = Vec::new();
+    for set in candidates {
+        // Anything with a satisfying subset has a minimal one, which came before it.
+        if set.satisfies(req) && !minimal.iter().any(|m| m.is_subset(set)) {
+            minimal.push(set);
+        }
+    }
+
+    minimal.into_iter()
+}
+
+/// Whether the trees rooted at `a` and `b` are satisfied by exactly the same requirements, however
+/// differently they're written, e.g. `boots | hammer` and `hammer | boots`. Tests can use this to
+/// check a simplification kept a tree's meaning.
+///
+/// We evaluate both trees under every combination of the requirements either one mentions.
+/// Tiers, settings and events count as plain requirements here, so `normal & boots` isn't
+/// equivalent to `boots` even though normal logic is always enabled.
+pub fn equivalent(a: ReqIndex, b: ReqIndex) -> bool {
+    equivalent_nodes(&REQ_CONTAINER.0, a.get(), b.get())
+}
+
+/// Same as `equivalent` for two trees in a builder, so codegen can merge trees that mean the same
+/// thing before they're packed into a container.
+pub fn equivalent_in<const N: usize>(builder: &ReqBuilder<N>, a: u16, b: u16) -> bool {
+    equivalent_nodes(builder.nodes(), a, b)
+}
+
+fn equivalent_nodes(nodes: &[RequirementNode], a: u16, b: u16) -> bool {
+    let mentioned = mentioned_in(nodes, &[a, b]);
+    // Walk every subset of the mentioned requirements' bits, from none up to all of them.
+    let mut bits = 0u16;
+    loop {
+        let set = ItemSet(bits);
+        if set.satisfies_in(nodes, a) != set.satisfies_in(nodes, b) {
+            return false;
+        }
+        if bits == mentioned.0 {
+            return true;
+        }
+        bits = bits.wrapping_sub(mentioned.0) & mentioned.0;
+    }
+}
+
+/// A way collecting something closes the tree rooted at `req`: a set of requirements the tree is
+/// satisfied under and one more requirement that unsatisfies it when added, or `None` if there's
+/// no such thing and the tree is monotone. Every tree our parser builds without negations is
+/// monotone, so one that isn't and doesn't negate anything is usually a bug in a hand-written or
+/// generated tree.
+///
+/// Like `equivalent` we try every combination of the requirements the tree mentions.
+pub fn non_monotone(req: ReqIndex) -> Option<(ItemSet, Requirement)> {
+    non_monotone_nodes(&REQ_CONTAINER.0, req.get())
+}
+
+/// Same as `non_monotone` for a tree in a builder.
+pub fn non_monotone_in<const N: usize>(
+    builder: &ReqBuilder<N>,
+    root: u16,
+) -> Option<(ItemSet, Requirement)> {
+    non_monotone_nodes(builder.nodes(), root)
+}
+
+pub(crate) fn non_monotone_nodes(
+    nodes: &[RequirementNode],
+    root: u16,
+) -> Option<(ItemSet, Requirement)> {
+    let mentioned = mentioned_in(nodes, &[root]);
+    let mut bits = 0u16;
+    loop {
+        let set = ItemSet(bits);
+        if set.satisfies_in(nodes, root) {
+            let closing = mentioned
+                .iter()
+                .filter(|&leaf| !set.contains(leaf))
+                .find(|&leaf| {
+                    let mut more = set;
+                    more.insert(leaf);
+                    !more.satisfies_in(nodes, root)
+                });
+            if let Some(leaf) = closing {
+                return Some((set, leaf));
+            }
+        }
+        if bits == mentioned.0 {
+            return None;
+        }
+        bits = bits.wrapping_sub(mentioned.0) & mentioned.0;
+    }
+}
 
-impl<const N: usize> Index<u16> for ReqArray<N> {
+// Every requirement the trees rooted at `roots` check, besides `Open` and `Locked`, with
+// negations counting as the requirement they negate.
+fn mentioned_in(nodes: &[RequirementNode], roots: &[u16]) -> ItemSet {
+    let mut mentioned = ItemSet::EMPTY;
+    let mut stack = Vec::from(roots);
+    while let Some(idx) = stack.pop() {
+        let node = nodes[idx as usize];
+        match node.req {
+            Requirement::Open | Requirement::Locked => (),
+            Requirement::Not(negated) => mentioned.insert(negated.requirement()),
+            req => mentioned.insert(req),
+        }
+        stack.extend(node.and.into_iter().chain(node.or).map(NonZeroU16::get));
+    }
+
+    mentioned
+}
+
+/// Render a requirement tree as a boolean expression with item names, e.g. `Boots | Hammer`,
+/// for showing logic to people. `req` has to index into `trees`, e.g. an edge's requirement and
+/// its graph's `StaticGraph::req_trees`. See `write_requirement`.
+pub fn format(trees: &[RequirementNode], req: ReqIndex) -> String {
+    let mut text = String::new();
+    // Writing to a string can't fail.
+    let _ = write_requirement(&mut text, trees, req);
+
+    text
+}
+
+/// Write a requirement tree as a boolean expression to any `fmt::Write`. A node's `and` link is
+/// what else has to hold if its requirement does and its `or` link is what we fall back on if it
+/// doesn't, so each node reads as `(req & and) | or`. We only add the parentheses that change the
+/// meaning. Like `format`, `req` has to index into `trees`.
+pub fn write_requirement(
+    out: &mut impl fmt::Write,
+    trees: &[RequirementNode],
+    req: ReqIndex,
+) -> fmt::Result {
+    write_node(out, trees, req, false)
+}
+
+fn write_node(
+    out: &mut impl fmt::Write,
+    trees: &[RequirementNode],
+    idx: ReqIndex,
+    nested: bool,
+) -> fmt::Result {
+    let node = &trees[idx.0 as usize];
+    let parens = nested && node.or.is_some();
+    if parens {
+        write!(out, "(")?;
+    }
+    match (node.req, node.and) {
+        (req, None) => write!(out, "{}", req)?,
+        (req, Some(and)) => {
+            let grouped = node.or.is_some();
+            if grouped {
+                write!(out, "(")?;
+            }
+            write!(out, "{} & ", req)?;
+            write_node(out, trees, ReqIndex::link(and), true)?;
+            if grouped {
+                write!(out, ")")?;
+            }
+        }
+    }
+    if let Some(or) = node.or {
+        write!(out, " | ")?;
+        write_node(out, trees, ReqIndex::link(or), false)?;
+    }
+    if parens {
+        write!(out, ")")?;
+    }
+
+    Ok(())
+}
+
+impl fmt::Display for ReqIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<const N: usize> fmt::Debug for ReqArray<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.0.iter()).finish()
+    }
+}
+
+impl<const N: usize> Index<ReqIndex> for ReqArray<N> {
     type Output = RequirementNode;
 
-    fn index(&self, idx: u16) -> &Self::Output {
-        &self.0[idx as usize]
+    fn index(&self, idx: ReqIndex) -> &Self::Output {
+        &self.0[idx.0 as usize]
     }
 }
 
@@ -94,54 +1029,126 @@ impl<const N: usize> Index<u16> for ReqArray<N> {
 /// This structure would probably be a constant associated with StaticGraph where StaticGraph
 /// implements some broader trait RandomizerGraph so a world model could be more flexible with how
 /// it holds this information where appropriate or necessary.
-pub static REQ_CONTAINER: ReqArray<7> = ReqArray([
-    // Indexes:
-    // 0 = open
-    // 1 = locked
-    // 2 = boots OR hammer
-    // 3 = hammer
-    // 4 = gloves
-    // 5 = gloves AND hammer
-    // 6 = flute
-    //
-    // These are first since they're always present and we need something to pad out the 0th
-    // element in order to index with NonZero types which we use in order to get a space
-    // optimization with the Option type. No requirement tree is allowed to use either as a leaf
-    // although only using the 0-index is prevented by the type system. Note that main graph model
-    // *is* allowed to use the zero index.
-    RequirementNode {
-        req: Requirement::Open,
-        and: None,
-        or: None,
-    },
-    RequirementNode {
-        req: Requirement::Locked,
-        and: None,
-        or: None,
-    },
-    RequirementNode {
-        req: Requirement::Boots,
-        and: None,
-        or: NonZeroU16::new(3), // Hammer
-    },
-    RequirementNode {
-        req: Requirement::Hammer,
-        and: None,
-        or: None,
-    },
-    RequirementNode {
-        req: Requirement::Gloves,
-        and: None,
-        or: None,
-    },
-    RequirementNode {
-        req: Requirement::Gloves,
-        and: NonZeroU16::new(3), // Hammer
-        or: None,
-    },
-    RequirementNode {
-        req: Requirement::Flute,
-        and: None,
-        or: None,
-    },
-]);
+pub static REQ_CONTAINER: ReqArray<REQ_CONTAINER_LEN> = REQ_TREES;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minimal_satisfying_sets() {
+        let sets = |idx| satisfying_sets(ReqIndex::new(idx).unwrap()).collect::<Vec<_>>();
+        assert_eq!(sets(0), [ItemSet::EMPTY]);
+        assert_eq!(sets(1), []);
+        assert_eq!(
+            sets(2),
+            [
+                ItemSet::from_iter([Requirement::Boots]),
+                ItemSet::from_iter([Requirement::Hammer])
+            ]
+        );
+        assert_eq!(sets(5)[0].to_string(), "Gloves & Hammer");
+        assert_eq!(ItemSet::EMPTY.to_string(), "Open");
+
+        // Checked against every combination of requirements, for every monotone tree: a set
+        // satisfies a tree exactly when it holds one of the tree's minimal sets.
+        for idx in 0..REQ_CONTAINER_LEN as u16 {
+            let req = ReqIndex::new(idx).unwrap();
+            if ReqTreeView::new(req).has_negation() {
+                continue;
+            }
+            let minimal = sets(idx);
+            assert!(minimal.iter().all(|m| m.satisfies(req)));
+            assert!(minimal.iter().all(|m| m.iter().all(|leaf| !m
+                .iter()
+                .filter(|&r| r != leaf)
+                .collect::<ItemSet>()
+                .satisfies(req))));
+            for bits in 0..1u16 << Requirement::COUNT {
+                let set = ItemSet(bits);
+                assert_eq!(
+                    set.satisfies(req),
+                    minimal.iter().any(|m| m.is_subset(set)),
+                    "{} {}",
+                    format(&REQ_CONTAINER.0, req),
+                    set
+                );
+            }
+        }
+        assert!(
+            ItemSet::from_iter([Requirement::Tier(Tier::Hard), Requirement::Flute])
+                .items()
+                .eq([Item::Flute])
+        );
+        assert_eq!(
+            (0..Requirement::COUNT as u16)
+                .filter_map(Requirement::from_id)
+                .map(|r| r.id())
+                .collect::<Vec<_>>(),
+            (0..Requirement::COUNT as u16).collect::<Vec<_>>()
+        );
+
+        // A negation is met by any set without what it negates, and ids round trip for those too.
+        let not_agahnim = Requirement::Event(EventId::Agahnim).negate();
+        assert_eq!(sets(15), [ItemSet::EMPTY]);
+        assert!(!ItemSet::from_iter([Requirement::Event(EventId::Agahnim)])
+            .satisfies(ReqIndex::new(15).unwrap()));
+        assert_eq!(Requirement::from_id(not_agahnim.id()), Some(not_agahnim));
+        assert_eq!(not_agahnim.negate().negate(), not_agahnim);
+        assert_eq!(Requirement::Open.negate(), Requirement::Locked);
+        assert_eq!(Requirement::from_id(Requirement::COUNT as u16), None);
+        assert_eq!(not_agahnim.to_string(), "!Agahnim");
+    }
+
+    #[test]
+    fn equivalent_trees() {
+        let sets = |idx| satisfying_sets(ReqIndex::new(idx).unwrap()).collect::<Vec<_>>();
+        // Two monotone trees are equivalent exactly when they have the same minimal sets.
+        let monotone = |&i: &u16| !ReqTreeView::new(ReqIndex::new(i).unwrap()).has_negation();
+        for a in (0..REQ_CONTAINER_LEN as u16).filter(monotone) {
+            for b in (0..REQ_CONTAINER_LEN as u16).filter(monotone) {
+                let (ra, rb) = (ReqIndex::new(a).unwrap(), ReqIndex::new(b).unwrap());
+                let (mut sa, mut sb) = (sets(a), sets(b));
+                sa.sort_unstable_by_key(|s| s.0);
+                sb.sort_unstable_by_key(|s| s.0);
+                assert_eq!(
+                    equivalent(ra, rb),
+                    sa == sb,
+                    "{} {}",
+                    format(&REQ_CONTAINER.0, ra),
+                    format(&REQ_CONTAINER.0, rb)
+                );
+            }
+        }
+
+        // Written differently, built differently, same meaning.
+        let mut builder = ReqBuilder::<16>::new();
+        let parse = |text| crate::parser::parse_requirement(text).unwrap();
+        let a = parse("boots & (gloves | hammer)")
+            .build(&mut builder)
+            .unwrap();
+        let b = parse("boots & hammer | gloves & boots")
+            .build(&mut builder)
+            .unwrap();
+        let c = parse("boots & gloves | hammer")
+            .build(&mut builder)
+            .unwrap();
+        assert_ne!(a, b);
+        assert!(equivalent_in(&builder, a, b));
+        assert!(!equivalent_in(&builder, a, c));
+
+        // `!agahnim` has the same minimal set as open but isn't met once Agahnim is beaten.
+        let not_agahnim = ReqIndex::new(15).unwrap();
+        assert!(equivalent(not_agahnim, not_agahnim));
+        assert!(!equivalent(ReqIndex::OPEN, not_agahnim));
+        let negated = crate::parser::parse_requirement_with_negation("!(boots | hammer)")
+            .unwrap()
+            .build(&mut builder)
+            .unwrap();
+        let pushed = crate::parser::parse_requirement_with_negation("!hammer & !boots")
+            .unwrap()
+            .build(&mut builder)
+            .unwrap();
+        assert!(equivalent_in(&builder, negated, pushed));
+    }
+}
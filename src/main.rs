@@ -1,6 +1,485 @@
-#![allow(dead_code)]
-#![allow(unused_imports)]
-use sparsegraph::graph::{new_static_graph, new_static_graph_open, StaticGraph};
+// The `sparsegraph` command line tool, for querying a world model without writing any Rust.
+use std::{
+    env, fs,
+    io::{self, BufRead, Write},
+    process::ExitCode,
+};
+
+use sparsegraph::{
+    analysis::explain_unreachable,
+    bench_support::{assumed_fill, item_pool},
+    bytes::BYTES_MAGIC,
+    codegen::GenTables,
+    export,
+    facade::{GenGraph, GenTrackerSession},
+    graph::new_static_graph,
+    logic::{CollectionState, Item, REQ_CONTAINER_LEN},
+    reachability::Reachability,
+    tracker::TrackerSession,
+    traversal::WalkOrder,
+};
+
+const USAGE: &str = "\
+usage: sparsegraph [--model FILE] <command> [options]
+
+Without --model we use the generated world model. A model file is either the binary format from
+`StaticGraph::to_bytes` or a plain text edge list. Nodes can be given by index or by name and
+item states are comma-separated item names, `all` or `none`.
+
+commands:
+  reachable [--state ITEMS] [--from NODE]   every node reachable from NODE (default 1)
+  path --to NODE [--from NODE] [--state ITEMS]
+                                            a path with as few edges as possible
+  spheres [--seed N] [--pool N]             fill a pool of N items (default 200) and list the
+                                            playthrough one sphere at a time
+  export [--dot FILE] [--graphml FILE] [--json FILE] [--docs FILE]
+                                            write the model out, `-` for stdout. --docs is
+                                            a Markdown description for reviewing the model
+  generate --out FILE                       build the model into a drop-in src/gen.rs if FILE
+                                            ends in .rs or `-` for stdout, and into the binary
+                                            format otherwise
+  repl [--state ITEMS]                      query the model interactively, `help` lists what
+                                            you can ask
+  profile                                   run a fixed search workload, e.g. under perf
+";
+
+const REPL_HELP: &str = "\
+collect ITEM, uncollect ITEM, toggle ITEM
+                      change what we're holding
+undo                  take back the last change
+state                 what we're holding
+reachable NODE        whether NODE is reachable holding what we're holding
+why-not NODE          what stands between us and NODE
+diff ITEMS ITEMS      the nodes reachable under one state but not the other, `.` for what
+                      we're holding
+help, quit
+";
+
+const PROGRESSION: [Item; 4] = [Item::Boots, Item::Gloves, Item::Flute, Item::Hammer];
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|a| a == "--help" || a == "-h") {
+        print!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    match Options::parse(&args).and_then(|options| run(&options)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("sparsegraph: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+// A command and its `--flag value` pairs. Every flag we take has a value.
+struct Options {
+    command: String,
+    flags: Vec<(String, String)>,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Options, String> {
+        let mut command = None;
+        let mut flags = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(flag) => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| format!("--{} needs a value", flag))?;
+                    flags.push((flag.to_string(), value.clone()));
+                }
+                None if command.is_none() => command = Some(arg.clone()),
+                None => return Err(format!("unexpected argument `{}`", arg)),
+            }
+        }
+        let command = command.ok_or("no command given, see --help")?;
+
+        Ok(Options { command, flags })
+    }
+
+    fn get(&self, flag: &str) -> Option<&str> {
+        self.flags
+            .iter()
+            .rev()
+            .find(|(f, _)| f == flag)
+            .map(|(_, v)| v.as_str())
+    }
+
+    // Fail on flags the command doesn't know rather than silently ignoring a typo.
+    fn expect(&self, known: &[&str]) -> Result<(), String> {
+        match self
+            .flags
+            .iter()
+            .find(|(f, _)| f != "model" && !known.contains(&f.as_str()))
+        {
+            Some((f, _)) => Err(format!("`{}` doesn't take --{}", self.command, f)),
+            None => Ok(()),
+        }
+    }
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let graph = match options.get("model") {
+        Some(path) => load_model(path)?,
+        None => new_static_graph(),
+    };
+    match options.command.as_str() {
+        "reachable" => {
+            options.expect(&["state", "from"])?;
+            reachable(&graph, options)
+        }
+        "path" => {
+            options.expect(&["state", "from", "to"])?;
+            path(&graph, options)
+        }
+        "spheres" => {
+            options.expect(&["seed", "pool"])?;
+            spheres(&graph, options)
+        }
+        "export" => {
+            options.expect(&["dot", "graphml", "json", "docs"])?;
+            export_model(&graph, options)
+        }
+        "generate" => {
+            options.expect(&["out"])?;
+            generate(&graph, options)
+        }
+        "repl" => {
+            options.expect(&["state"])?;
+            repl(&graph, state(options)?)
+        }
+        "profile" => {
+            options.expect(&[])?;
+            profile(&graph);
+            Ok(())
+        }
+        other => Err(format!("unknown command `{}`, see --help", other)),
+    }
+}
+
+fn load_model(path: &str) -> Result<GenGraph, String> {
+    let bytes = fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+    if bytes.starts_with(&BYTES_MAGIC[..2]) {
+        return GenGraph::from_bytes(&bytes).map_err(|e| format!("{}: {}", path, e));
+    }
+    let text = String::from_utf8(bytes).map_err(|_| format!("{}: not a model file", path))?;
+
+    GenGraph::from_edge_list(text.lines()).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn parse_state(text: &str) -> Result<CollectionState, String> {
+    match text {
+        "all" => return Ok(CollectionState::ALL_ITEMS),
+        "none" | "" => return Ok(CollectionState::NOTHING),
+        _ => (),
+    }
+    let mut state = CollectionState::NOTHING;
+    for name in text.split(',').map(str::trim) {
+        state.set(parse_item(name)?, true);
+    }
+
+    Ok(state)
+}
+
+fn parse_item(name: &str) -> Result<Item, String> {
+    PROGRESSION
+        .into_iter()
+        .find(|i| i.to_string().eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("unknown item `{}`", name))
+}
+
+fn state(options: &Options) -> Result<CollectionState, String> {
+    options
+        .get("state")
+        .map_or(Ok(CollectionState::default()), parse_state)
+}
+
+// A node by index or, failing that, by name.
+fn parse_node(graph: &GenGraph, text: &str) -> Result<u16, String> {
+    match text.parse::<u16>() {
+        Ok(node) if graph.nodes().contains(&node) => Ok(node),
+        Ok(node) => Err(format!("there's no node {}", node)),
+        Err(_) => graph
+            .nodes()
+            .find(|&n| {
+                graph
+                    .node_name(n)
+                    .is_some_and(|name| name.eq_ignore_ascii_case(text))
+            })
+            .ok_or_else(|| format!("there's no node named `{}`", text)),
+    }
+}
+
+fn node_label(graph: &GenGraph, node: u16) -> String {
+    match graph.node_name(node) {
+        Some(name) => format!("{} {}", node, name),
+        None => node.to_string(),
+    }
+}
+
+fn reachable(graph: &GenGraph, options: &Options) -> Result<(), String> {
+    let from = options
+        .get("from")
+        .map_or(Ok(1), |n| parse_node(graph, n))?;
+    let state = state(options)?;
+    let mut reached: Vec<u16> = graph
+        .edges_from(from, WalkOrder::BreadthFirst, state)
+        .map(|e| e.dst)
+        .collect();
+    reached.push(from);
+    reached.sort_unstable();
+    reached
+        .iter()
+        .for_each(|&n| println!("{}", node_label(graph, n)));
+    eprintln!(
+        "{} of {} nodes reachable",
+        reached.len(),
+        graph.node_count()
+    );
+
+    Ok(())
+}
+
+fn path(graph: &GenGraph, options: &Options) -> Result<(), String> {
+    let from = options
+        .get("from")
+        .map_or(Ok(1), |n| parse_node(graph, n))?;
+    let to = parse_node(graph, options.get("to").ok_or("path needs --to")?)?;
+    let path = graph
+        .shortest_path(from, to, state(options)?)
+        .ok_or_else(|| format!("{} isn't reachable from {}", to, from))?;
+    path.iter()
+        .for_each(|&n| println!("{}", node_label(graph, n)));
+    eprintln!("{} edges", path.len() - 1);
+
+    Ok(())
+}
+
+fn spheres(graph: &GenGraph, options: &Options) -> Result<(), String> {
+    let number = |flag: &str, default: u64| {
+        options.get(flag).map_or(Ok(default), |v| {
+            v.parse().map_err(|_| format!("--{} needs a number", flag))
+        })
+    };
+    let (seed, pool) = (number("seed", 0)?, number("pool", 200)?);
+    let placement = assumed_fill(graph, &item_pool(pool as usize), seed)
+        .ok_or("there aren't enough item locations for the pool")?;
+    // Collect everything we can reach, one sphere at a time.
+    let mut remaining: Vec<(u16, Item)> = placement
+        .into_iter()
+        .filter(|(_, i)| i.is_progression())
+        .collect();
+    let mut session = TrackerSession::new(graph, CollectionState::NOTHING);
+    let mut sphere = 0;
+    while !remaining.is_empty() {
+        let found: Vec<(u16, Item)>;
+        (found, remaining) = remaining
+            .into_iter()
+            .partition(|&(l, _)| session.is_reachable(l));
+        if found.is_empty() {
+            return Err(format!("{} items are never reachable", remaining.len()));
+        }
+        println!(
+            "sphere {}: {} checks reachable",
+            sphere,
+            session.reachable_checks().len()
+        );
+        for &(location, item) in &found {
+            println!("  {} at {}", item, node_label(graph, location));
+            session.collect(item);
+        }
+        sphere += 1;
+    }
+
+    Ok(())
+}
+
+fn export_model(graph: &GenGraph, options: &Options) -> Result<(), String> {
+    let mut wrote = false;
+    for (flag, text) in [
+        ("dot", export::dot(graph).to_string()),
+        ("graphml", export::graphml(graph).to_string()),
+        ("json", export::json(graph).to_string()),
+        ("docs", export::describe_model(graph, &[])),
+    ] {
+        match options.get(flag) {
+            Some("-") => print!("{}", text),
+            Some(path) => fs::write(path, text).map_err(|e| format!("{}: {}", path, e))?,
+            None => continue,
+        }
+        wrote = true;
+    }
+
+    match wrote {
+        true => Ok(()),
+        false => Err("export needs at least one of --dot, --graphml, --json or --docs".to_string()),
+    }
+}
+
+// Loading the model already validated it, so all that's left is writing it out.
+fn generate(graph: &GenGraph, options: &Options) -> Result<(), String> {
+    let path = options.get("out").ok_or("generate needs --out")?;
+    let own_trees = graph.req_trees().len() > REQ_CONTAINER_LEN;
+    if own_trees && (path == "-" || path.ends_with(".rs")) {
+        return Err("the model has requirements only the binary format can hold".to_string());
+    }
+    match path {
+        "-" => print!("{}", GenTables::from_graph(graph)),
+        _ if path.ends_with(".rs") => {
+            let module = GenTables::from_graph(graph).to_string();
+            fs::write(path, module).map_err(|e| format!("{}: {}", path, e))?
+        }
+        _ => fs::write(path, graph.to_bytes()).map_err(|e| format!("{}: {}", path, e))?,
+    }
+
+    Ok(())
+}
+
+// Read commands from stdin until it closes or we're asked to quit. A bad command is reported and
+// we carry on, so a typo doesn't throw away the session.
+fn repl(graph: &GenGraph, state: CollectionState) -> Result<(), String> {
+    let mut session = TrackerSession::new(graph, state);
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("> ");
+        io::stdout().flush().map_err(|e| e.to_string())?;
+        let Some(line) = lines.next() else {
+            println!();
+            return Ok(());
+        };
+        let line = line.map_err(|e| e.to_string())?;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [] => continue,
+            ["quit" | "exit"] => return Ok(()),
+            command => {
+                if let Err(error) = repl_command(graph, &mut session, command) {
+                    println!("{}", error);
+                }
+            }
+        }
+    }
+}
+
+fn repl_command(
+    graph: &GenGraph,
+    session: &mut GenTrackerSession<'_>,
+    command: &[&str],
+) -> Result<(), String> {
+    match command {
+        ["help"] => print!("{}", REPL_HELP),
+        ["collect", item] => {
+            session.collect(parse_item(item)?);
+        }
+        ["uncollect", item] => session.uncollect(parse_item(item)?),
+        ["toggle", item] => {
+            let item = parse_item(item)?;
+            match session.collection_state().has(item) {
+                true => session.uncollect(item),
+                false => {
+                    session.collect(item);
+                }
+            }
+        }
+        ["undo"] => {
+            if !session.undo() {
+                return Err("nothing to undo".to_string());
+            }
+        }
+        ["state"] => {
+            let state = session.collection_state();
+            let held: Vec<String> = PROGRESSION
+                .into_iter()
+                .filter(|&i| state.has(i))
+                .map(|i| i.to_string())
+                .collect();
+            match held.is_empty() {
+                true => println!("nothing"),
+                false => println!("{}", held.join(", ")),
+            }
+        }
+        ["reachable", node] => {
+            let node = parse_node(graph, node)?;
+            match session.is_reachable(node) {
+                true => println!("{} is reachable", node_label(graph, node)),
+                false => println!("{} isn't reachable", node_label(graph, node)),
+            }
+        }
+        ["why-not", node] => {
+            let node = parse_node(graph, node)?;
+            let Some(why) = explain_unreachable(graph, session.collection_state(), node) else {
+                println!("{} is reachable", node_label(graph, node));
+                return Ok(());
+            };
+            if why.blocked_edges.is_empty() {
+                println!("nothing leads to {} from the root", node_label(graph, node));
+                return Ok(());
+            }
+            println!("blocked at:");
+            for e in &why.blocked_edges {
+                println!(
+                    "  {} -> {} (edge {}, needs {})",
+                    node_label(graph, e.src),
+                    node_label(graph, e.dest),
+                    e.edge,
+                    e.req
+                );
+            }
+            match why.unlocking_items.as_slice() {
+                [] => println!("no single item gets us there"),
+                items => {
+                    let items: Vec<String> = items.iter().map(|i| i.to_string()).collect();
+                    println!("any of these gets us there: {}", items.join(", "));
+                }
+            }
+        }
+        ["diff", a, b] => {
+            let state = |text: &str| match text {
+                "." => Ok(session.collection_state().clone()),
+                _ => parse_state(text),
+            };
+            let (a, b) = (
+                reachable_set(graph, state(a)?),
+                reachable_set(graph, state(b)?),
+            );
+            let (mut only_a, mut only_b) = (0, 0);
+            for node in graph.nodes() {
+                match (a[node as usize], b[node as usize]) {
+                    (true, false) => {
+                        println!("- {}", node_label(graph, node));
+                        only_a += 1;
+                    }
+                    (false, true) => {
+                        println!("+ {}", node_label(graph, node));
+                        only_b += 1;
+                    }
+                    _ => (),
+                }
+            }
+            println!(
+                "{} only in the first, {} only in the second",
+                only_a, only_b
+            );
+        }
+        _ => return Err(format!("don't know `{}`, try `help`", command.join(" "))),
+    }
+
+    Ok(())
+}
+
+// Whether each node is reachable under `state`, by index.
+fn reachable_set(graph: &GenGraph, state: CollectionState) -> Vec<bool> {
+    let mut reached = vec![false; graph.node_count() as usize + 1];
+    graph
+        .bfs_iter_with_state(state)
+        .for_each(|n| reached[u16::from(n) as usize] = true);
+
+    reached
+}
 
 static LOCATION_INDEXES: [u16; 100] = [
     2851, 6033, 10712, 14682, 1251, 13953, 15897, 10330, 3926, 14633, 6830, 6781, 19886, 7807,
@@ -12,38 +491,32 @@ static LOCATION_INDEXES: [u16; 100] = [
     17719, 5773, 467, 1124, 18757, 3654, 8623, 11253, 1835, 4214, 17634, 11051, 9803, 15729,
 ];
 
-fn main() {
-    let graph = new_static_graph();
-    search_bfs(&graph);
-    search_dfs(&graph);
-    sim_bfs(&graph);
-    sim_dfs(&graph);
+// What this binary used to do on its own: a fixed workload to point a profiler at.
+fn profile(graph: &GenGraph) {
+    search_bfs(graph);
+    search_dfs(graph);
+    sim_batch(graph);
 }
 
-fn search_bfs<const M: usize, const N: usize>(graph: &StaticGraph<M, N>) {
+fn search_bfs(graph: &GenGraph) {
     let mut bfs_iter = graph.bfs_iter();
     (1..=20_000).step_by(1).for_each(|i| {
         bfs_iter.search(std::hint::black_box(i));
     });
 }
 
-fn search_dfs<const M: usize, const N: usize>(graph: &StaticGraph<M, N>) {
+fn search_dfs(graph: &GenGraph) {
     let mut dfs_iter = graph.dfs_iter();
     (1..=20_000).step_by(1).for_each(|i| {
         dfs_iter.search(std::hint::black_box(i));
     });
 }
 
-fn sim_dfs<const M: usize, const N: usize>(graph: &StaticGraph<M, N>) {
-    let mut dfs_iter = graph.dfs_iter();
-    LOCATION_INDEXES.iter().for_each(|l| {
-        dfs_iter.search(std::hint::black_box(*l));
-    });
-}
-
-fn sim_bfs<const M: usize, const N: usize>(graph: &StaticGraph<M, N>) {
-    let mut bfs_iter = graph.bfs_iter();
-    LOCATION_INDEXES.iter().for_each(|l| {
-        bfs_iter.search(std::hint::black_box(*l));
-    });
+fn sim_batch(graph: &GenGraph) {
+    let reachable = Reachability::batch(
+        graph,
+        &CollectionState::default(),
+        std::hint::black_box(&LOCATION_INDEXES),
+    );
+    std::hint::black_box(reachable);
 }
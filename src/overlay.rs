@@ -0,0 +1,288 @@
+use alloc::{collections::VecDeque, sync::Arc, vec, vec::Vec};
+use core::{iter::Enumerate, num::NonZeroU16, slice};
+
+use crate::{
+    graph::StaticGraph,
+    logic::{CollectionState, EvaluateLogic, ReqIndex},
+};
+
+/// Where an overridden edge leads and what it needs.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct EdgeOverride {
+    pub target: u16,
+    pub req: ReqIndex,
+}
+
+/// A handful of edge edits layered over a borrowed graph, for asking "what if this door led
+/// there?" without copying the graph's arrays. Reads go through the overrides first and fall back
+/// to the base graph. Edits never touch the base; `apply_to` writes them into a graph once we've
+/// decided to keep them.
+///
+/// Overrides live in a small sorted array. Speculative edits are a few edges at a time so a
+/// binary search over them is cheaper than hashing, and we don't need std for it.
+pub struct OverlayGraph<'base, const M: usize, const N: usize, T = ()> {
+    base: &'base StaticGraph<M, N, T>,
+    overrides: Vec<(u16, EdgeOverride)>,
+}
+
+impl<'base, const M: usize, const N: usize, T> StaticGraph<M, N, T> {
+    /// Start an overlay over this graph with no edits.
+    pub fn overlay(&'base self) -> OverlayGraph<'base, M, N, T> {
+        OverlayGraph {
+            base: self,
+            overrides: Vec::new(),
+        }
+    }
+}
+
+impl<'base, const M: usize, const N: usize, T> OverlayGraph<'base, M, N, T> {
+    pub fn base(&self) -> &'base StaticGraph<M, N, T> {
+        self.base
+    }
+
+    /// The number of overridden edges.
+    pub fn len(&self) -> usize {
+        self.overrides.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.overrides.is_empty()
+    }
+
+    /// Every overridden edge in edge order.
+    pub fn overrides(&self) -> &[(u16, EdgeOverride)] {
+        &self.overrides
+    }
+
+    /// Point an edge at a different node, keeping its requirement.
+    ///
+    /// Panics if the edge or the target isn't in the base graph.
+    pub fn set_edge_target(&mut self, edge: u16, target: u16) {
+        assert!(
+            (1..=self.base.node_count).contains(&target),
+            "target {} isn't a node in the graph",
+            target
+        );
+        self.edit(edge).target = target;
+    }
+
+    /// Give an edge a different requirement, keeping its target.
+    ///
+    /// Panics if the edge isn't in the base graph.
+    pub fn set_edge_req(&mut self, edge: u16, req: ReqIndex) {
+        self.edit(edge).req = req;
+    }
+
+    /// Drop any override for an edge so it reads from the base graph again.
+    pub fn reset_edge(&mut self, edge: u16) {
+        if let Ok(i) = self.find(edge) {
+            self.overrides.remove(i);
+        }
+    }
+
+    /// Drop every override.
+    pub fn clear(&mut self) {
+        self.overrides.clear();
+    }
+
+    /// Where an edge leads with our overrides applied.
+    pub fn edge_target(&self, edge: u16) -> u16 {
+        match self.find(edge) {
+            Ok(i) => self.overrides[i].1.target,
+            Err(_) => u16::from(self.base.edge_pointers[edge]),
+        }
+    }
+
+    /// An edge's requirement with our overrides applied.
+    pub fn edge_req(&self, edge: u16) -> ReqIndex {
+        match self.find(edge) {
+            Ok(i) => self.overrides[i].1.req,
+            Err(_) => self.base.edge_data[edge as usize],
+        }
+    }
+
+    /// Same as `StaticGraph::get_neighbors_out` but with our overrides applied, so we give back an
+    /// iterator over the node's destinations instead of a slice of the base graph.
+    pub fn get_neighbors_out(&self, n: Option<NonZeroU16>) -> (OverlayNeighbors<'_>, u16) {
+        let (edge_pointers, edge_offset) = self.base.get_neighbors_out(n);
+        // Overrides are sorted, so the ones for this node's edges are a contiguous run.
+        let end = edge_offset + edge_pointers.len() as u16;
+        let first = self.overrides.partition_point(|&(e, _)| e < edge_offset);
+        let last = self.overrides.partition_point(|&(e, _)| e < end);
+        let neighbors = OverlayNeighbors {
+            edges: edge_pointers.iter().enumerate(),
+            edge_offset,
+            overrides: &self.overrides[first..last],
+        };
+
+        (neighbors, edge_offset)
+    }
+
+    /// `(source, edge index, destination)` for every edge, like `StaticGraph::edges`.
+    pub fn edges(&self) -> impl Iterator<Item = (u16, u16, u16)> + '_ {
+        self.base.nodes().flat_map(move |src| {
+            let (neighbors, edge_offset) = self.get_neighbors_out(NonZeroU16::new(src));
+            neighbors
+                .enumerate()
+                .map(move |(i, dest)| (src, edge_offset + i as u16, u16::from(dest)))
+        })
+    }
+
+    /// Every node reachable from the root under `state` with our overrides applied, in
+    /// breadth-first order. Our walkers only know how to walk a `StaticGraph` and wouldn't see
+    /// the overrides, so this runs its own search.
+    pub fn reachable(&self, state: &CollectionState) -> Vec<u16> {
+        let mut seen = vec![false; self.base.node_count as usize + 1];
+        let mut order = Vec::new();
+        let mut queue = VecDeque::from([1u16]);
+        seen[1] = true;
+        while let Some(node) = queue.pop_front() {
+            order.push(node);
+            let (neighbors, edge_offset) = self.get_neighbors_out(NonZeroU16::new(node));
+            for (i, dest) in neighbors.enumerate() {
+                let dest = u16::from(dest);
+                let edge = edge_offset + i as u16;
+                if !seen[dest as usize]
+                    && state.eval_logic_tree_in(self.base.req_trees(), self.edge_req(edge))
+                {
+                    seen[dest as usize] = true;
+                    queue.push_back(dest);
+                }
+            }
+        }
+
+        order
+    }
+
+    pub fn is_reachable(&self, node: u16, state: &CollectionState) -> bool {
+        self.reachable(state).contains(&node)
+    }
+
+    /// Write our overrides into `graph`, e.g. a copy of the base graph we want to keep the edits
+    /// in.
+    pub fn apply_to<U>(&self, graph: &mut StaticGraph<M, N, U>) {
+        for &(edge, EdgeOverride { target, req }) in &self.overrides {
+            graph.edge_pointers.0[edge as usize] = NonZeroU16::new(target).unwrap();
+            if graph.edge_data[edge as usize] != req {
+                Arc::make_mut(&mut graph.edge_data)[edge as usize] = req;
+            }
+        }
+    }
+
+    fn find(&self, edge: u16) -> Result<usize, usize> {
+        self.overrides.binary_search_by_key(&edge, |&(e, _)| e)
+    }
+
+    // The override for an edge, starting from the base graph's edge if there isn't one yet.
+    fn edit(&mut self, edge: u16) -> &mut EdgeOverride {
+        assert!(
+            (1..=self.base.edge_count).contains(&edge),
+            "edge {} isn't in the graph",
+            edge
+        );
+        let i = match self.find(edge) {
+            Ok(i) => i,
+            Err(i) => {
+                let base = EdgeOverride {
+                    target: u16::from(self.base.edge_pointers[edge]),
+                    req: self.base.edge_data[edge as usize],
+                };
+                self.overrides.insert(i, (edge, base));
+                i
+            }
+        };
+
+        &mut self.overrides[i].1
+    }
+}
+
+/// A node's destinations in an `OverlayGraph`. See `OverlayGraph::get_neighbors_out`.
+#[derive(Clone, Debug)]
+pub struct OverlayNeighbors<'a> {
+    edges: Enumerate<slice::Iter<'a, NonZeroU16>>,
+    edge_offset: u16,
+    // Only the overrides for this node's edges, in edge order.
+    overrides: &'a [(u16, EdgeOverride)],
+}
+
+impl Iterator for OverlayNeighbors<'_> {
+    type Item = NonZeroU16;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (i, &dest) = self.edges.next()?;
+        match self.overrides.split_first() {
+            Some((&(edge, o), rest)) if edge == self.edge_offset + i as u16 => {
+                self.overrides = rest;
+                NonZeroU16::new(o.target)
+            }
+            _ => Some(dest),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.edges.size_hint()
+    }
+}
+
+impl ExactSizeIterator for OverlayNeighbors<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::new_static_graph;
+
+    #[test]
+    fn speculative_edits() {
+        let graph = new_static_graph();
+        let state = CollectionState::default();
+        let mut overlay = graph.overlay();
+        assert!(overlay.is_empty());
+        assert!(overlay.edges().eq(graph.edges()));
+        assert_eq!(
+            overlay.reachable(&state).len(),
+            graph.dfs_iter_with_state(state.clone()).count()
+        );
+
+        // Send the root's first edge somewhere new and lock its second.
+        let (root_edges, offset) = graph.get_neighbors_out(NonZeroU16::new(1));
+        let old_target = u16::from(root_edges[0]);
+        let new_target = graph.node_count();
+        overlay.set_edge_target(offset, new_target);
+        overlay.set_edge_req(offset + 1, ReqIndex::LOCKED);
+        overlay.set_edge_req(offset, ReqIndex::OPEN);
+        assert_eq!(overlay.len(), 2);
+        assert_eq!(overlay.edge_target(offset), new_target);
+        assert_eq!(overlay.edge_target(offset + 1), u16::from(root_edges[1]));
+        assert_eq!(overlay.edge_req(offset + 1), ReqIndex::LOCKED);
+        let (neighbors, _) = overlay.get_neighbors_out(NonZeroU16::new(1));
+        assert_eq!(neighbors.len(), root_edges.len());
+        assert_eq!(
+            overlay.get_neighbors_out(NonZeroU16::new(1)).0.next(),
+            NonZeroU16::new(new_target)
+        );
+        // The base graph doesn't change.
+        assert_eq!(
+            u16::from(graph.get_neighbors_out(NonZeroU16::new(1)).0[0]),
+            old_target
+        );
+
+        // Searching the overlay matches searching a graph with the edits written in.
+        let mut edited = new_static_graph();
+        overlay.apply_to(&mut edited);
+        assert!(overlay.edges().eq(edited.edges()));
+        let mut expected: Vec<u16> = edited
+            .dfs_iter_with_state(state.clone())
+            .map(u16::from)
+            .collect();
+        let mut reached = overlay.reachable(&state);
+        assert!(overlay.is_reachable(new_target, &state));
+        expected.sort_unstable();
+        reached.sort_unstable();
+        assert_eq!(reached, expected);
+
+        overlay.reset_edge(offset);
+        assert_eq!(overlay.edge_target(offset), old_target);
+        overlay.clear();
+        assert!(overlay.edges().eq(graph.edges()));
+    }
+}
@@ -0,0 +1,72 @@
+// A breadth-first search that shares each level of the frontier between rayon's threads, for a
+// single sweep over a graph big enough that one thread is the bottleneck, e.g. a multiworld
+// composed out of several worlds. Smaller graphs are faster with a plain `BfsIter`.
+use alloc::vec::Vec;
+use core::num::NonZeroU16;
+
+use rayon::prelude::*;
+
+use crate::{
+    constants::*,
+    graph::{AccessCache, AtomicVisitedCache, StaticGraph, VisitedCache},
+    logic::CollectionState,
+};
+
+// How many frontier nodes a thread takes at a time. Rayon steals whole chunks from busy threads,
+// so this only needs to be big enough that the stealing doesn't cost more than the nodes.
+const FRONTIER_CHUNK: usize = 64;
+
+impl<const M: usize, const N: usize, T: Sync> StaticGraph<M, N, T> {
+    /// Every node reachable from the root under `state`, like running `.bfs_iter_with_state()`
+    /// to the end but with each level of the search split between threads. Nodes are claimed
+    /// through an `AtomicVisitedCache` so each one is expanded exactly once.
+    pub fn par_reachable(&self, state: &CollectionState) -> VisitedCache<VISITED_BITFIELD_LEN> {
+        let mut access = AccessCache::<ACCESS_BITFIELD_LEN>::with_len(Self::ACCESS_LEN);
+        self.compute_access_mask(state, &mut access);
+        let visited = AtomicVisitedCache::<VISITED_BITFIELD_LEN>::with_len(Self::VISITED_LEN);
+        visited.mark_visited(1);
+        let mut frontier = Vec::from([1u16]);
+        while !frontier.is_empty() {
+            frontier = frontier
+                .par_chunks(FRONTIER_CHUNK)
+                .flat_map_iter(|chunk| {
+                    let mut next = Vec::new();
+                    for &node in chunk {
+                        let (edges, edge_offset) = self.get_neighbors_out(NonZeroU16::new(node));
+                        for (i, &dest) in edges.iter().enumerate() {
+                            let dest = u16::from(dest);
+                            if access.check_access(edge_offset + i as u16)
+                                && !visited.test_set_visited(dest)
+                            {
+                                next.push(dest);
+                            }
+                        }
+                    }
+                    next
+                })
+                .collect();
+        }
+
+        visited.into_visited()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bench_support::state_changes, graph::new_static_graph};
+
+    #[test]
+    fn parallel_sweep() {
+        let graph = new_static_graph();
+        for state in state_changes() {
+            let reached = graph.par_reachable(&state);
+            let expected: Vec<u16> = graph.bfs_iter_with_state(state).map(u16::from).collect();
+            assert!(expected.iter().all(|&n| reached.check_visited(n)));
+            assert_eq!(
+                reached.iter().map(|w| w.count_ones()).sum::<u32>() as usize,
+                expected.len()
+            );
+        }
+    }
+}
@@ -0,0 +1,619 @@
+use alloc::{boxed::Box, vec::Vec};
+use core::{fmt, mem, num::NonZeroU16};
+
+use crate::logic::{
+    CollectionState, EvaluateLogic, EventFlags, EventId, NaryIndex, NaryTrees, ReqBuilder,
+    ReqIndex, Requirement, RequirementNode, SettingFlag, SettingsState, Tier, TierMask,
+    REQ_CONTAINER_LEN,
+};
+
+/// A requirement expression as it's written in plain text, e.g. `gloves & (boots | hammer)`.
+///
+/// Edges only ever carry an index into their graph's requirement trees, so an expression has to
+/// be built into them with `ReqExpr::build`, or matched to one of the library's with
+/// `ReqExpr::tree`, before a graph can use it. See `StaticGraph::req_trees`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReqExpr {
+    Req(Requirement),
+    And(Box<ReqExpr>, Box<ReqExpr>),
+    Or(Box<ReqExpr>, Box<ReqExpr>),
+}
+
+impl ReqExpr {
+    /// Evaluate the expression directly against a collection state.
+    pub fn eval(&self, state: &CollectionState) -> bool {
+        match self {
+            ReqExpr::Req(req) => state.eval_requirement(*req),
+            ReqExpr::And(a, b) => a.eval(state) && b.eval(state),
+            ReqExpr::Or(a, b) => a.eval(state) || b.eval(state),
+        }
+    }
+
+    /// The first tree in `REQ_CONTAINER` that's satisfied by exactly the same collection states
+    /// as this expression, if there is one. We only have four progression items, two optional
+    /// tiers, two settings and two events so comparing every state is cheap, and it means
+    /// `boots | hammer` finds the same tree as `hammer | boots`.
+    pub fn tree(&self) -> Option<ReqIndex> {
+        (0..REQ_CONTAINER_LEN as u16)
+            .filter_map(ReqIndex::new)
+            .find(|&idx| all_states().all(|s| s.eval_logic_tree(idx) == self.eval(&s)))
+    }
+
+    /// Add the expression to `builder` as one of our binary trees and return its root, sharing
+    /// any subtree the builder already has. Returns `None` if the builder runs out of room.
+    ///
+    /// A node carries on to its AND link when it's satisfied and its OR link when it isn't, so
+    /// we build each operand knowing where to go after it: `a & b` is `a` carrying on to `b` when
+    /// satisfied, and `a | b` is `a` falling back to `b` when not.
+    pub fn build<const N: usize>(&self, builder: &mut ReqBuilder<N>) -> Option<u16> {
+        match self.fold() {
+            // Trees can't link to these, so they're only ever a whole tree on their own.
+            ReqExpr::Req(Requirement::Open) => Some(ReqIndex::OPEN.get()),
+            ReqExpr::Req(Requirement::Locked) => Some(ReqIndex::LOCKED.get()),
+            expr => expr.build_linked(builder, None, None),
+        }
+    }
+
+    fn build_linked<const N: usize>(
+        &self,
+        builder: &mut ReqBuilder<N>,
+        and: Option<NonZeroU16>,
+        or: Option<NonZeroU16>,
+    ) -> Option<u16> {
+        match self {
+            ReqExpr::Req(req) => builder.insert(RequirementNode { req: *req, and, or }),
+            ReqExpr::And(a, b) => {
+                let b = NonZeroU16::new(b.build_linked(builder, and, or)?);
+                a.build_linked(builder, b, or)
+            }
+            ReqExpr::Or(a, b) => {
+                let b = NonZeroU16::new(b.build_linked(builder, and, or)?);
+                a.build_linked(builder, and, b)
+            }
+        }
+    }
+
+    /// The expression satisfied exactly when this one isn't, with the negation pushed down to the
+    /// requirements so `!(boots | hammer)` is `!boots & !hammer`.
+    pub fn negate(&self) -> ReqExpr {
+        match self {
+            ReqExpr::Req(req) => ReqExpr::Req(req.negate()),
+            ReqExpr::And(a, b) => ReqExpr::Or(Box::new(a.negate()), Box::new(b.negate())),
+            ReqExpr::Or(a, b) => ReqExpr::And(Box::new(a.negate()), Box::new(b.negate())),
+        }
+    }
+
+    /// Whether the expression checks any negated requirement, which makes any tree built from
+    /// it non-monotone. See `Requirement::Not`.
+    pub fn has_negation(&self) -> bool {
+        match self {
+            ReqExpr::Req(req) => req.is_negated(),
+            ReqExpr::And(a, b) | ReqExpr::Or(a, b) => a.has_negation() || b.has_negation(),
+        }
+    }
+
+    // The same expression with open and locked folded away, unless it's nothing but one of them.
+    fn fold(&self) -> ReqExpr {
+        use Requirement::{Locked, Open};
+        match self {
+            ReqExpr::Req(_) => self.clone(),
+            ReqExpr::And(a, b) => match (a.fold(), b.fold()) {
+                (ReqExpr::Req(Open), e) | (e, ReqExpr::Req(Open)) => e,
+                (ReqExpr::Req(Locked), _) | (_, ReqExpr::Req(Locked)) => ReqExpr::Req(Locked),
+                (a, b) => ReqExpr::And(Box::new(a), Box::new(b)),
+            },
+            ReqExpr::Or(a, b) => match (a.fold(), b.fold()) {
+                (ReqExpr::Req(Locked), e) | (e, ReqExpr::Req(Locked)) => e,
+                (ReqExpr::Req(Open), _) | (_, ReqExpr::Req(Open)) => ReqExpr::Req(Open),
+                (a, b) => ReqExpr::Or(Box::new(a), Box::new(b)),
+            },
+        }
+    }
+
+    /// Add the expression to `trees` and return its root. Chains of the same operator become one
+    /// node, so `a & b & c` is a single `All` with three children rather than two nested ones.
+    pub fn nary(&self, trees: &mut NaryTrees) -> NaryIndex {
+        match self {
+            ReqExpr::Req(req) => trees.leaf(*req),
+            ReqExpr::And(..) => {
+                let mut children = Vec::new();
+                self.flatten(trees, &mut children);
+                trees.all(&children)
+            }
+            ReqExpr::Or(..) => {
+                let mut children = Vec::new();
+                self.flatten(trees, &mut children);
+                trees.any(&children)
+            }
+        }
+    }
+
+    // The roots of every operand in a chain of our own operator.
+    fn flatten(&self, trees: &mut NaryTrees, children: &mut Vec<NaryIndex>) {
+        let (ReqExpr::And(a, b) | ReqExpr::Or(a, b)) = self else {
+            return children.push(self.nary(trees));
+        };
+        for side in [a, b] {
+            match mem::discriminant(self) == mem::discriminant(&**side) {
+                true => side.flatten(trees, children),
+                false => children.push(side.nary(trees)),
+            }
+        }
+    }
+}
+
+// Every combination of progression items, tiers, settings and events.
+pub(crate) fn all_states() -> impl Iterator<Item = CollectionState> {
+    (0..1024u16).map(|bits| {
+        let mut tiers = TierMask::NORMAL;
+        for (bit, tier) in [(16, Tier::Hard), (32, Tier::Glitched)] {
+            if bits & bit != 0 {
+                tiers = tiers.with(tier);
+            }
+        }
+        CollectionState {
+            boots: bits & 1 != 0,
+            gloves: bits & 2 != 0,
+            hammer: bits & 4 != 0,
+            flute: bits & 8 != 0,
+            tiers,
+            settings: SettingsState::NONE
+                .with(SettingFlag::Swordless, bits & 64 != 0)
+                .with(SettingFlag::Keysanity, bits & 128 != 0),
+            events: EventId::ALL
+                .into_iter()
+                .enumerate()
+                .filter(|&(i, _)| bits & 256 << i != 0)
+                .fold(EventFlags::NONE, |events, (_, event)| events.with(event)),
+        }
+    })
+}
+
+/// How deep an expression can nest parentheses and negations. Parsing recurses into each one, so
+/// this keeps an untrusted model file from overflowing the stack.
+pub const MAX_DEPTH: usize = 64;
+
+/// How many `&` and `|` an expression can have. Everything that walks a `ReqExpr` or a tree built
+/// from one recurses up to once per requirement in it, so this bounds those too.
+pub const MAX_OPERATORS: usize = 256;
+
+/// Why `parse_requirement` rejected an expression. Positions are byte offsets into the input.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The expression ended where we expected a requirement or an opening parenthesis.
+    UnexpectedEnd,
+    /// A character that can't start a token, or a token in the wrong place.
+    Unexpected(usize),
+    /// A word that isn't the name of a requirement.
+    UnknownName(usize),
+    /// An opening parenthesis that's never closed.
+    Unclosed(usize),
+    /// A negation, which only `parse_requirement_with_negation` accepts.
+    Negation(usize),
+    /// The expression nests deeper than `MAX_DEPTH` here.
+    TooDeep(usize),
+    /// The expression has more than `MAX_OPERATORS` operators, the last of them here.
+    TooLong(usize),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedEnd => write!(f, "unexpected end of expression"),
+            ParseError::Unexpected(at) => write!(f, "unexpected token at {}", at),
+            ParseError::UnknownName(at) => write!(f, "unknown requirement at {}", at),
+            ParseError::Unclosed(at) => write!(f, "parenthesis at {} is never closed", at),
+            ParseError::Negation(at) => write!(f, "negation at {} isn't allowed", at),
+            ParseError::TooDeep(at) => {
+                write!(f, "expression nests deeper than {} at {}", MAX_DEPTH, at)
+            }
+            ParseError::TooLong(at) => {
+                write!(f, "more than {} operators at {}", MAX_OPERATORS, at)
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Token<'a> {
+    Word(&'a str),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+    negation: bool,
+    // How many parentheses and negations we're inside of.
+    nesting: usize,
+    operators: usize,
+}
+
+/// Parse a requirement expression. Requirements are named by the items they check (`boots`,
+/// `gloves`, `flute` and `hammer`), the tiers they check (`normal`, `hard` and `glitched`), the
+/// settings they check (`swordless` and `keysanity`), the events they wait for (`agahnim` and
+/// `crystal_switch`) or are `open` or `locked`, case-insensitively. They combine
+/// with `&` (or `and`) and `|` (or `or`), where `&` binds tighter, and can be grouped with
+/// parentheses.
+///
+/// Negations are rejected since anything using them has to give up on logic being monotone. See
+/// `parse_requirement_with_negation`.
+pub fn parse_requirement(text: &str) -> Result<ReqExpr, ParseError> {
+    parse(text, false)
+}
+
+/// Parse a requirement expression that can also negate any requirement or group with `!` (or
+/// `not`), which binds tighter than `&`, e.g. `hammer & !agahnim` for an entrance that closes
+/// once Agahnim is beaten. Collecting something can close an edge using a negation, so a graph
+/// with one reports it from `StaticGraph::is_monotone` and caches that rely on monotonicity
+/// have to check that before taking shortcuts.
+pub fn parse_requirement_with_negation(text: &str) -> Result<ReqExpr, ParseError> {
+    parse(text, true)
+}
+
+fn parse(text: &str, negation: bool) -> Result<ReqExpr, ParseError> {
+    let mut parser = Parser {
+        text,
+        pos: 0,
+        negation,
+        nesting: 0,
+        operators: 0,
+    };
+    let expr = parser.or()?;
+    match parser.next()? {
+        None => Ok(expr),
+        Some((at, _)) => Err(ParseError::Unexpected(at)),
+    }
+}
+
+impl<'a> Parser<'a> {
+    // `(offset, length, token)` for the next token without consuming it.
+    fn peek(&self) -> Result<Option<(usize, usize, Token<'a>)>, ParseError> {
+        let rest = &self.text[self.pos..];
+        let at = self.pos + (rest.len() - rest.trim_start().len());
+        let rest = &self.text[at..];
+        let (len, token) = match rest.chars().next() {
+            None => return Ok(None),
+            Some('&') => (1, Token::And),
+            Some('|') => (1, Token::Or),
+            Some('!') => (1, Token::Not),
+            Some('(') => (1, Token::Open),
+            Some(')') => (1, Token::Close),
+            Some(c) if c.is_ascii_alphabetic() => {
+                let len = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len());
+                match &rest[..len] {
+                    w if w.eq_ignore_ascii_case("and") => (len, Token::And),
+                    w if w.eq_ignore_ascii_case("or") => (len, Token::Or),
+                    w if w.eq_ignore_ascii_case("not") => (len, Token::Not),
+                    w => (len, Token::Word(w)),
+                }
+            }
+            Some(_) => return Err(ParseError::Unexpected(at)),
+        };
+
+        Ok(Some((at, len, token)))
+    }
+
+    fn next(&mut self) -> Result<Option<(usize, Token<'a>)>, ParseError> {
+        let token = self.peek()?;
+        if let Some((at, len, _)) = token {
+            self.pos = at + len;
+        }
+
+        Ok(token.map(|(at, _, t)| (at, t)))
+    }
+
+    // Consume an operator we peeked at `at`, if the expression doesn't have too many already.
+    fn operator(&mut self, at: usize) -> Result<(), ParseError> {
+        if self.operators == MAX_OPERATORS {
+            return Err(ParseError::TooLong(at));
+        }
+        self.operators += 1;
+        self.next()?;
+
+        Ok(())
+    }
+
+    fn or(&mut self) -> Result<ReqExpr, ParseError> {
+        let mut expr = self.and()?;
+        while let Some((at, _, Token::Or)) = self.peek()? {
+            self.operator(at)?;
+            expr = ReqExpr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<ReqExpr, ParseError> {
+        let mut expr = self.atom()?;
+        while let Some((at, _, Token::And)) = self.peek()? {
+            self.operator(at)?;
+            expr = ReqExpr::And(Box::new(expr), Box::new(self.atom()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn atom(&mut self) -> Result<ReqExpr, ParseError> {
+        match self.next()? {
+            None => Err(ParseError::UnexpectedEnd),
+            Some((at, Token::Open)) => {
+                let expr = self.nested(at, Self::or)?;
+                match self.next()? {
+                    Some((_, Token::Close)) => Ok(expr),
+                    Some((at, _)) => Err(ParseError::Unexpected(at)),
+                    None => Err(ParseError::Unclosed(at)),
+                }
+            }
+            Some((at, Token::Not)) => match self.negation {
+                true => Ok(self.nested(at, Self::atom)?.negate()),
+                false => Err(ParseError::Negation(at)),
+            },
+            Some((at, Token::Word(word))) => requirement(word)
+                .map(ReqExpr::Req)
+                .ok_or(ParseError::UnknownName(at)),
+            Some((at, _)) => Err(ParseError::Unexpected(at)),
+        }
+    }
+
+    // Parse what's inside a parenthesis or negation at `at`, if we aren't nested too deep already.
+    fn nested<R>(
+        &mut self,
+        at: usize,
+        parse: impl FnOnce(&mut Self) -> Result<R, ParseError>,
+    ) -> Result<R, ParseError> {
+        if self.nesting == MAX_DEPTH {
+            return Err(ParseError::TooDeep(at));
+        }
+        self.nesting += 1;
+        let result = parse(self);
+        self.nesting -= 1;
+
+        result
+    }
+}
+
+// The name each requirement goes by in plain text, matched case-insensitively.
+const REQUIREMENT_NAMES: [(&str, Requirement); 13] = [
+    ("open", Requirement::Open),
+    ("locked", Requirement::Locked),
+    ("boots", Requirement::Boots),
+    ("gloves", Requirement::Gloves),
+    ("flute", Requirement::Flute),
+    ("hammer", Requirement::Hammer),
+    ("normal", Requirement::Tier(Tier::Normal)),
+    ("hard", Requirement::Tier(Tier::Hard)),
+    ("glitched", Requirement::Tier(Tier::Glitched)),
+    ("swordless", Requirement::Setting(SettingFlag::Swordless)),
+    ("keysanity", Requirement::Setting(SettingFlag::Keysanity)),
+    ("agahnim", Requirement::Event(EventId::Agahnim)),
+    ("crystal_switch", Requirement::Event(EventId::CrystalSwitch)),
+];
+
+/// The requirement named `word`, or negated with a leading `!`.
+pub(crate) fn requirement(word: &str) -> Option<Requirement> {
+    if let Some(word) = word.strip_prefix('!') {
+        return requirement(word).map(|req| req.negate());
+    }
+    REQUIREMENT_NAMES
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(word))
+        .map(|&(_, req)| req)
+}
+
+/// Write the name `parse_requirement_with_negation` knows `req` by, and `requirement` reads back.
+pub(crate) fn write_requirement_name(out: &mut impl fmt::Write, req: Requirement) -> fmt::Result {
+    match req {
+        Requirement::Not(negated) => write!(out, "!{}", requirement_name(negated.requirement())),
+        req => write!(out, "{}", requirement_name(req)),
+    }
+}
+
+/// The name `parse_requirement` knows `req` by. Negations don't have one of their own, see
+/// `write_requirement_name`.
+pub(crate) fn requirement_name(req: Requirement) -> &'static str {
+    REQUIREMENT_NAMES
+        .iter()
+        .find(|&&(_, r)| r == req)
+        .map(|&(name, _)| name)
+        // Every requirement besides a negation has a name.
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::{NaryNode, Negatable};
+
+    #[test]
+    fn parse_and_resolve() {
+        let tree = |s: &str| parse_requirement(s).unwrap().tree().map(|r| r.get());
+        assert_eq!(tree("open"), Some(0));
+        assert_eq!(tree("Locked"), Some(1));
+        assert_eq!(tree("boots | hammer"), Some(2));
+        assert_eq!(tree("hammer or boots"), Some(2));
+        assert_eq!(tree("hammer"), Some(3));
+        assert_eq!(tree("(gloves) AND hammer"), Some(5));
+        assert_eq!(tree("hammer & gloves | hammer & gloves"), Some(5));
+        assert_eq!(tree("flute"), Some(6));
+        assert_eq!(tree("Swordless"), Some(11));
+        assert_eq!(tree("boots & flute"), None);
+
+        // `&` binds tighter than `|`.
+        assert_eq!(
+            parse_requirement("boots | gloves & hammer").unwrap(),
+            ReqExpr::Or(
+                Box::new(ReqExpr::Req(Requirement::Boots)),
+                Box::new(ReqExpr::And(
+                    Box::new(ReqExpr::Req(Requirement::Gloves)),
+                    Box::new(ReqExpr::Req(Requirement::Hammer)),
+                )),
+            )
+        );
+
+        assert_eq!(parse_requirement(""), Err(ParseError::UnexpectedEnd));
+        assert_eq!(parse_requirement("boots &"), Err(ParseError::UnexpectedEnd));
+        assert_eq!(
+            parse_requirement("boots sword"),
+            Err(ParseError::Unexpected(6))
+        );
+        assert_eq!(parse_requirement("sword"), Err(ParseError::UnknownName(0)));
+        assert_eq!(parse_requirement("(boots"), Err(ParseError::Unclosed(0)));
+        assert_eq!(
+            parse_requirement("boots + gloves"),
+            Err(ParseError::Unexpected(6))
+        );
+    }
+
+    #[test]
+    fn nesting_limit() {
+        let nested = |depth: usize| std::format!("{}boots{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(parse_requirement(&nested(MAX_DEPTH)).is_ok());
+        assert_eq!(
+            parse_requirement(&nested(MAX_DEPTH + 1)),
+            Err(ParseError::TooDeep(MAX_DEPTH))
+        );
+        // Far past the limit, as a hostile model file might be, fails the same way.
+        let huge = nested(100_000);
+        assert_eq!(
+            parse_requirement(&huge),
+            Err(ParseError::TooDeep(MAX_DEPTH))
+        );
+        let negated = std::format!("{}boots", "!".repeat(100_000));
+        assert_eq!(
+            parse_requirement_with_negation(&negated),
+            Err(ParseError::TooDeep(MAX_DEPTH))
+        );
+        // A long chain of operators builds a tree as deep as it is long, so it's capped too.
+        let chain = |terms: usize| std::vec!["boots"; terms].join(" & ");
+        assert!(parse_requirement(&chain(MAX_OPERATORS + 1)).is_ok());
+        let too_long = chain(100_000);
+        let last = (MAX_OPERATORS + 1) * "boots & ".len() - 2;
+        assert_eq!(parse_requirement(&too_long), Err(ParseError::TooLong(last)));
+    }
+
+    #[test]
+    fn negation() {
+        assert_eq!(
+            parse_requirement("hammer & !agahnim"),
+            Err(ParseError::Negation(9))
+        );
+        assert_eq!(parse_requirement("not boots"), Err(ParseError::Negation(0)));
+
+        let parse = |s: &str| parse_requirement_with_negation(s).unwrap();
+        assert_eq!(parse("!agahnim").tree().map(|r| r.get()), Some(15));
+        assert_eq!(parse("NOT agahnim").tree().map(|r| r.get()), Some(15));
+        assert_eq!(parse("!!hammer").tree().map(|r| r.get()), Some(3));
+        assert_eq!(parse("!open").tree().map(|r| r.get()), Some(1));
+        assert!(!parse("boots | hammer").has_negation());
+        assert!(parse("boots & !flute").has_negation());
+
+        // `!` binds tighter than `&` and pushes down through groups.
+        let expr = parse("!(boots | hammer) & gloves");
+        for state in all_states() {
+            let expected = !(state.boots || state.hammer) && state.gloves;
+            assert_eq!(expr.eval(&state), expected);
+        }
+        let mut builder = ReqBuilder::<32>::from_trees(&crate::logic::REQ_TREES);
+        let root = expr.build(&mut builder).unwrap();
+        for state in all_states() {
+            let satisfied = |req: Requirement| state.eval_requirement(req);
+            assert_eq!(builder.eval(root, satisfied), expr.eval(&state));
+        }
+        assert!(crate::logic::non_monotone_in(&builder, root).is_some());
+
+        // Negated names read back for certificates.
+        assert_eq!(
+            requirement("!Agahnim"),
+            Some(Requirement::Not(Negatable::Event(EventId::Agahnim)))
+        );
+        let mut name = alloc::string::String::new();
+        write_requirement_name(&mut name, Requirement::Boots.negate()).unwrap();
+        assert_eq!(name, "!boots");
+    }
+
+    #[test]
+    fn nary_trees() {
+        let mut trees = NaryTrees::new();
+        let expr = parse_requirement("boots & gloves | flute & hammer").unwrap();
+        let root = expr.nary(&mut trees);
+        // One `Any`, two `All`s and four leaves, without repeating anything.
+        assert_eq!(trees.len(), 7);
+        assert!(matches!(trees.node(root), NaryNode::Any { len: 2, .. }));
+        for state in all_states() {
+            assert_eq!(state.eval_nary(&trees, root), expr.eval(&state));
+        }
+
+        // Chains flatten and shared subexpressions are only stored once.
+        let chain = parse_requirement("boots & (gloves & flute) & hammer").unwrap();
+        let chain = chain.nary(&mut trees);
+        assert_eq!(trees.children(chain).len(), 4);
+        let before = trees.len();
+        let a = parse_requirement("(boots | hammer) & gloves").unwrap();
+        let b = parse_requirement("gloves & (hammer | boots)").unwrap();
+        assert_eq!(a.nary(&mut trees), b.nary(&mut trees));
+        assert_eq!(trees.len(), before + 2);
+        let root = parse_requirement("(hammer | boots) & gloves | (boots | hammer) & flute")
+            .unwrap()
+            .nary(&mut trees);
+        assert_eq!(trees.len(), before + 4);
+        for state in all_states() {
+            let expected = (state.gloves || state.flute) && (state.boots || state.hammer);
+            assert_eq!(state.eval_nary(&trees, root), expected);
+        }
+    }
+
+    #[test]
+    fn hash_consed_trees() {
+        let mut builder = ReqBuilder::<32>::from_trees(&crate::logic::REQ_TREES);
+        let mut build = |s: &str| parse_requirement(s).unwrap().build(&mut builder);
+        // Expressions our trees already cover find them rather than adding anything.
+        assert_eq!(build("hammer"), Some(3));
+        assert_eq!(build("gloves & hammer"), Some(5));
+        assert_eq!(build("boots | hammer"), Some(2));
+        assert_eq!(build("open & locked | hammer & open"), Some(3));
+        assert_eq!(build("boots | open"), Some(0));
+        assert_eq!(builder.len(), REQ_CONTAINER_LEN);
+
+        let text = "boots & gloves | flute & hammer";
+        let root = parse_requirement(text)
+            .unwrap()
+            .build(&mut builder)
+            .unwrap();
+        let len = builder.len();
+        let again = parse_requirement(text).unwrap().build(&mut builder);
+        assert_eq!(again, Some(root));
+        assert_eq!(builder.len(), len);
+        let expr = parse_requirement(text).unwrap();
+        for state in all_states() {
+            let satisfied = |req: Requirement| match req {
+                Requirement::Open => true,
+                Requirement::Locked => false,
+                req => req.item().is_some_and(|i| state.has(i)),
+            };
+            assert_eq!(builder.eval(root, satisfied), expr.eval(&state));
+        }
+
+        // Copying our own trees into an empty builder shares the hammer node between them.
+        let mut fresh = ReqBuilder::<32>::new();
+        for tree in 2..REQ_CONTAINER_LEN as u16 {
+            fresh.insert_tree(&crate::logic::REQ_TREES, tree).unwrap();
+        }
+        assert_eq!(fresh.len(), REQ_CONTAINER_LEN);
+
+        // A full builder gives up instead of growing.
+        let mut small = ReqBuilder::<3>::new();
+        assert_eq!(
+            parse_requirement("boots & hammer")
+                .unwrap()
+                .build(&mut small),
+            None
+        );
+    }
+}
@@ -0,0 +1,920 @@
+use alloc::{boxed::Box, vec::Vec};
+#[cfg(not(feature = "parallel"))]
+use core::cell::RefCell;
+use core::{
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+#[cfg(feature = "parallel")]
+use std::sync::Mutex;
+
+use crate::{
+    graph::StaticGraph,
+    logic::{CollectionState, Item},
+    region::Region,
+    rng,
+    tracker::TrackerSession,
+};
+
+/// Where each progression item went, as `(location node, item)` in the order they were placed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Placement(pub Vec<(u16, Item)>);
+
+impl Placement {
+    pub fn location_of(&self, item: Item) -> Option<u16> {
+        self.0.iter().find(|&&(_, i)| i == item).map(|&(l, _)| l)
+    }
+
+    pub fn item_at(&self, location: u16) -> Option<Item> {
+        self.0
+            .iter()
+            .find(|&&(l, _)| l == location)
+            .map(|&(_, i)| i)
+    }
+
+    // Exchange whatever is at two locations, where nothing at all stands for a junk item.
+    fn swapped(&self, a: u16, b: u16) -> Placement {
+        Placement(
+            self.0
+                .iter()
+                .map(|&(l, item)| match l {
+                    l if l == a => (b, item),
+                    l if l == b => (a, item),
+                    l => (l, item),
+                })
+                .collect(),
+        )
+    }
+}
+
+/// One step of a playthrough: every placed item we can collect with the items from the spheres
+/// before it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Sphere {
+    pub items: Vec<(u16, Item)>,
+    /// How many of the search's locations first became reachable in this sphere.
+    pub locations: usize,
+}
+
+/// A single planned placement ("plando") constraint.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PlandoConstraint {
+    /// The item must be placed at the location.
+    Fixed(Item, u16),
+    /// The item must not be placed at the location.
+    Excluded(Item, u16),
+}
+
+impl fmt::Display for PlandoConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlandoConstraint::Fixed(item, l) => write!(f, "{} must be at node {}", item, l),
+            PlandoConstraint::Excluded(item, l) => write!(f, "{} must not be at node {}", item, l),
+        }
+    }
+}
+
+/// User-specified placements and exclusions for a `PlacementSearch`. See
+/// `PlacementSearch::with_plando`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Plando {
+    pub constraints: Vec<PlandoConstraint>,
+}
+
+impl Plando {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require `item` to be placed at `location`.
+    pub fn place(mut self, item: Item, location: u16) -> Self {
+        self.constraints
+            .push(PlandoConstraint::Fixed(item, location));
+        self
+    }
+
+    /// Forbid placing `item` at `location`.
+    pub fn exclude(mut self, item: Item, location: u16) -> Self {
+        self.constraints
+            .push(PlandoConstraint::Excluded(item, location));
+        self
+    }
+}
+
+/// Why `PlacementSearch::with_plando` rejected a set of constraints.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PlandoError {
+    /// The constraint names an item the search isn't placing.
+    NotPlaced(PlandoConstraint),
+    /// The constraint names a node that isn't one of the search's locations.
+    NotALocation(PlandoConstraint),
+    /// The two constraints can't both hold, e.g. an item fixed at two locations.
+    Conflict(PlandoConstraint, PlandoConstraint),
+    /// The fixed placement's location can't be reached even with every other item collected.
+    Unreachable(PlandoConstraint),
+    /// There's nowhere left to put the item once the constraints are applied.
+    NoLocation(Item),
+    /// The goal can't be reached even with every item collected.
+    GoalUnreachable,
+}
+
+impl fmt::Display for PlandoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlandoError::NotPlaced(c) => write!(f, "{}: item isn't being placed", c),
+            PlandoError::NotALocation(c) => write!(f, "{}: node isn't a location", c),
+            PlandoError::Conflict(a, b) => write!(f, "{} conflicts with {}", a, b),
+            PlandoError::Unreachable(c) => write!(f, "{}: location is unreachable", c),
+            PlandoError::NoLocation(item) => write!(f, "nowhere left to place {}", item),
+            PlandoError::GoalUnreachable => write!(f, "goal is unreachable"),
+        }
+    }
+}
+
+/// An `(item, location)` pair the search could try next.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Candidate {
+    pub item: Item,
+    pub location: u16,
+    pub region: Option<Region>,
+    /// How many items were placed before this one, a rough measure of how far into the
+    /// playthrough the location is.
+    pub placed: usize,
+}
+
+/// Shapes which of the reachable candidate locations a `PlacementSearch` tries first, so a
+/// seed's "flavor" (progression early or late, dungeon items in their dungeon...) can be tuned
+/// without touching the search itself.
+///
+/// Candidates are tried in a random order where a candidate with twice the weight is twice as
+/// likely to come first. Candidates with a weight of zero are only tried once every other one
+/// has failed. A bias only ever changes the order, so it can't make the search miss a beatable
+/// placement.
+pub trait LocationBias: Sync {
+    fn weight(&self, candidate: &Candidate) -> u32;
+}
+
+/// Every candidate is equally likely.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Uniform;
+
+impl LocationBias for Uniform {
+    fn weight(&self, _candidate: &Candidate) -> u32 {
+        1
+    }
+}
+
+impl<F: Fn(&Candidate) -> u32 + Sync> LocationBias for F {
+    fn weight(&self, candidate: &Candidate) -> u32 {
+        self(candidate)
+    }
+}
+
+/// A branch-and-bound search for a beatable placement of progression items, for fills where
+/// simply placing items at random and retrying keeps failing (plando constraints, restricted
+/// logic, small location pools...)
+///
+/// We fill forward: every item goes to a location that's reachable with the items placed before
+/// it, so any complete placement we find can be collected in placement order. Each branch tries
+/// one more `(item, location)` pair on its own clone of a `TrackerSession`, so collecting the
+/// item is an incremental search. Before branching we check the partial placement against the
+/// most permissive completion, where every item still to be placed is assumed collected. If the
+/// goal or some free location allowed for each remaining item isn't reachable even then, no
+/// completion can be beatable and we prune the whole branch.
+///
+/// Fixed placements from a `Plando` are pre-placed: we collect them as soon as their location
+/// becomes reachable rather than branching on them.
+///
+/// Which candidate we try first is up to a `LocationBias` and the search's seed. With the
+/// `parallel` feature, branches near the root of the search are explored in parallel on rayon's
+/// thread pool and the rest sequentially in each thread. The first placement found by any thread
+/// wins, so the result is only deterministic for a seed with a parallel depth of zero. Without it
+/// every branch is explored in order and the parallel depth is ignored.
+pub struct PlacementSearch<'graph, const M: usize, const N: usize, T = ()> {
+    graph: &'graph StaticGraph<M, N, T>,
+    items: Vec<Item>,
+    locations: Vec<u16>,
+    goal: u16,
+    parallel_depth: usize,
+    fixed: Vec<(u16, Item)>,
+    excluded: Vec<(u16, Item)>,
+    bias: Box<dyn LocationBias + 'graph>,
+    seed: u64,
+}
+
+// What every branch of one search shares. Parallel branches race to store what they find, so the
+// result needs a lock, while a sequential search only ever runs one branch at a time.
+struct Shared<'a, F> {
+    allowed: &'a F,
+    found: AtomicBool,
+    #[cfg(feature = "parallel")]
+    result: Mutex<Option<Placement>>,
+    #[cfg(not(feature = "parallel"))]
+    result: RefCell<Option<Placement>>,
+}
+
+impl<'a, F> Shared<'a, F> {
+    fn new(allowed: &'a F) -> Self {
+        Shared {
+            allowed,
+            found: AtomicBool::new(false),
+            result: Default::default(),
+        }
+    }
+
+    // Keep the first placement any branch finds and tell the others to stop. Returns whether
+    // this one was the first.
+    fn store(&self, placement: Placement) -> bool {
+        #[cfg(feature = "parallel")]
+        let mut result = self.result.lock().unwrap();
+        #[cfg(not(feature = "parallel"))]
+        let mut result = self.result.borrow_mut();
+        let first = result.is_none();
+        if first {
+            *result = Some(placement);
+        }
+        self.found.store(true, Ordering::Relaxed);
+
+        first
+    }
+
+    fn into_result(self) -> Option<Placement> {
+        #[cfg(feature = "parallel")]
+        return self.result.into_inner().unwrap();
+        #[cfg(not(feature = "parallel"))]
+        return self.result.into_inner();
+    }
+}
+
+impl<'graph, const M: usize, const N: usize, T: Sync> PlacementSearch<'graph, M, N, T> {
+    /// How many levels of the search tree branch in parallel unless told otherwise.
+    pub const DEFAULT_PARALLEL_DEPTH: usize = 2;
+
+    /// Get a search placing each of `items` at one of the graph's item locations so that `goal`
+    /// is reachable. Any progression item we aren't placing is assumed to be collected from the
+    /// start, and junk or repeated items are ignored.
+    pub fn new(graph: &'graph StaticGraph<M, N, T>, items: &[Item], goal: u16) -> Self {
+        let mut unique: Vec<Item> = Vec::with_capacity(items.len());
+        items
+            .iter()
+            .filter(|i| i.is_progression())
+            .for_each(|&i| match unique.contains(&i) {
+                true => (),
+                false => unique.push(i),
+            });
+
+        PlacementSearch {
+            graph,
+            items: unique,
+            locations: (1..=graph.node_count)
+                .filter(|&n| graph.item_data(n).is_some())
+                .collect(),
+            goal,
+            parallel_depth: Self::DEFAULT_PARALLEL_DEPTH,
+            fixed: Vec::new(),
+            excluded: Vec::new(),
+            bias: Box::new(Uniform),
+            seed: 0,
+        }
+    }
+
+    /// Only place items at `locations` instead of every item location in the graph.
+    pub fn with_locations(mut self, locations: &[u16]) -> Self {
+        self.locations = locations.to_vec();
+        self
+    }
+
+    pub fn with_parallel_depth(mut self, depth: usize) -> Self {
+        self.parallel_depth = depth;
+        self
+    }
+
+    pub fn with_bias(mut self, bias: impl LocationBias + 'graph) -> Self {
+        self.bias = Box::new(bias);
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Apply planned placements and exclusions, replacing any applied before. We check that each
+    /// constraint makes sense on its own and against the others, and that the constraints don't
+    /// already rule out every beatable placement under the most permissive completion. That's
+    /// the same bound the search prunes with, so it can't catch everything; the search returning
+    /// None is the only complete check.
+    pub fn with_plando(mut self, plando: &Plando) -> Result<Self, PlandoError> {
+        self.fixed.clear();
+        self.excluded.clear();
+        for (i, &c) in plando.constraints.iter().enumerate() {
+            let (PlandoConstraint::Fixed(item, l) | PlandoConstraint::Excluded(item, l)) = c;
+            if !self.items.contains(&item) {
+                return Err(PlandoError::NotPlaced(c));
+            }
+            if !self.locations.contains(&l) {
+                return Err(PlandoError::NotALocation(c));
+            }
+            let conflict = plando.constraints[..i]
+                .iter()
+                .find(|&&other| match (c, other) {
+                    (PlandoConstraint::Fixed(a, x), PlandoConstraint::Fixed(b, y)) => {
+                        (a == b) != (x == y)
+                    }
+                    (PlandoConstraint::Fixed(a, x), PlandoConstraint::Excluded(b, y))
+                    | (PlandoConstraint::Excluded(a, x), PlandoConstraint::Fixed(b, y)) => {
+                        a == b && x == y
+                    }
+                    (PlandoConstraint::Excluded(..), PlandoConstraint::Excluded(..)) => false,
+                });
+            if let Some(&other) = conflict {
+                return Err(PlandoError::Conflict(other, c));
+            }
+            let list = match c {
+                PlandoConstraint::Fixed(..) => &mut self.fixed,
+                PlandoConstraint::Excluded(..) => &mut self.excluded,
+            };
+            if !list.contains(&(l, item)) {
+                list.push((l, item));
+            }
+        }
+
+        // The root of the search, with every item we'd branch on assumed collected.
+        let mut assumed = self.session();
+        let mut placed = Vec::new();
+        self.remaining(&placed).for_each(|i| {
+            assumed.collect(i);
+        });
+        self.sweep_fixed(&mut assumed, &mut placed);
+        if let Some(&(l, item)) = self.fixed.iter().find(|f| !placed.contains(f)) {
+            return Err(PlandoError::Unreachable(PlandoConstraint::Fixed(item, l)));
+        }
+        if !assumed.is_reachable(self.goal) {
+            return Err(PlandoError::GoalUnreachable);
+        }
+        let allowed = |_, _| true;
+        let shared = Shared::new(&allowed);
+        let stuck = self.items.iter().copied().find(|&item| {
+            !self.fixed.iter().any(|&(_, i)| i == item)
+                && self
+                    .free_locations(&shared, &assumed, &placed, item)
+                    .next()
+                    .is_none()
+        });
+
+        match stuck {
+            Some(item) => Err(PlandoError::NoLocation(item)),
+            None => Ok(self),
+        }
+    }
+
+    /// Search for a beatable placement where `allowed(item, location)` holds for every placed
+    /// item. Returns None if there isn't one.
+    pub fn search<F>(&self, allowed: F) -> Option<Placement>
+    where
+        F: Fn(Item, u16) -> bool + Sync,
+    {
+        span!(
+            DEBUG,
+            "placement_search",
+            goal = self.goal,
+            items = self.items.len()
+        );
+        let shared = Shared::new(&allowed);
+        self.branch(&shared, self.session(), Vec::new(), 0);
+
+        shared.into_result()
+    }
+
+    /// Returns whether we can reach the goal by repeatedly collecting every item whose location
+    /// is reachable, starting from the same state a search would.
+    pub fn is_beatable(&self, placement: &Placement) -> bool {
+        if self
+            .items
+            .iter()
+            .any(|&i| placement.location_of(i).is_none())
+        {
+            return false;
+        }
+        let mut session = self.session();
+        let mut remaining = placement.0.clone();
+        loop {
+            let before = remaining.len();
+            remaining.retain(|&(location, item)| match session.is_reachable(location) {
+                true => {
+                    session.collect(item);
+                    false
+                }
+                false => true,
+            });
+            if remaining.len() == before {
+                break;
+            }
+        }
+
+        remaining.is_empty() && session.is_reachable(self.goal)
+    }
+
+    /// Split `placement` into spheres, stopping at the first sphere with nothing new to collect.
+    /// For a beatable placement every item ends up in some sphere.
+    pub fn spheres(&self, placement: &Placement) -> Vec<Sphere> {
+        let mut session = self.session();
+        let mut remaining = placement.0.clone();
+        let mut spheres: Vec<Sphere> = Vec::new();
+        let mut seen = 0;
+        loop {
+            let reachable = self
+                .locations
+                .iter()
+                .filter(|&&l| session.is_reachable(l))
+                .count();
+            let items: Vec<(u16, Item)>;
+            (items, remaining) = remaining
+                .into_iter()
+                .partition(|&(l, _)| session.is_reachable(l));
+            if items.is_empty() {
+                break;
+            }
+            items.iter().for_each(|&(_, i)| {
+                session.collect(i);
+            });
+            spheres.push(Sphere {
+                items,
+                locations: reachable.saturating_sub(seen),
+            });
+            seen = reachable;
+        }
+
+        spheres
+    }
+
+    /// A progression balancing pass. A sphere holding fewer than `min_items` items means the
+    /// playthrough hinges on very few checks, so we try swapping items from later spheres with
+    /// whatever is at the locations reachable at that point (another item or junk). A swap is
+    /// kept if the placement stays beatable and it makes the spheres less thin overall, or as thin
+    /// but fewer, and we keep going until no swap helps. Fixed placements never move, and every
+    /// item stays somewhere it's allowed.
+    ///
+    /// This takes the first improving swap it finds rather than the best one, so it's a cheap
+    /// polish for a placement from `search` and not an optimal rebalance.
+    pub fn balance<F>(&self, placement: &Placement, min_items: usize, allowed: F) -> Placement
+    where
+        F: Fn(Item, u16) -> bool,
+    {
+        let score = |p: &Placement| {
+            let spheres = self.spheres(p);
+            let thinness: usize = spheres
+                .iter()
+                .map(|s| min_items.saturating_sub(s.items.len()))
+                .sum();
+            (thinness, spheres.len())
+        };
+        let fixed = |l: u16| self.fixed.iter().any(|&(f, _)| f == l);
+        let may_hold = |item: Item, l: u16| !self.excluded.contains(&(l, item)) && allowed(item, l);
+        let mut best = placement.clone();
+        let mut best_score = score(&best);
+
+        'improve: while best_score.0 > 0 {
+            let spheres = self.spheres(&best);
+            let mut session = self.session();
+            for (k, sphere) in spheres.iter().enumerate() {
+                if sphere.items.len() < min_items {
+                    let early: Vec<u16> = self
+                        .locations
+                        .iter()
+                        .copied()
+                        .filter(|&l| session.is_reachable(l) && !fixed(l))
+                        .collect();
+                    let later = spheres[k + 1..].iter().flat_map(|s| s.items.iter());
+                    for &(from, item) in later.filter(|&&(l, _)| !fixed(l)) {
+                        for &to in early.iter().filter(|&&to| may_hold(item, to)) {
+                            match best.item_at(to) {
+                                Some(other) if !may_hold(other, from) => continue,
+                                _ => (),
+                            }
+                            let candidate = best.swapped(from, to);
+                            if !self.is_beatable(&candidate) {
+                                continue;
+                            }
+                            let candidate_score = score(&candidate);
+                            if candidate_score < best_score {
+                                best = candidate;
+                                best_score = candidate_score;
+                                continue 'improve;
+                            }
+                        }
+                    }
+                }
+                sphere.items.iter().for_each(|&(_, i)| {
+                    session.collect(i);
+                });
+            }
+            break;
+        }
+
+        best
+    }
+
+    // A session with none of the items we're placing. Branches never undo anything so they
+    // don't need a history.
+    fn session(&self) -> TrackerSession<'graph, M, N, T> {
+        let mut state = CollectionState::ALL_ITEMS;
+        self.items.iter().for_each(|&i| {
+            state.set(i, false);
+        });
+        let mut session = TrackerSession::new(self.graph, state);
+        session.set_history_limit(0);
+
+        session
+    }
+
+    // The items we still have to branch on.
+    fn remaining<'a>(&'a self, placed: &'a [(u16, Item)]) -> impl Iterator<Item = Item> + 'a {
+        self.items.iter().copied().filter(move |&item| {
+            !placed.iter().any(|&(_, i)| i == item) && !self.fixed.iter().any(|&(_, i)| i == item)
+        })
+    }
+
+    // Collect every fixed placement we can reach, including any that collecting one of the others
+    // makes reachable.
+    fn sweep_fixed(
+        &self,
+        session: &mut TrackerSession<'graph, M, N, T>,
+        placed: &mut Vec<(u16, Item)>,
+    ) {
+        while let Some(&(l, item)) = self
+            .fixed
+            .iter()
+            .find(|&&f| session.is_reachable(f.0) && !placed.contains(&f))
+        {
+            session.collect(item);
+            placed.push((l, item));
+        }
+    }
+
+    fn branch<F>(
+        &self,
+        shared: &Shared<'_, F>,
+        mut session: TrackerSession<'graph, M, N, T>,
+        mut placed: Vec<(u16, Item)>,
+        depth: usize,
+    ) where
+        F: Fn(Item, u16) -> bool + Sync,
+    {
+        if shared.found.load(Ordering::Relaxed) {
+            return;
+        }
+        self.sweep_fixed(&mut session, &mut placed);
+        let remaining: Vec<Item> = self.remaining(&placed).collect();
+        if remaining.is_empty() {
+            let complete = self.fixed.iter().all(|f| placed.contains(f));
+            if complete && session.is_reachable(self.goal) && shared.store(Placement(placed)) {
+                event!(DEBUG, depth, "found placement");
+            }
+            return;
+        }
+        if !self.may_complete(shared, &session, &placed, &remaining) {
+            event!(DEBUG, depth, placed = placed.len(), "pruned");
+            return;
+        }
+
+        let mut candidates: Vec<(f64, (u16, Item))> = remaining
+            .iter()
+            .flat_map(|&item| {
+                self.free_locations(shared, &session, &placed, item)
+                    .map(move |l| (l, item))
+            })
+            .map(|(location, item)| {
+                let candidate = Candidate {
+                    item,
+                    location,
+                    region: self.graph.region_of(location),
+                    placed: placed.len(),
+                };
+                (self.sort_key(&candidate), (location, item))
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let candidates = candidates.into_iter().map(|(_, c)| c);
+        let step = |(location, item): (u16, Item)| {
+            event!(DEBUG, depth, location, item = ?item, "place");
+            let mut session = session.clone();
+            session.collect(item);
+            let mut placed = placed.clone();
+            placed.push((location, item));
+            self.branch(shared, session, placed, depth + 1);
+        };
+        match depth < self.parallel_depth {
+            #[cfg(feature = "parallel")]
+            true => {
+                let step = &step;
+                rayon::scope(|s| candidates.for_each(|c| s.spawn(move |_| step(c))));
+            }
+            _ => candidates.for_each(step),
+        }
+    }
+
+    // A weighted random shuffle (Efraimidis and Spirakis): sorting by `u^(1/w)` for a uniform
+    // `u` in (0, 1) puts candidates first in proportion to their weight. We take `u` from a hash
+    // of the seed and the candidate instead of a shared RNG so the order doesn't depend on which
+    // thread gets there first.
+    fn sort_key(&self, candidate: &Candidate) -> f64 {
+        let x = rng::mix(
+            (self.seed
+                ^ ((candidate.placed as u64) << 32)
+                ^ ((candidate.location as u64) << 8)
+                ^ candidate.item as u64)
+                .wrapping_add(0x9E3779B97F4A7C15),
+        );
+        let u = ((x >> 11) + 1) as f64 / (1u64 << 53) as f64;
+        match self.bias.weight(candidate) {
+            0 => f64::NEG_INFINITY,
+            w => rng::ln(u) / w as f64,
+        }
+    }
+
+    // Our bound. Collecting the remaining items can only make more reachable, so if the goal or
+    // somewhere to put each remaining item isn't reachable with all of them, it never will be.
+    fn may_complete<F>(
+        &self,
+        shared: &Shared<'_, F>,
+        session: &TrackerSession<'graph, M, N, T>,
+        placed: &[(u16, Item)],
+        remaining: &[Item],
+    ) -> bool
+    where
+        F: Fn(Item, u16) -> bool + Sync,
+    {
+        let mut assumed = session.clone();
+        remaining.iter().for_each(|&i| {
+            assumed.collect(i);
+        });
+        let mut placed = placed.to_vec();
+        self.sweep_fixed(&mut assumed, &mut placed);
+
+        assumed.is_reachable(self.goal)
+            && self.fixed.iter().all(|f| placed.contains(f))
+            && remaining.iter().all(|&item| {
+                self.free_locations(shared, &assumed, &placed, item)
+                    .next()
+                    .is_some()
+            })
+    }
+
+    // Reachable locations without an item yet, or one planned for them, that `item` is allowed
+    // at.
+    fn free_locations<'a, F>(
+        &'a self,
+        shared: &'a Shared<'_, F>,
+        session: &'a TrackerSession<'graph, M, N, T>,
+        placed: &'a [(u16, Item)],
+        item: Item,
+    ) -> impl Iterator<Item = u16> + 'a
+    where
+        F: Fn(Item, u16) -> bool + Sync,
+    {
+        self.locations.iter().copied().filter(move |&l| {
+            session.is_reachable(l)
+                && !placed.iter().any(|&(p, _)| p == l)
+                && !self.fixed.iter().any(|&(p, _)| p == l)
+                && !self.excluded.contains(&(l, item))
+                && (shared.allowed)(item, l)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{constants::*, graph::new_static_graph};
+
+    type Graph = StaticGraph<NUM_VERTICES_PADDED, NUM_EDGES_PADDED>;
+    type Session<'graph> = TrackerSession<'graph, NUM_VERTICES_PADDED, NUM_EDGES_PADDED>;
+
+    const ITEMS: [Item; 2] = [Item::Gloves, Item::Hammer];
+
+    // Sessions placing `ITEMS` before we have either of them, once we have the hammer and once we
+    // have both, and a goal only reachable with both.
+    struct Fixture<'graph> {
+        at_start: Session<'graph>,
+        with_hammer: Session<'graph>,
+        with_all: Session<'graph>,
+        goal: u16,
+    }
+
+    fn fixture(graph: &Graph) -> Fixture<'_> {
+        let mut start = CollectionState::ALL_ITEMS;
+        ITEMS.iter().for_each(|&i| {
+            start.set(i, false);
+        });
+        let at_start = TrackerSession::new(graph, start);
+        let mut with_hammer = at_start.clone();
+        with_hammer.collect(Item::Hammer);
+        let mut with_all = with_hammer.clone();
+        with_all.collect(Item::Gloves);
+        let goal = (1..=graph.node_count)
+            .find(|&n| with_all.is_reachable(n) && !with_hammer.is_reachable(n))
+            .unwrap();
+
+        Fixture {
+            at_start,
+            with_hammer,
+            with_all,
+            goal,
+        }
+    }
+
+    #[test]
+    fn branch_and_bound_placement() {
+        let graph = new_static_graph();
+        let Fixture {
+            at_start,
+            with_hammer,
+            goal,
+            ..
+        } = fixture(&graph);
+        // Locations only reachable once we have the hammer, so the search has to place it first.
+        let behind_hammer: Vec<u16> = with_hammer
+            .reachable_checks()
+            .into_iter()
+            .filter(|&l| !at_start.is_reachable(l))
+            .collect();
+        assert!(!behind_hammer.is_empty());
+
+        for depth in [0, PlacementSearch::<0, 0>::DEFAULT_PARALLEL_DEPTH] {
+            let search = PlacementSearch::new(&graph, &ITEMS, goal).with_parallel_depth(depth);
+            let placement = search
+                .search(|item, l| item == Item::Hammer || behind_hammer.contains(&l))
+                .unwrap();
+            assert!(search.is_beatable(&placement));
+            assert!(behind_hammer.contains(&placement.location_of(Item::Gloves).unwrap()));
+        }
+
+        // Nowhere to put the gloves, or nowhere reachable to put anything.
+        let search = PlacementSearch::new(&graph, &ITEMS, goal);
+        assert_eq!(search.search(|item, _| item != Item::Gloves), None);
+        let search = search.with_locations(&behind_hammer);
+        assert_eq!(search.search(|_, _| true), None);
+        assert!(!search.is_beatable(&Placement::default()));
+    }
+
+    #[test]
+    fn location_bias() {
+        let graph = new_static_graph();
+        let Fixture { at_start, goal, .. } = fixture(&graph);
+        let open = at_start.reachable_checks();
+        let search = || PlacementSearch::new(&graph, &ITEMS, goal).with_parallel_depth(0);
+
+        // Different seeds shuffle the candidates differently.
+        let placements: Vec<Placement> = (0..8)
+            .map(|seed| search().with_seed(seed).search(|_, _| true).unwrap())
+            .collect();
+        assert!(placements.windows(2).any(|w| w[0] != w[1]));
+        assert_eq!(
+            search().with_seed(3).search(|_, _| true),
+            Some(placements[3].clone())
+        );
+
+        // Only one location is weighted for the hammer, so it's always tried first.
+        let favorite = open[open.len() / 2];
+        let bias = move |c: &Candidate| match c.item {
+            Item::Hammer => (c.location == favorite) as u32,
+            _ => (c.location != favorite) as u32,
+        };
+        for seed in 0..4 {
+            let placement = search()
+                .with_bias(bias)
+                .with_seed(seed)
+                .search(|_, _| true)
+                .unwrap();
+            assert_eq!(placement.location_of(Item::Hammer), Some(favorite));
+        }
+
+        // A region bias keeps the gloves in the Light World when it can.
+        let light_world = |c: &Candidate| match c.region {
+            Some(Region::LightWorld) => 1,
+            _ => 0,
+        };
+        let placement = search().with_bias(light_world).search(|_, _| true).unwrap();
+        let gloves = placement.location_of(Item::Gloves).unwrap();
+        assert_eq!(graph.region_of(gloves), Some(Region::LightWorld));
+    }
+
+    #[test]
+    fn progression_balancing() {
+        let graph = new_static_graph();
+        let Fixture {
+            at_start,
+            with_hammer,
+            goal,
+            ..
+        } = fixture(&graph);
+        let open = at_start.reachable_checks();
+        let behind_hammer = with_hammer
+            .reachable_checks()
+            .into_iter()
+            .find(|&l| !at_start.is_reachable(l))
+            .unwrap();
+
+        // A chain: the gloves are only reachable once we find the hammer.
+        let search = PlacementSearch::new(&graph, &ITEMS, goal);
+        let chain = Placement(std::vec![
+            (open[0], Item::Hammer),
+            (behind_hammer, Item::Gloves)
+        ]);
+        let spheres = search.spheres(&chain);
+        assert_eq!(spheres.len(), 2);
+        assert!(spheres.iter().all(|s| s.items.len() == 1));
+        assert_eq!(spheres[0].locations, open.len());
+        assert!(spheres[1].locations > 0);
+
+        let balanced = search.balance(&chain, 2, |_, _| true);
+        assert!(search.is_beatable(&balanced));
+        let spheres = search.spheres(&balanced);
+        assert_eq!(spheres.len(), 1);
+        assert_eq!(spheres[0].items.len(), 2);
+        // Nothing improves a sphere that's already thick enough.
+        assert_eq!(search.balance(&balanced, 2, |_, _| true), balanced);
+
+        // Fixed items stay where they are, and so does anything with nowhere else to go.
+        let plando = Plando::new().place(Item::Gloves, behind_hammer);
+        let search = search.with_plando(&plando).unwrap();
+        assert_eq!(search.balance(&chain, 2, |_, _| true), chain);
+    }
+
+    #[test]
+    fn plando_constraints() {
+        let graph = new_static_graph();
+        let Fixture {
+            at_start,
+            with_hammer,
+            with_all,
+            goal,
+        } = fixture(&graph);
+        let open = at_start.reachable_checks();
+        // Gloves can't be placed anywhere only the gloves can open.
+        let behind_gloves = with_all
+            .reachable_checks()
+            .into_iter()
+            .find(|&l| !with_hammer.is_reachable(l))
+            .unwrap();
+
+        let search = || PlacementSearch::new(&graph, &ITEMS, goal).with_parallel_depth(0);
+        let plando = Plando::new()
+            .place(Item::Hammer, open[0])
+            .exclude(Item::Gloves, open[1]);
+        let constrained = search().with_plando(&plando).unwrap();
+        let placement = constrained.search(|_, _| true).unwrap();
+        assert!(constrained.is_beatable(&placement));
+        assert_eq!(placement.location_of(Item::Hammer), Some(open[0]));
+        assert_ne!(placement.location_of(Item::Gloves), Some(open[1]));
+
+        let hammer_at = |l| PlandoConstraint::Fixed(Item::Hammer, l);
+        let check = |plando: Plando| search().with_plando(&plando).err();
+        assert_eq!(
+            check(Plando::new().place(Item::Flute, open[0])),
+            Some(PlandoError::NotPlaced(PlandoConstraint::Fixed(
+                Item::Flute,
+                open[0]
+            )))
+        );
+        assert_eq!(
+            check(Plando::new().place(Item::Hammer, 0)),
+            Some(PlandoError::NotALocation(hammer_at(0)))
+        );
+        assert_eq!(
+            check(
+                Plando::new()
+                    .place(Item::Hammer, open[0])
+                    .place(Item::Hammer, open[1])
+            ),
+            Some(PlandoError::Conflict(
+                hammer_at(open[0]),
+                hammer_at(open[1])
+            ))
+        );
+        assert_eq!(
+            check(
+                Plando::new()
+                    .place(Item::Hammer, open[0])
+                    .exclude(Item::Hammer, open[0])
+            ),
+            Some(PlandoError::Conflict(
+                hammer_at(open[0]),
+                PlandoConstraint::Excluded(Item::Hammer, open[0])
+            ))
+        );
+        let gloves_behind_gloves = PlandoConstraint::Fixed(Item::Gloves, behind_gloves);
+        assert_eq!(
+            check(Plando::new().place(Item::Gloves, behind_gloves)),
+            Some(PlandoError::Unreachable(gloves_behind_gloves))
+        );
+        let everywhere = (1..=graph.node_count)
+            .filter(|&n| graph.item_data(n).is_some())
+            .fold(Plando::new(), |p, l| p.exclude(Item::Gloves, l));
+        assert_eq!(
+            check(everywhere),
+            Some(PlandoError::NoLocation(Item::Gloves))
+        );
+    }
+}
@@ -0,0 +1,134 @@
+// A best-first walker ordered by a caller's key rather than by distance.
+use alloc::collections::BinaryHeap;
+use core::{cmp::Reverse, fmt, iter::FusedIterator, num::NonZeroU16};
+
+use crate::{
+    gen::SEARCH_QUEUE_SIZE,
+    graph::{NodeData, StaticGraph},
+    logic::{CollectionState, EvaluateLogic},
+    traversal::TraversalCore,
+};
+
+/// A graph walker that always expands the reached node with the highest key next, so a search
+/// can explore the most promising parts of the graph first, e.g. regions with lots of unchecked
+/// item locations. Ties go to the lower node index.
+///
+/// Each node's key is computed once, when it's first reached, and nodes are pushed once like in
+/// our other searches, so the heap never holds more than a BFS queue would.
+pub struct PriorityIter<'graph, const M: usize, const N: usize, T, F> {
+    pub core: TraversalCore<'graph, M, N, T>,
+    pub open: BinaryHeap<(u32, Reverse<u16>)>,
+    key: F,
+}
+
+impl<'graph, const M: usize, const N: usize, T, F> PriorityIter<'graph, M, N, T, F>
+where
+    F: Fn(u16, &NodeData) -> u32,
+{
+    pub(crate) fn new(graph: &'graph StaticGraph<M, N, T>, state: CollectionState, key: F) -> Self {
+        let mut priority_iter = PriorityIter {
+            core: TraversalCore::new(graph, 1, state),
+            open: BinaryHeap::with_capacity(SEARCH_QUEUE_SIZE),
+            key,
+        };
+        priority_iter.push(priority_iter.core.root);
+
+        priority_iter
+    }
+
+    /// Returns whether `target` is reachable, expanding nodes until it's yielded or the search is
+    /// exhausted.
+    pub fn search(&mut self, target: u16) -> bool {
+        self.any(|n| u16::from(n) == target)
+    }
+
+    fn push(&mut self, node: u16) {
+        let key = (self.key)(node, self.core.graph.node_data(node));
+        self.open.push((key, Reverse(node)));
+    }
+}
+
+impl<const M: usize, const N: usize, T, F> Iterator for PriorityIter<'_, M, N, T, F>
+where
+    F: Fn(u16, &NodeData) -> u32,
+{
+    type Item = NonZeroU16;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (_, Reverse(node)) = self.open.pop()?;
+        let (core, open, key) = (&mut self.core, &mut self.open, &self.key);
+        let graph = core.graph;
+        core.visit_neighbors_out(NonZeroU16::new(node), |n| {
+            open.push((key(n, graph.node_data(n)), Reverse(n)));
+        });
+
+        NonZeroU16::new(node)
+    }
+
+    // Every node on the heap will be yielded and at most every node we haven't marked visited
+    // yet might be.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let pending = self.open.len();
+
+        (pending, Some(pending + self.core.unvisited()))
+    }
+}
+
+impl<const M: usize, const N: usize, T, F> FusedIterator for PriorityIter<'_, M, N, T, F> where
+    F: Fn(u16, &NodeData) -> u32
+{
+}
+
+impl<const M: usize, const N: usize, T, F> EvaluateLogic for PriorityIter<'_, M, N, T, F> {
+    fn collection_state(&self) -> &CollectionState {
+        &self.core.collection_state
+    }
+}
+
+impl<const M: usize, const N: usize, T, F> fmt::Debug for PriorityIter<'_, M, N, T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PriorityIter")
+            .field("core", &self.core)
+            .field("open", &self.open.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{new_static_graph, NodeType};
+    use std::collections::HashSet;
+
+    #[test]
+    fn priority_order() {
+        let graph = new_static_graph();
+        let reached: HashSet<u16> = graph.bfs_iter().map(u16::from).collect();
+
+        // Any key reaches the same nodes, once each.
+        let by_index: Vec<u16> = graph
+            .priority_iter(|n, _| n as u32)
+            .map(u16::from)
+            .collect();
+        assert_eq!(by_index.len(), reached.len());
+        assert_eq!(by_index.iter().copied().collect::<HashSet<_>>(), reached);
+
+        // Preferring item locations reaches them sooner on the whole than a plain search does.
+        let is_item = |data: &NodeData| data.node_type == NodeType::Item;
+        let items_first: Vec<u16> = graph
+            .priority_iter(|_, data| is_item(data) as u32)
+            .map(u16::from)
+            .collect();
+        let position = |order: &[u16], n: u16| order.iter().position(|&m| m == n).unwrap();
+        let bfs: Vec<u16> = graph.bfs_iter().map(u16::from).collect();
+        let items: Vec<u16> = bfs
+            .iter()
+            .copied()
+            .filter(|&n| is_item(graph.node_data(n)))
+            .collect();
+        let total = |order: &[u16]| items.iter().map(|&n| position(order, n)).sum::<usize>();
+        assert!(total(&items_first) <= total(&bfs));
+
+        assert!(graph.priority_iter(|_, _| 0).search(*bfs.last().unwrap()));
+    }
+}
@@ -0,0 +1,117 @@
+// Every unsafe block in the crate lives in this module. The hot paths skip checks that the graph's
+// construction already guarantees, and rather than each of them carrying its own unsafe block we
+// give them small wrappers here that either check what they rely on themselves or only accept
+// proof that it was checked, plus debug assertions in case a proof is ever wrong. Keeping them
+// together means there's one short file to audit.
+//
+// The tests below only use tiny inputs so they're quick under Miri and the sanitizers:
+//
+//     cargo miri test --lib raw::
+//     RUSTFLAGS=-Zsanitizer=address cargo test --lib -Zbuild-std --target x86_64-unknown-linux-gnu raw::
+//
+// See .github/workflows/unsafe.yml.
+use core::num::NonZeroU16;
+
+/// `NonZeroU16::new` for constants, e.g. the generated tables in gen.rs, where a zero is a
+/// compile error instead of a panic.
+pub(crate) const fn nonzero(n: u16) -> NonZeroU16 {
+    match NonZeroU16::new(n) {
+        Some(n) => n,
+        None => panic!("zero isn't a valid index here"),
+    }
+}
+
+/// Proof that none of a graph's node pointers point past the end of its edge array. The only way
+/// to get one is from `check_pointers`. See `StaticGraph::validate`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct PointersInBounds(());
+
+/// Returns a `PointersInBounds` if every pointer in `pointers` is at most `edge_count`, the length
+/// of the edge array the pointers index into.
+pub(crate) fn check_pointers(
+    pointers: &[NonZeroU16],
+    edge_count: usize,
+) -> Option<PointersInBounds> {
+    match pointers
+        .iter()
+        .all(|&p| u16::from(p) as usize <= edge_count)
+    {
+        true => Some(PointersInBounds(())),
+        false => None,
+    }
+}
+
+/// `edges[start..end]` without bounds checks, or an empty slice if `start` is past `end`.
+/// `edges` has to be the edge array the pointers `in_bounds` vouches for were checked against.
+#[inline(always)]
+pub(crate) fn edge_range(
+    edges: &[NonZeroU16],
+    start: u16,
+    end: u16,
+    _in_bounds: PointersInBounds,
+) -> &[NonZeroU16] {
+    let start = start.min(end) as usize;
+    debug_assert!(end as usize <= edges.len());
+    // SAFETY: `end` is one of the checked pointers, so it's within `edges`, and `start` is at
+    // most `end`.
+    unsafe { edges.get_unchecked(start..end as usize) }
+}
+
+/// `slice` as chunks of exactly `C` elements.
+///
+/// Panics if the length isn't a multiple of `C`. Our callers chunk fixed-size arrays, so the
+/// check is decided at compile time and costs nothing.
+#[inline(always)]
+pub(crate) fn exact_chunks<const C: usize, T>(slice: &[T]) -> &[[T; C]] {
+    assert!(C != 0 && slice.len() % C == 0);
+    // SAFETY: Just checked.
+    unsafe { slice.as_chunks_unchecked() }
+}
+
+/// Zero every word of `words` with one `write_bytes` rather than a store per word, which is how
+/// our caches forget everything between searches.
+#[inline]
+pub(crate) fn zero_words(words: &mut [u64]) {
+    // SAFETY: The pointer and length come from a live mutable slice and all zeroes is a valid
+    // u64.
+    unsafe { core::ptr::write_bytes(words.as_mut_ptr(), 0, words.len()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::StaticGraph;
+
+    #[test]
+    fn wrappers_stay_in_bounds() {
+        const EDGES: [NonZeroU16; 4] = [nonzero(1), nonzero(2), nonzero(3), nonzero(4)];
+        let pointers = [nonzero(1), nonzero(3), nonzero(4)];
+        assert!(check_pointers(&pointers, 3).is_none());
+        let in_bounds = check_pointers(&pointers, EDGES.len()).unwrap();
+        assert_eq!(edge_range(&EDGES, 1, 3, in_bounds), &EDGES[1..3]);
+        assert_eq!(edge_range(&EDGES, 4, 4, in_bounds), &[]);
+        assert_eq!(edge_range(&EDGES, 4, 1, in_bounds), &[]);
+
+        let chunks = exact_chunks::<2, _>(&EDGES);
+        assert_eq!(chunks, &[[EDGES[0], EDGES[1]], [EDGES[2], EDGES[3]]]);
+        assert!(std::panic::catch_unwind(|| exact_chunks::<3, _>(&EDGES)).is_err());
+
+        let mut words = [u64::MAX; 3];
+        zero_words(&mut words[1..]);
+        assert_eq!(words, [u64::MAX, 0, 0]);
+        zero_words(&mut []);
+
+        // A graph small enough for Miri, walked through the unchecked path.
+        let mut graph: StaticGraph<8, 64> =
+            StaticGraph::from_edge_list(["1 2", "1 3", "2 3", "3 1"]).unwrap();
+        assert!(graph.is_validated());
+        let edges: Vec<(u16, u16, u16)> = graph.edges().collect();
+        assert_eq!(edges, [(1, 1, 2), (1, 2, 3), (2, 3, 3), (3, 4, 1)]);
+        for node in 0..7 {
+            graph.get_neighbors_out(NonZeroU16::new(node));
+        }
+        graph.node_pointers.0[7] = nonzero(u16::MAX);
+        assert!(graph.validate().is_err());
+        assert!(graph.get_neighbors_out(NonZeroU16::new(6)).0.is_empty());
+    }
+}
@@ -0,0 +1,472 @@
+use alloc::{boxed::Box, vec, vec::Vec};
+use core::{fmt, num::NonZeroU16};
+
+use crate::{
+    constants::*,
+    graph::{StaticGraph, VisitedCache},
+    logic::{CollectionState, EventId},
+    solver::{Closure, FixedPoint},
+    traversal::TraversalCore,
+};
+
+/// Whole-graph reachability queries. Fill algorithms tend to check the same set of locations
+/// over and over as the collection state changes, and searching for each one individually
+/// restarts or resumes a traversal per location. Since a single full traversal of the graph is
+/// cheap, it's simpler and usually faster to exhaust one and answer every query from the visited
+/// cache afterwards.
+pub struct Reachability;
+
+impl Reachability {
+    /// Runs one full traversal of `graph` under `state` and returns a bitset where bit `i` is set
+    /// if `locations[i]` is reachable from the root.
+    pub fn batch<const M: usize, const N: usize, T>(
+        graph: &StaticGraph<M, N, T>,
+        state: &CollectionState,
+        locations: &[u16],
+    ) -> BitBox {
+        let mut dfs_iter = graph.dfs_iter_with_state(state.clone());
+        dfs_iter.by_ref().for_each(drop);
+        let mut reachable = BitBox::new(locations.len());
+        locations
+            .iter()
+            .enumerate()
+            .filter(|(_, &l)| dfs_iter.core.visited.check_visited(l))
+            .for_each(|(i, _)| reachable.set(i));
+
+        reachable
+    }
+
+    /// Search `graph` under `state` with event requirements resolved, where `events` says which
+    /// node makes each event happen. Reaching an event's node can open edges that lead to more
+    /// event nodes, so this keeps searching until no new events happen. See `FixedPoint` for
+    /// resolving items picked up along the way too.
+    pub fn with_events<const M: usize, const N: usize, T>(
+        graph: &StaticGraph<M, N, T>,
+        state: &CollectionState,
+        events: &[(EventId, u16)],
+    ) -> Closure {
+        FixedPoint::new().with_events(events).solve(graph, state)
+    }
+}
+
+impl<const M: usize, const N: usize, T> StaticGraph<M, N, T> {
+    /// Sort every item location by whether `state` can get to it, the summary a tracker or seed
+    /// validator shows. Locations are locked if we can't reach them even with every item and
+    /// event, keeping the state's logic tiers and settings since those don't change during a
+    /// seed.
+    pub fn checks(&self, state: &CollectionState) -> CheckReport {
+        let mut everything = CollectionState::ALL_ITEMS;
+        everything.tiers = state.tiers;
+        everything.settings = state.settings;
+        let mut current = self.dfs_iter_with_state(state.clone());
+        current.by_ref().for_each(drop);
+        let mut possible = self.dfs_iter_with_state(everything);
+        possible.by_ref().for_each(drop);
+
+        let mut report = CheckReport::default();
+        for location in self.nodes().filter(|&n| self.item_data(n).is_some()) {
+            if current.core.visited.check_visited(location) {
+                report.reachable.push(location);
+            } else if possible.core.visited.check_visited(location) {
+                report.unreachable.push(location);
+            } else {
+                report.locked.push(location);
+            }
+        }
+
+        report
+    }
+}
+
+/// Reachable sets remembered across collection states, for fill loops that ask about a state,
+/// collect or take away one item and ask again.
+///
+/// Collection states form a lattice under `CollectionState::is_subset_of` and reachability only
+/// grows going up it, so every state we've swept bounds the ones around it. A node reachable
+/// under a state below the one we're asked about is reachable, and a node unreachable under a
+/// state above it is unreachable. We only sweep when neither answers the question, and then we
+/// start from the biggest reachable set we have below the state instead of from the root.
+///
+/// This relies on logic being monotone, which it is as long as requirements only ever check for
+/// things being there. On a graph that negates something, see `StaticGraph::is_monotone`, we only
+/// answer from states we've swept exactly and every sweep starts from the root.
+pub struct StateLattice<'graph, const M: usize, const N: usize, T = ()> {
+    graph: &'graph StaticGraph<M, N, T>,
+    monotone: bool,
+    // Oldest first, so when we're full we forget whatever was swept longest ago.
+    entries: Vec<(CollectionState, VisitedCache<VISITED_BITFIELD_LEN>)>,
+    capacity: usize,
+    sweeps: usize,
+}
+
+impl<'graph, const M: usize, const N: usize, T> StateLattice<'graph, M, N, T> {
+    /// A cache remembering the reachable sets of up to `capacity` states, at least one.
+    pub fn new(graph: &'graph StaticGraph<M, N, T>, capacity: usize) -> Self {
+        StateLattice {
+            graph,
+            monotone: graph.is_monotone(),
+            entries: Vec::new(),
+            capacity: capacity.max(1),
+            sweeps: 0,
+        }
+    }
+
+    /// Whether `node` is reachable under `state`, sweeping the graph only if the states we've
+    /// seen don't already say.
+    pub fn is_reachable(&mut self, state: &CollectionState, node: u16) -> bool {
+        match self.bound(state, node) {
+            Some(reachable) => reachable,
+            None => self.reachable(state).check_visited(node),
+        }
+    }
+
+    /// Whether `node` is reachable under `state` as far as the states we've seen can tell
+    /// without sweeping, or `None` if they can't.
+    pub fn bound(&self, state: &CollectionState, node: u16) -> Option<bool> {
+        self.entries.iter().find_map(|(seen, visited)| {
+            let (below, above) = (seen.is_subset_of(state), state.is_subset_of(seen));
+            match (visited.check_visited(node), below, above) {
+                (reachable, true, true) => Some(reachable),
+                _ if !self.monotone => None,
+                (true, true, _) => Some(true),
+                (false, _, true) => Some(false),
+                _ => None,
+            }
+        })
+    }
+
+    /// Every node reachable under `state`, from the cache if we've swept it already.
+    pub fn reachable(&mut self, state: &CollectionState) -> &VisitedCache<VISITED_BITFIELD_LEN> {
+        let seen = self
+            .entries
+            .iter()
+            .position(|(seen, _)| seen.is_subset_of(state) && state.is_subset_of(seen));
+        let i = match seen {
+            Some(i) => i,
+            None => self.sweep(state),
+        };
+
+        &self.entries[i].1
+    }
+
+    /// How many times we've had to search the graph.
+    pub fn sweeps(&self) -> usize {
+        self.sweeps
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    // Search under `state` from whatever we already know it reaches and remember the result,
+    // returning where it went.
+    fn sweep(&mut self, state: &CollectionState) -> usize {
+        let mut core = TraversalCore::new(self.graph, 1, state.clone());
+        let below = self
+            .entries
+            .iter()
+            .filter(|(seen, _)| self.monotone && seen.is_subset_of(state))
+            .max_by_key(|(_, visited)| visited.iter_ones().count());
+        if let Some((_, visited)) = below {
+            core.visited = visited.clone();
+            core.visited_count = visited.iter_ones().count() as u16;
+        }
+        // Everything we start with still has to be expanded since edges out of it may have
+        // opened.
+        let mut stack: Vec<u16> = core.visited.iter_ones().collect();
+        while let Some(node) = stack.pop() {
+            core.visit_neighbors_out(NonZeroU16::new(node), |n| stack.push(n));
+        }
+        self.sweeps += 1;
+
+        if self.entries.len() == self.capacity {
+            self.entries.remove(0);
+        }
+        self.entries.push((state.clone(), core.visited));
+
+        self.entries.len() - 1
+    }
+}
+
+impl<const M: usize, const N: usize, T> fmt::Debug for StateLattice<'_, M, N, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StateLattice")
+            .field("monotone", &self.monotone)
+            .field("states", &self.entries.len())
+            .field("capacity", &self.capacity)
+            .field("sweeps", &self.sweeps)
+            .finish()
+    }
+}
+
+/// Every item location in a graph, in node order, split up by reachability. See
+/// `StaticGraph::checks`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CheckReport {
+    /// Reachable with what we have now.
+    pub reachable: Vec<u16>,
+    /// Out of reach for now but reachable with every item.
+    pub unreachable: Vec<u16>,
+    /// Out of reach no matter what we collect.
+    pub locked: Vec<u16>,
+}
+
+impl CheckReport {
+    /// The number of item locations in the graph.
+    pub fn len(&self) -> usize {
+        self.reachable.len() + self.unreachable.len() + self.locked.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl fmt::Display for CheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} reachable, {} unreachable, {} locked",
+            self.reachable.len(),
+            self.unreachable.len(),
+            self.locked.len()
+        )
+    }
+}
+
+/// A minimal fixed-length boxed bitset using the same most-significant-bit-first layout as our
+/// visited and access caches.
+#[derive(Clone, PartialEq, Eq)]
+pub struct BitBox {
+    words: Box<[u64]>,
+    len: usize,
+}
+
+impl BitBox {
+    const BITMASK_CUR: u64 = 0x80000000_00000000;
+
+    /// Get a new bitset with `len` bits, all unset.
+    pub fn new(len: usize) -> Self {
+        BitBox {
+            words: vec![0u64; len.div_ceil(64)].into_boxed_slice(),
+            len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the bit at `idx`. Panics if `idx` is out of bounds.
+    pub fn get(&self, idx: usize) -> bool {
+        assert!(idx < self.len);
+        (self.words[idx >> 6] & (Self::BITMASK_CUR >> (idx & 0x3F))) != 0
+    }
+
+    /// Sets the bit at `idx`. Panics if `idx` is out of bounds.
+    pub fn set(&mut self, idx: usize) {
+        assert!(idx < self.len);
+        self.words[idx >> 6] |= Self::BITMASK_CUR >> (idx & 0x3F);
+    }
+
+    /// The number of set bits.
+    pub fn count_ones(&self) -> u32 {
+        self.words.iter().map(|w| w.count_ones()).sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.len).map(|i| self.get(i))
+    }
+}
+
+impl fmt::Debug for BitBox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BitBox")
+            .field("len", &self.len)
+            .field("ones", &self.count_ones())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+
+    use super::*;
+    use crate::{
+        graph::new_static_graph,
+        logic::{EventFlags, EventId, Item, ReqIndex},
+    };
+
+    #[test]
+    fn events_settle() {
+        let mut graph = new_static_graph();
+        // Edges that needed the flute wait for Agahnim instead and edges that needed the hammer
+        // wait for the crystal switch.
+        let swaps = [(6, 13), (3, 14)].map(|(from, to)| (ReqIndex::new(from), ReqIndex::new(to)));
+        for req in Arc::make_mut(&mut graph.edge_data).iter_mut() {
+            if let Some(&(_, to)) = swaps.iter().find(|&&(from, _)| from == Some(*req)) {
+                *req = to.unwrap();
+            }
+        }
+        let state = CollectionState::default();
+        let reached = |events: EventFlags| {
+            let mut state = state.clone();
+            state.events = events;
+            graph
+                .dfs_iter_with_state(state)
+                .map(u16::from)
+                .collect::<Vec<_>>()
+        };
+        // Agahnim is at the root and the crystal switch is somewhere only Agahnim opens up.
+        let agahnim = EventFlags::NONE.with(EventId::Agahnim);
+        let before = reached(EventFlags::NONE);
+        let switch = *reached(agahnim)
+            .iter()
+            .find(|n| !before.contains(n))
+            .unwrap();
+        let events = [(EventId::Agahnim, 1), (EventId::CrystalSwitch, switch)];
+
+        let closure = Reachability::with_events(&graph, &state, &events);
+        assert_eq!(closure.state.events, EventFlags::ALL);
+        assert_eq!(closure.rounds, 3);
+        let all = reached(EventFlags::ALL);
+        assert!(all.iter().all(|&n| closure.visited.check_visited(n)));
+        assert!(all.len() > before.len());
+
+        // Nothing happens if the events are out of reach.
+        let unreachable = (1..=graph.node_count()).find(|n| !all.contains(n)).unwrap();
+        let closure = Reachability::with_events(&graph, &state, &[(EventId::Agahnim, unreachable)]);
+        assert_eq!(closure.state.events, EventFlags::NONE);
+        assert_eq!(closure.rounds, 1);
+    }
+
+    #[test]
+    fn check_report() {
+        let graph = new_static_graph();
+        let items = graph
+            .nodes()
+            .filter(|&n| graph.item_data(n).is_some())
+            .count();
+        let nothing = graph.checks(&CollectionState::default());
+        let everything = graph.checks(&CollectionState::ALL_ITEMS);
+        assert_eq!(nothing.len(), items);
+        assert_eq!(everything.len(), items);
+        assert!(everything.unreachable.is_empty());
+        assert_eq!(nothing.locked, everything.locked);
+        assert!(nothing.reachable.len() < everything.reachable.len());
+        assert_eq!(
+            std::format!("{}", everything),
+            std::format!(
+                "{} reachable, 0 unreachable, {} locked",
+                everything.reachable.len(),
+                everything.locked.len()
+            )
+        );
+
+        // Locking every edge out of the root locks everything.
+        let mut locked = new_static_graph();
+        let (edges, offset) = locked.get_neighbors_out(core::num::NonZeroU16::new(1));
+        let root_edges = offset as usize..offset as usize + edges.len();
+        Arc::make_mut(&mut locked.edge_data)[root_edges].fill(ReqIndex::LOCKED);
+        let report = locked.checks(&CollectionState::ALL_ITEMS);
+        assert_eq!(report.locked.len(), items);
+    }
+
+    #[test]
+    fn state_lattice() {
+        let graph = new_static_graph();
+        let mut lattice = StateLattice::new(&graph, 8);
+        let nothing = CollectionState::NOTHING;
+        let reached = |state: &CollectionState| {
+            let mut dfs_iter = graph.dfs_iter_with_state(state.clone());
+            dfs_iter.by_ref().for_each(drop);
+            dfs_iter.core.visited
+        };
+        let nodes: Vec<u16> = graph.nodes().step_by(97).collect();
+
+        // Collect one item at a time, asking about every node after each one like a fill would.
+        let mut state = nothing.clone();
+        let mut queries = 0;
+        for item in [Item::Hammer, Item::Boots, Item::Flute, Item::Gloves] {
+            state.set(item, true);
+            let expected = reached(&state);
+            for &node in nodes.iter() {
+                assert_eq!(
+                    lattice.is_reachable(&state, node),
+                    expected.check_visited(node)
+                );
+                queries += 1;
+            }
+            assert!(expected
+                .iter_ones()
+                .eq(lattice.reachable(&state).iter_ones()));
+        }
+        assert_eq!(lattice.sweeps(), 4);
+
+        // Taking items away again is bounded from above, and anything reached with nothing at all
+        // is known once we've swept that.
+        let sweeps = lattice.sweeps();
+        lattice.reachable(&nothing);
+        let mut without_flute = state.clone();
+        without_flute.set(Item::Flute, false);
+        let expected = reached(&without_flute);
+        let mut answered = 0;
+        for &node in nodes.iter() {
+            if let Some(reachable) = lattice.bound(&without_flute, node) {
+                assert_eq!(reachable, expected.check_visited(node));
+                answered += 1;
+            }
+            assert_eq!(
+                lattice.is_reachable(&without_flute, node),
+                expected.check_visited(node)
+            );
+        }
+        assert!(answered > 0);
+        assert!(lattice.sweeps() <= sweeps + 2);
+        assert!(lattice.sweeps() < queries);
+
+        // A cache of one forgets the state before, and still answers correctly.
+        let mut small = StateLattice::new(&graph, 0);
+        let everything = reached(&CollectionState::ALL_ITEMS);
+        assert!(small.is_reachable(&nothing, 1));
+        for &node in nodes.iter() {
+            assert_eq!(
+                small.is_reachable(&CollectionState::ALL_ITEMS, node),
+                everything.check_visited(node)
+            );
+        }
+        assert_eq!(small.entries.len(), 1);
+
+        // An entrance out of the root that closes once Agahnim is beaten means the state with
+        // Agahnim beaten reaches less, so nothing carries over between states.
+        let mut closing = new_static_graph();
+        let (edges, offset) = closing.get_neighbors_out(core::num::NonZeroU16::new(1));
+        let root_edges = offset as usize..offset as usize + edges.len();
+        Arc::make_mut(&mut closing.edge_data)[root_edges].fill(ReqIndex::new(15).unwrap());
+        assert!(!closing.is_monotone());
+        assert!(graph.is_monotone());
+        let beaten = CollectionState {
+            events: EventFlags::NONE.with(EventId::Agahnim),
+            ..CollectionState::ALL_ITEMS
+        };
+        let mut lattice = StateLattice::new(&closing, 8);
+        lattice.reachable(&nothing);
+        let open = nodes
+            .iter()
+            .filter(|&&node| node != 1 && lattice.is_reachable(&nothing, node))
+            .count();
+        assert!(open > 0);
+        assert!(nodes
+            .iter()
+            .all(|&node| lattice.bound(&beaten, node).is_none()));
+        for &node in nodes.iter() {
+            assert_eq!(lattice.is_reachable(&beaten, node), node == 1);
+        }
+        assert_eq!(lattice.sweeps(), 2);
+    }
+}
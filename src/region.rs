@@ -0,0 +1,319 @@
+use alloc::{collections::VecDeque, sync::Arc, vec::Vec};
+use core::{fmt, num::NonZeroU16};
+
+use crate::{
+    graph::StaticGraph,
+    logic::{CollectionState, EvaluateLogic, ReqIndex, RequirementNode, REQ_CONTAINER},
+};
+
+/// The coarse areas of the world model. Every node in the graph belongs to exactly one region (the
+/// zero index and padding nodes belong to none), which lets tools present results at a human
+/// scale instead of as raw node indexes. The discriminants are the region ids used by the
+/// generated `REGION_OF` table and the wide metadata tables.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u16)]
+pub enum Region {
+    LightWorld = 1,
+    DarkWorld,
+    DeathMountain,
+    DarkDeathMountain,
+    HyruleCastle,
+    LightWorldDungeons,
+    DarkWorldDungeons,
+    GanonsTower,
+}
+
+impl Region {
+    pub const ALL: [Region; 8] = [
+        Region::LightWorld,
+        Region::DarkWorld,
+        Region::DeathMountain,
+        Region::DarkDeathMountain,
+        Region::HyruleCastle,
+        Region::LightWorldDungeons,
+        Region::DarkWorldDungeons,
+        Region::GanonsTower,
+    ];
+
+    /// Get the region with the given id. Returns None for 0 (no region) and any unknown id.
+    pub const fn from_id(id: u16) -> Option<Region> {
+        match id {
+            1..=8 => Some(Region::ALL[id as usize - 1]),
+            _ => None,
+        }
+    }
+
+    pub const fn id(self) -> u16 {
+        self as u16
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Region::LightWorld => "Light World",
+            Region::DarkWorld => "Dark World",
+            Region::DeathMountain => "Death Mountain",
+            Region::DarkDeathMountain => "Dark Death Mountain",
+            Region::HyruleCastle => "Hyrule Castle",
+            Region::LightWorldDungeons => "Light World Dungeons",
+            Region::DarkWorldDungeons => "Dark World Dungeons",
+            Region::GanonsTower => "Ganon's Tower",
+        }
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// How much of a region is reachable under some collection state. "Checks" are the region's item
+/// locations, which is usually what a player or tracker actually cares about.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RegionSummary {
+    pub region: Region,
+    pub reachable_nodes: u16,
+    pub total_nodes: u16,
+    pub reachable_checks: u16,
+    pub total_checks: u16,
+}
+
+impl fmt::Display for RegionSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} reachable: {}/{} checks",
+            self.region, self.reachable_checks, self.total_checks
+        )
+    }
+}
+
+/// Runs one full traversal of `graph` under `state` and summarizes what's reachable in every
+/// region, in `Region::ALL` order.
+pub fn region_summaries<const M: usize, const N: usize, T>(
+    graph: &StaticGraph<M, N, T>,
+    state: &CollectionState,
+) -> Vec<RegionSummary> {
+    let mut dfs_iter = graph.dfs_iter_with_state(state.clone());
+    dfs_iter.by_ref().for_each(drop);
+    let visited = &dfs_iter.core.visited;
+
+    let mut summaries: Vec<RegionSummary> = Region::ALL
+        .iter()
+        .map(|&region| RegionSummary {
+            region,
+            reachable_nodes: 0,
+            total_nodes: 0,
+            reachable_checks: 0,
+            total_checks: 0,
+        })
+        .collect();
+    for node in 1..=graph.node_count {
+        let Some(region) = graph.region_of(node) else {
+            continue;
+        };
+        let summary = &mut summaries[region as usize - 1];
+        let reachable = visited.check_visited(node) as u16;
+        summary.total_nodes += 1;
+        summary.reachable_nodes += reachable;
+        if graph.item_data(node).is_some() {
+            summary.total_checks += 1;
+            summary.reachable_checks += reachable;
+        }
+    }
+
+    summaries
+}
+
+/// A transition between two regions in a `RegionGraph`. It can be taken if any of the requirements
+/// on the edges it was condensed from is satisfied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegionEdge {
+    pub from: Region,
+    pub to: Region,
+    /// The distinct requirements of every edge between the two regions, in ascending order.
+    pub reqs: Vec<ReqIndex>,
+}
+
+impl RegionEdge {
+    /// Whether any of the requirements is satisfied, where `trees` are the requirement trees of
+    /// the graph we were condensed from. See `RegionGraph::req_trees`.
+    pub fn is_traversable(&self, trees: &[RequirementNode], logic: &impl EvaluateLogic) -> bool {
+        self.reqs
+            .iter()
+            .any(|&r| logic.eval_logic_tree_in(trees, r))
+    }
+}
+
+/// A tiny meta-graph whose nodes are regions. See `StaticGraph::condense_regions`.
+///
+/// It ignores how nodes are connected inside a region, so it can only ever over-approximate
+/// reachability: if a region can't be reached here it can't be reached in the full graph either,
+/// but not the other way around. That makes it useful for ruling things out cheaply before paying
+/// for a traversal of the whole graph.
+#[derive(Clone, Debug, Default)]
+pub struct RegionGraph {
+    /// Every inter-region transition, ordered by `(from, to)`.
+    pub edges: Vec<RegionEdge>,
+    req_trees: Option<Arc<[RequirementNode]>>,
+}
+
+impl RegionGraph {
+    pub(crate) fn new<const M: usize, const N: usize, T>(graph: &StaticGraph<M, N, T>) -> Self {
+        let mut transitions: Vec<(Region, Region, ReqIndex)> = Vec::new();
+        for node in 1..=graph.node_count {
+            let Some(from) = graph.region_of(node) else {
+                continue;
+            };
+            let (edge_pointers, edge_offset) = graph.get_neighbors_out(NonZeroU16::new(node));
+            for (i, &dest) in edge_pointers.iter().enumerate() {
+                match graph.region_of(u16::from(dest)) {
+                    Some(to) if to != from => {
+                        transitions.push((from, to, graph.edge_data[(edge_offset as usize) + i]))
+                    }
+                    _ => (),
+                }
+            }
+        }
+        transitions.sort_unstable();
+        transitions.dedup();
+
+        let mut edges: Vec<RegionEdge> = Vec::new();
+        for (from, to, req) in transitions {
+            match edges.last_mut() {
+                Some(e) if e.from == from && e.to == to => e.reqs.push(req),
+                _ => edges.push(RegionEdge {
+                    from,
+                    to,
+                    reqs: alloc::vec![req],
+                }),
+            }
+        }
+
+        RegionGraph {
+            edges,
+            req_trees: graph.req_trees.clone(),
+        }
+    }
+
+    /// The requirement trees our edges' requirements index into, the same as the graph's we were
+    /// condensed from. See `StaticGraph::req_trees`.
+    pub fn req_trees(&self) -> &[RequirementNode] {
+        match &self.req_trees {
+            Some(trees) => trees,
+            None => &REQ_CONTAINER.0,
+        }
+    }
+
+    /// Iterate over the transitions leaving `region`.
+    pub fn edges_out(&self, region: Region) -> impl Iterator<Item = &RegionEdge> + '_ {
+        self.edges.iter().filter(move |e| e.from == region)
+    }
+
+    /// Returns whether each region (indexed like `Region::ALL`) may be reachable from `start`
+    /// under `logic`.
+    pub fn reachable_regions(
+        &self,
+        start: Region,
+        logic: &impl EvaluateLogic,
+    ) -> [bool; Region::ALL.len()] {
+        let mut reachable = [false; Region::ALL.len()];
+        reachable[start as usize - 1] = true;
+        let mut queue = VecDeque::from([start]);
+        while let Some(region) = queue.pop_front() {
+            self.edges_out(region)
+                .filter(|e| e.is_traversable(self.req_trees(), logic))
+                .for_each(|e| {
+                    if !reachable[e.to as usize - 1] {
+                        reachable[e.to as usize - 1] = true;
+                        queue.push_back(e.to);
+                    }
+                });
+        }
+
+        reachable
+    }
+
+    /// Returns false only if `to` is certainly unreachable from `from` under `logic`.
+    pub fn may_reach(&self, from: Region, to: Region, logic: &impl EvaluateLogic) -> bool {
+        self.reachable_regions(from, logic)[to as usize - 1]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        graph::{new_static_graph, new_static_graph_open},
+        logic::{EventFlags, SettingsState, TierMask},
+    };
+
+    #[test]
+    fn regions() {
+        let graph = new_static_graph();
+        assert_eq!(graph.region_of(0), None);
+        assert_eq!(graph.region_of(1), Some(Region::LightWorld));
+        for node in 1..=graph.node_count {
+            let region = graph.region_of(node).map(Region::id);
+            let wide = graph
+                .place_data(node)
+                .map(|d| d.region)
+                .or(graph.item_data(node).map(|d| d.region))
+                .or(graph.door_data(node).map(|d| d.region));
+            assert_eq!(region, wide);
+        }
+        let total: usize = Region::ALL
+            .iter()
+            .map(|&r| graph.nodes_in_region(r).count())
+            .sum();
+        assert_eq!(total, graph.node_count as usize);
+
+        let summaries = region_summaries(&graph, &CollectionState::default());
+        assert_eq!(summaries.len(), Region::ALL.len());
+        assert!(summaries
+            .iter()
+            .all(|s| s.reachable_checks <= s.total_checks && s.reachable_nodes <= s.total_nodes));
+        assert_eq!(
+            std::format!("{}", summaries[1]),
+            std::format!(
+                "Dark World reachable: {}/{} checks",
+                summaries[1].reachable_checks,
+                summaries[1].total_checks
+            )
+        );
+
+        // With no logic every node is reachable.
+        let open = new_static_graph_open();
+        let mut bfs_iter = open.bfs_iter();
+        bfs_iter.by_ref().for_each(drop);
+        let reachable: u16 = region_summaries(&open, &CollectionState::default())
+            .iter()
+            .map(|s| s.reachable_nodes)
+            .sum();
+        assert_eq!(reachable, bfs_iter.core.visited_count);
+    }
+
+    #[test]
+    fn condensed_regions() {
+        let graph = new_static_graph();
+        let meta = graph.condense_regions();
+        assert!(meta
+            .edges
+            .iter()
+            .all(|e| e.from != e.to && !e.reqs.is_empty()));
+        assert!(meta
+            .edges
+            .windows(2)
+            .all(|w| (w[0].from, w[0].to) < (w[1].from, w[1].to)));
+
+        // Anything the full graph can reach, the meta-graph must consider reachable too.
+        let nothing = CollectionState::NOTHING;
+        for state in [CollectionState::default(), nothing] {
+            let coarse = meta.reachable_regions(Region::LightWorld, &state);
+            region_summaries(&graph, &state)
+                .iter()
+                .filter(|s| s.reachable_nodes > 0)
+                .for_each(|s| assert!(coarse[s.region as usize - 1]));
+        }
+    }
+}
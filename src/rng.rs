@@ -0,0 +1,84 @@
+/// A tiny seeded generator (SplitMix64) for the places we need reproducible randomness without
+/// pulling in a dependency, e.g. shuffling doors. It's fast and statistically fine for shuffles
+/// but obviously not suitable for anything security related.
+pub(crate) struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub(crate) const fn new(seed: u64) -> Self {
+        SplitMix64(seed)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+        mix(self.0)
+    }
+
+    /// A number in `0..n`, using a multiply and shift rather than a modulo so it's very nearly
+    /// unbiased for any `n` we'd use.
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+
+    /// Fisher-Yates shuffle.
+    pub(crate) fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            slice.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// SplitMix64's finalizer, which also makes a decent hash for combining a seed with other values.
+pub(crate) const fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D049BB133111EB);
+
+    x ^ (x >> 31)
+}
+
+/// The natural log of a positive, finite, normal `x`. Without std, core has no float math, so we
+/// split off the exponent and sum the series for `ln(m) = 2 atanh((m - 1) / (m + 1))` over the
+/// mantissa `m` in [1, 2). That's within a few ulps of std's `ln` and the same on every platform.
+pub(crate) fn ln(x: f64) -> f64 {
+    let bits = x.to_bits();
+    let exponent = ((bits >> 52) & 0x7FF) as i64 - 1023;
+    let mantissa = f64::from_bits((bits & ((1 << 52) - 1)) | (1023 << 52));
+    let z = (mantissa - 1.0) / (mantissa + 1.0);
+    let z2 = z * z;
+    // z is at most 1/3, so each term is at most a ninth of the last and 20 of them is plenty.
+    let (mut term, mut sum) = (z, 0.0);
+    for k in 0..20 {
+        sum += term / (2 * k + 1) as f64;
+        term *= z2;
+    }
+
+    2.0 * sum + exponent as f64 * core::f64::consts::LN_2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ln_matches_std() {
+        let mut rng = SplitMix64::new(7);
+        for x in [
+            1.0,
+            2.0,
+            0.5,
+            1.5,
+            f64::MIN_POSITIVE,
+            f64::MAX,
+            1.0 / (1u64 << 53) as f64,
+        ]
+        .into_iter()
+        .chain((0..1000).map(|_| ((rng.next_u64() >> 11) + 1) as f64 / (1u64 << 53) as f64))
+        {
+            let (ours, std) = (ln(x), x.ln());
+            assert!(
+                (ours - std).abs() <= 4.0 * f64::EPSILON * std.abs().max(1.0),
+                "{}",
+                x
+            );
+        }
+    }
+}
@@ -0,0 +1,262 @@
+// Reusing traversal buffers across searches so fill loops don't allocate for every walk.
+use core::{
+    fmt,
+    ops::{Deref, DerefMut},
+};
+
+use crate::{
+    bfs_iter::{BfsIter, BfsQueue},
+    constants::*,
+    dfs_iter::{DfsIter, DfsStack},
+    graph::{AccessCache, StaticGraph, VisitedCache},
+    logic::CollectionState,
+    traversal::{LogicMode, TraversalCore},
+};
+
+/// The buffers a graph walker needs, kept around between searches. Every walker allocates a
+/// visited bitfield, two access bitfields and its frontier, which adds up to tens of kilobytes
+/// per search of our generated graph, so a fill loop running thousands of searches should lend
+/// the same buffers to each of them instead:
+///
+/// ```ignore
+/// let mut scratch = TraversalScratch::new();
+/// for state in states {
+///     let reachable = graph.bfs_iter_with_state_in(state, &mut scratch).count();
+/// }
+/// ```
+///
+/// A walker borrowed from a scratch hands its buffers back when it's dropped. Buffers are
+/// allocated the first time they're needed, so an empty scratch is free to create. Bitfields
+/// are sized for the graph they were last lent to and reallocated when lent to a graph with
+/// different tables, so a scratch is best kept to one graph.
+#[derive(Default)]
+pub struct TraversalScratch {
+    visited: Option<VisitedCache<VISITED_BITFIELD_LEN>>,
+    edge_access: Option<AccessCache<ACCESS_BITFIELD_LEN>>,
+    edge_evaluated: Option<AccessCache<ACCESS_BITFIELD_LEN>>,
+    stack: Option<DfsStack>,
+    queue: Option<BfsQueue>,
+}
+
+impl TraversalScratch {
+    /// A scratch with every buffer already allocated.
+    pub fn new() -> Self {
+        TraversalScratch {
+            visited: Some(VisitedCache::new()),
+            edge_access: Some(AccessCache::new()),
+            edge_evaluated: Some(AccessCache::new()),
+            stack: Some(DfsStack::new()),
+            queue: Some(BfsQueue::new()),
+        }
+    }
+
+    fn core<'graph, const M: usize, const N: usize, T>(
+        &mut self,
+        graph: &'graph StaticGraph<M, N, T>,
+        state: CollectionState,
+    ) -> TraversalCore<'graph, M, N, T> {
+        let parts = (
+            self.visited.take().unwrap_or_default(),
+            self.edge_access.take().unwrap_or_default(),
+            self.edge_evaluated.take().unwrap_or_default(),
+        );
+
+        TraversalCore::from_parts(graph, 1, state, LogicMode::Eager, parts)
+    }
+
+    fn put_core<const M: usize, const N: usize, T>(&mut self, core: TraversalCore<'_, M, N, T>) {
+        self.visited = Some(core.visited);
+        self.edge_access = Some(core.edge_access);
+        self.edge_evaluated = Some(core.edge_evaluated);
+    }
+}
+
+impl fmt::Debug for TraversalScratch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TraversalScratch")
+            .field("visited", &self.visited.is_some())
+            .field("edge_access", &self.edge_access.is_some())
+            .field("edge_evaluated", &self.edge_evaluated.is_some())
+            .field("stack", &self.stack.is_some())
+            .field("queue", &self.queue.is_some())
+            .finish()
+    }
+}
+
+/// A walker whose buffers can go back into a `TraversalScratch` once we're done with it.
+pub trait Recycle {
+    fn recycle(self, scratch: &mut TraversalScratch);
+}
+
+impl<const M: usize, const N: usize, T> Recycle for DfsIter<'_, M, N, T> {
+    fn recycle(self, scratch: &mut TraversalScratch) {
+        scratch.stack = Some(self.search_stack);
+        scratch.put_core(self.core);
+    }
+}
+
+impl<const M: usize, const N: usize, T> Recycle for BfsIter<'_, M, N, T> {
+    fn recycle(self, scratch: &mut TraversalScratch) {
+        scratch.queue = Some(self.search_queue);
+        scratch.put_core(self.core);
+    }
+}
+
+/// A walker built from a `TraversalScratch`'s buffers. It derefs to the walker and returns the
+/// buffers to the scratch when dropped.
+pub struct Lent<'s, I: Recycle> {
+    // Only ever `None` while we're being dropped.
+    iter: Option<I>,
+    scratch: &'s mut TraversalScratch,
+}
+
+impl<I: Recycle> Deref for Lent<'_, I> {
+    type Target = I;
+
+    fn deref(&self) -> &Self::Target {
+        self.iter.as_ref().unwrap()
+    }
+}
+
+impl<I: Recycle> DerefMut for Lent<'_, I> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.iter.as_mut().unwrap()
+    }
+}
+
+impl<I: Recycle + Iterator> Iterator for Lent<'_, I> {
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.deref_mut().next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.deref().size_hint()
+    }
+}
+
+impl<I: Recycle> Drop for Lent<'_, I> {
+    fn drop(&mut self) {
+        if let Some(iter) = self.iter.take() {
+            iter.recycle(self.scratch);
+        }
+    }
+}
+
+impl<I: Recycle + fmt::Debug> fmt::Debug for Lent<'_, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Lent").field(self.deref()).finish()
+    }
+}
+
+impl<'graph, const M: usize, const N: usize, T> StaticGraph<M, N, T> {
+    /// Same as `.dfs_iter()` but built from `scratch`'s buffers instead of allocating new ones.
+    pub fn dfs_iter_with<'s>(
+        &'graph self,
+        scratch: &'s mut TraversalScratch,
+    ) -> Lent<'s, DfsIter<'graph, M, N, T>> {
+        self.dfs_iter_with_state_in(CollectionState::default(), scratch)
+    }
+
+    /// Same as `.dfs_iter_with_state()` but built from `scratch`'s buffers instead of allocating
+    /// new ones.
+    pub fn dfs_iter_with_state_in<'s>(
+        &'graph self,
+        state: CollectionState,
+        scratch: &'s mut TraversalScratch,
+    ) -> Lent<'s, DfsIter<'graph, M, N, T>> {
+        let mut search_stack = scratch.stack.take().unwrap_or_default();
+        search_stack.clear();
+        let mut dfs_iter = DfsIter {
+            core: scratch.core(self, state),
+            search_stack,
+        };
+        dfs_iter.search_stack.push(dfs_iter.core.root);
+
+        Lent {
+            iter: Some(dfs_iter),
+            scratch,
+        }
+    }
+
+    /// Same as `.bfs_iter()` but built from `scratch`'s buffers instead of allocating new ones.
+    pub fn bfs_iter_with<'s>(
+        &'graph self,
+        scratch: &'s mut TraversalScratch,
+    ) -> Lent<'s, BfsIter<'graph, M, N, T>> {
+        self.bfs_iter_with_state_in(CollectionState::default(), scratch)
+    }
+
+    /// Same as `.bfs_iter_with_state()` but built from `scratch`'s buffers instead of allocating
+    /// new ones.
+    pub fn bfs_iter_with_state_in<'s>(
+        &'graph self,
+        state: CollectionState,
+        scratch: &'s mut TraversalScratch,
+    ) -> Lent<'s, BfsIter<'graph, M, N, T>> {
+        let mut search_queue = scratch.queue.take().unwrap_or_default();
+        search_queue.clear();
+        let mut bfs_iter = BfsIter {
+            core: scratch.core(self, state),
+            search_queue,
+        };
+        bfs_iter.search_queue.push_back(bfs_iter.core.root);
+
+        Lent {
+            iter: Some(bfs_iter),
+            scratch,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        graph::new_static_graph,
+        logic::{EventFlags, SettingsState, TierMask},
+    };
+
+    #[test]
+    fn reuse_scratch_buffers() {
+        let graph = new_static_graph();
+        let mut scratch = TraversalScratch::default();
+        assert!(scratch.visited.is_none());
+        let states = [
+            CollectionState::default(),
+            CollectionState::ALL_ITEMS,
+            CollectionState {
+                gloves: true,
+                ..CollectionState::NOTHING
+            },
+        ];
+        for state in states {
+            let expected_dfs = graph.dfs_iter_with_state(state.clone()).count();
+            let expected_bfs = graph.bfs_iter_with_state(state.clone()).count();
+            // Stop one search partway so the next has to clear what it left behind.
+            graph
+                .dfs_iter_with_state_in(state.clone(), &mut scratch)
+                .take(10)
+                .for_each(drop);
+            let visited = scratch.visited.as_ref().map(|v| v.as_ptr());
+            assert!(visited.is_some());
+            assert_eq!(
+                graph
+                    .dfs_iter_with_state_in(state.clone(), &mut scratch)
+                    .count(),
+                expected_dfs
+            );
+            let bfs_iter = graph.bfs_iter_with_state_in(state, &mut scratch);
+            assert_eq!(bfs_iter.core.visited_count, 1);
+            assert_eq!(bfs_iter.count(), expected_bfs);
+            // Same buffers every time.
+            assert_eq!(scratch.visited.as_ref().map(|v| v.as_ptr()), visited);
+        }
+        assert_eq!(
+            graph.bfs_iter_with(&mut scratch).count(),
+            graph.bfs_iter().count()
+        );
+    }
+}
@@ -0,0 +1,174 @@
+// Resolving logic that depends on reachability itself, like events and items picked up on the way.
+use alloc::vec::Vec;
+
+use crate::{
+    constants::VISITED_BITFIELD_LEN,
+    graph::{StaticGraph, VisitedCache},
+    logic::{CollectionState, EventId, Item},
+    tracker::TrackerSession,
+};
+
+/// A fixed-point reachability solver. Randomizer logic is full of requirements that depend on
+/// what we can reach: an event happens when we get to its node and an item is collected when we
+/// get to its location, and either can open edges leading to more of both. We search, add
+/// everything the search reached to the state and search again until the state stops changing,
+/// which leaves us with the closure an actual logic check needs.
+///
+/// ```ignore
+/// let closure = FixedPoint::new()
+///     .with_events(&[(EventId::Agahnim, agahnim)])
+///     .with_items(&placements)
+///     .solve(&graph, &state);
+/// let beatable = closure.visited.check_visited(ganon);
+/// ```
+///
+/// Updates only ever add to the state so reachability only ever grows, which lets us extend a
+/// single tracker session each round instead of searching the whole graph again.
+#[derive(Clone, Debug, Default)]
+pub struct FixedPoint<'a> {
+    events: &'a [(EventId, u16)],
+    items: &'a [(u16, Item)],
+    vanilla_items: bool,
+}
+
+impl<'a> FixedPoint<'a> {
+    /// A solver that doesn't update anything, so solving is a single search.
+    pub fn new() -> Self {
+        FixedPoint::default()
+    }
+
+    /// Make each event happen once its node is reached.
+    pub fn with_events(mut self, events: &'a [(EventId, u16)]) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Collect each `(location, item)` once its location is reached, e.g. a fill's placements.
+    pub fn with_items(mut self, items: &'a [(u16, Item)]) -> Self {
+        self.items = items;
+        self
+    }
+
+    /// Also collect the vanilla item at every item location reached.
+    pub fn with_vanilla_items(mut self) -> Self {
+        self.vanilla_items = true;
+        self
+    }
+
+    /// Search `graph` from the root under `state`, updating the state and searching further
+    /// until nothing changes. Events and items already in `state` count as found wherever they
+    /// are.
+    pub fn solve<const M: usize, const N: usize, T>(
+        &self,
+        graph: &StaticGraph<M, N, T>,
+        state: &CollectionState,
+    ) -> Closure {
+        let mut session = TrackerSession::new(graph, state.clone());
+        let mut rounds = 1;
+        loop {
+            let mut next = session.collection_state().clone();
+            for &(event, node) in self.events {
+                if session.is_reachable(node) {
+                    next.events = next.events.with(event);
+                }
+            }
+            let mut changed = next.events != session.collection_state().events;
+            for &(location, item) in self.items {
+                if session.is_reachable(location) {
+                    changed |= next.set(item, true);
+                }
+            }
+            if self.vanilla_items {
+                for location in session.reachable_checks() {
+                    let item = graph.item_data(location).unwrap().vanilla_item;
+                    changed |= next.set(item, true);
+                }
+            }
+            if !changed {
+                break;
+            }
+
+            session.core.collection_state = next;
+            session.revalidate();
+            rounds += 1;
+        }
+
+        Closure {
+            state: session.core.collection_state,
+            visited: session.core.visited,
+            rounds,
+        }
+    }
+}
+
+/// Where a search settles once everything it reaches has been added to the state. See
+/// `FixedPoint`.
+#[derive(Debug)]
+pub struct Closure {
+    /// The state we started from with every event and item we reached added.
+    pub state: CollectionState,
+    /// Every node reachable under `state`.
+    pub visited: VisitedCache<VISITED_BITFIELD_LEN>,
+    /// How many times we searched, including the last one that found nothing new.
+    pub rounds: u32,
+}
+
+impl Closure {
+    pub fn is_reachable(&self, node: u16) -> bool {
+        self.visited.check_visited(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bench_support::{assumed_fill, item_pool},
+        graph::new_static_graph,
+        logic::{EventFlags, SettingsState, TierMask},
+    };
+
+    #[test]
+    fn collect_until_settled() {
+        let graph = new_static_graph();
+        let nothing = CollectionState::NOTHING;
+        assert_eq!(FixedPoint::new().solve(&graph, &nothing).rounds, 1);
+
+        // Picking up vanilla items settles where searching from scratch every round does.
+        let closure = FixedPoint::new()
+            .with_vanilla_items()
+            .solve(&graph, &nothing);
+        let mut state = nothing.clone();
+        let expected: Vec<u16> = loop {
+            let reached: Vec<u16> = graph
+                .dfs_iter_with_state(state.clone())
+                .map(u16::from)
+                .collect();
+            let mut changed = false;
+            for &node in &reached {
+                if let Some(data) = graph.item_data(node) {
+                    changed |= state.set(data.vanilla_item, true);
+                }
+            }
+            if !changed {
+                break reached;
+            }
+        };
+        assert!(expected.iter().all(|&n| closure.is_reachable(n)));
+        let reached = graph.nodes().filter(|&n| closure.is_reachable(n)).count();
+        assert_eq!(reached, expected.len());
+        assert!([Item::Boots, Item::Gloves, Item::Flute, Item::Hammer]
+            .iter()
+            .all(|&i| closure.state.has(i) == state.has(i)));
+
+        // A completable fill collects every progression item from nothing.
+        let placements = assumed_fill(&graph, &item_pool(200), 9).unwrap();
+        let closure = FixedPoint::new()
+            .with_items(&placements)
+            .solve(&graph, &nothing);
+        assert!(closure.rounds > 1);
+        assert!(placements
+            .iter()
+            .all(|&(_, item)| !item.is_progression() || closure.state.has(item)));
+    }
+}
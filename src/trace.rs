@@ -0,0 +1,19 @@
+// Thin wrappers over `tracing`'s macros that expand to nothing unless the `trace` feature is
+// enabled, so instrumented code doesn't need a cfg at every call site. Without the feature the
+// arguments aren't evaluated at all, so only pass values we already have on hand.
+
+/// Emit an event, e.g. `event!(TRACE, node, frontier = queue.len(), "expand")`.
+macro_rules! event {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "trace")]
+        tracing::event!(tracing::Level::$level, $($arg)+);
+    };
+}
+
+/// Enter a span until the end of the enclosing block, e.g. `span!(DEBUG, "search", goal)`.
+macro_rules! span {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "trace")]
+        let _span = tracing::span!(tracing::Level::$level, $($arg)+).entered();
+    };
+}
@@ -0,0 +1,694 @@
+use alloc::{boxed::Box, collections::VecDeque, sync::Arc, vec::Vec};
+use core::{fmt, num::NonZeroU16};
+
+use crate::{
+    constants::*,
+    graph::{AccessCache, ReqMasks, StaticGraph, TrickMask},
+    logic::{CollectionState, EventFlags, Item, SettingsState, TierMask},
+    traversal::TraversalCore,
+};
+
+/// The main loop of an auto-tracker: a collection state that changes one item at a time and the
+/// set of nodes reachable under it. Rather than traversing the whole graph after every change, we
+/// keep our caches between changes and only do the work the change requires.
+///
+/// Collecting an item can only ever make more edges traversable, so we rebuild access from the
+/// graph's precomputed requirement masks and resume the search from the newly accessible edges
+/// leaving nodes we'd already reached. Uncollecting an item can make nodes unreachable, but only
+/// nodes downstream of an edge that closed, so we forget those and search again from the edges
+/// into them that are still open. See `revalidate`.
+///
+/// Every change is also kept in a bounded history so mis-clicks can be undone cheaply. Undoing a
+/// collect doesn't search at all since we remember which nodes it made reachable, and undoing an
+/// uncollect is just an incremental collect.
+///
+/// Tricks the player has turned on in their settings open their edges whatever the edges'
+/// requirements, see `enable_trick`.
+///
+/// All of this relies on collecting only opening edges. On a graph that negates something, see
+/// `StaticGraph::is_monotone`, collecting can close edges too, so there collects and their undos
+/// go through `revalidate` like everything else.
+pub struct TrackerSession<'graph, const M: usize, const N: usize, T = ()> {
+    pub core: TraversalCore<'graph, M, N, T>,
+    // These only depend on the graph, so clones of a session share them.
+    masks: Arc<ReqMasks<ACCESS_BITFIELD_LEN>>,
+    // The node each edge leaves from, so a newly accessible edge can be resumed from.
+    edge_sources: Arc<[u16]>,
+    // The edges each of the graph's tricks opens, by trick index.
+    trick_masks: Arc<[AccessCache<ACCESS_BITFIELD_LEN>]>,
+    tricks: TrickMask,
+    monotone: bool,
+    frontier: Vec<u16>,
+    history: VecDeque<StateDelta>,
+    redo: Vec<StateDelta>,
+    history_limit: usize,
+}
+
+/// A single change to a tracker session's collection state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StateDelta {
+    pub item: Item,
+    pub collected: bool,
+    // The nodes a collect made reachable, so undoing it is just unmarking them.
+    newly_reachable: Vec<u16>,
+}
+
+impl<'graph, const M: usize, const N: usize, T> TrackerSession<'graph, M, N, T> {
+    /// How many changes we can undo unless told otherwise.
+    pub const DEFAULT_HISTORY_LIMIT: usize = 100;
+
+    /// Start a session from the graph's root with the given collection state.
+    pub fn new(graph: &'graph StaticGraph<M, N, T>, state: CollectionState) -> Self {
+        let mut edge_sources = alloc::vec![0u16; N].into_boxed_slice();
+        for node in 1..=graph.node_count {
+            let (edge_pointers, edge_offset) = graph.get_neighbors_out(NonZeroU16::new(node));
+            let start = edge_offset as usize;
+            edge_sources[start..start + edge_pointers.len()].fill(node);
+        }
+        let trick_masks = (0..graph.tricks().len())
+            .map(|i| {
+                let mut mask = AccessCache::with_len(StaticGraph::<M, N, T>::ACCESS_LEN);
+                graph
+                    .trick_edges(TrickMask::single(i))
+                    .for_each(|e| mask.set_access(e));
+                mask
+            })
+            .collect();
+        let mut session = TrackerSession {
+            masks: Arc::from(graph.req_masks()),
+            core: TraversalCore::new(graph, 1, state),
+            edge_sources: Arc::from(edge_sources),
+            trick_masks,
+            tricks: TrickMask::NONE,
+            monotone: graph.is_monotone(),
+            frontier: Vec::new(),
+            history: VecDeque::new(),
+            redo: Vec::new(),
+            history_limit: Self::DEFAULT_HISTORY_LIMIT,
+        };
+        session.frontier.push(session.core.root);
+        session.expand(&mut Vec::new());
+
+        session
+    }
+
+    pub fn collection_state(&self) -> &CollectionState {
+        &self.core.collection_state
+    }
+
+    /// Set how many changes we can undo, dropping the oldest ones if there are already more.
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.history_limit = limit;
+        while self.history.len() > limit {
+            self.history.pop_front();
+        }
+    }
+
+    /// Mark `item` collected and extend reachability. Returns the number of newly reachable
+    /// nodes.
+    pub fn collect(&mut self, item: Item) -> u16 {
+        match self.apply_collect(item) {
+            Some(delta) => {
+                let added = delta.newly_reachable.len() as u16;
+                self.record(delta);
+                added
+            }
+            None => 0,
+        }
+    }
+
+    /// Mark `item` not collected and revalidate reachability.
+    pub fn uncollect(&mut self, item: Item) {
+        if let Some(delta) = self.apply_uncollect(item) {
+            self.record(delta);
+        }
+    }
+
+    /// Choose which logic tiers are enabled and bring reachability up to date. Changes recorded
+    /// under the old tiers can't be undone precisely under the new ones, so this also clears the
+    /// undo and redo history.
+    pub fn set_tiers(&mut self, tiers: TierMask) {
+        if self.core.collection_state.tiers == tiers {
+            return;
+        }
+        self.core.collection_state.tiers = tiers;
+        self.revalidate();
+        self.history.clear();
+        self.redo.clear();
+    }
+
+    /// Set the game mode flags the seed was generated with. Like `set_tiers` this changes what
+    /// every requirement tree checking a setting evaluates to, so it clears the undo and redo
+    /// history too.
+    pub fn set_settings(&mut self, settings: SettingsState) {
+        if self.core.collection_state.settings == settings {
+            return;
+        }
+        self.core.collection_state.settings = settings;
+        self.revalidate();
+        self.history.clear();
+        self.redo.clear();
+    }
+
+    /// The tricks currently enabled, as bits into the graph's `tricks`.
+    pub fn tricks(&self) -> TrickMask {
+        self.tricks
+    }
+
+    /// Enable the graph's trick called `name`, opening every edge it annotates. Returns false if
+    /// the graph has no such trick. Like `set_tiers` this clears the undo and redo history.
+    pub fn enable_trick(&mut self, name: &str) -> bool {
+        match self.core.graph.trick(name) {
+            Some(trick) => {
+                self.set_tricks(self.tricks.with(trick));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Disable the graph's trick called `name`. Returns false if the graph has no such trick.
+    pub fn disable_trick(&mut self, name: &str) -> bool {
+        match self.core.graph.trick(name) {
+            Some(trick) => {
+                self.set_tricks(self.tricks.without(trick));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Choose exactly which tricks are enabled and bring reachability up to date.
+    pub fn set_tricks(&mut self, tricks: TrickMask) {
+        if self.tricks == tricks {
+            return;
+        }
+        self.tricks = tricks;
+        self.revalidate();
+        self.history.clear();
+        self.redo.clear();
+    }
+
+    /// Revert the most recent change still in the history. Returns false if there's nothing to
+    /// undo.
+    pub fn undo(&mut self) -> bool {
+        let Some(delta) = self.history.pop_back() else {
+            return false;
+        };
+        match delta.collected {
+            true if !self.monotone => {
+                self.core.collection_state.set(delta.item, false);
+                self.revalidate();
+            }
+            true => {
+                self.core.collection_state.set(delta.item, false);
+                self.rebuild_access();
+                delta
+                    .newly_reachable
+                    .iter()
+                    .for_each(|&n| self.core.visited.unmark_visited(n));
+                self.core.visited_count -= delta.newly_reachable.len() as u16;
+            }
+            false => {
+                self.apply_collect(delta.item);
+            }
+        };
+        self.redo.push(delta);
+
+        true
+    }
+
+    /// Re-apply the most recently undone change. Returns false if there's nothing to redo. Any
+    /// new change clears what can be redone.
+    pub fn redo(&mut self) -> bool {
+        let Some(delta) = self.redo.pop() else {
+            return false;
+        };
+        let delta = match delta.collected {
+            true => self.apply_collect(delta.item),
+            false => self.apply_uncollect(delta.item),
+        };
+        if let Some(delta) = delta {
+            self.push_history(delta);
+        }
+
+        true
+    }
+
+    fn record(&mut self, delta: StateDelta) {
+        self.redo.clear();
+        self.push_history(delta);
+    }
+
+    fn push_history(&mut self, delta: StateDelta) {
+        if self.history_limit == 0 {
+            return;
+        }
+        if self.history.len() == self.history_limit {
+            self.history.pop_front();
+        }
+        self.history.push_back(delta);
+    }
+
+    fn apply_collect(&mut self, item: Item) -> Option<StateDelta> {
+        if !self.core.collection_state.set(item, true) {
+            return None;
+        }
+        if !self.monotone {
+            let reachable_before = self.core.visited.clone();
+            self.revalidate();
+            let newly_reachable = self
+                .core
+                .visited
+                .iter_ones()
+                .filter(|&n| !reachable_before.check_visited(n))
+                .collect();
+
+            return Some(StateDelta {
+                item,
+                collected: true,
+                newly_reachable,
+            });
+        }
+        let previous_access = self.core.edge_access.clone();
+        self.rebuild_access();
+        let mut newly_reachable = Vec::new();
+        // Collecting only ever opens edges.
+        for edge in AccessCache::diff(&previous_access, &self.core.edge_access) {
+            if self
+                .core
+                .visited
+                .check_visited(self.edge_sources[edge as usize])
+            {
+                let dest = u16::from(self.core.graph.edge_pointers[edge]);
+                if !self.core.visited.test_set_visited(dest) {
+                    self.core.visited_count += 1;
+                    self.frontier.push(dest);
+                    newly_reachable.push(dest);
+                }
+            }
+        }
+        self.expand(&mut newly_reachable);
+
+        Some(StateDelta {
+            item,
+            collected: true,
+            newly_reachable,
+        })
+    }
+
+    fn apply_uncollect(&mut self, item: Item) -> Option<StateDelta> {
+        if !self.core.collection_state.set(item, false) {
+            return None;
+        }
+        self.revalidate();
+
+        Some(StateDelta {
+            item,
+            collected: false,
+            newly_reachable: Vec::new(),
+        })
+    }
+
+    /// Returns whether `node` is reachable under the current collection state.
+    pub fn is_reachable(&self, node: u16) -> bool {
+        self.core.visited.check_visited(node)
+    }
+
+    /// Every reachable item location, in node index order.
+    pub fn reachable_checks(&self) -> Vec<u16> {
+        self.core
+            .visited
+            .iter_ones()
+            .filter(|&n| n <= self.core.graph.node_count && self.core.graph.item_data(n).is_some())
+            .collect()
+    }
+
+    /// Bring access and reachability up to date with the current collection state, e.g. after
+    /// changing several items at once through `core.collection_state`.
+    ///
+    /// Removing items means the visited set over-approximates reachability. The nodes that may
+    /// have become unreachable are exactly those downstream of an edge that closed, so we sweep
+    /// them out of the visited set and then search again from every open edge leading back into
+    /// them (or anywhere else new) from a node that's still reached. Everything upstream of the
+    /// change keeps its visited bit. Returns how many fewer nodes are reachable than before.
+    pub fn revalidate(&mut self) -> u16 {
+        let reachable_before = self.core.visited_count;
+        let previous_access = self.core.edge_access.clone();
+        self.rebuild_access();
+        let graph = self.core.graph;
+        let mut suspects = Vec::new();
+        let mut changed = false;
+        for edge in AccessCache::diff(&previous_access, &self.core.edge_access) {
+            changed = true;
+            let closed = previous_access.check_access(edge);
+            if closed
+                && self
+                    .core
+                    .visited
+                    .check_visited(self.edge_sources[edge as usize])
+            {
+                suspects.push(u16::from(graph.edge_pointers[edge]));
+            }
+        }
+        if !changed {
+            return 0;
+        }
+
+        // Mark and sweep everything we can get to from a suspect through nodes we'd reached. The
+        // root is always reachable.
+        while let Some(node) = suspects.pop() {
+            if node == self.core.root || !self.core.visited.check_visited(node) {
+                continue;
+            }
+            self.core.visited.unmark_visited(node);
+            self.core.visited_count -= 1;
+            let (edge_pointers, edge_offset) = graph.get_neighbors_out(NonZeroU16::new(node));
+            for (i, &n) in edge_pointers.iter().enumerate() {
+                if self.core.check_edge(edge_offset + i as u16) {
+                    suspects.push(u16::from(n));
+                }
+            }
+        }
+
+        // Resume from every open edge leaving a reached node for one we haven't, which covers
+        // both edges back into the swept nodes and edges opened by collected items.
+        for edge in 1..=graph.edge_count {
+            let (src, dest) = (self.edge_sources[edge as usize], graph.edge_pointers[edge]);
+            if self.core.visited.check_visited(src)
+                && self.core.check_edge(edge)
+                && !self.core.visited.test_set_visited(u16::from(dest))
+            {
+                self.core.visited_count += 1;
+                self.frontier.push(u16::from(dest));
+            }
+        }
+        self.expand(&mut Vec::new());
+
+        reachable_before.saturating_sub(self.core.visited_count)
+    }
+
+    // Recompute edge access from the collection state, then open every enabled trick's edges.
+    fn rebuild_access(&mut self) {
+        let memo = self.core.graph.req_memo(&self.core.collection_state);
+        let edge_access = &mut self.core.edge_access;
+        edge_access.rebuild_from_masks(&self.masks, &memo);
+        for (i, mask) in self.trick_masks.iter().enumerate() {
+            if self.tricks.intersects(TrickMask::single(i)) {
+                edge_access.union(mask);
+            }
+        }
+    }
+
+    // Search from every node in the frontier until it's empty, adding every node we visit to
+    // `visited`.
+    fn expand(&mut self, visited: &mut Vec<u16>) {
+        let TrackerSession { core, frontier, .. } = self;
+        while let Some(node) = frontier.pop() {
+            core.visit_neighbors_out(NonZeroU16::new(node), |n| {
+                frontier.push(n);
+                visited.push(n);
+            });
+        }
+    }
+}
+
+/// A clone is an independent session with its own caches and history, e.g. for trying out a
+/// change without committing to it.
+impl<const M: usize, const N: usize, T> Clone for TrackerSession<'_, M, N, T> {
+    fn clone(&self) -> Self {
+        TrackerSession {
+            core: self.core.clone(),
+            masks: Arc::clone(&self.masks),
+            edge_sources: Arc::clone(&self.edge_sources),
+            trick_masks: Arc::clone(&self.trick_masks),
+            tricks: self.tricks,
+            monotone: self.monotone,
+            frontier: self.frontier.clone(),
+            history: self.history.clone(),
+            redo: self.redo.clone(),
+            history_limit: self.history_limit,
+        }
+    }
+}
+
+impl<const M: usize, const N: usize, T> fmt::Debug for TrackerSession<'_, M, N, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TrackerSession")
+            .field("core", &self.core)
+            .field("history", &self.history.len())
+            .field("redo", &self.redo.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        graph::new_static_graph,
+        logic::{ReqIndex, SettingFlag, Tier, REQ_TREES},
+        parser::parse_requirement_with_negation,
+        types::ReqBuilder,
+    };
+
+    fn reachable_from_scratch<const M: usize, const N: usize>(
+        graph: &StaticGraph<M, N>,
+        state: &CollectionState,
+    ) -> Vec<u16> {
+        let mut bfs_iter = graph.bfs_iter_with_state(state.clone());
+        bfs_iter.by_ref().for_each(drop);
+        (1..=graph.node_count)
+            .filter(|&n| bfs_iter.core.visited.check_visited(n))
+            .collect()
+    }
+
+    #[test]
+    fn tracker_session() {
+        let graph = new_static_graph();
+        let nothing = CollectionState::NOTHING;
+        let mut session = TrackerSession::new(&graph, nothing);
+        let reachable = |s: &TrackerSession<'_, NUM_VERTICES_PADDED, NUM_EDGES_PADDED>| {
+            (1..=graph.node_count)
+                .filter(|&n| s.is_reachable(n))
+                .collect::<Vec<u16>>()
+        };
+        assert_eq!(
+            reachable(&session),
+            reachable_from_scratch(&graph, session.collection_state())
+        );
+
+        for item in [
+            Item::Hammer,
+            Item::Boots,
+            Item::Rupees,
+            Item::Gloves,
+            Item::Flute,
+        ] {
+            let before = session.core.visited_count;
+            let added = session.collect(item);
+            assert_eq!(session.core.visited_count, before + added);
+            assert_eq!(
+                reachable(&session),
+                reachable_from_scratch(&graph, session.collection_state())
+            );
+        }
+        assert_eq!(session.collect(Item::Hammer), 0);
+
+        session.uncollect(Item::Hammer);
+        assert!(!session.collection_state().hammer);
+        assert_eq!(
+            reachable(&session),
+            reachable_from_scratch(&graph, session.collection_state())
+        );
+        assert!(session
+            .reachable_checks()
+            .iter()
+            .all(|&n| graph.item_data(n).is_some() && session.is_reachable(n)));
+    }
+
+    #[test]
+    fn items_that_close_edges() {
+        // 1 2 3 4 in a line with a branch to 5 behind the hammer, where 2 3 is only open without
+        // it, so collecting the hammer loses 3 and 4.
+        let mut trees = ReqBuilder::<64>::from_trees(&REQ_TREES);
+        let not_hammer = parse_requirement_with_negation("!hammer")
+            .unwrap()
+            .build(&mut trees)
+            .unwrap();
+        let mut builder =
+            StaticGraph::<64, 64>::from_edge_list(["1 2", "2 3", "3 4", "1 5 hammer"])
+                .unwrap()
+                .to_builder();
+        builder.set_req_trees(trees.nodes());
+        builder.edge_mut(2, 3).unwrap().req = ReqIndex::new_in(not_hammer, trees.nodes()).unwrap();
+        let graph: StaticGraph<64, 64> = builder.freeze().unwrap();
+        assert!(!graph.is_monotone());
+
+        let mut session = TrackerSession::new(&graph, CollectionState::NOTHING);
+        let reachable = |s: &TrackerSession<'_, 64, 64>| {
+            (1..=graph.node_count)
+                .filter(|&n| s.is_reachable(n))
+                .collect::<Vec<u16>>()
+        };
+        assert_eq!(reachable(&session), [1, 2, 3, 4]);
+        assert_eq!(session.collect(Item::Hammer), 1);
+        assert_eq!(reachable(&session), [1, 2, 5]);
+        assert_eq!(
+            reachable(&session),
+            reachable_from_scratch(&graph, session.collection_state())
+        );
+        assert_eq!(session.core.visited_count, 3);
+
+        assert!(session.undo());
+        assert_eq!(reachable(&session), [1, 2, 3, 4]);
+        assert_eq!(session.core.visited_count, 4);
+        assert!(session.redo());
+        assert_eq!(reachable(&session), [1, 2, 5]);
+        assert_eq!(
+            reachable(&session),
+            reachable_from_scratch(&graph, session.collection_state())
+        );
+    }
+
+    #[test]
+    fn revalidate_matches_recomputing() {
+        let graph = new_static_graph();
+        let mut session = TrackerSession::new(&graph, CollectionState::ALL_ITEMS);
+        let all = session.core.visited_count;
+        let reachable = |s: &TrackerSession<'_, NUM_VERTICES_PADDED, NUM_EDGES_PADDED>| {
+            (1..=graph.node_count)
+                .filter(|&n| s.is_reachable(n))
+                .collect::<Vec<u16>>()
+        };
+        assert_eq!(session.revalidate(), 0);
+
+        // Every combination of items, changing several at once between checks.
+        for bits in [0b1010u8, 0b0101, 0, 0b1111, 0b0110, 0b1001, 0b0011, 0b1100] {
+            let state = &mut session.core.collection_state;
+            state.boots = bits & 1 != 0;
+            state.gloves = bits & 2 != 0;
+            state.hammer = bits & 4 != 0;
+            state.flute = bits & 8 != 0;
+            let before = session.core.visited_count;
+            let lost = session.revalidate();
+            let expected = reachable_from_scratch(&graph, session.collection_state());
+            assert_eq!(reachable(&session), expected);
+            assert_eq!(session.core.visited_count as usize, expected.len());
+            assert_eq!(lost, before.saturating_sub(expected.len() as u16));
+        }
+        session.core.collection_state = CollectionState::ALL_ITEMS;
+        session.revalidate();
+        assert_eq!(session.core.visited_count, all);
+    }
+
+    #[test]
+    fn tier_and_setting_toggles() {
+        // Let hard logic use the hammer wherever normal logic needs gloves, and only allow the
+        // flute in swordless seeds.
+        let swapped = || {
+            let mut graph = new_static_graph();
+            let swaps =
+                [(4, 9), (6, 11)].map(|(from, to)| (ReqIndex::new(from), ReqIndex::new(to)));
+            for req in Arc::make_mut(&mut graph.edge_data).iter_mut() {
+                if let Some(&(_, to)) = swaps.iter().find(|&&(from, _)| from == Some(*req)) {
+                    *req = to.unwrap();
+                }
+            }
+            graph
+        };
+        let graph = swapped();
+        let mut session = TrackerSession::new(&graph, CollectionState::default());
+        session.collect(Item::Boots);
+        let normal = session.core.visited_count;
+        session.set_tiers(TierMask::up_to(Tier::Hard));
+        assert!(session.core.visited_count > normal);
+        assert!(!session.undo());
+        let expected = reachable_from_scratch(&graph, session.collection_state());
+        assert_eq!(session.core.visited_count as usize, expected.len());
+
+        session.set_tiers(TierMask::NORMAL);
+        assert_eq!(session.core.visited_count, normal);
+
+        session.set_settings(SettingsState::NONE.with(SettingFlag::Swordless, true));
+        assert!(session.core.visited_count > normal);
+        let expected = reachable_from_scratch(&graph, session.collection_state());
+        assert_eq!(session.core.visited_count as usize, expected.len());
+        session.set_settings(SettingsState::NONE);
+        assert_eq!(session.core.visited_count, normal);
+
+        // Tricks open their edges just like opening their requirements would.
+        assert!(!session.enable_trick("no_such_trick"));
+        assert!(session.enable_trick("Hover"));
+        assert_eq!(session.tricks(), graph.trick("hover").unwrap());
+        for name in graph.tricks() {
+            assert!(session.enable_trick(name));
+        }
+        let mut opened = swapped();
+        for edge in graph.trick_edges(session.tricks()) {
+            Arc::make_mut(&mut opened.edge_data)[edge as usize] = ReqIndex::OPEN;
+        }
+        let expected = reachable_from_scratch(&opened, session.collection_state());
+        assert!(expected.len() > normal as usize);
+        assert_eq!(session.core.visited_count as usize, expected.len());
+        assert!(session.disable_trick("hover"));
+        assert!(!session.tricks().intersects(graph.trick("hover").unwrap()));
+        session.set_tricks(TrickMask::NONE);
+        assert_eq!(session.core.visited_count, normal);
+    }
+
+    #[test]
+    fn undo_redo() {
+        let graph = new_static_graph();
+        let nothing = CollectionState::NOTHING;
+        let mut session = TrackerSession::new(&graph, nothing.clone());
+        assert!(!session.undo());
+        let snapshot = |s: &TrackerSession<'_, NUM_VERTICES_PADDED, NUM_EDGES_PADDED>| {
+            let visited: Vec<u16> = (1..=graph.node_count)
+                .filter(|&n| s.is_reachable(n))
+                .collect();
+            let access: Vec<u64> = s.core.edge_access.iter().copied().collect();
+            (
+                s.collection_state().clone(),
+                visited,
+                access,
+                s.core.visited_count,
+            )
+        };
+        let key = |s: &(CollectionState, Vec<u16>, Vec<u64>, u16)| {
+            (
+                [s.0.boots, s.0.hammer, s.0.gloves, s.0.flute],
+                s.1.clone(),
+                s.2.clone(),
+                s.3,
+            )
+        };
+
+        let mut snapshots = std::vec![key(&snapshot(&session))];
+        session.collect(Item::Hammer);
+        snapshots.push(key(&snapshot(&session)));
+        session.collect(Item::Boots);
+        snapshots.push(key(&snapshot(&session)));
+        session.uncollect(Item::Hammer);
+        snapshots.push(key(&snapshot(&session)));
+
+        for expected in snapshots.iter().rev().skip(1) {
+            assert!(session.undo());
+            assert_eq!(&key(&snapshot(&session)), expected);
+        }
+        assert!(!session.undo());
+        for expected in snapshots.iter().skip(1) {
+            assert!(session.redo());
+            assert_eq!(&key(&snapshot(&session)), expected);
+        }
+        assert!(!session.redo());
+
+        // A new change clears the redo history, and the history is bounded.
+        session.undo();
+        session.collect(Item::Flute);
+        assert!(!session.redo());
+        session.set_history_limit(1);
+        assert!(session.undo());
+        assert!(!session.undo());
+    }
+}
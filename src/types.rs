@@ -0,0 +1,626 @@
+// Plain definitions shared by the library, our build script and sparsegraph-macros, which
+// include this file with `#[path]`. Codegen reads and writes these types so keeping a single copy
+// means the generated tables and the library can't silently disagree about them. Everything in
+// here has to stay dependency free and only use core; formatting and anything else that's only
+// needed by one side lives with that side.
+
+use core::{num::NonZeroU16, ops::Index};
+
+/// In many cases when we're traversing the graph we don't need to concern ourselves with a node's
+/// full metadata. We may not even care what type of node it is. We don't use this struct in our
+/// demonstration but the intention is that every node has a type and associated "wide" metadata
+/// (generated from our plain text world model at compile time, not hard coded) which are stored in
+/// separate arrays by node type and only accessed when needed.
+///
+/// We store these in an array separate from node pointers for the sake of cache efficiency; a
+/// traversing iterator can choose whether it cares about them or not.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeData {
+    pub node_type: NodeType,
+    pub data_index: u16,
+}
+
+// We can also derive Copy for practically free but I want to avoid implicit copies of these types
+// for now. We always access the original owned version by reference.
+impl NodeData {
+    pub const fn default() -> Self {
+        NodeData {
+            node_type: NodeType::Place,
+            data_index: 0u16,
+        }
+    }
+}
+
+impl NodeData {
+    pub const DEFAULT: NodeData = NodeData::default();
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NodeType {
+    Place, // ie: "Region" in ER, a logically distinct place where the player can just "be."
+    Item,
+    Door,
+}
+
+/// A small set of flags describing what it takes to open a door. These are informational; the
+/// requirements that actually gate traversal still live on the door's edges.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct DoorFlags(pub(crate) u8);
+
+impl DoorFlags {
+    pub const NONE: DoorFlags = DoorFlags(0);
+    pub const SMALL_KEY: DoorFlags = DoorFlags(1 << 0);
+    pub const BIG_KEY: DoorFlags = DoorFlags(1 << 1);
+    pub const BOMBABLE: DoorFlags = DoorFlags(1 << 2);
+
+    pub const fn contains(&self, other: DoorFlags) -> bool {
+        (self.0 & other.0) == other.0
+    }
+}
+
+/// What sort of transition an edge models.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum EdgeType {
+    /// Just walking from one place to another.
+    Normal,
+    /// Going into or out of a door node.
+    Door,
+    /// A warp, e.g. a portal or flute spot.
+    Warp,
+    /// A logical shortcut that skips over nodes the player would otherwise walk through.
+    Shortcut,
+}
+
+impl EdgeType {
+    /// Whether taking the edge goes through a loading zone, i.e. it's a door or a warp, rather
+    /// than moving around within one room.
+    pub const fn crosses_loading_zone(self) -> bool {
+        matches!(self, EdgeType::Door | EdgeType::Warp)
+    }
+}
+
+/// A set of tricks, one bit per entry of a graph's trick list. A trick is a technique players
+/// can opt into, e.g. fake flippers, that opens particular edges whatever their requirement says.
+/// Unlike logic tiers they don't change what any requirement tree means, so turning one on only
+/// ever touches its own edges. See `StaticGraph::tricks`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct TrickMask(pub(crate) u8);
+
+impl TrickMask {
+    pub const NONE: TrickMask = TrickMask(0);
+    /// The most tricks a graph can have.
+    pub const MAX: usize = u8::BITS as usize;
+
+    /// Just the trick at `index` in a graph's trick list.
+    ///
+    /// Panics if `index` isn't below `MAX`.
+    pub const fn single(index: usize) -> TrickMask {
+        assert!(index < TrickMask::MAX, "trick index out of range");
+        TrickMask(1 << index)
+    }
+
+    pub const fn with(self, other: TrickMask) -> TrickMask {
+        TrickMask(self.0 | other.0)
+    }
+
+    pub const fn without(self, other: TrickMask) -> TrickMask {
+        TrickMask(self.0 & !other.0)
+    }
+
+    /// Whether any trick is in both sets.
+    pub const fn intersects(&self, other: TrickMask) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+/// Items that can be placed at `NodeType::Item` locations. Only progression items have any
+/// bearing on logic; everything else is represented by a single junk item.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Item {
+    Boots,
+    Gloves,
+    Flute,
+    Hammer,
+    Rupees,
+}
+
+impl Item {
+    pub const fn is_progression(&self) -> bool {
+        !matches!(self, Item::Rupees)
+    }
+}
+
+/// How hard a piece of logic expects the player to play. Randomizers usually let players choose
+/// which tiers their seed's logic can assume, e.g. whether it can require a hard trick or a
+/// glitch, and the same world model serves every choice since requirement trees can check a tier
+/// like any other requirement. `Normal` logic is always enabled.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum Tier {
+    Normal,
+    Hard,
+    Glitched,
+}
+
+impl Tier {
+    pub const ALL: [Tier; 3] = [Tier::Normal, Tier::Hard, Tier::Glitched];
+}
+
+/// A game mode flag a seed is generated with, e.g. whether swords are removed from the item pool
+/// or keys can be found outside their dungeons. Settings are fixed for the whole session so
+/// requirement trees checking one are evaluated once per setting rather than per item collected.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum SettingFlag {
+    Swordless,
+    Keysanity,
+}
+
+impl SettingFlag {
+    pub const ALL: [SettingFlag; 2] = [SettingFlag::Swordless, SettingFlag::Keysanity];
+}
+
+/// Something that happens in the world when the player gets to a particular node, e.g. beating
+/// Agahnim or hitting a crystal switch, which other parts of the logic can depend on. Unlike
+/// items, whether an event has happened follows from the graph itself, so events are resolved
+/// by searching until every reachable event node has been reached. See
+/// `Reachability::with_events`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum EventId {
+    Agahnim,
+    CrystalSwitch,
+}
+
+impl EventId {
+    pub const ALL: [EventId; 2] = [EventId::Agahnim, EventId::CrystalSwitch];
+}
+
+/// Edge traversal requirements that our graph walker is responsible for exhaustively implementing
+/// evaluation of. In the simplest case, these represent an item in collection state which we
+/// can quickly check for the presence of. But these can also check combinations including graph
+/// state and game configuration/settings state (e.g., we can program a CanWaterWalk requirement
+/// or check if we're dealing with OHKO mode.) Additionally, a production implementation would be
+/// slightly complicated by parameterized requirements (e.g. HasRupees(500), HasHealth(12))
+///
+/// We put Open and Locked to represent situations where traversal is always possible or we want to
+/// limit our graph operations to smaller subgraphs (e.g. single dungeons.) These are encoded here
+/// to hopefully avoid extra branches from encoding them as a separate enum higher in the main graph
+/// representation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u16)]
+pub enum Requirement {
+    Open,
+    Boots,
+    Gloves,
+    Flute,
+    Hammer,
+    Locked,
+    /// Satisfied when the tier is enabled, so ANDing it into a tree marks that part of the logic
+    /// as only in logic for that tier.
+    Tier(Tier),
+    /// Satisfied when the seed was generated with the setting.
+    Setting(SettingFlag),
+    /// Satisfied once the event's node has been reached.
+    Event(EventId),
+    /// Satisfied while the requirement it negates isn't, e.g. an entrance that closes once an
+    /// event has happened. Collecting something can close an edge checking one of these, so a
+    /// graph using them isn't monotone. See `StaticGraph::is_monotone`.
+    Not(Negatable),
+}
+
+impl Requirement {
+    /// The item this requirement checks collection state for, if any.
+    pub const fn item(&self) -> Option<Item> {
+        match self {
+            Requirement::Boots => Some(Item::Boots),
+            Requirement::Gloves => Some(Item::Gloves),
+            Requirement::Flute => Some(Item::Flute),
+            Requirement::Hammer => Some(Item::Hammer),
+            Requirement::Open
+            | Requirement::Locked
+            | Requirement::Tier(_)
+            | Requirement::Setting(_)
+            | Requirement::Event(_)
+            | Requirement::Not(_) => None,
+        }
+    }
+
+    /// The tier this requirement checks is enabled, if any.
+    pub const fn tier(&self) -> Option<Tier> {
+        match self {
+            Requirement::Tier(tier) => Some(*tier),
+            _ => None,
+        }
+    }
+
+    /// The setting this requirement checks is enabled, if any.
+    pub const fn setting(&self) -> Option<SettingFlag> {
+        match self {
+            Requirement::Setting(flag) => Some(*flag),
+            _ => None,
+        }
+    }
+
+    /// The event this requirement checks has happened, if any.
+    pub const fn event(&self) -> Option<EventId> {
+        match self {
+            Requirement::Event(event) => Some(*event),
+            _ => None,
+        }
+    }
+
+    /// How many distinct requirements there are besides negations, one past the highest `id` of
+    /// one. A `Not` takes the id of the requirement it negates plus this.
+    pub const COUNT: usize = 6 + Tier::ALL.len() + SettingFlag::ALL.len() + EventId::ALL.len();
+
+    /// The requirement with the given id. Returns None for any unknown id.
+    pub const fn from_id(id: u16) -> Option<Requirement> {
+        const TIERS: usize = 6;
+        const SETTINGS: usize = TIERS + Tier::ALL.len();
+        const EVENTS: usize = SETTINGS + SettingFlag::ALL.len();
+        let id = id as usize;
+        match id {
+            0 => Some(Requirement::Open),
+            1 => Some(Requirement::Boots),
+            2 => Some(Requirement::Gloves),
+            3 => Some(Requirement::Flute),
+            4 => Some(Requirement::Hammer),
+            5 => Some(Requirement::Locked),
+            _ if id < SETTINGS => Some(Requirement::Tier(Tier::ALL[id - TIERS])),
+            _ if id < EVENTS => Some(Requirement::Setting(SettingFlag::ALL[id - SETTINGS])),
+            _ if id < Requirement::COUNT => Some(Requirement::Event(EventId::ALL[id - EVENTS])),
+            _ if id < 2 * Requirement::COUNT => {
+                match Requirement::from_id((id - Requirement::COUNT) as u16) {
+                    Some(Requirement::Open | Requirement::Locked) | None => None,
+                    Some(req) => Some(req.negate()),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// A number unique to each requirement, for anything keyed or indexed by requirement.
+    pub const fn id(&self) -> u16 {
+        match self {
+            Requirement::Open => 0,
+            Requirement::Boots => 1,
+            Requirement::Gloves => 2,
+            Requirement::Flute => 3,
+            Requirement::Hammer => 4,
+            Requirement::Locked => 5,
+            Requirement::Tier(tier) => 6 + *tier as u16,
+            Requirement::Setting(flag) => 6 + Tier::ALL.len() as u16 + *flag as u16,
+            Requirement::Event(event) => {
+                6 + (Tier::ALL.len() + SettingFlag::ALL.len()) as u16 + *event as u16
+            }
+            Requirement::Not(negated) => Requirement::COUNT as u16 + negated.requirement().id(),
+        }
+    }
+
+    /// The requirement satisfied exactly when this one isn't. Open and locked swap, and negating
+    /// a `Not` gets back the requirement it negates.
+    pub const fn negate(&self) -> Requirement {
+        let negated = match self {
+            Requirement::Open => return Requirement::Locked,
+            Requirement::Locked => return Requirement::Open,
+            Requirement::Not(negated) => return negated.requirement(),
+            Requirement::Boots => Negatable::Boots,
+            Requirement::Gloves => Negatable::Gloves,
+            Requirement::Flute => Negatable::Flute,
+            Requirement::Hammer => Negatable::Hammer,
+            Requirement::Tier(tier) => Negatable::Tier(*tier),
+            Requirement::Setting(flag) => Negatable::Setting(*flag),
+            Requirement::Event(event) => Negatable::Event(*event),
+        };
+
+        Requirement::Not(negated)
+    }
+
+    /// Whether this is a `Not`, the only kind of requirement that can stop holding as we collect
+    /// things.
+    pub const fn is_negated(&self) -> bool {
+        matches!(self, Requirement::Not(_))
+    }
+}
+
+/// A requirement `Requirement::Not` can negate, which is any of them that checks some state. Open
+/// and locked are already each other's negation and negating a negation just gets the original
+/// back, so neither has a variant. See `Requirement::negate`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Negatable {
+    Boots,
+    Gloves,
+    Flute,
+    Hammer,
+    Tier(Tier),
+    Setting(SettingFlag),
+    Event(EventId),
+}
+
+impl Negatable {
+    /// The requirement this negates.
+    pub const fn requirement(&self) -> Requirement {
+        match self {
+            Negatable::Boots => Requirement::Boots,
+            Negatable::Gloves => Requirement::Gloves,
+            Negatable::Flute => Requirement::Flute,
+            Negatable::Hammer => Requirement::Hammer,
+            Negatable::Tier(tier) => Requirement::Tier(*tier),
+            Negatable::Setting(flag) => Requirement::Setting(*flag),
+            Negatable::Event(event) => Requirement::Event(*event),
+        }
+    }
+}
+
+/// Typically randomizers, whether they use a location list or graph world model, will encode their
+/// logical constraints as opaque functions that will take collection and world state as inputs.
+/// Our logic is modeled as plain text data which is transformed into simple tree-shaped and-or
+/// expressions that are evaluated transparently by our graph walker. They are then packed together
+/// into an array-like structure accessed by index where every edge only has to carry an index to
+/// a tree's root node.
+///
+/// This provides us a lot more flexibility across the board. We can "see" the requirements for
+/// any given path from one node to another (and reduce them to a simplified expression,) we can
+/// optimize similar and identical requirement trees by only including them in the backing
+/// structure once, and we can easily modify requirements, even allowing users to provide their
+/// own logic (encoded in plain text) to be placed into the backing structure and used at
+/// randomize time.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(align(4))]
+pub struct RequirementNode {
+    pub req: Requirement,
+    pub and: Option<NonZeroU16>,
+    pub or: Option<NonZeroU16>,
+}
+
+/// A generic newtype array wrapper that holds our RequirementNode trees.
+#[repr(transparent)]
+pub struct ReqArray<const N: usize>(pub(crate) [RequirementNode; N]);
+
+impl<const N: usize> Index<u16> for ReqArray<N> {
+    type Output = RequirementNode;
+
+    fn index(&self, idx: u16) -> &Self::Output {
+        &self.0[idx as usize]
+    }
+}
+
+/// Packs requirement trees into a `ReqArray` of up to `N` nodes, hash-consing them as they go in:
+/// a node with the same requirement and the same links as one we already have gets that node's
+/// index back instead of a new slot. Trees are added leaves first, so subtrees that are
+/// structurally equal end up as the same index and are stored once however many rules use them.
+///
+/// We look nodes up with a linear scan since this has to stay core only. Building even a few
+/// thousand rules is a one-off cost at codegen time.
+pub struct ReqBuilder<const N: usize> {
+    nodes: [RequirementNode; N],
+    len: usize,
+}
+
+impl<const N: usize> Default for ReqBuilder<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> ReqBuilder<N> {
+    /// A builder holding only the open and locked trees, which every container starts with.
+    pub fn new() -> Self {
+        Self::from_trees(&ReqArray([REQ_TREES.0[0], REQ_TREES.0[1]]))
+    }
+
+    /// A builder holding `trees` at their current indexes, so anything already pointing into
+    /// them stays valid. Start from `REQ_TREES` to extend the library's trees.
+    ///
+    /// Panics if `trees` doesn't fit in `N` nodes.
+    pub fn from_trees<const M: usize>(trees: &ReqArray<M>) -> Self {
+        assert!(M <= N, "a builder for {} nodes can't hold {}", N, M);
+        let mut nodes = [trees.0[0]; N];
+        nodes[..M].copy_from_slice(&trees.0);
+
+        ReqBuilder { nodes, len: M }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Every node so far, indexed the way edges and links point into them.
+    pub fn nodes(&self) -> &[RequirementNode] {
+        &self.nodes[..self.len]
+    }
+
+    /// The index of a node equal to `node`, if we have one.
+    pub fn find(&self, node: &RequirementNode) -> Option<u16> {
+        self.nodes()
+            .iter()
+            .position(|n| n == node)
+            .map(|i| i as u16)
+    }
+
+    /// Add `node` and return its index, or the index of an equal node we already have. Returns
+    /// `None` if it's new and we're full.
+    ///
+    /// Links have to point at nodes added before this one, e.g. from earlier calls.
+    pub fn insert(&mut self, node: RequirementNode) -> Option<u16> {
+        debug_assert!([node.and, node.or]
+            .into_iter()
+            .flatten()
+            .all(|l| (u16::from(l) as usize) < self.len));
+        if let Some(idx) = self.find(&node) {
+            return Some(idx);
+        }
+        let idx = self.len;
+        *self.nodes.get_mut(idx)? = node;
+        self.len += 1;
+
+        Some(idx as u16)
+    }
+
+    /// Copy the tree rooted at `root` in `trees` into ours, leaves first, and return its new root.
+    /// Whatever we already have an equal subtree for is shared rather than copied. Returns `None`
+    /// if we run out of room.
+    pub fn insert_tree<const M: usize>(&mut self, trees: &ReqArray<M>, root: u16) -> Option<u16> {
+        let node = trees[root];
+        let mut link = |l: Option<NonZeroU16>| match l {
+            // Links never point at the open tree, so neither do their copies.
+            Some(l) => self.insert_tree(trees, u16::from(l)).map(NonZeroU16::new),
+            None => Some(None),
+        };
+        let (and, or) = (link(node.and)?, link(node.or)?);
+
+        self.insert(RequirementNode {
+            req: node.req,
+            and,
+            or,
+        })
+    }
+
+    /// Evaluate the tree rooted at `root` the way the library does, with `satisfied` deciding
+    /// each requirement. Lints can use this before the trees are anywhere the library can see.
+    pub fn eval(&self, mut root: u16, mut satisfied: impl FnMut(Requirement) -> bool) -> bool {
+        loop {
+            let node = &self.nodes()[root as usize];
+            let next = match satisfied(node.req) {
+                true => node.and.ok_or(true),
+                false => node.or.ok_or(false),
+            };
+            match next {
+                Ok(link) => root = u16::from(link),
+                Err(result) => break result,
+            }
+        }
+    }
+
+    /// The finished array. Slots we never filled repeat the first node and nothing points at
+    /// them.
+    pub fn into_array(self) -> ReqArray<N> {
+        ReqArray(self.nodes)
+    }
+}
+
+pub const REQ_CONTAINER_LEN: usize = 16;
+
+/// The same trees as `REQ_CONTAINER` as a constant, since statics can't be read during const
+/// evaluation. Everything evaluated at runtime should go through the static so there's only ever
+/// one copy of it.
+pub const REQ_TREES: ReqArray<REQ_CONTAINER_LEN> = ReqArray([
+    // Indexes:
+    // 0 = open
+    // 1 = locked
+    // 2 = boots OR hammer
+    // 3 = hammer
+    // 4 = gloves
+    // 5 = gloves AND hammer
+    // 6 = flute
+    // 7 = hard
+    // 8 = glitched
+    // 9 = gloves OR (hammer AND hard)
+    // 11 = swordless
+    // 12 = keysanity
+    // 13 = Agahnim defeated
+    // 14 = crystal switch hit
+    // 15 = Agahnim NOT defeated
+    //
+    // These are first since they're always present and we need something to pad out the 0th
+    // element in order to index with NonZero types which we use in order to get a space
+    // optimization with the Option type. No requirement tree is allowed to use either as a leaf
+    // although only using the 0-index is prevented by the type system. Note that main graph model
+    // *is* allowed to use the zero index.
+    RequirementNode {
+        req: Requirement::Open,
+        and: None,
+        or: None,
+    },
+    RequirementNode {
+        req: Requirement::Locked,
+        and: None,
+        or: None,
+    },
+    RequirementNode {
+        req: Requirement::Boots,
+        and: None,
+        or: NonZeroU16::new(3), // Hammer
+    },
+    RequirementNode {
+        req: Requirement::Hammer,
+        and: None,
+        or: None,
+    },
+    RequirementNode {
+        req: Requirement::Gloves,
+        and: None,
+        or: None,
+    },
+    RequirementNode {
+        req: Requirement::Gloves,
+        and: NonZeroU16::new(3), // Hammer
+        or: None,
+    },
+    RequirementNode {
+        req: Requirement::Flute,
+        and: None,
+        or: None,
+    },
+    RequirementNode {
+        req: Requirement::Tier(Tier::Hard),
+        and: None,
+        or: None,
+    },
+    RequirementNode {
+        req: Requirement::Tier(Tier::Glitched),
+        and: None,
+        or: None,
+    },
+    RequirementNode {
+        req: Requirement::Gloves,
+        and: None,
+        or: NonZeroU16::new(10), // Hammer AND hard
+    },
+    // Only reachable through tree 9.
+    RequirementNode {
+        req: Requirement::Hammer,
+        and: NonZeroU16::new(7), // Hard
+        or: None,
+    },
+    RequirementNode {
+        req: Requirement::Setting(SettingFlag::Swordless),
+        and: None,
+        or: None,
+    },
+    RequirementNode {
+        req: Requirement::Setting(SettingFlag::Keysanity),
+        and: None,
+        or: None,
+    },
+    RequirementNode {
+        req: Requirement::Event(EventId::Agahnim),
+        and: None,
+        or: None,
+    },
+    RequirementNode {
+        req: Requirement::Event(EventId::CrystalSwitch),
+        and: None,
+        or: None,
+    },
+    // An entrance that closes once Agahnim is beaten, so any graph using this isn't monotone.
+    RequirementNode {
+        req: Requirement::Not(Negatable::Event(EventId::Agahnim)),
+        and: None,
+        or: None,
+    },
+]);
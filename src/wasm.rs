@@ -0,0 +1,77 @@
+use alloc::{string::ToString, vec::Vec};
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    facade::GenGraph,
+    logic::{CollectionState, SettingFlag, SettingsState, Tier, TierMask},
+    reachability::Reachability,
+};
+
+/// A minimal wrapper around a graph and a collection state for JavaScript, so web-based trackers
+/// can embed the crate directly. The graph comes from bytes produced by `StaticGraph::to_bytes`
+/// and every query runs against the current collection state.
+#[wasm_bindgen]
+pub struct WasmGraph {
+    graph: GenGraph,
+    state: CollectionState,
+}
+
+#[wasm_bindgen]
+impl WasmGraph {
+    /// Build a graph from its byte encoding, throwing if the bytes are malformed.
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: &[u8]) -> Result<WasmGraph, JsValue> {
+        GenGraph::from_bytes(bytes)
+            .map(|graph| WasmGraph {
+                graph,
+                state: CollectionState::default(),
+            })
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen(js_name = nodeCount)]
+    pub fn node_count(&self) -> u16 {
+        self.graph.node_count
+    }
+
+    #[wasm_bindgen(js_name = setCollectionState)]
+    pub fn set_collection_state(&mut self, boots: bool, gloves: bool, flute: bool, hammer: bool) {
+        self.state = CollectionState {
+            boots,
+            gloves,
+            flute,
+            hammer,
+            tiers: self.state.tiers,
+            settings: self.state.settings,
+            events: self.state.events,
+        };
+    }
+
+    /// Choose which logic tiers beyond normal logic reachability can assume.
+    #[wasm_bindgen(js_name = setTiers)]
+    pub fn set_tiers(&mut self, hard: bool, glitched: bool) {
+        let mut tiers = TierMask::NORMAL;
+        for (tier, enabled) in [(Tier::Hard, hard), (Tier::Glitched, glitched)] {
+            if enabled {
+                tiers = tiers.with(tier);
+            }
+        }
+        self.state.tiers = tiers;
+    }
+
+    /// Set the game mode flags the seed was generated with.
+    #[wasm_bindgen(js_name = setSettings)]
+    pub fn set_settings(&mut self, swordless: bool, keysanity: bool) {
+        self.state.settings = SettingsState::NONE
+            .with(SettingFlag::Swordless, swordless)
+            .with(SettingFlag::Keysanity, keysanity);
+    }
+
+    /// Returns a 1 for every id in `ids` reachable from the root under the current collection
+    /// state and a 0 for every other id.
+    pub fn reachable(&self, ids: &[u16]) -> Vec<u8> {
+        let reachable = Reachability::batch(&self.graph, &self.state, ids);
+        reachable.iter().map(u8::from).collect()
+    }
+}
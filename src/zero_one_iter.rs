@@ -0,0 +1,156 @@
+// A cheapest-first walker for graphs where every edge is either free or costs one.
+use alloc::{boxed::Box, collections::VecDeque, vec};
+use core::{fmt, iter::FusedIterator, num::NonZeroU16};
+
+use crate::{
+    gen::SEARCH_QUEUE_SIZE,
+    graph::StaticGraph,
+    logic::{CollectionState, EvaluateLogic},
+    traversal::TraversalCore,
+};
+
+/// A graph walker that yields nodes in order of how many loading zones it takes to reach them,
+/// which is a cheap estimate of how long a route is without weighing every edge. Edges that cross
+/// a loading zone (see `EdgeType::crosses_loading_zone`) cost one and the rest are free.
+///
+/// This is a 0-1 BFS: nodes reached over a free edge go on the front of the deque and the rest on
+/// the back, so the deque is always sorted by cost and we never need a heap. Like `AStarIter` a
+/// node may be pushed again when a cheaper path to it turns up, and the stale entries are skipped
+/// when popped.
+pub struct ZeroOneIter<'graph, const M: usize, const N: usize, T = ()> {
+    pub core: TraversalCore<'graph, M, N, T>,
+    pub deque: VecDeque<(u16, u16)>,
+    // Fewest loading zones from the root to each node found so far.
+    cost: Box<[u16]>,
+}
+
+impl<'graph, const M: usize, const N: usize, T> ZeroOneIter<'graph, M, N, T> {
+    pub(crate) fn new(graph: &'graph StaticGraph<M, N, T>, state: CollectionState) -> Self {
+        let mut zero_one_iter = ZeroOneIter {
+            core: TraversalCore::new(graph, 1, state),
+            deque: VecDeque::with_capacity(SEARCH_QUEUE_SIZE),
+            cost: vec![u16::MAX; M].into_boxed_slice(),
+        };
+        let root = zero_one_iter.core.root;
+        zero_one_iter.cost[root as usize] = 0;
+        zero_one_iter.deque.push_back((root, 0));
+
+        zero_one_iter
+    }
+
+    /// The fewest loading zones between the root and `node` found so far, if it's been reached.
+    /// It's final once `node` has been yielded.
+    pub fn cost(&self, node: u16) -> Option<u16> {
+        match self.cost[node as usize] {
+            u16::MAX => None,
+            c => Some(c),
+        }
+    }
+
+    /// Returns whether `target` is reachable, expanding nodes until it's yielded or the search is
+    /// exhausted.
+    pub fn search(&mut self, target: u16) -> bool {
+        self.any(|n| u16::from(n) == target)
+    }
+
+    fn visit_neighbors_out(&mut self, node: u16) {
+        let cost = self.cost[node as usize];
+        let graph = self.core.graph;
+        let (edge_pointers, edge_offset) = graph.get_neighbors_out(NonZeroU16::new(node));
+        for (i, &n) in edge_pointers.iter().enumerate() {
+            let edge = edge_offset + i as u16;
+            let node_index = u16::from(n);
+            let penalty = graph.edge_kinds[edge as usize].crosses_loading_zone();
+            let next_cost = cost + penalty as u16;
+            if !self.core.check_edge(edge) || next_cost >= self.cost[node_index as usize] {
+                continue;
+            }
+            if !self.core.visited.test_set_visited(node_index) {
+                self.core.visited_count += 1;
+            }
+            self.cost[node_index as usize] = next_cost;
+            match penalty {
+                false => self.deque.push_front((node_index, next_cost)),
+                true => self.deque.push_back((node_index, next_cost)),
+            }
+        }
+    }
+}
+
+impl<const M: usize, const N: usize, T> Iterator for ZeroOneIter<'_, M, N, T> {
+    type Item = NonZeroU16;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((node, cost)) = self.deque.pop_front() {
+            // A cheaper path to this node was found after this entry was pushed.
+            if cost > self.cost[node as usize] {
+                continue;
+            }
+            self.visit_neighbors_out(node);
+            return NonZeroU16::new(node);
+        }
+
+        None
+    }
+}
+
+impl<const M: usize, const N: usize, T> FusedIterator for ZeroOneIter<'_, M, N, T> {}
+
+impl<const M: usize, const N: usize, T> EvaluateLogic for ZeroOneIter<'_, M, N, T> {
+    fn collection_state(&self) -> &CollectionState {
+        &self.core.collection_state
+    }
+}
+
+impl<const M: usize, const N: usize, T> fmt::Debug for ZeroOneIter<'_, M, N, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ZeroOneIter")
+            .field("core", &self.core)
+            .field("deque", &self.deque.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::new_static_graph_open;
+    use std::collections::HashSet;
+
+    #[test]
+    fn nondecreasing_cost() {
+        let graph = new_static_graph_open();
+        let mut zero_one_iter = graph.zero_one_iter();
+        let order: Vec<u16> = zero_one_iter.by_ref().map(u16::from).collect();
+        let reached: HashSet<u16> = graph.bfs_iter().map(u16::from).collect();
+        assert_eq!(order.len(), reached.len());
+        assert_eq!(order.iter().copied().collect::<HashSet<_>>(), reached);
+
+        let costs: Vec<u16> = order
+            .iter()
+            .map(|&n| zero_one_iter.cost(n).unwrap())
+            .collect();
+        assert!(costs.windows(2).all(|w| w[0] <= w[1]));
+        assert!(costs.last() > Some(&0));
+
+        // Every cost is the fewest loading zones over any path, checked by relaxing every edge of
+        // the open graph until nothing changes.
+        let mut best = vec![u16::MAX; graph.node_count() as usize + 1];
+        best[1] = 0;
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (src, edge, dst) in graph.edges() {
+                let penalty = graph.edge_data(edge).kind.crosses_loading_zone() as u16;
+                let through = best[src as usize].saturating_add(penalty);
+                if through < best[dst as usize] {
+                    best[dst as usize] = through;
+                    changed = true;
+                }
+            }
+        }
+        assert!(order
+            .iter()
+            .all(|&n| zero_one_iter.cost(n) == Some(best[n as usize])));
+    }
+}